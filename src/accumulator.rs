@@ -0,0 +1,225 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Append-only cryptographic accumulator over typed elements.
+//!
+//! The elements are encoded into scalars with [`PoseidonEncode`], hashed with
+//! [`sponge::hash`] and appended to a [`PoseidonTree`], whose root is the
+//! value of the accumulator. The positions of the elements in the tree are
+//! kept by the accumulator, so adding an element, and proving it, only takes
+//! the element.
+//!
+//! The [`Witness`] of an element is a [`MaintainedOpening`] of its leaf: it
+//! follows the elements later added to the accumulator, without requesting it
+//! again, one element at a time or in batches with [`Accumulator::update`].
+
+use alloc::collections::BTreeMap;
+use core::fmt;
+use core::marker::PhantomData;
+
+use bls12_381::Scalar as BlsScalar;
+
+use crate::map::PoseidonEncode;
+use crate::sponge;
+use crate::tree::{
+    MaintainedOpening, PoseidonBranch, PoseidonTree, ScalarLeaf,
+};
+
+/// Hash the encoding of `element`.
+fn hash<T: PoseidonEncode>(element: &T) -> BlsScalar {
+    sponge::hash(&element.poseidon_encode())
+}
+
+/// Proof that an element is in an [`Accumulator`].
+#[derive(Debug, Clone, Copy)]
+pub struct Witness<const DEPTH: usize> {
+    opening: MaintainedOpening<DEPTH>,
+}
+
+impl<const DEPTH: usize> Witness<DEPTH> {
+    /// Return the number of elements of the accumulator the witness is up to
+    /// date with.
+    pub const fn len(&self) -> u64 {
+        self.opening.len()
+    }
+
+    /// Return `true` if the witness is up to date with no element, which
+    /// can't happen for a witness obtained from an accumulator.
+    pub const fn is_empty(&self) -> bool {
+        self.opening.is_empty()
+    }
+
+    /// Return the value of the accumulator the witness is up to date with.
+    pub const fn root(&self) -> &BlsScalar {
+        self.opening.root()
+    }
+
+    /// Return the merkle opening of the element in the tree of the
+    /// accumulator, to be opened in a circuit with
+    /// [`merkle_opening`](crate::tree::merkle_opening).
+    pub const fn branch(&self) -> &PoseidonBranch<DEPTH> {
+        self.opening.branch()
+    }
+
+    /// Update the witness with the next element added to the accumulator,
+    /// returning the new value of the accumulator.
+    ///
+    /// Only the elements newly added to the accumulator may be passed, in the
+    /// order they were added. Since [`Accumulator::add`] ignores an element
+    /// already in the accumulator, appending it again here would leave the
+    /// witness out of sync; use [`Accumulator::update`] when it isn't known
+    /// whether an element was new.
+    ///
+    /// # Panics
+    /// If the accumulator is full.
+    pub fn append<T: PoseidonEncode>(&mut self, element: &T) -> BlsScalar {
+        self.opening.append(hash(element))
+    }
+}
+
+/// Append-only set of elements, committed to under a single root.
+pub struct Accumulator<T, const DEPTH: usize> {
+    tree: PoseidonTree<ScalarLeaf, u64, DEPTH>,
+    positions: BTreeMap<[u8; 32], u64>,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T, const DEPTH: usize> fmt::Debug for Accumulator<T, DEPTH> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Accumulator")
+            .field("tree", &self.tree)
+            .field("positions", &self.positions)
+            .finish()
+    }
+}
+
+impl<T, const DEPTH: usize> Clone for Accumulator<T, DEPTH> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: self.tree.clone(),
+            positions: self.positions.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, const DEPTH: usize> Default for Accumulator<T, DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const DEPTH: usize> Accumulator<T, DEPTH> {
+    /// Create a new, empty, accumulator.
+    pub const fn new() -> Self {
+        Self {
+            tree: PoseidonTree::new(),
+            positions: BTreeMap::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Return the current value of the accumulator.
+    pub fn root(&self) -> BlsScalar {
+        self.tree.root()
+    }
+
+    /// Return the number of elements in the accumulator.
+    pub fn len(&self) -> u64 {
+        self.positions.len() as u64
+    }
+
+    /// Return `true` if the accumulator holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Bring `witness` up to date with the elements added to the accumulator
+    /// since it was last updated, returning the current value of the
+    /// accumulator.
+    ///
+    /// Return `None`, leaving the witness untouched, if the witness is ahead
+    /// of the accumulator, or from another accumulator.
+    pub fn update(&self, witness: &mut Witness<DEPTH>) -> Option<BlsScalar> {
+        let mut updated = *witness;
+        for pos in witness.len()..self.len() {
+            let element = self.tree.get(pos)?;
+            updated.opening.append(*element.scalar());
+        }
+
+        match *updated.root() == self.root() {
+            true => {
+                *witness = updated;
+                Some(*witness.root())
+            }
+            false => None,
+        }
+    }
+
+    /// Bring a batch of witnesses up to date with the accumulator.
+    ///
+    /// Return `false` if one of the witnesses couldn't be updated, see
+    /// [`Accumulator::update`]. The other witnesses are updated nonetheless.
+    pub fn update_all(&self, witnesses: &mut [Witness<DEPTH>]) -> bool {
+        witnesses
+            .iter_mut()
+            .fold(true, |updated, w| self.update(w).is_some() && updated)
+    }
+}
+
+impl<T, const DEPTH: usize> Accumulator<T, DEPTH>
+where
+    T: PoseidonEncode,
+{
+    /// Return `true` if `element` is in the accumulator.
+    pub fn contains(&self, element: &T) -> bool {
+        self.positions.contains_key(&hash(element).to_bytes())
+    }
+
+    /// Add an element to the accumulator, returning its witness.
+    ///
+    /// Adding an element already in the accumulator leaves it untouched, and
+    /// returns the current witness of the element.
+    ///
+    /// # Panics
+    /// If the accumulator is full.
+    pub fn add(&mut self, element: &T) -> Witness<DEPTH> {
+        let element_hash = hash(element);
+
+        if !self.positions.contains_key(&element_hash.to_bytes()) {
+            let capacity = 4u64.checked_pow(DEPTH as u32);
+            assert!(
+                capacity.map_or(true, |c| self.len() < c),
+                "the accumulator is full"
+            );
+
+            let pos = self.tree.push(ScalarLeaf::new(element_hash));
+            self.positions.insert(element_hash.to_bytes(), pos);
+        }
+
+        self.prove(element)
+            .expect("the element is in the accumulator")
+    }
+
+    /// Return the witness of `element`, if it is in the accumulator.
+    pub fn prove(&self, element: &T) -> Option<Witness<DEPTH>> {
+        let pos = self.positions.get(&hash(element).to_bytes())?;
+
+        self.tree
+            .maintained_opening(*pos)
+            .map(|opening| Witness { opening })
+    }
+
+    /// Check that `witness` proves `element` in the accumulator of value
+    /// `root`.
+    pub fn verify(
+        root: &BlsScalar,
+        witness: &Witness<DEPTH>,
+        element: &T,
+    ) -> bool {
+        witness.branch().verify(root, &hash(element))
+    }
+}
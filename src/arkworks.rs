@@ -0,0 +1,208 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Conversions between the scalars of this crate and the arkworks
+//! [`Fr`] type, together with `ark-serialize` implementations for the tree
+//! types and a `CryptographicSponge` implementation for the [`Duplex`]
+//! sponge.
+//!
+//! Both types represent the same field and share the same canonical 32-bytes
+//! little-endian encoding, so a hash computed with this crate can be handed to
+//! an arkworks protocol, and vice-versa, without any loss. Since neither type
+//! is defined in this crate, the conversions are provided as functions.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::Serializable;
+
+use crate::sponge::duplex::Duplex;
+
+use ark_bls12_381::Fr;
+use ark_crypto_primitives::sponge::{Absorb, CryptographicSponge};
+use ark_ff::{BigInteger, PrimeField};
+
+/// Convert a [`BlsScalar`] into an arkworks [`Fr`].
+pub fn to_ark(scalar: &BlsScalar) -> Fr {
+    Fr::from_le_bytes_mod_order(&scalar.to_bytes())
+}
+
+/// Convert an arkworks [`Fr`] into a [`BlsScalar`].
+pub fn from_ark(fr: &Fr) -> BlsScalar {
+    let mut bytes = [0u8; BlsScalar::SIZE];
+    bytes.copy_from_slice(&fr.into_bigint().to_bytes_le());
+
+    BlsScalar::from_bytes(&bytes)
+        .expect("An arkworks scalar is always canonical")
+}
+
+/// The [`Duplex`] sponge can serve as the Fiat-Shamir sponge of arkworks
+/// protocols. The configuration of the sponge is the domain it is created
+/// with, see [`Duplex::new`].
+///
+/// Every absorbed input is prefixed with a scalar framing its length: the
+/// number of its field elements in the lowest 64 bits, and the number of its
+/// sponge bytes in the following 64 bits. Successive inputs can't collide
+/// with their concatenation, and byte strings that only differ by trailing
+/// zeros, which pack into the same field elements, can't collide either.
+///
+/// Bytes and bits are squeezed from the little-endian representation of the
+/// squeezed scalars, discarding their most significant bit so the outputs are
+/// uniformly distributed.
+impl CryptographicSponge for Duplex {
+    type Config = BlsScalar;
+
+    fn new(domain: &Self::Config) -> Self {
+        Duplex::new(*domain)
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        let elements: Vec<BlsScalar> = input
+            .to_sponge_field_elements_as_vec::<Fr>()
+            .iter()
+            .map(from_ark)
+            .collect();
+        let bytes = input.to_sponge_bytes_as_vec().len();
+
+        let frame =
+            BlsScalar::from_raw([elements.len() as u64, bytes as u64, 0, 0]);
+
+        Duplex::absorb(self, &[frame]);
+        Duplex::absorb(self, &elements);
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let mut bytes = vec![0u8; num_bytes];
+        Duplex::squeeze_bytes(self, &mut bytes);
+
+        bytes
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let usable_bits = Fr::MODULUS_BIT_SIZE as usize - 1;
+
+        let mut bits = Vec::with_capacity(num_bits + usable_bits);
+        while bits.len() < num_bits {
+            let bytes = self.squeeze().to_bytes();
+            bits.extend(
+                (0..usable_bits).map(|i| (bytes[i / 8] >> (i % 8)) & 1 == 1),
+            );
+        }
+        bits.truncate(num_bits);
+
+        bits
+    }
+}
+
+#[cfg(any(feature = "alloc", feature = "verify-only"))]
+mod tree {
+    use super::{from_ark, to_ark};
+
+    use crate::tree::{PoseidonBranch, PoseidonLevel};
+
+    use ark_bls12_381::Fr;
+    use ark_serialize::{
+        CanonicalDeserialize, CanonicalSerialize, Compress, Read,
+        SerializationError, Valid, Validate, Write,
+    };
+
+    impl CanonicalSerialize for PoseidonLevel {
+        fn serialize_with_mode<W: Write>(
+            &self,
+            mut writer: W,
+            compress: Compress,
+        ) -> Result<(), SerializationError> {
+            for scalar in self.level.iter() {
+                to_ark(scalar).serialize_with_mode(&mut writer, compress)?;
+            }
+            self.index.serialize_with_mode(&mut writer, compress)
+        }
+
+        fn serialized_size(&self, compress: Compress) -> usize {
+            self.level
+                .iter()
+                .map(|scalar| to_ark(scalar).serialized_size(compress))
+                .sum::<usize>()
+                + self.index.serialized_size(compress)
+        }
+    }
+
+    impl Valid for PoseidonLevel {
+        fn check(&self) -> Result<(), SerializationError> {
+            Ok(())
+        }
+    }
+
+    impl CanonicalDeserialize for PoseidonLevel {
+        fn deserialize_with_mode<R: Read>(
+            mut reader: R,
+            compress: Compress,
+            validate: Validate,
+        ) -> Result<Self, SerializationError> {
+            let mut level = PoseidonLevel::default();
+
+            for scalar in level.level.iter_mut() {
+                let fr =
+                    Fr::deserialize_with_mode(&mut reader, compress, validate)?;
+                *scalar = from_ark(&fr);
+            }
+            level.index =
+                u64::deserialize_with_mode(&mut reader, compress, validate)?;
+
+            Ok(level)
+        }
+    }
+
+    impl<const DEPTH: usize> CanonicalSerialize for PoseidonBranch<DEPTH> {
+        fn serialize_with_mode<W: Write>(
+            &self,
+            mut writer: W,
+            compress: Compress,
+        ) -> Result<(), SerializationError> {
+            for level in self.path.iter() {
+                level.serialize_with_mode(&mut writer, compress)?;
+            }
+            to_ark(&self.root).serialize_with_mode(&mut writer, compress)
+        }
+
+        fn serialized_size(&self, compress: Compress) -> usize {
+            self.path
+                .iter()
+                .map(|level| level.serialized_size(compress))
+                .sum::<usize>()
+                + to_ark(&self.root).serialized_size(compress)
+        }
+    }
+
+    impl<const DEPTH: usize> Valid for PoseidonBranch<DEPTH> {
+        fn check(&self) -> Result<(), SerializationError> {
+            Ok(())
+        }
+    }
+
+    impl<const DEPTH: usize> CanonicalDeserialize for PoseidonBranch<DEPTH> {
+        fn deserialize_with_mode<R: Read>(
+            mut reader: R,
+            compress: Compress,
+            validate: Validate,
+        ) -> Result<Self, SerializationError> {
+            let mut path = [PoseidonLevel::default(); DEPTH];
+            for level in path.iter_mut() {
+                *level = PoseidonLevel::deserialize_with_mode(
+                    &mut reader,
+                    compress,
+                    validate,
+                )?;
+            }
+
+            let root = Fr::deserialize_with_mode(reader, compress, validate)?;
+            let root = from_ark(&root);
+
+            Ok(Self { path, root })
+        }
+    }
+}
@@ -0,0 +1,390 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Gadgets over the `bellman` constraint system, for the Groth16 circuits
+//! built with `bellman` or `bellperson`.
+//!
+//! The gadgets mirror the sponge and the merkle openings of this crate
+//! instantiated with a set of [`Params`]: [`sponge_gadget`] computes the same
+//! hash as [`sponge::hash_with_params`], and [`merkle_opening`] recomputes the
+//! same root as [`PoseidonBranch::verify_with`]. The constants of the `Hades`
+//! permutation aren't exported by the hades crate, so the default
+//! instantiation is reproduced by loading its round constants and MDS matrix
+//! with [`Params::new`].
+//!
+//! Scalars are passed in and out as [`AllocatedNum`], the way the gadgets of
+//! `bellman` compose, so the gadgets can be called from any
+//! [`Circuit::synthesize`].
+//!
+//! [`sponge::hash_with_params`]: crate::sponge::hash_with_params
+//! [`Circuit::synthesize`]: ::bellman::Circuit::synthesize
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ::bellman::gadgets::boolean::AllocatedBit;
+use ::bellman::gadgets::num::AllocatedNum;
+use ::bellman::{ConstraintSystem, LinearCombination, SynthesisError};
+use bls12_381::Scalar as BlsScalar;
+
+use crate::params::Params;
+use crate::permutation::assert_sponge_width;
+use crate::tree::{assert_tree_width, PoseidonBranch};
+
+/// Mirror [`Params::permute`] over `bellman`, replacing `state` with the
+/// allocated output of the permutation.
+///
+/// The round constants and the MDS matrix are folded into linear
+/// combinations, so only the S-boxes cost constraints, three per S-box.
+///
+/// # Panics
+///
+/// Panics if the length of `state` doesn't match the width.
+pub fn permute<CS>(
+    mut cs: CS,
+    params: &Params,
+    state: &mut [AllocatedNum<BlsScalar>],
+) -> Result<(), SynthesisError>
+where
+    CS: ConstraintSystem<BlsScalar>,
+{
+    assert_eq!(
+        state.len(),
+        params.width(),
+        "The state must match the width of the parameters"
+    );
+
+    let mut combinations: Vec<Combination> =
+        state.iter().map(Combination::from).collect();
+    permute_combinations(
+        cs.namespace(|| "permutation"),
+        params,
+        &mut combinations,
+    )?;
+
+    for (i, (s, c)) in state.iter_mut().zip(&combinations).enumerate() {
+        *s = c.allocate(cs.namespace(|| format!("output {i}")))?;
+    }
+
+    Ok(())
+}
+
+/// Mirror [`sponge::hash_with_params`] over `bellman`.
+///
+/// The circuit is defined by the length of `messages`, as with the PLONK
+/// [`sponge::gadget`], and the padding is a constant of the circuit.
+///
+/// # Panics
+///
+/// Panics if the width of `params` is smaller than `2`.
+///
+/// [`sponge::hash_with_params`]: crate::sponge::hash_with_params
+/// [`sponge::gadget`]: crate::sponge::gadget
+pub fn sponge_gadget<CS>(
+    mut cs: CS,
+    params: &Params,
+    messages: &[AllocatedNum<BlsScalar>],
+) -> Result<AllocatedNum<BlsScalar>, SynthesisError>
+where
+    CS: ConstraintSystem<BlsScalar>,
+{
+    let width = params.width();
+    assert_sponge_width(width);
+
+    let mut state = vec![Combination::zero(); width];
+
+    let l = messages.len();
+    let m = l / (width - 1);
+    let n = m * (width - 1);
+    let last_iteration = if l == n {
+        m.saturating_sub(1)
+    } else {
+        l / (width - 1)
+    };
+
+    for (i, chunk) in messages.chunks(width - 1).enumerate() {
+        for (s, c) in state[1..].iter_mut().zip(chunk.iter()) {
+            s.add(&Combination::from(c));
+        }
+
+        if i == last_iteration && chunk.len() < width - 1 {
+            state[chunk.len() + 1].add_constant::<CS>(BlsScalar::one());
+        } else if i == last_iteration {
+            permute_combinations(
+                cs.namespace(|| "padding"),
+                params,
+                &mut state,
+            )?;
+
+            state[1].add_constant::<CS>(BlsScalar::one());
+        }
+
+        permute_combinations(
+            cs.namespace(|| format!("chunk {i}")),
+            params,
+            &mut state,
+        )?;
+    }
+
+    state[1].allocate(cs.namespace(|| "hash"))
+}
+
+/// Mirror [`PoseidonBranch::verify_with`] over `bellman`, returning the root
+/// recomputed from `leaf`.
+///
+/// The levels of the branch are allocated as witnesses, and every level
+/// checks that the node computed from the level below is found at its
+/// offset, as the PLONK [`merkle_opening`] does. The caller is expected to
+/// constrain the returned root, usually to a public input.
+///
+/// # Panics
+///
+/// Panics if the width of `params` isn't one more than the arity of the
+/// tree.
+///
+/// [`merkle_opening`]: crate::tree::merkle_opening
+pub fn merkle_opening<CS, const DEPTH: usize>(
+    mut cs: CS,
+    params: &Params,
+    branch: &PoseidonBranch<DEPTH>,
+    leaf: &AllocatedNum<BlsScalar>,
+) -> Result<AllocatedNum<BlsScalar>, SynthesisError>
+where
+    CS: ConstraintSystem<BlsScalar>,
+{
+    assert_tree_width(params.width());
+
+    let mut node = Combination::from(leaf);
+    for (l, level) in branch.as_ref().iter().enumerate() {
+        let mut cs = cs.namespace(|| format!("level {l}"));
+
+        let mut state = level
+            .as_ref()
+            .iter()
+            .enumerate()
+            .map(|(i, n)| {
+                let num = AllocatedNum::alloc(
+                    cs.namespace(|| format!("node {i}")),
+                    || Ok(*n),
+                )?;
+                Ok(Combination::from(&num))
+            })
+            .collect::<Result<Vec<_>, SynthesisError>>()?;
+
+        assert_offset(
+            cs.namespace(|| "offset"),
+            &state,
+            level.offset(),
+            &node,
+        )?;
+
+        permute_combinations(cs.namespace(|| "hash"), params, &mut state)?;
+        node = state[1].clone();
+    }
+
+    node.allocate(cs.namespace(|| "root"))
+}
+
+/// A linear combination of the variables of a constraint system, along with
+/// its value when the witnesses are known.
+#[derive(Clone)]
+struct Combination {
+    lc: LinearCombination<BlsScalar>,
+    value: Option<BlsScalar>,
+}
+
+impl From<&AllocatedNum<BlsScalar>> for Combination {
+    fn from(num: &AllocatedNum<BlsScalar>) -> Self {
+        Self {
+            lc: LinearCombination::zero() + num.get_variable(),
+            value: num.get_value(),
+        }
+    }
+}
+
+impl Combination {
+    fn zero() -> Self {
+        Self {
+            lc: LinearCombination::zero(),
+            value: Some(BlsScalar::zero()),
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.lc = self.lc.clone() + &other.lc;
+        self.value = self.value.zip(other.value).map(|(a, b)| a + b);
+    }
+
+    fn add_constant<CS>(&mut self, constant: BlsScalar)
+    where
+        CS: ConstraintSystem<BlsScalar>,
+    {
+        self.lc = self.lc.clone() + (constant, CS::one());
+        self.value = self.value.map(|v| v + constant);
+    }
+
+    fn scaled(&self, coefficient: BlsScalar) -> Self {
+        Self {
+            lc: LinearCombination::zero() + (coefficient, &self.lc),
+            value: self.value.map(|v| v * coefficient),
+        }
+    }
+
+    fn value(&self) -> Result<BlsScalar, SynthesisError> {
+        self.value.ok_or(SynthesisError::AssignmentMissing)
+    }
+
+    /// Allocate the product of `self` and `other`.
+    fn mul<CS>(
+        &self,
+        mut cs: CS,
+        other: &Self,
+    ) -> Result<Combination, SynthesisError>
+    where
+        CS: ConstraintSystem<BlsScalar>,
+    {
+        let value = self.value.zip(other.value).map(|(a, b)| a * b);
+        let product = cs.alloc(
+            || "product",
+            || value.ok_or(SynthesisError::AssignmentMissing),
+        )?;
+
+        cs.enforce(
+            || "multiplication",
+            |lc| lc + &self.lc,
+            |lc| lc + &other.lc,
+            |lc| lc + product,
+        );
+
+        Ok(Self {
+            lc: LinearCombination::zero() + product,
+            value,
+        })
+    }
+
+    /// Allocate the combination as a single variable.
+    fn allocate<CS>(
+        &self,
+        mut cs: CS,
+    ) -> Result<AllocatedNum<BlsScalar>, SynthesisError>
+    where
+        CS: ConstraintSystem<BlsScalar>,
+    {
+        let num =
+            AllocatedNum::alloc(cs.namespace(|| "value"), || self.value())?;
+
+        cs.enforce(
+            || "allocation",
+            |lc| lc + &self.lc,
+            |lc| lc + CS::one(),
+            |lc| lc + num.get_variable(),
+        );
+
+        Ok(num)
+    }
+}
+
+/// Apply the permutation of `params` to a state of linear combinations.
+fn permute_combinations<CS>(
+    mut cs: CS,
+    params: &Params,
+    state: &mut [Combination],
+) -> Result<(), SynthesisError>
+where
+    CS: ConstraintSystem<BlsScalar>,
+{
+    let width = params.width();
+
+    let rounds = params.round_constants().chunks(width).enumerate();
+    for (round, constants) in rounds {
+        let mut cs = cs.namespace(|| format!("round {round}"));
+
+        for (s, c) in state.iter_mut().zip(constants) {
+            s.add_constant::<CS>(*c);
+        }
+
+        if params.is_full_round(round) {
+            for (i, s) in state.iter_mut().enumerate() {
+                *s = quintic_s_box(cs.namespace(|| format!("s-box {i}")), s)?;
+            }
+        } else {
+            state[width - 1] = quintic_s_box(
+                cs.namespace(|| format!("s-box {}", width - 1)),
+                &state[width - 1],
+            )?;
+        }
+
+        let product: Vec<Combination> = params
+            .mds()
+            .chunks(width)
+            .map(|row| {
+                row.iter().zip(state.iter()).fold(
+                    Combination::zero(),
+                    |mut acc, (m, s)| {
+                        acc.add(&s.scaled(*m));
+                        acc
+                    },
+                )
+            })
+            .collect();
+        state.clone_from_slice(&product);
+    }
+
+    Ok(())
+}
+
+/// Allocate the bits selecting `offset` among the children of a level held
+/// in `state`, and check that `node` is the child they select.
+fn assert_offset<CS>(
+    mut cs: CS,
+    state: &[Combination],
+    offset: usize,
+    node: &Combination,
+) -> Result<(), SynthesisError>
+where
+    CS: ConstraintSystem<BlsScalar>,
+{
+    let mut sum = LinearCombination::zero();
+
+    for (i, child) in state[1..].iter().enumerate() {
+        let bit = AllocatedBit::alloc(
+            cs.namespace(|| format!("bit {i}")),
+            Some(i == offset),
+        )?;
+        sum = sum + bit.get_variable();
+
+        // The child must be the node wherever the bit is set
+        cs.enforce(
+            || format!("selection {i}"),
+            |lc| lc + bit.get_variable(),
+            |lc| lc + &child.lc - &node.lc,
+            |lc| lc,
+        );
+    }
+
+    cs.enforce(
+        || "single selection",
+        |lc| lc + &sum,
+        |lc| lc + CS::one(),
+        |lc| lc + CS::one(),
+    );
+
+    Ok(())
+}
+
+fn quintic_s_box<CS>(
+    mut cs: CS,
+    s: &Combination,
+) -> Result<Combination, SynthesisError>
+where
+    CS: ConstraintSystem<BlsScalar>,
+{
+    let s2 = s.mul(cs.namespace(|| "square"), s)?;
+    let s4 = s2.mul(cs.namespace(|| "fourth"), &s2)?;
+
+    s4.mul(cs.namespace(|| "fifth"), s)
+}
@@ -0,0 +1,36 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Poseidon instantiations over the scalar field of the BN254 curve, the
+//! field of the Ethereum precompiles.
+//!
+//! The parameters are generated with the Grain LFSR of the reference
+//! implementation, as [`Params::generate`] does for any field. Over the BN254
+//! scalar field, the round constants and the MDS matrices generated at the
+//! 128 bits security level are the ones of circomlib, which most on-chain
+//! Poseidon verifiers are built on. The sponge of this crate, with its
+//! capacity, padding and output, can be computed over these parameters with
+//! [`sponge::hash_with_params`].
+//!
+//! [`sponge::hash_with_params`]: crate::sponge::hash_with_params
+
+use crate::params::{Error, Params, SecurityLevel};
+
+pub use halo2curves::bn256::Fr as Bn254Scalar;
+
+/// Parameters of a permutation over the scalar field of BN254.
+pub type Bn254Params = Params<Bn254Scalar>;
+
+/// Generate the parameters of a permutation of the given `width` over the
+/// scalar field of BN254, with the round numbers of the security `level`.
+///
+/// Return an error if the width isn't supported by the security level.
+pub fn params(
+    width: usize,
+    level: SecurityLevel,
+) -> Result<Bn254Params, Error> {
+    Params::with_security(width, level)
+}
@@ -0,0 +1,249 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Encryption of scalars with the `Hades` permutation.
+//!
+//! The cipher follows the encryption scheme of
+//! <https://eprint.iacr.org/2019/458.pdf>, section 5, keyed with a shared
+//! secret point, such as the output of a Diffie-Hellman key exchange over
+//! JubJub. The state is initialized with the domain of the cipher, the length
+//! of the message, the coordinates of the secret and a nonce, and a message of
+//! [`PoseidonCipher::capacity`] scalars is encrypted with two permutations.
+//! The last scalar of the cipher authenticates the others, so decrypting with
+//! the wrong secret or nonce fails, and so does decrypting a cipher any scalar
+//! of which was tampered with. The cipher is therefore not malleable, and
+//! needs no tag of its own: [`PoseidonCipher::try_decrypt`] reports such a
+//! failure as [`Error::DecryptionFailed`].
+
+#[cfg(feature = "alloc")]
+mod stream;
+#[cfg(feature = "alloc")]
+mod zk;
+
+use core::fmt;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::{DeserializableSlice, Serializable};
+use hades::WIDTH;
+use jubjub::AffinePoint as JubJubAffine;
+
+use crate::permutation::{Hades, Permutation};
+
+#[cfg(feature = "alloc")]
+pub use stream::{decapsulate, encapsulate, keystream};
+#[cfg(feature = "alloc")]
+pub use zk::{
+    decrypt_gadget, decrypt_gadget_with, encrypt_gadget, encrypt_gadget_with,
+};
+
+/// Maximum number of scalars of an encrypted message.
+const MESSAGE_CAPACITY: usize = 2;
+
+/// Number of scalars of a cipher.
+const CIPHER_SIZE: usize = MESSAGE_CAPACITY + 1;
+
+/// Errors that can occur while decrypting a [`PoseidonCipher`] or an
+/// encapsulated message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The cipher was tampered with, or it wasn't encrypted with the given
+    /// secret and nonce.
+    DecryptionFailed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DecryptionFailed => write!(f, "the decryption failed"),
+        }
+    }
+}
+
+/// Encrypted message of at most [`PoseidonCipher::capacity`] scalars.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PoseidonCipher {
+    cipher: [BlsScalar; CIPHER_SIZE],
+}
+
+impl Serializable<{ BlsScalar::SIZE * CIPHER_SIZE }> for PoseidonCipher {
+    type Error = bytes::Error;
+
+    fn from_bytes(buf: &[u8; Self::SIZE]) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let mut bytes = &buf[..];
+
+        let mut cipher = [BlsScalar::zero(); CIPHER_SIZE];
+        for scalar in cipher.iter_mut() {
+            *scalar = BlsScalar::from_reader(&mut bytes)?;
+        }
+
+        Ok(Self::new(cipher))
+    }
+
+    fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut buf = [0u8; Self::SIZE];
+
+        for (i, scalar) in self.cipher.iter().enumerate() {
+            buf[i * BlsScalar::SIZE..(i + 1) * BlsScalar::SIZE]
+                .copy_from_slice(&scalar.to_bytes());
+        }
+
+        buf
+    }
+}
+
+impl PoseidonCipher {
+    /// Create a new cipher from its scalars.
+    pub const fn new(cipher: [BlsScalar; CIPHER_SIZE]) -> Self {
+        Self { cipher }
+    }
+
+    /// Return the scalars of the cipher.
+    pub const fn cipher(&self) -> &[BlsScalar; CIPHER_SIZE] {
+        &self.cipher
+    }
+
+    /// Return the maximum number of scalars of an encrypted message.
+    pub const fn capacity() -> usize {
+        MESSAGE_CAPACITY
+    }
+
+    /// Return the number of scalars of a cipher.
+    pub const fn cipher_size() -> usize {
+        CIPHER_SIZE
+    }
+
+    /// Return the state of the permutation before the encryption.
+    fn initial_state(
+        secret: &JubJubAffine,
+        nonce: BlsScalar,
+    ) -> [BlsScalar; WIDTH] {
+        [
+            // Domain of the cipher, the maximum length of a message
+            BlsScalar::from_raw([0x100000000u64, 0, 0, 0]),
+            // The length of the message is always the capacity, since the
+            // absent scalars are replaced by zero
+            BlsScalar::from_raw([MESSAGE_CAPACITY as u64, 0, 0, 0]),
+            secret.get_u(),
+            secret.get_v(),
+            nonce,
+        ]
+    }
+
+    /// Encrypt `message` with the given `secret` and `nonce`.
+    ///
+    /// A message shorter than [`PoseidonCipher::capacity`] is padded with
+    /// zeros. Return `None` if the message is longer than the capacity.
+    pub fn encrypt(
+        message: &[BlsScalar],
+        secret: &JubJubAffine,
+        nonce: &BlsScalar,
+    ) -> Option<Self> {
+        Self::encrypt_with(&Hades, message, secret, nonce)
+    }
+
+    /// Encrypt `message` with the given `secret` and `nonce`, as
+    /// [`PoseidonCipher::encrypt`] does, with the permutation `perm`.
+    ///
+    /// # Panics
+    /// If the width of `perm` doesn't match the state of the cipher.
+    pub fn encrypt_with<P>(
+        perm: &P,
+        message: &[BlsScalar],
+        secret: &JubJubAffine,
+        nonce: &BlsScalar,
+    ) -> Option<Self>
+    where
+        P: Permutation<Scalar = BlsScalar>,
+    {
+        assert_cipher_width(perm.width());
+
+        if message.len() > MESSAGE_CAPACITY {
+            return None;
+        }
+
+        let mut cipher = [BlsScalar::zero(); CIPHER_SIZE];
+
+        let mut state = Self::initial_state(secret, *nonce);
+        perm.permute(&mut state);
+
+        (0..MESSAGE_CAPACITY).for_each(|i| {
+            state[i + 1] += message.get(i).copied().unwrap_or_default();
+            cipher[i] = state[i + 1];
+        });
+
+        perm.permute(&mut state);
+        cipher[MESSAGE_CAPACITY] = state[1];
+
+        Some(Self::new(cipher))
+    }
+
+    /// Decrypt the cipher with the given `secret` and `nonce`.
+    ///
+    /// Return `None` if the cipher wasn't encrypted with them.
+    pub fn decrypt(
+        &self,
+        secret: &JubJubAffine,
+        nonce: &BlsScalar,
+    ) -> Option<[BlsScalar; MESSAGE_CAPACITY]> {
+        self.decrypt_with(&Hades, secret, nonce)
+    }
+
+    /// Decrypt the cipher with the given `secret` and `nonce`, as
+    /// [`PoseidonCipher::decrypt`] does, with the permutation `perm`.
+    ///
+    /// # Panics
+    /// If the width of `perm` doesn't match the state of the cipher.
+    pub fn decrypt_with<P>(
+        &self,
+        perm: &P,
+        secret: &JubJubAffine,
+        nonce: &BlsScalar,
+    ) -> Option<[BlsScalar; MESSAGE_CAPACITY]>
+    where
+        P: Permutation<Scalar = BlsScalar>,
+    {
+        assert_cipher_width(perm.width());
+
+        let mut message = [BlsScalar::zero(); MESSAGE_CAPACITY];
+
+        let mut state = Self::initial_state(secret, *nonce);
+        perm.permute(&mut state);
+
+        (0..MESSAGE_CAPACITY).for_each(|i| {
+            message[i] = self.cipher[i] - state[i + 1];
+            state[i + 1] = self.cipher[i];
+        });
+
+        perm.permute(&mut state);
+
+        (self.cipher[MESSAGE_CAPACITY] == state[1]).then_some(message)
+    }
+
+    /// Decrypt the cipher with the given `secret` and `nonce`, as with
+    /// [`PoseidonCipher::decrypt`].
+    ///
+    /// Return [`Error::DecryptionFailed`] if the cipher was tampered with, or
+    /// if it wasn't encrypted with them.
+    pub fn try_decrypt(
+        &self,
+        secret: &JubJubAffine,
+        nonce: &BlsScalar,
+    ) -> Result<[BlsScalar; MESSAGE_CAPACITY], Error> {
+        self.decrypt(secret, nonce).ok_or(Error::DecryptionFailed)
+    }
+}
+
+/// Assert that a permutation of `width` elements permutes the state of the
+/// cipher.
+fn assert_cipher_width(width: usize) {
+    assert_eq!(
+        width, WIDTH,
+        "the permutation should permute the state of the cipher"
+    );
+}
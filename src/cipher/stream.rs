@@ -0,0 +1,99 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::Error;
+use crate::sponge::duplex::Duplex;
+
+use bls12_381::Scalar as BlsScalar;
+use jubjub::AffinePoint as JubJubAffine;
+
+/// Domain of the duplex sponge of the [`keystream`].
+const STREAM_DOMAIN: u64 = 0x100000002;
+
+/// Return `len` scalars of the keystream of the given `secret` and `nonce`.
+///
+/// The keystream is squeezed out of a duplex sponge keyed with the secret,
+/// the nonce and the length of the stream, so streams of different lengths
+/// are independent. It's the one [`encapsulate`] adds to a message of `len`
+/// scalars.
+pub fn keystream(
+    secret: &JubJubAffine,
+    nonce: &BlsScalar,
+    len: usize,
+) -> Vec<BlsScalar> {
+    let mut stream = vec![BlsScalar::zero(); len];
+    keyed(secret, nonce, len).squeeze_into(&mut stream);
+
+    stream
+}
+
+/// Encrypt a `message` of any length with the given `secret` and `nonce`.
+///
+/// Every scalar of the message is added to the scalar of the [`keystream`] at
+/// the same position. The encrypted scalars are then absorbed back into the
+/// sponge, and a tag authenticating them is appended to the cipher, which is
+/// one scalar longer than the message.
+pub fn encapsulate(
+    message: &[BlsScalar],
+    secret: &JubJubAffine,
+    nonce: &BlsScalar,
+) -> Vec<BlsScalar> {
+    let mut sponge = keyed(secret, nonce, message.len());
+
+    let mut cipher = vec![BlsScalar::zero(); message.len()];
+    sponge.squeeze_into(&mut cipher);
+    cipher.iter_mut().zip(message).for_each(|(c, m)| *c += m);
+
+    sponge.absorb(&cipher);
+    cipher.push(sponge.squeeze());
+
+    cipher
+}
+
+/// Decrypt a `cipher` produced by [`encapsulate`] with the given `secret` and
+/// `nonce`.
+///
+/// Return [`Error::DecryptionFailed`] if the tag of the cipher doesn't
+/// authenticate its scalars under the secret and nonce.
+pub fn decapsulate(
+    cipher: &[BlsScalar],
+    secret: &JubJubAffine,
+    nonce: &BlsScalar,
+) -> Result<Vec<BlsScalar>, Error> {
+    let (tag, cipher) = cipher.split_last().ok_or(Error::DecryptionFailed)?;
+
+    let mut sponge = keyed(secret, nonce, cipher.len());
+
+    let mut message = vec![BlsScalar::zero(); cipher.len()];
+    sponge.squeeze_into(&mut message);
+    message
+        .iter_mut()
+        .zip(cipher)
+        .for_each(|(m, c)| *m = c - *m);
+
+    sponge.absorb(cipher);
+    if sponge.squeeze() != *tag {
+        return Err(Error::DecryptionFailed);
+    }
+
+    Ok(message)
+}
+
+/// Return a duplex sponge keyed for a stream of `len` scalars.
+fn keyed(secret: &JubJubAffine, nonce: &BlsScalar, len: usize) -> Duplex {
+    let mut sponge = Duplex::new(BlsScalar::from(STREAM_DOMAIN));
+    sponge.absorb(&[
+        secret.get_u(),
+        secret.get_v(),
+        *nonce,
+        BlsScalar::from(len as u64),
+    ]);
+
+    sponge
+}
@@ -4,8 +4,8 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use crate::cipher::PoseidonCipher;
-use hades::GadgetStrategy;
+use crate::cipher::{assert_cipher_width, PoseidonCipher};
+use crate::permutation::{Hades, PermutationGadget};
 
 use plonk::prelude::*;
 
@@ -34,16 +34,52 @@ impl PoseidonCipher {
 /// Given a shared secret calculated using any key protocol compatible with bls
 /// and jubjub, perform the encryption of the message.
 ///
-/// The returned set of variables is the cipher text
-pub fn encrypt<C>(
+/// The returned set of variables is the cipher text, equal to the one of
+/// [`PoseidonCipher::encrypt`] for the same message, secret and nonce.
+///
+/// # Panics
+///
+/// If the message is longer than [`PoseidonCipher::capacity`].
+pub fn encrypt_gadget<C>(
+    composer: &mut C,
+    shared_secret: &WitnessPoint,
+    nonce: Witness,
+    message: &[Witness],
+) -> [Witness; PoseidonCipher::cipher_size()]
+where
+    C: Composer,
+{
+    encrypt_gadget_with(composer, &Hades, shared_secret, nonce, message)
+}
+
+/// Perform the encryption of the message as [`encrypt_gadget`] does, with the
+/// permutation `perm`.
+///
+/// The returned set of variables is the cipher text, equal to the one of
+/// [`PoseidonCipher::encrypt_with`] for the same permutation, message, secret
+/// and nonce.
+///
+/// # Panics
+///
+/// If the message is longer than [`PoseidonCipher::capacity`], or if the
+/// width of `perm` doesn't match the state of the cipher.
+pub fn encrypt_gadget_with<C, P>(
     composer: &mut C,
+    perm: &P,
     shared_secret: &WitnessPoint,
     nonce: Witness,
     message: &[Witness],
 ) -> [Witness; PoseidonCipher::cipher_size()]
 where
     C: Composer,
+    P: PermutationGadget,
 {
+    assert_cipher_width(perm.width());
+    assert!(
+        message.len() <= PoseidonCipher::capacity(),
+        "the message doesn't fit in a cipher"
+    );
+
     let ks0 = *shared_secret.x();
     let ks1 = *shared_secret.y();
 
@@ -52,7 +88,7 @@ where
     let mut state =
         PoseidonCipher::initial_state_circuit(composer, ks0, ks1, nonce);
 
-    GadgetStrategy::gadget(composer, &mut state);
+    perm.permute_gadget(composer, &mut state);
 
     (0..PoseidonCipher::capacity()).for_each(|i| {
         let x = if i < message.len() {
@@ -69,7 +105,7 @@ where
         cipher[i] = state[i + 1];
     });
 
-    GadgetStrategy::gadget(composer, &mut state);
+    perm.permute_gadget(composer, &mut state);
     cipher[PoseidonCipher::capacity()] = state[1];
 
     cipher
@@ -78,8 +114,11 @@ where
 /// Given a shared secret calculated using any key protocol compatible with bls
 /// and jubjub, perform the decryption of the cipher.
 ///
-/// The returned set of variables is the original message
-pub fn decrypt<C>(
+/// The returned set of variables is the original message, as returned by
+/// [`PoseidonCipher::decrypt`]. The circuit is unsatisfiable if the cipher
+/// was tampered with, or if it wasn't encrypted with the given secret and
+/// nonce.
+pub fn decrypt_gadget<C>(
     composer: &mut C,
     shared_secret: &WitnessPoint,
     nonce: Witness,
@@ -88,6 +127,31 @@ pub fn decrypt<C>(
 where
     C: Composer,
 {
+    decrypt_gadget_with(composer, &Hades, shared_secret, nonce, cipher)
+}
+
+/// Perform the decryption of the cipher as [`decrypt_gadget`] does, with the
+/// permutation `perm`.
+///
+/// The returned set of variables is the original message, as returned by
+/// [`PoseidonCipher::decrypt_with`] for the same permutation.
+///
+/// # Panics
+///
+/// If the width of `perm` doesn't match the state of the cipher.
+pub fn decrypt_gadget_with<C, P>(
+    composer: &mut C,
+    perm: &P,
+    shared_secret: &WitnessPoint,
+    nonce: Witness,
+    cipher: &[Witness],
+) -> [Witness; PoseidonCipher::capacity()]
+where
+    C: Composer,
+    P: PermutationGadget,
+{
+    assert_cipher_width(perm.width());
+
     let ks0 = *shared_secret.x();
     let ks1 = *shared_secret.y();
 
@@ -95,7 +159,7 @@ where
     let mut state =
         PoseidonCipher::initial_state_circuit(composer, ks0, ks1, nonce);
 
-    GadgetStrategy::gadget(composer, &mut state);
+    perm.permute_gadget(composer, &mut state);
 
     (0..PoseidonCipher::capacity()).for_each(|i| {
         let constraint = Constraint::new()
@@ -109,7 +173,7 @@ where
         state[i + 1] = cipher[i];
     });
 
-    GadgetStrategy::gadget(composer, &mut state);
+    perm.permute_gadget(composer, &mut state);
 
     composer.assert_equal(cipher[PoseidonCipher::capacity()], state[1]);
 
@@ -0,0 +1,106 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! The Poseidon hash of circomlib over the scalar field of BN254.
+//!
+//! circomlib hashes `n` inputs, from `1` to [`MAX_INPUTS`], with a
+//! permutation of width `n + 1` and the parameters of [`bn254::params`] at
+//! the 128 bits security level. Its round structure differs from the one of
+//! [`Params`] in the partial rounds only, which apply the S-box to the first
+//! element of the state instead of the last one.
+//!
+//! The hash isn't a sponge: the state is initialized with a zero followed by
+//! the inputs, without any padding, it's permuted once and the first element
+//! of the state is the output. Hashes of different numbers of inputs are
+//! computed with different permutations.
+//!
+//! [`bn254::params`]: crate::bn254::params
+//! [`Params`]: crate::params::Params
+
+use alloc::vec;
+
+use ff::Field;
+
+use crate::bn254::{self, Bn254Params, Bn254Scalar};
+use crate::params::{Error, SecurityLevel};
+use crate::permutation::Permutation;
+
+/// Maximum number of inputs hashed by circomlib.
+pub const MAX_INPUTS: usize = 16;
+
+/// The Poseidon hash of circomlib for a fixed number of inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircomPoseidon {
+    params: Bn254Params,
+}
+
+impl CircomPoseidon {
+    /// Generate the hash of `inputs` inputs.
+    ///
+    /// Return an error if `inputs` is zero or bigger than [`MAX_INPUTS`].
+    pub fn new(inputs: usize) -> Result<Self, Error> {
+        if inputs == 0 || inputs > MAX_INPUTS {
+            return Err(Error::InvalidWidth(inputs + 1));
+        }
+
+        let params = bn254::params(inputs + 1, SecurityLevel::Bits128)?;
+
+        Ok(Self { params })
+    }
+
+    /// Parameters of the permutation.
+    pub fn params(&self) -> &Bn254Params {
+        &self.params
+    }
+
+    /// Number of inputs of the hash.
+    pub fn inputs(&self) -> usize {
+        self.params.width() - 1
+    }
+
+    /// Hash `inputs` as circomlib does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of inputs doesn't match the one of the hash.
+    pub fn hash(&self, inputs: &[Bn254Scalar]) -> Bn254Scalar {
+        assert_eq!(
+            inputs.len(),
+            self.inputs(),
+            "The number of inputs must match the one of the hash"
+        );
+
+        let mut state = vec![Bn254Scalar::ZERO; self.params.width()];
+        state[1..].copy_from_slice(inputs);
+
+        Permutation::permute(self, &mut state);
+
+        state[0]
+    }
+}
+
+impl Permutation for CircomPoseidon {
+    type Scalar = Bn254Scalar;
+
+    fn width(&self) -> usize {
+        self.params.width()
+    }
+
+    fn permute(&self, state: &mut [Bn254Scalar]) {
+        self.params.permute_with_partial(state, 0);
+    }
+}
+
+/// Hash `inputs` as circomlib does.
+///
+/// The parameters are generated on every call, [`CircomPoseidon`] should be
+/// preferred to hash several times the same number of inputs.
+///
+/// Return an error if the number of inputs is zero or bigger than
+/// [`MAX_INPUTS`].
+pub fn hash(inputs: &[Bn254Scalar]) -> Result<Bn254Scalar, Error> {
+    CircomPoseidon::new(inputs.len()).map(|poseidon| poseidon.hash(inputs))
+}
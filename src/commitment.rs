@@ -0,0 +1,57 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Hiding commitments to scalars.
+//!
+//! A commitment is the hash of a random blinder followed by the committed
+//! values, under a domain of its own, so it can't be confused with the hash
+//! of the same scalars computed for another purpose. The commitment hides the
+//! values as long as the blinder is kept secret, and binds them by the
+//! collision resistance of the sponge.
+
+use alloc::vec::Vec;
+
+use bls12_381::Scalar as BlsScalar;
+
+use crate::sponge;
+
+#[cfg(feature = "alloc")]
+use plonk::prelude::*;
+
+/// Domain of the hash of the commitments.
+const DOMAIN: u64 = 0x100000003;
+
+/// Commit to `values` with the given `blinder`.
+pub fn commit(values: &[BlsScalar], blinder: BlsScalar) -> BlsScalar {
+    let mut input = Vec::with_capacity(values.len() + 1);
+
+    input.push(blinder);
+    input.extend_from_slice(values);
+
+    sponge::hash_with_domain(DOMAIN, &input)
+}
+
+/// Mirror the implementation of [`commit`] inside of a PLONK circuit.
+///
+/// The returned witness is the commitment to `values`, so asserting it equals
+/// a public commitment proves the knowledge of its opening. The number of
+/// values is part of the circuit description.
+#[cfg(feature = "alloc")]
+pub fn gadget<C>(
+    composer: &mut C,
+    values: &[Witness],
+    blinder: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    let mut input = Vec::with_capacity(values.len() + 1);
+
+    input.push(blinder);
+    input.extend_from_slice(values);
+
+    sponge::gadget_with_domain(composer, DOMAIN, &input)
+}
@@ -0,0 +1,38 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Extension of the PLONK [`Composer`] with the gadgets of this crate.
+//!
+//! The methods of [`PoseidonComposer`] forward to the free functions of the
+//! [`sponge`] and [`tree`] modules, and produce the same circuits.
+
+use crate::sponge;
+use crate::tree::{self, PoseidonBranch};
+
+use plonk::prelude::*;
+
+/// Poseidon gadgets callable as methods of a [`Composer`].
+///
+/// The trait is implemented for every composer, so it only needs to be in
+/// scope to be used.
+pub trait PoseidonComposer: Composer {
+    /// Hash `messages` with the sponge, as with [`sponge::gadget`].
+    fn poseidon_hash(&mut self, messages: &[Witness]) -> Witness {
+        sponge::gadget(self, messages)
+    }
+
+    /// Perform the merkle opening of `leaf` against `branch`, returning the
+    /// computed root, as with [`tree::merkle_opening`].
+    fn merkle_opening<const DEPTH: usize>(
+        &mut self,
+        branch: &PoseidonBranch<DEPTH>,
+        leaf: Witness,
+    ) -> Witness {
+        tree::merkle_opening(self, branch, leaf)
+    }
+}
+
+impl<C: Composer> PoseidonComposer for C {}
@@ -0,0 +1,129 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Jive compression mode.
+//!
+//! Jive compresses a fixed number of inputs with a single application of the
+//! permutation, returning the sum of every element of the state before and
+//! after the permutation. This is cheaper than the sponge construction, which
+//! needs an additional permutation for the padding whenever the number of
+//! inputs fills the rate, and is well suited for the nodes of a merkle tree.
+//!
+//! The first element of the state is set to the number of inputs, so the
+//! outputs of [`jive2`] and [`jive4`] never collide with each other.
+//! [`jive_with`] compresses any number of inputs fitting next to the first
+//! element with any [`Permutation`], over the field of the permutation.
+
+use alloc::vec;
+
+use bls12_381::Scalar as BlsScalar;
+use hades::{ScalarStrategy, Strategy, WIDTH};
+
+use crate::permutation::Permutation;
+
+#[cfg(feature = "alloc")]
+use hades::GadgetStrategy;
+#[cfg(feature = "alloc")]
+use plonk::prelude::*;
+
+/// Compress two scalars into one with the Jive mode.
+pub fn jive2(inputs: &[BlsScalar; 2]) -> BlsScalar {
+    jive(inputs)
+}
+
+/// Compress four scalars into one with the Jive mode.
+pub fn jive4(inputs: &[BlsScalar; 4]) -> BlsScalar {
+    jive(inputs)
+}
+
+fn jive(inputs: &[BlsScalar]) -> BlsScalar {
+    let mut state = [BlsScalar::zero(); WIDTH];
+
+    state[0] = BlsScalar::from(inputs.len() as u64);
+    state[1..=inputs.len()].copy_from_slice(inputs);
+
+    let input = state;
+    ScalarStrategy::new().perm(&mut state);
+
+    input
+        .iter()
+        .zip(state.iter())
+        .fold(BlsScalar::zero(), |acc, (i, o)| acc + i + o)
+}
+
+/// Compress the scalars into one with the Jive mode, as [`jive2`] and
+/// [`jive4`] do, using the permutation `perm`.
+///
+/// # Panics
+///
+/// Panics if the inputs don't fit in the state of `perm` next to its first
+/// element.
+pub fn jive_with<P>(perm: &P, inputs: &[P::Scalar]) -> P::Scalar
+where
+    P: Permutation,
+{
+    assert!(
+        inputs.len() < perm.width(),
+        "the inputs should fit next to the first element of the state"
+    );
+
+    let mut state = vec![P::Scalar::ZERO; perm.width()];
+
+    state[0] = P::Scalar::from(inputs.len() as u64);
+    state[1..=inputs.len()].copy_from_slice(inputs);
+
+    let input = state.clone();
+    perm.permute(&mut state);
+
+    input
+        .iter()
+        .zip(state.iter())
+        .fold(P::Scalar::ZERO, |acc, (i, o)| acc + i + o)
+}
+
+/// Mirror the implementation of [`jive2`] inside of a PLONK circuit.
+#[cfg(feature = "alloc")]
+pub fn jive2_gadget<C>(composer: &mut C, inputs: &[Witness; 2]) -> Witness
+where
+    C: Composer,
+{
+    jive_gadget(composer, inputs)
+}
+
+/// Mirror the implementation of [`jive4`] inside of a PLONK circuit.
+#[cfg(feature = "alloc")]
+pub fn jive4_gadget<C>(composer: &mut C, inputs: &[Witness; 4]) -> Witness
+where
+    C: Composer,
+{
+    jive_gadget(composer, inputs)
+}
+
+#[cfg(feature = "alloc")]
+fn jive_gadget<C>(composer: &mut C, inputs: &[Witness]) -> Witness
+where
+    C: Composer,
+{
+    let mut state = [C::ZERO; WIDTH];
+
+    state[0] = composer.append_constant(BlsScalar::from(inputs.len() as u64));
+    state[1..=inputs.len()].copy_from_slice(inputs);
+
+    let input = state;
+    GadgetStrategy::gadget(composer, &mut state);
+
+    input.iter().zip(state.iter()).fold(C::ZERO, |acc, (i, o)| {
+        let constraint = Constraint::new()
+            .left(1)
+            .a(acc)
+            .right(1)
+            .b(*i)
+            .fourth(1)
+            .d(*o);
+
+        composer.gate_add(constraint)
+    })
+}
@@ -0,0 +1,147 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Number of constraints appended to a circuit by the gadgets of this crate.
+//!
+//! The counts are computed from the structure of the gadgets, without
+//! building any circuit, so they can be used to size the `PublicParameters`
+//! of a circuit before compiling it. Every gate counts as a constraint,
+//! including the ones of the public inputs and of the equality assertions,
+//! while appending a witness doesn't.
+
+use hades::WIDTH;
+
+use crate::params::Params;
+
+/// Number of full rounds of the `Hades` permutation.
+const FULL_ROUNDS: usize = 8;
+
+/// Number of partial rounds of the `Hades` permutation.
+const PARTIAL_ROUNDS: usize = 59;
+
+/// Number of constraints of a quintic s-box, one per multiplication.
+const S_BOX: usize = 3;
+
+/// Number of constraints of a level of a merkle opening, besides its
+/// permutation: a boolean and a sum constraint per offset bit, the check of
+/// the sum of the bits, and two multiplications and an equality per node.
+const OPENING_LEVEL: usize = 2 * (WIDTH - 1) + 1 + 3 * (WIDTH - 1);
+
+/// Return the number of constraints of a permutation of the `Hades` strategy.
+///
+/// The round constants are added to the state once, before the first round,
+/// and then folded into the linear layer, which takes two constraints per
+/// element of the state.
+pub const fn permutation() -> usize {
+    WIDTH
+        + FULL_ROUNDS * WIDTH * S_BOX
+        + PARTIAL_ROUNDS * S_BOX
+        + (FULL_ROUNDS + PARTIAL_ROUNDS) * 2 * WIDTH
+}
+
+/// Return the number of constraints of [`Params::gadget`].
+///
+/// Every round adds the round constants and multiplies by the MDS matrix
+/// with a constraint per element of the state and of the matrix respectively.
+pub const fn params_permutation(params: &Params) -> usize {
+    let width = params.width();
+    let full_rounds = params.full_rounds();
+    let partial_rounds = params.partial_rounds();
+
+    (full_rounds + partial_rounds) * (width + width * width)
+        + (full_rounds * width + partial_rounds) * S_BOX
+}
+
+/// Return the number of constraints of [`sponge::gadget`] for a message of
+/// `input_len` scalars.
+///
+/// [`sponge::gadget`]: crate::sponge::gadget
+pub const fn sponge(input_len: usize) -> usize {
+    sponge_with(input_len, WIDTH, permutation())
+}
+
+/// Return the number of constraints of [`sponge::gadget_with_params`] for a
+/// message of `input_len` scalars.
+///
+/// [`sponge::gadget_with_params`]: crate::sponge::gadget_with_params
+pub const fn sponge_with_params(params: &Params, input_len: usize) -> usize {
+    sponge_with(input_len, params.width(), params_permutation(params))
+}
+
+/// Return the number of constraints of the sponge over a state of `width`
+/// elements, for a permutation of `permutation` constraints.
+///
+/// Every scalar of the message is absorbed with a constraint, except for the
+/// ones of the first chunk, which are absorbed into the zero state, and the
+/// padding is absorbed with a constraint as well. A permutation is applied
+/// for every full chunk of the message, and for the last chunk holding the
+/// padding.
+const fn sponge_with(
+    input_len: usize,
+    width: usize,
+    permutation: usize,
+) -> usize {
+    if input_len == 0 {
+        return 0;
+    }
+
+    input_len.saturating_sub(width - 1)
+        + 1
+        + (input_len / (width - 1) + 1) * permutation
+}
+
+/// Return the number of constraints of [`tree::merkle_opening`] for a branch
+/// of the given `depth`.
+///
+/// This is also the number of constraints of [`tree::dyn_merkle_opening`].
+///
+/// [`tree::merkle_opening`]: crate::tree::merkle_opening
+/// [`tree::dyn_merkle_opening`]: crate::tree::dyn_merkle_opening
+pub const fn merkle_opening(depth: usize) -> usize {
+    depth * (OPENING_LEVEL + permutation())
+}
+
+/// Return the number of constraints of [`tree::merkle_opening_public`] for a
+/// branch of the given `depth`.
+///
+/// [`tree::merkle_opening_public`]: crate::tree::merkle_opening_public
+pub const fn merkle_opening_public(depth: usize) -> usize {
+    merkle_opening(depth) + 2
+}
+
+/// Return the number of constraints of [`tree::merkle_opening_with_pos`] for
+/// a branch of the given `depth`.
+///
+/// [`tree::merkle_opening_with_pos`]: crate::tree::merkle_opening_with_pos
+pub const fn merkle_opening_with_pos(depth: usize) -> usize {
+    merkle_opening(depth) + depth * (WIDTH - 2)
+}
+
+/// Return the number of constraints of [`tree::merkle_opening_preimage`] for
+/// a branch of the given `depth` and a preimage of `preimage_len` scalars.
+///
+/// [`tree::merkle_opening_preimage`]: crate::tree::merkle_opening_preimage
+pub const fn merkle_opening_preimage(
+    depth: usize,
+    preimage_len: usize,
+) -> usize {
+    sponge(preimage_len) + merkle_opening(depth)
+}
+
+/// Return the number of constraints of [`tree::merkle_openings`] for
+/// `openings` branches of the given `depth`.
+///
+/// The top level is hashed once for all the openings, saving a permutation
+/// for every opening after the first.
+///
+/// [`tree::merkle_openings`]: crate::tree::merkle_openings
+pub const fn merkle_openings(depth: usize, openings: usize) -> usize {
+    if depth == 0 || openings == 0 {
+        return 0;
+    }
+
+    openings * merkle_opening(depth) - (openings - 1) * permutation()
+}
@@ -0,0 +1,116 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! UniFFI bindings for Swift and Kotlin.
+//!
+//! Scalars cross the boundary as their 32 canonical little-endian bytes, and
+//! branches with the layout of their `bytes::Serializable` implementation: the
+//! levels of the path, from the leaf up to the root, followed by the root.
+//! Since the depth of a branch can't be generic over the FFI, it is inferred
+//! from the length of its bytes.
+//!
+//! The bindings are generated with `uniffi-bindgen` from the crate compiled as
+//! a `cdylib` with the `uniffi` feature enabled.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::{DeserializableSlice, Serializable};
+use hades::{ScalarStrategy, Strategy, WIDTH};
+
+use crate::sponge;
+
+/// Size of a serialized level: its scalars followed by its `u64` offset.
+const LEVEL_SIZE: usize = BlsScalar::SIZE * WIDTH + u64::SIZE;
+
+/// Errors returned by the bindings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum FfiError {
+    /// A scalar is not correctly encoded.
+    InvalidScalar,
+    /// A branch is not correctly encoded.
+    InvalidBranch,
+}
+
+impl fmt::Display for FfiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidScalar => f.write_str("invalid scalar"),
+            Self::InvalidBranch => f.write_str("invalid branch"),
+        }
+    }
+}
+
+impl std::error::Error for FfiError {}
+
+fn scalar(bytes: &[u8]) -> Result<BlsScalar, FfiError> {
+    BlsScalar::from_slice(bytes).map_err(|_| FfiError::InvalidScalar)
+}
+
+fn scalars(messages: &[Vec<u8>]) -> Result<Vec<BlsScalar>, FfiError> {
+    messages.iter().map(|m| scalar(m)).collect()
+}
+
+/// Hash the `messages` with [`sponge::hash`].
+#[uniffi::export]
+pub fn poseidon_hash(messages: Vec<Vec<u8>>) -> Result<Vec<u8>, FfiError> {
+    let messages = scalars(&messages)?;
+    Ok(sponge::hash(&messages).to_bytes().to_vec())
+}
+
+/// Hash the `messages` with [`sponge::truncated::hash`].
+#[uniffi::export]
+pub fn poseidon_truncated_hash(
+    messages: Vec<Vec<u8>>,
+) -> Result<Vec<u8>, FfiError> {
+    let messages = scalars(&messages)?;
+    Ok(sponge::truncated::hash(&messages).to_bytes().to_vec())
+}
+
+/// Check that `branch` is a valid opening of `leaf` against `root`.
+#[uniffi::export]
+pub fn poseidon_verify_branch(
+    branch: Vec<u8>,
+    root: Vec<u8>,
+    leaf: Vec<u8>,
+) -> Result<bool, FfiError> {
+    if branch.len() < BlsScalar::SIZE
+        || (branch.len() - BlsScalar::SIZE) % LEVEL_SIZE != 0
+    {
+        return Err(FfiError::InvalidBranch);
+    }
+
+    let root = scalar(&root)?;
+    let (path, branch_root) = branch.split_at(branch.len() - BlsScalar::SIZE);
+    let branch_root = BlsScalar::from_slice(branch_root)
+        .map_err(|_| FfiError::InvalidBranch)?;
+
+    let mut needle = scalar(&leaf)?;
+    for level in path.chunks(LEVEL_SIZE) {
+        let mut bytes = level;
+
+        let mut state = [BlsScalar::zero(); WIDTH];
+        for s in state.iter_mut() {
+            *s = BlsScalar::from_reader(&mut bytes)
+                .map_err(|_| FfiError::InvalidBranch)?;
+        }
+        let offset = u64::from_reader(&mut bytes)
+            .map_err(|_| FfiError::InvalidBranch)?;
+        let offset =
+            usize::try_from(offset).map_err(|_| FfiError::InvalidBranch)?;
+
+        if offset == 0 || offset >= WIDTH || state[offset] != needle {
+            return Ok(false);
+        }
+
+        ScalarStrategy::new().perm(&mut state);
+        needle = state[1];
+    }
+
+    Ok(needle == root && branch_root == root)
+}
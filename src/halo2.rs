@@ -0,0 +1,514 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! A Halo2 chip for the Poseidon permutation over the BLS12-381 scalar field.
+//!
+//! [`PoseidonChip`] mirrors the sponge and the merkle openings of this crate
+//! instantiated with a set of [`Params`] of width `W`: [`PoseidonChip::hash`]
+//! computes the same hash as [`sponge::hash_with_params`], and
+//! [`PoseidonChip::merkle_opening`] recomputes the same root as
+//! [`PoseidonBranch::verify_with`], so the same trees and branches can be
+//! consumed by a Halo2 circuit. The constants of the `Hades` permutation
+//! aren't exported by the hades crate, so the default instantiation is
+//! reproduced by loading its round constants and MDS matrix with
+//! [`Params::new`].
+//!
+//! The columns of the chip don't depend on the parameters: every round of a
+//! permutation takes a row, with the round constants and the MDS matrix
+//! assigned to fixed columns, so any parameters of width `W` can be used with
+//! the same configuration.
+//!
+//! [`sponge::hash_with_params`]: crate::sponge::hash_with_params
+
+use alloc::vec::Vec;
+use core::array;
+
+use bls12_381::Scalar as BlsScalar;
+use halo2_proofs::circuit::{AssignedCell, Chip, Layouter, Value};
+use halo2_proofs::plonk::{
+    Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector,
+};
+use halo2_proofs::poly::Rotation;
+
+use crate::params::Params;
+use crate::permutation::assert_sponge_width;
+use crate::tree::{assert_tree_width, PoseidonBranch};
+
+/// A cell holding a scalar of a circuit.
+pub type Cell = AssignedCell<BlsScalar, BlsScalar>;
+
+/// Columns and selectors of a [`PoseidonChip`] of width `W`.
+#[derive(Debug, Clone)]
+pub struct PoseidonConfig<const W: usize> {
+    state: [Column<Advice>; W],
+    round_constants: [Column<Fixed>; W],
+    mds: [[Column<Fixed>; W]; W],
+    s_full: Selector,
+    s_partial: Selector,
+    s_add: Selector,
+    s_level: Selector,
+}
+
+/// A chip computing the Poseidon permutation of width `W`, along with the
+/// sponge and the merkle openings built on it.
+#[derive(Debug, Clone)]
+pub struct PoseidonChip<const W: usize> {
+    config: PoseidonConfig<W>,
+}
+
+impl<const W: usize> Chip<BlsScalar> for PoseidonChip<W> {
+    type Config = PoseidonConfig<W>;
+    type Loaded = ();
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    fn loaded(&self) -> &Self::Loaded {
+        &()
+    }
+}
+
+impl<const W: usize> PoseidonChip<W> {
+    /// Create a chip from its configuration.
+    pub const fn construct(config: PoseidonConfig<W>) -> Self {
+        Self { config }
+    }
+
+    /// Configure the columns, the selectors and the gates of the chip.
+    ///
+    /// A fixed column is enabled for the constants of the circuit, the zero
+    /// state of the sponge and its padding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `W` is smaller than `2`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<BlsScalar>,
+    ) -> PoseidonConfig<W> {
+        assert_sponge_width(W);
+
+        let state = array::from_fn(|_| meta.advice_column());
+        let round_constants = array::from_fn(|_| meta.fixed_column());
+        let mds = array::from_fn(|_| array::from_fn(|_| meta.fixed_column()));
+
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        state
+            .iter()
+            .for_each(|column| meta.enable_equality(*column));
+
+        let s_full = meta.selector();
+        let s_partial = meta.selector();
+        let s_add = meta.selector();
+        let s_level = meta.selector();
+
+        // Every round adds the round constants to the state, applies the
+        // S-box to the whole state or to its last element only, and
+        // multiplies it by the MDS matrix into the next row
+        for (name, selector, full) in [
+            ("full round", s_full, true),
+            ("partial round", s_partial, false),
+        ] {
+            meta.create_gate(name, |meta| {
+                let s = meta.query_selector(selector);
+
+                let mut words = Vec::with_capacity(W);
+                let columns = state.iter().zip(round_constants.iter());
+                for (j, (column, constant)) in columns.enumerate() {
+                    let word = meta.query_advice(*column, Rotation::cur())
+                        + meta.query_fixed(*constant, Rotation::cur());
+                    if full || j == W - 1 {
+                        words.push(quintic_s_box(word));
+                    } else {
+                        words.push(word);
+                    }
+                }
+
+                let mut constraints = Vec::with_capacity(W);
+                for (row, column) in mds.iter().zip(state.iter()) {
+                    let mut product = Expression::Constant(BlsScalar::zero());
+                    for (m, word) in row.iter().zip(words.iter()) {
+                        let m = meta.query_fixed(*m, Rotation::cur());
+                        product = product + m * word.clone();
+                    }
+
+                    let next = meta.query_advice(*column, Rotation::next());
+                    constraints.push(s.clone() * (next - product));
+                }
+
+                constraints
+            });
+        }
+
+        // The state of the first row and the message of the second one are
+        // summed into the third row
+        meta.create_gate("absorb", |meta| {
+            let s = meta.query_selector(s_add);
+
+            state
+                .iter()
+                .map(|column| {
+                    let a = meta.query_advice(*column, Rotation::cur());
+                    let b = meta.query_advice(*column, Rotation::next());
+                    let c = meta.query_advice(*column, Rotation(2));
+
+                    s.clone() * (c - a - b)
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // The children of a level are in the first row, and the node opened
+        // from the level below is in the first column of the second row,
+        // followed by the bits selecting its offset
+        meta.create_gate("level", |meta| {
+            let s = meta.query_selector(s_level);
+            let one = Expression::Constant(BlsScalar::one());
+            let node = meta.query_advice(state[0], Rotation::next());
+
+            let mut constraints = Vec::with_capacity(2 * W - 1);
+            let mut sum = Expression::Constant(BlsScalar::zero());
+            for column in &state[1..] {
+                let child = meta.query_advice(*column, Rotation::cur());
+                let bit = meta.query_advice(*column, Rotation::next());
+
+                constraints.push(
+                    s.clone() * bit.clone() * (one.clone() - bit.clone()),
+                );
+                constraints
+                    .push(s.clone() * bit.clone() * (child - node.clone()));
+                sum = sum + bit;
+            }
+            constraints.push(s * (sum - one));
+
+            constraints
+        });
+
+        PoseidonConfig {
+            state,
+            round_constants,
+            mds,
+            s_full,
+            s_partial,
+            s_add,
+            s_level,
+        }
+    }
+
+    /// Apply the permutation defined by `params` to `state`, returning the
+    /// permuted state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the width of `params` isn't `W`.
+    pub fn permute(
+        &self,
+        mut layouter: impl Layouter<BlsScalar>,
+        params: &Params,
+        state: &[Cell; W],
+    ) -> Result<[Cell; W], Error> {
+        assert_eq!(
+            params.width(),
+            W,
+            "The state must match the width of the parameters"
+        );
+
+        let config = &self.config;
+        let rounds = params.full_rounds() + params.partial_rounds();
+
+        layouter.assign_region(
+            || "permutation",
+            |mut region| {
+                let mut cells = state
+                    .iter()
+                    .zip(config.state.iter())
+                    .map(|(cell, column)| {
+                        cell.copy_advice(|| "input", &mut region, *column, 0)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                for round in 0..rounds {
+                    let constants = &params.round_constants()[round * W..];
+                    for (column, c) in
+                        config.round_constants.iter().zip(constants)
+                    {
+                        region.assign_fixed(
+                            || "round constant",
+                            *column,
+                            round,
+                            || Value::known(*c),
+                        )?;
+                    }
+
+                    let rows = config.mds.iter().zip(params.mds().chunks(W));
+                    for (columns, row) in rows {
+                        for (column, m) in columns.iter().zip(row) {
+                            region.assign_fixed(
+                                || "mds",
+                                *column,
+                                round,
+                                || Value::known(*m),
+                            )?;
+                        }
+                    }
+
+                    if params.is_full_round(round) {
+                        config.s_full.enable(&mut region, round)?;
+                    } else {
+                        config.s_partial.enable(&mut region, round)?;
+                    }
+
+                    let values: Value<Vec<BlsScalar>> = cells
+                        .iter()
+                        .map(|cell| cell.value().copied())
+                        .collect();
+                    let values = values.map(|mut values| {
+                        let mut product = [BlsScalar::zero(); W];
+                        params.round(round, &mut values, &mut product);
+                        values
+                    });
+
+                    cells = config
+                        .state
+                        .iter()
+                        .enumerate()
+                        .map(|(i, column)| {
+                            region.assign_advice(
+                                || "state",
+                                *column,
+                                round + 1,
+                                || values.as_ref().map(|values| values[i]),
+                            )
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                }
+
+                Ok(array::from_fn(|i| cells[i].clone()))
+            },
+        )
+    }
+
+    /// Mirror [`sponge::hash_with_params`] with the chip, returning the cell
+    /// of the hash of `messages`.
+    ///
+    /// The circuit is defined by the length of `messages`, as with the PLONK
+    /// [`sponge::gadget`], and the padding is a constant of the circuit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the width of `params` isn't `W`.
+    ///
+    /// [`sponge::hash_with_params`]: crate::sponge::hash_with_params
+    /// [`sponge::gadget`]: crate::sponge::gadget
+    pub fn hash(
+        &self,
+        mut layouter: impl Layouter<BlsScalar>,
+        params: &Params,
+        messages: &[Cell],
+    ) -> Result<Cell, Error> {
+        let config = &self.config;
+
+        let mut state = layouter.assign_region(
+            || "initial state",
+            |mut region| {
+                let cells = config
+                    .state
+                    .iter()
+                    .map(|column| {
+                        region.assign_advice_from_constant(
+                            || "zero",
+                            *column,
+                            0,
+                            BlsScalar::zero(),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(array::from_fn(|i| cells[i].clone()))
+            },
+        )?;
+
+        let l = messages.len();
+        let m = l / (W - 1);
+        let n = m * (W - 1);
+        let last_iteration = if l == n {
+            m.saturating_sub(1)
+        } else {
+            l / (W - 1)
+        };
+
+        for (i, chunk) in messages.chunks(W - 1).enumerate() {
+            let last = i == last_iteration;
+
+            // The padding follows the last chunk if there is room for it,
+            // and is absorbed on its own after an extra permutation otherwise
+            let padding = (last && chunk.len() < W - 1).then_some(chunk.len());
+            state = self.absorb(
+                layouter.namespace(|| "absorb"),
+                &state,
+                chunk,
+                padding,
+            )?;
+
+            if last && chunk.len() == W - 1 {
+                state = self.permute(
+                    layouter.namespace(|| "permutation"),
+                    params,
+                    &state,
+                )?;
+                state = self.absorb(
+                    layouter.namespace(|| "padding"),
+                    &state,
+                    &[],
+                    Some(0),
+                )?;
+            }
+
+            state = self.permute(
+                layouter.namespace(|| "permutation"),
+                params,
+                &state,
+            )?;
+        }
+
+        Ok(state[1].clone())
+    }
+
+    /// Mirror [`PoseidonBranch::verify_with`] with the chip, returning the
+    /// cell of the root recomputed from `leaf`.
+    ///
+    /// The levels of the branch are assigned as witnesses, and every level
+    /// checks that the node computed from the level below is found at its
+    /// offset, as the PLONK [`merkle_opening`] does. The caller is expected
+    /// to constrain the returned root, usually to an instance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the width of `params` isn't `W`, or if `W` isn't one more
+    /// than the arity of the tree.
+    ///
+    /// [`merkle_opening`]: crate::tree::merkle_opening
+    pub fn merkle_opening<const DEPTH: usize>(
+        &self,
+        mut layouter: impl Layouter<BlsScalar>,
+        params: &Params,
+        branch: &PoseidonBranch<DEPTH>,
+        leaf: &Cell,
+    ) -> Result<Cell, Error> {
+        assert_tree_width(W);
+
+        let config = &self.config;
+
+        let mut node = leaf.clone();
+        for level in branch.as_ref() {
+            let children = layouter.assign_region(
+                || "level",
+                |mut region| {
+                    config.s_level.enable(&mut region, 0)?;
+                    node.copy_advice(
+                        || "node",
+                        &mut region,
+                        config.state[0],
+                        1,
+                    )?;
+
+                    let mut cells = Vec::with_capacity(W);
+                    let nodes = config.state.iter().zip(level.as_ref());
+                    for (i, (column, child)) in nodes.enumerate() {
+                        cells.push(region.assign_advice(
+                            || "child",
+                            *column,
+                            0,
+                            || Value::known(*child),
+                        )?);
+
+                        if i > 0 {
+                            let bit = (i == level.offset() + 1) as u64;
+                            region.assign_advice(
+                                || "offset bit",
+                                *column,
+                                1,
+                                || Value::known(BlsScalar::from(bit)),
+                            )?;
+                        }
+                    }
+
+                    Ok(array::from_fn(|i| cells[i].clone()))
+                },
+            )?;
+
+            let state = self.permute(
+                layouter.namespace(|| "level permutation"),
+                params,
+                &children,
+            )?;
+            node = state[1].clone();
+        }
+
+        Ok(node)
+    }
+
+    /// Add the message `chunk` to the rate of `state`, along with the padding
+    /// after the message element of offset `padding`.
+    fn absorb(
+        &self,
+        mut layouter: impl Layouter<BlsScalar>,
+        state: &[Cell; W],
+        chunk: &[Cell],
+        padding: Option<usize>,
+    ) -> Result<[Cell; W], Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "absorb",
+            |mut region| {
+                config.s_add.enable(&mut region, 0)?;
+
+                let mut sums = Vec::with_capacity(W);
+                let columns = config.state.iter().zip(state.iter());
+                for (i, (column, cell)) in columns.enumerate() {
+                    let s =
+                        cell.copy_advice(|| "state", &mut region, *column, 0)?;
+
+                    let message = i.checked_sub(1).and_then(|j| chunk.get(j));
+                    let m = match message {
+                        Some(m) => m.copy_advice(
+                            || "message",
+                            &mut region,
+                            *column,
+                            1,
+                        )?,
+                        None => {
+                            let pad = padding.map(|p| p + 1) == Some(i);
+                            region.assign_advice_from_constant(
+                                || "padding",
+                                *column,
+                                1,
+                                BlsScalar::from(pad as u64),
+                            )?
+                        }
+                    };
+
+                    let sum = s.value().zip(m.value()).map(|(s, m)| *s + *m);
+                    sums.push(region.assign_advice(
+                        || "sum",
+                        *column,
+                        2,
+                        || sum,
+                    )?);
+                }
+
+                Ok(array::from_fn(|i| sums[i].clone()))
+            },
+        )
+    }
+}
+
+fn quintic_s_box(word: Expression<BlsScalar>) -> Expression<BlsScalar> {
+    let word2 = word.clone() * word.clone();
+
+    word2.clone() * word2 * word
+}
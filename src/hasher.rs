@@ -0,0 +1,78 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Poseidon [`Hasher`] for the `rs_merkle` crate.
+//!
+//! Leaves are hashed with the sponge [`hash`](crate::sponge::hash) of their
+//! bytes, and the inner nodes of the tree with the [`jive2`] 2-to-1
+//! compression of their children. Digests are the canonical little-endian
+//! bytes of the resulting scalars.
+
+use alloc::vec::Vec;
+
+use bls12_381::Scalar as BlsScalar;
+use rs_merkle::Hasher;
+
+use crate::compress::jive2;
+use crate::sponge::{self, byte_scalars};
+
+/// Poseidon hasher for `rs_merkle` trees.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PoseidonHasher;
+
+impl PoseidonHasher {
+    fn scalar(hash: &[u8; 32]) -> BlsScalar {
+        let mut wide = [0u8; 64];
+        wide[..32].copy_from_slice(hash);
+
+        BlsScalar::from_bytes_wide(&wide)
+    }
+}
+
+impl Hasher for PoseidonHasher {
+    type Hash = [u8; 32];
+
+    fn hash(data: &[u8]) -> Self::Hash {
+        let scalars: Vec<BlsScalar> = byte_scalars(data).collect();
+        sponge::hash(&scalars).to_bytes()
+    }
+
+    fn concat_and_hash(
+        left: &Self::Hash,
+        right: Option<&Self::Hash>,
+    ) -> Self::Hash {
+        match right {
+            Some(right) => {
+                jive2(&[Self::scalar(left), Self::scalar(right)]).to_bytes()
+            }
+            None => *left,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rs_merkle::MerkleTree;
+
+    #[test]
+    fn merkle_proof() {
+        let leaves: Vec<[u8; 32]> =
+            (0..11u8).map(|i| PoseidonHasher::hash(&[i; 45])).collect();
+
+        let tree = MerkleTree::<PoseidonHasher>::from_leaves(&leaves);
+        let root = tree.root().expect("The tree should have a root");
+
+        let indices = [2, 3, 9];
+        let proof = tree.proof(&indices);
+        let proven: Vec<[u8; 32]> =
+            indices.iter().map(|&i| leaves[i]).collect();
+
+        assert!(proof.verify(root, &indices, &proven, leaves.len()));
+        assert!(!proof.verify(root, &indices, &leaves[..3], leaves.len()));
+    }
+}
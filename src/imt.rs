@@ -0,0 +1,337 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Indexed merkle tree holding a set of scalars, such as nullifiers.
+//!
+//! Every leaf holds a value of the set along with the index and the value of
+//! the next larger value, so the leaves form a linked list sorted by value. A
+//! value is proven to be absent from the set by opening the leaf of the
+//! largest smaller value, its low leaf, and showing that the value sits
+//! between the low leaf and the next one.
+//!
+//! The leaf at index zero holds the value zero, and the last leaf of the list
+//! points to [`MAX_VALUE`]. The values of the set must therefore lie strictly
+//! between zero and [`MAX_VALUE`], which holds for the outputs of
+//! [`sponge::truncated::hash`](crate::sponge::truncated::hash).
+//!
+//! The leaves are stored in a [`SparseMerkleTree`] keyed by their index, with
+//! the hash of the leaf as value.
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod zk;
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use zk::{insertion, non_membership};
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use bls12_381::Scalar as BlsScalar;
+
+use crate::smt::{SmtOpening, SparseMerkleTree};
+use crate::sponge;
+
+/// Number of bits of the values of the set.
+pub const VALUE_BITS: usize = 252;
+
+/// Upper bound of the values of the set, equal to `2^252`.
+pub const MAX_VALUE: BlsScalar = BlsScalar::from_raw([0, 0, 0, 1 << 60]);
+
+/// Errors that can occur while inserting into an [`IndexedMerkleTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The value is not smaller than [`MAX_VALUE`].
+    OutOfRange,
+    /// The value is already in the set.
+    Duplicate,
+    /// Every leaf of the tree is taken.
+    Full,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange => write!(f, "the value is out of range"),
+            Self::Duplicate => write!(f, "the value is already in the set"),
+            Self::Full => write!(f, "the tree is full"),
+        }
+    }
+}
+
+/// Return the bytes of `value` ordered as its integer representation.
+fn ordered(value: &BlsScalar) -> [u8; 32] {
+    let mut bytes = value.to_bytes();
+    bytes.reverse();
+    bytes
+}
+
+/// Leaf of an [`IndexedMerkleTree`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IndexedLeaf {
+    value: BlsScalar,
+    next_index: u64,
+    next_value: BlsScalar,
+}
+
+impl IndexedLeaf {
+    /// Value held by the leaf.
+    pub const fn value(&self) -> &BlsScalar {
+        &self.value
+    }
+
+    /// Index of the leaf holding the next larger value.
+    pub const fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    /// Next larger value of the set, or [`MAX_VALUE`] if there is none.
+    pub const fn next_value(&self) -> &BlsScalar {
+        &self.next_value
+    }
+
+    /// Return the hash of the leaf.
+    pub fn hash(&self) -> BlsScalar {
+        let next_index = BlsScalar::from(self.next_index);
+        sponge::hash(&[self.value, next_index, self.next_value])
+    }
+
+    /// Return `true` if `value` lies strictly between the value of the leaf
+    /// and the next one.
+    fn encloses(&self, value: &BlsScalar) -> bool {
+        let value = ordered(value);
+        ordered(&self.value) < value && value < ordered(&self.next_value)
+    }
+}
+
+/// Proof that a value is not in the set of an [`IndexedMerkleTree`], made of
+/// the opening of its low leaf.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NonMembershipProof<const DEPTH: usize> {
+    index: u64,
+    leaf: IndexedLeaf,
+    opening: SmtOpening<DEPTH>,
+}
+
+impl<const DEPTH: usize> NonMembershipProof<DEPTH> {
+    /// Index of the low leaf.
+    pub const fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// The low leaf, holding the largest value smaller than the proven one.
+    pub const fn leaf(&self) -> &IndexedLeaf {
+        &self.leaf
+    }
+
+    /// Opening of the low leaf.
+    pub const fn opening(&self) -> &SmtOpening<DEPTH> {
+        &self.opening
+    }
+
+    /// Check that `value` is not in the set of the tree with the given `root`.
+    pub fn verify(&self, root: &BlsScalar, value: &BlsScalar) -> bool {
+        let index = BlsScalar::from(self.index);
+
+        self.leaf.encloses(value)
+            && self.opening.verify(root, &index, &self.leaf.hash())
+    }
+}
+
+/// Proof that inserting a value into the set of an [`IndexedMerkleTree`]
+/// yields a given root.
+///
+/// The proof holds the non-membership proof of the value before the insertion,
+/// and the opening of the empty leaf taking the value once the low leaf points
+/// to it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct InsertionProof<const DEPTH: usize> {
+    low: NonMembershipProof<DEPTH>,
+    index: u64,
+    opening: SmtOpening<DEPTH>,
+}
+
+impl<const DEPTH: usize> InsertionProof<DEPTH> {
+    /// Proof that the value wasn't in the set before the insertion.
+    pub const fn low(&self) -> &NonMembershipProof<DEPTH> {
+        &self.low
+    }
+
+    /// Index of the leaf holding the inserted value.
+    pub const fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Opening of the leaf holding the inserted value.
+    pub const fn opening(&self) -> &SmtOpening<DEPTH> {
+        &self.opening
+    }
+
+    /// Check that inserting `value` into the set of the tree with `old_root`
+    /// yields the tree with `new_root`.
+    pub fn verify(
+        &self,
+        old_root: &BlsScalar,
+        new_root: &BlsScalar,
+        value: &BlsScalar,
+    ) -> bool {
+        let low_index = BlsScalar::from(self.low.index);
+        let index = BlsScalar::from(self.index);
+
+        let low = IndexedLeaf {
+            next_index: self.index,
+            next_value: *value,
+            ..self.low.leaf
+        };
+        let root = self.low.opening.root(&low_index, &low.hash());
+
+        let leaf = IndexedLeaf {
+            value: *value,
+            next_index: self.low.leaf.next_index,
+            next_value: self.low.leaf.next_value,
+        };
+
+        self.low.verify(old_root, value)
+            && self.opening.verify_non_membership(&root, &index)
+            && self.opening.verify(new_root, &index, &leaf.hash())
+    }
+}
+
+/// Indexed merkle tree of a given depth, holding a set of scalars.
+#[derive(Debug, Clone)]
+pub struct IndexedMerkleTree<const DEPTH: usize> {
+    leaves: Vec<IndexedLeaf>,
+    sorted: BTreeMap<[u8; 32], u64>,
+    tree: SparseMerkleTree<DEPTH>,
+}
+
+impl<const DEPTH: usize> Default for IndexedMerkleTree<DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const DEPTH: usize> IndexedMerkleTree<DEPTH> {
+    /// Create a new tree, holding only the leaf of the value zero.
+    ///
+    /// # Panics
+    /// If `DEPTH` is zero or greater than
+    /// [`smt::MAX_DEPTH`](crate::smt::MAX_DEPTH).
+    pub fn new() -> Self {
+        assert!(DEPTH > 0, "the tree must hold more than one leaf");
+
+        let leaf = IndexedLeaf {
+            value: BlsScalar::zero(),
+            next_index: 0,
+            next_value: MAX_VALUE,
+        };
+
+        let mut tree = SparseMerkleTree::new();
+        let _ = tree.insert(BlsScalar::zero(), leaf.hash());
+
+        let mut sorted = BTreeMap::new();
+        sorted.insert(ordered(&leaf.value), 0);
+
+        Self {
+            leaves: vec![leaf],
+            sorted,
+            tree,
+        }
+    }
+
+    /// Return the root of the tree.
+    pub fn root(&self) -> BlsScalar {
+        self.tree.root()
+    }
+
+    /// Return the number of values in the set, not counting zero.
+    pub fn len(&self) -> usize {
+        self.leaves.len() - 1
+    }
+
+    /// Return `true` if no value was inserted into the set.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return `true` if `value` is in the set.
+    ///
+    /// Zero is always in the set.
+    pub fn contains(&self, value: &BlsScalar) -> bool {
+        self.sorted.contains_key(&ordered(value))
+    }
+
+    /// Return the leaf on a provided index.
+    pub fn leaf(&self, index: u64) -> Option<&IndexedLeaf> {
+        self.leaves.get(index as usize)
+    }
+
+    /// Return a proof that `value` is not in the set.
+    ///
+    /// Return `None` if `value` is in the set, or if it's not smaller than
+    /// [`MAX_VALUE`].
+    pub fn non_membership(
+        &self,
+        value: &BlsScalar,
+    ) -> Option<NonMembershipProof<DEPTH>> {
+        let (_, &index) = self.sorted.range(..ordered(value)).next_back()?;
+        let leaf = self.leaves[index as usize];
+
+        if !leaf.encloses(value) {
+            return None;
+        }
+
+        Some(NonMembershipProof {
+            index,
+            leaf,
+            opening: self.tree.opening(&BlsScalar::from(index)),
+        })
+    }
+
+    /// Insert `value` into the set, returning the proof of the insertion.
+    pub fn insert(
+        &mut self,
+        value: BlsScalar,
+    ) -> Result<InsertionProof<DEPTH>, Error> {
+        if ordered(&value) >= ordered(&MAX_VALUE) {
+            return Err(Error::OutOfRange);
+        }
+
+        let index = self.leaves.len() as u64;
+        if DEPTH < 64 && index >> DEPTH != 0 {
+            return Err(Error::Full);
+        }
+
+        let low = self.non_membership(&value).ok_or(Error::Duplicate)?;
+
+        // Point the low leaf to the new one, which takes over its pointer
+        let low_leaf = &mut self.leaves[low.index as usize];
+        let leaf = IndexedLeaf {
+            value,
+            next_index: low_leaf.next_index,
+            next_value: low_leaf.next_value,
+        };
+        low_leaf.next_index = index;
+        low_leaf.next_value = value;
+
+        // Indices are smaller than 2^DEPTH, so their leaves never collide
+        let low_hash = low_leaf.hash();
+        let _ = self.tree.insert(BlsScalar::from(low.index), low_hash);
+
+        let opening = self.tree.opening(&BlsScalar::from(index));
+        let _ = self.tree.insert(BlsScalar::from(index), leaf.hash());
+
+        self.leaves.push(leaf);
+        self.sorted.insert(ordered(&value), index);
+
+        Ok(InsertionProof {
+            low,
+            index,
+            opening,
+        })
+    }
+}
@@ -0,0 +1,110 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::{InsertionProof, NonMembershipProof, VALUE_BITS};
+use crate::{smt, sponge};
+
+use plonk::prelude::*;
+
+/// Witnesses of an opened low leaf.
+struct LowLeaf {
+    index: Witness,
+    value: Witness,
+    next_index: Witness,
+    next_value: Witness,
+    root: Witness,
+}
+
+/// Constrain `a` to be smaller than `b`, both being smaller than `2^252`.
+fn assert_less_than<C>(composer: &mut C, a: Witness, b: Witness)
+where
+    C: Composer,
+{
+    // `b - a - 1` only fits in the bits of a value if `a < b`
+    let constraint = Constraint::new()
+        .left(1)
+        .a(b)
+        .right(-BlsScalar::one())
+        .b(a)
+        .constant(-BlsScalar::one());
+    let diff = composer.gate_add(constraint);
+
+    composer.component_decomposition::<VALUE_BITS>(diff);
+}
+
+/// Open the low leaf of `proof` and constrain `value` to lie between the low
+/// leaf and the next one.
+fn open_low<C, const DEPTH: usize>(
+    composer: &mut C,
+    proof: &NonMembershipProof<DEPTH>,
+    value: Witness,
+) -> LowLeaf
+where
+    C: Composer,
+{
+    let leaf = &proof.leaf;
+
+    let index = composer.append_witness(BlsScalar::from(proof.index));
+    let low_value = composer.append_witness(leaf.value);
+    let next_index = composer.append_witness(BlsScalar::from(leaf.next_index));
+    let next_value = composer.append_witness(leaf.next_value);
+
+    assert_less_than(composer, low_value, value);
+    assert_less_than(composer, value, next_value);
+
+    let hash = sponge::gadget(composer, &[low_value, next_index, next_value]);
+    let root = smt::merkle_opening(composer, &proof.opening, index, hash);
+
+    LowLeaf {
+        index,
+        value: low_value,
+        next_index,
+        next_value,
+        root,
+    }
+}
+
+/// Prove that `value` is not in the set of an indexed merkle tree and return
+/// the calculated root
+pub fn non_membership<C, const DEPTH: usize>(
+    composer: &mut C,
+    proof: &NonMembershipProof<DEPTH>,
+    value: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    open_low(composer, proof, value).root
+}
+
+/// Prove the insertion of `value` into the set of the indexed merkle tree with
+/// `old_root` and return the calculated root after the insertion
+pub fn insertion<C, const DEPTH: usize>(
+    composer: &mut C,
+    proof: &InsertionProof<DEPTH>,
+    old_root: Witness,
+    value: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    let low = open_low(composer, &proof.low, value);
+    composer.assert_equal(low.root, old_root);
+
+    // Point the low leaf to the new leaf
+    let index = composer.append_witness(BlsScalar::from(proof.index));
+    let hash = sponge::gadget(composer, &[low.value, index, value]);
+    let root =
+        smt::merkle_opening(composer, &proof.low.opening, low.index, hash);
+
+    // The new leaf must be empty before taking over the pointer of the low leaf
+    let empty_root = smt::non_membership(composer, &proof.opening, index);
+    composer.assert_equal(empty_root, root);
+
+    let hash =
+        sponge::gadget(composer, &[value, low.next_index, low.next_value]);
+    smt::merkle_opening(composer, &proof.opening, index, hash)
+}
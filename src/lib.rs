@@ -5,13 +5,118 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 #![cfg_attr(feature = "alloc", warn(missing_docs), doc = include_str!("../README.md"))]
+#![no_std]
+
+extern crate alloc;
+
+#[cfg(any(feature = "std", feature = "uniffi"))]
+extern crate std;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
+/// Jive compression mode
+pub mod compress;
+
+/// Runtime-loadable parameters for the Poseidon permutation
+pub mod params;
+
+/// The Poseidon2 permutation
+pub mod poseidon2;
+
+/// Abstraction over the permutation used by the sponge, the tree and the cipher
+pub mod permutation;
+
+/// Hiding commitments to scalars
+pub mod commitment;
+
+/// Extension of the PLONK composer with the Poseidon gadgets
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub mod composer;
+
+/// Number of constraints of the gadgets
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub mod constraint_count;
+
+/// Derivation of the nullifiers of notes
+pub mod nullifier;
 
 /// Module containing a fixed-length Poseidon hash implementation
 pub mod perm_uses;
 
+/// Append-only cryptographic accumulators over typed elements
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub mod accumulator;
+
+/// Sparse merkle trees keyed by scalars
+#[cfg(any(feature = "alloc", feature = "verify-only"))]
+pub mod smt;
+
+/// Indexed merkle trees holding sets of scalars
+#[cfg(any(feature = "alloc", feature = "verify-only"))]
+pub mod imt;
+
+/// Merkle maps from typed keys to typed values
+#[cfg(any(feature = "alloc", feature = "verify-only"))]
+pub mod map;
+
+/// Merkle mountain ranges over leaf hashes
+#[cfg(any(feature = "alloc", feature = "verify-only"))]
+pub mod mmr;
+
+/// Encryption of scalars with the Poseidon permutation
+pub mod cipher;
+
 /// Reference implementation for the Poseidon Sponge hash function
 pub mod sponge;
 
+/// The Poseidon hash of the neptune crate
+pub mod neptune;
+
+/// Poseidon instantiations over the BN254 scalar field
+#[cfg(feature = "bn254")]
+pub mod bn254;
+
+/// The Poseidon hash of circomlib
+#[cfg(feature = "circom")]
+pub mod circom;
+
+/// Poseidon instantiations over the Pallas and Vesta base fields
+#[cfg(feature = "pasta")]
+pub mod pasta;
+
+/// Compatibility layer with the arkworks ecosystem
+#[cfg(feature = "arkworks")]
+pub mod arkworks;
+
+/// R1CS gadgets over `ark-relations`
+#[cfg(feature = "r1cs")]
+pub mod r1cs;
+
+/// Halo2 chip for the Poseidon permutation
+#[cfg(feature = "halo2")]
+pub mod halo2;
+
+/// Gadgets over the `bellman` constraint system
+#[cfg(feature = "bellman")]
+pub mod bellman;
+
+/// UniFFI bindings for mobile platforms
+#[cfg(feature = "uniffi")]
+pub mod ffi;
+
+/// Poseidon hasher for the `rs_merkle` crate
+#[cfg(feature = "rs-merkle")]
+pub mod hasher;
+
+/// Protobuf messages for poseidon openings
+#[cfg(feature = "proto")]
+pub mod proto;
+
+/// Fiat-Shamir transcript over the Poseidon sponge
+#[cfg(feature = "transcript")]
+pub mod transcript;
+
 /// The module handling poseidon-trees.
-#[cfg(feature = "alloc")]
+#[cfg(any(feature = "alloc", feature = "verify-only"))]
 pub mod tree;
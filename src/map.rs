@@ -0,0 +1,245 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Merkle map from typed keys to typed values.
+//!
+//! Keys and values are encoded into scalars with [`PoseidonEncode`], and their
+//! encodings are hashed with [`sponge::hash`]. The map is a
+//! [`SparseMerkleTree`] setting the hash of every key to the hash of its
+//! value, so its openings are openings of the tree, typed by the key and value
+//! they open.
+//!
+//! The depth of the map defaults to [`MAX_DEPTH`], where every key has its own
+//! leaf. Shallower maps are cheaper to open in a circuit, at the cost of
+//! rejecting keys whose hash shares the leaf of another key.
+//!
+//! A [`VersionedMap`] keeps every version of the map, answering queries
+//! against any of its past roots.
+
+mod versioned;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod zk;
+
+pub use versioned::VersionedMap;
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use zk::{merkle_opening, non_membership};
+
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+
+use bls12_381::Scalar as BlsScalar;
+
+use crate::smt::{Error, SmtOpening, SparseMerkleTree, MAX_DEPTH};
+use crate::sponge;
+
+/// A type that can be stored in a [`PoseidonMap`] must implement this trait,
+/// encoding its values into scalars.
+///
+/// The encoding of a type used in circuits should have a constant length,
+/// since the gadgets of the map take the encodings as witnesses.
+pub trait PoseidonEncode {
+    /// Encode the value into scalars.
+    fn poseidon_encode(&self) -> Vec<BlsScalar>;
+}
+
+impl PoseidonEncode for BlsScalar {
+    fn poseidon_encode(&self) -> Vec<BlsScalar> {
+        vec![*self]
+    }
+}
+
+impl PoseidonEncode for u64 {
+    fn poseidon_encode(&self) -> Vec<BlsScalar> {
+        vec![BlsScalar::from(*self)]
+    }
+}
+
+impl<T, const N: usize> PoseidonEncode for [T; N]
+where
+    T: PoseidonEncode,
+{
+    fn poseidon_encode(&self) -> Vec<BlsScalar> {
+        self.iter().flat_map(T::poseidon_encode).collect()
+    }
+}
+
+/// Hash the encoding of `t`.
+fn hash<T: PoseidonEncode>(t: &T) -> BlsScalar {
+    sponge::hash(&t.poseidon_encode())
+}
+
+/// Opening of a key of a [`PoseidonMap`].
+///
+/// The opening either shows the key set to a value, or the key absent from
+/// the map, depending on how it was obtained.
+pub struct MapOpening<K, V, const DEPTH: usize = MAX_DEPTH> {
+    opening: SmtOpening<DEPTH>,
+    _marker: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V, const DEPTH: usize> fmt::Debug for MapOpening<K, V, DEPTH> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapOpening")
+            .field("opening", &self.opening)
+            .finish()
+    }
+}
+
+impl<K, V, const DEPTH: usize> Clone for MapOpening<K, V, DEPTH> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V, const DEPTH: usize> Copy for MapOpening<K, V, DEPTH> {}
+
+impl<K, V, const DEPTH: usize> Default for MapOpening<K, V, DEPTH> {
+    fn default() -> Self {
+        Self::new(SmtOpening::default())
+    }
+}
+
+impl<K, V, const DEPTH: usize> MapOpening<K, V, DEPTH> {
+    /// Create an opening of a map from an opening of its tree.
+    pub const fn new(opening: SmtOpening<DEPTH>) -> Self {
+        Self {
+            opening,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Return the opening of the tree of the map.
+    pub const fn opening(&self) -> &SmtOpening<DEPTH> {
+        &self.opening
+    }
+}
+
+impl<K, V, const DEPTH: usize> MapOpening<K, V, DEPTH>
+where
+    K: PoseidonEncode,
+    V: PoseidonEncode,
+{
+    /// Check that `key` is set to `value` in the map with the given `root`.
+    pub fn verify(&self, root: &BlsScalar, key: &K, value: &V) -> bool {
+        self.opening.verify(root, &hash(key), &hash(value))
+    }
+
+    /// Check that `key` is not in the map with the given `root`.
+    pub fn verify_non_membership(&self, root: &BlsScalar, key: &K) -> bool {
+        self.opening.verify_non_membership(root, &hash(key))
+    }
+}
+
+/// Merkle map of a given depth, from keys to values.
+#[derive(Debug, Clone)]
+pub struct PoseidonMap<K, V, const DEPTH: usize = MAX_DEPTH> {
+    entries: BTreeMap<[u8; 32], (K, V)>,
+    tree: SparseMerkleTree<DEPTH>,
+}
+
+impl<K, V, const DEPTH: usize> Default for PoseidonMap<K, V, DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, const DEPTH: usize> PoseidonMap<K, V, DEPTH> {
+    /// Create a new, empty, map.
+    ///
+    /// # Panics
+    /// If `DEPTH` is greater than [`MAX_DEPTH`].
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            tree: SparseMerkleTree::new(),
+        }
+    }
+
+    /// Return the root of the map.
+    pub fn root(&self) -> BlsScalar {
+        self.tree.root()
+    }
+
+    /// Return the number of keys in the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Return `true` if the map holds no keys.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Provides an iterator over the keys and values of the map, ordered by
+    /// the hashes of the keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.values().map(|(key, value)| (key, value))
+    }
+}
+
+impl<K, V, const DEPTH: usize> PoseidonMap<K, V, DEPTH>
+where
+    K: PoseidonEncode,
+    V: PoseidonEncode,
+{
+    /// Return the value of `key`, if it is in the map.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries
+            .get(&hash(key).to_bytes())
+            .map(|(_, value)| value)
+    }
+
+    /// Return `true` if `key` is in the map.
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.entries.contains_key(&hash(key).to_bytes())
+    }
+
+    /// Set the value of `key`, returning its previous value if any.
+    ///
+    /// Return [`Error::KeyCollision`] if the hash of `key` shares its leaf
+    /// with the hash of another key, which can't happen in maps of
+    /// [`MAX_DEPTH`].
+    pub fn insert(&mut self, key: K, value: V) -> Result<Option<V>, Error> {
+        let key_hash = hash(&key);
+
+        self.tree.insert(key_hash, hash(&value))?;
+
+        let previous = self.entries.insert(key_hash.to_bytes(), (key, value));
+        Ok(previous.map(|(_, value)| value))
+    }
+
+    /// Remove `key` from the map, returning its value if it was in the map.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let key_hash = hash(key);
+
+        self.tree.remove(&key_hash)?;
+        self.entries
+            .remove(&key_hash.to_bytes())
+            .map(|(_, value)| value)
+    }
+
+    /// Return the opening of `key` set to its value, if it is in the map.
+    pub fn opening(&self, key: &K) -> Option<MapOpening<K, V, DEPTH>> {
+        let key_hash = hash(key);
+
+        match self.entries.contains_key(&key_hash.to_bytes()) {
+            true => Some(MapOpening::new(self.tree.opening(&key_hash))),
+            false => None,
+        }
+    }
+
+    /// Return the opening proving that `key` is not in the map.
+    ///
+    /// Return `None` if the leaf of `key` holds a key, be it `key` or another
+    /// key sharing its leaf.
+    pub fn non_membership(&self, key: &K) -> Option<MapOpening<K, V, DEPTH>> {
+        self.tree.non_membership(&hash(key)).map(MapOpening::new)
+    }
+}
@@ -0,0 +1,242 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::{hash, MapOpening, PoseidonEncode};
+use crate::smt::{
+    bit, empty_hashes, hash_leaf, hash_node, path, Error, SmtOpening, MAX_DEPTH,
+};
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use bls12_381::Scalar as BlsScalar;
+
+/// Nodes on the path of a key, with the siblings from the bottom up to the
+/// root.
+struct Walk<const DEPTH: usize> {
+    siblings: [BlsScalar; DEPTH],
+    leaf: BlsScalar,
+}
+
+/// Merkle map keeping every version of itself.
+///
+/// The nodes of the map are stored by their hash, so every mutation only
+/// stores the nodes on the path of its key, sharing the others with the
+/// previous versions. Any version can then be queried by its root, for as
+/// long as the map lives.
+#[derive(Debug, Clone)]
+pub struct VersionedMap<K, V, const DEPTH: usize = MAX_DEPTH> {
+    nodes: BTreeMap<[u8; 32], (BlsScalar, BlsScalar)>,
+    leaves: BTreeMap<[u8; 32], (BlsScalar, K, V)>,
+    empty: Vec<BlsScalar>,
+    roots: Vec<BlsScalar>,
+    known: BTreeSet<[u8; 32]>,
+}
+
+impl<K, V, const DEPTH: usize> Default for VersionedMap<K, V, DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, const DEPTH: usize> VersionedMap<K, V, DEPTH> {
+    /// Create a new, empty, map.
+    ///
+    /// # Panics
+    /// If `DEPTH` is greater than [`MAX_DEPTH`].
+    pub fn new() -> Self {
+        assert!(DEPTH <= MAX_DEPTH, "the depth of the map is too large");
+
+        let empty = empty_hashes(DEPTH);
+        let root = empty[DEPTH];
+
+        Self {
+            nodes: BTreeMap::new(),
+            leaves: BTreeMap::new(),
+            empty,
+            roots: vec![root],
+            known: BTreeSet::from([root.to_bytes()]),
+        }
+    }
+
+    /// Return the root of the latest version of the map.
+    pub fn root(&self) -> BlsScalar {
+        self.roots[self.roots.len() - 1]
+    }
+
+    /// Return the roots of every version of the map, from the oldest to the
+    /// latest.
+    ///
+    /// A mutation leaving the map unchanged doesn't create a new version.
+    pub fn roots(&self) -> &[BlsScalar] {
+        &self.roots
+    }
+
+    /// Return `true` if `root` is the root of a version of the map.
+    pub fn contains_root(&self, root: &BlsScalar) -> bool {
+        self.known.contains(&root.to_bytes())
+    }
+
+    /// Walk the path of `key` from `root` down to its leaf.
+    ///
+    /// Return `None` if `root` is not the root of a version of the map.
+    fn walk(&self, root: &BlsScalar, key: &BlsScalar) -> Option<Walk<DEPTH>> {
+        if !self.contains_root(root) {
+            return None;
+        }
+
+        let path = path(key, DEPTH);
+        let mut siblings = [BlsScalar::zero(); DEPTH];
+
+        let mut node = *root;
+        for height in (0..DEPTH).rev() {
+            let (left, right) = match node == self.empty[height + 1] {
+                true => (self.empty[height], self.empty[height]),
+                false => *self.nodes.get(&node.to_bytes())?,
+            };
+
+            let (child, sibling) = match bit(&path, height) {
+                true => (right, left),
+                false => (left, right),
+            };
+
+            siblings[height] = sibling;
+            node = child;
+        }
+
+        Some(Walk {
+            siblings,
+            leaf: node,
+        })
+    }
+
+    /// Set the leaf of `key` and hash it up to a new root, storing the nodes
+    /// on the way.
+    fn update(&mut self, key: &BlsScalar, walk: Walk<DEPTH>, leaf: BlsScalar) {
+        let path = path(key, DEPTH);
+
+        let root = walk.siblings.iter().enumerate().fold(
+            leaf,
+            |node, (height, sibling)| {
+                let (left, right) = match bit(&path, height) {
+                    true => (*sibling, node),
+                    false => (node, *sibling),
+                };
+
+                let parent = hash_node(&left, &right);
+                if parent != self.empty[height + 1] {
+                    self.nodes.insert(parent.to_bytes(), (left, right));
+                }
+
+                parent
+            },
+        );
+
+        if root != self.root() {
+            self.roots.push(root);
+            self.known.insert(root.to_bytes());
+        }
+    }
+}
+
+impl<K, V, const DEPTH: usize> VersionedMap<K, V, DEPTH>
+where
+    K: PoseidonEncode,
+    V: PoseidonEncode,
+{
+    /// Return the value of `key` in the latest version of the map.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.get_at(&self.root(), key)
+    }
+
+    /// Return the value of `key` in the version of the map with the given
+    /// `root`.
+    ///
+    /// Return `None` if `key` is not in that version, or if `root` is not
+    /// the root of a version of the map.
+    pub fn get_at(&self, root: &BlsScalar, key: &K) -> Option<&V> {
+        let key_hash = hash(key);
+        let walk = self.walk(root, &key_hash)?;
+
+        self.leaves
+            .get(&walk.leaf.to_bytes())
+            .filter(|(k, _, _)| *k == key_hash)
+            .map(|(_, _, value)| value)
+    }
+
+    /// Set the value of `key`, creating a new version of the map. Return the
+    /// root of the new version.
+    ///
+    /// Return [`Error::KeyCollision`] if the hash of `key` shares its leaf
+    /// with the hash of another key, which can't happen in maps of
+    /// [`MAX_DEPTH`].
+    pub fn insert(&mut self, key: K, value: V) -> Result<BlsScalar, Error> {
+        let key_hash = hash(&key);
+        let walk = self
+            .walk(&self.root(), &key_hash)
+            .expect("the nodes of the latest version are stored");
+
+        if let Some((k, _, _)) = self.leaves.get(&walk.leaf.to_bytes()) {
+            if *k != key_hash {
+                return Err(Error::KeyCollision);
+            }
+        }
+
+        let leaf = hash_leaf(&key_hash, &hash(&value));
+        self.leaves.insert(leaf.to_bytes(), (key_hash, key, value));
+        self.update(&key_hash, walk, leaf);
+
+        Ok(self.root())
+    }
+
+    /// Remove `key`, creating a new version of the map. Return the root of the
+    /// new version, or `None` if `key` is not in the map.
+    pub fn remove(&mut self, key: &K) -> Option<BlsScalar> {
+        let key_hash = hash(key);
+        let walk = self.walk(&self.root(), &key_hash)?;
+
+        match self.leaves.get(&walk.leaf.to_bytes()) {
+            Some((k, _, _)) if *k == key_hash => {}
+            _ => return None,
+        }
+
+        self.update(&key_hash, walk, BlsScalar::zero());
+
+        Some(self.root())
+    }
+
+    /// Return the opening of `key` set to its value in the version of the
+    /// map with the given `root`, if it is in that version.
+    pub fn opening_at(
+        &self,
+        root: &BlsScalar,
+        key: &K,
+    ) -> Option<MapOpening<K, V, DEPTH>> {
+        self.get_at(root, key)?;
+
+        let walk = self.walk(root, &hash(key))?;
+        Some(MapOpening::new(SmtOpening::new(walk.siblings)))
+    }
+
+    /// Return the opening proving that `key` is not in the version of the map
+    /// with the given `root`.
+    ///
+    /// Return `None` if the leaf of `key` holds a key in that version, or if
+    /// `root` is not the root of a version of the map.
+    pub fn non_membership_at(
+        &self,
+        root: &BlsScalar,
+        key: &K,
+    ) -> Option<MapOpening<K, V, DEPTH>> {
+        let walk = self.walk(root, &hash(key))?;
+
+        match walk.leaf == BlsScalar::zero() {
+            true => Some(MapOpening::new(SmtOpening::new(walk.siblings))),
+            false => None,
+        }
+    }
+}
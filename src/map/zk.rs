@@ -0,0 +1,49 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::MapOpening;
+use crate::smt;
+use crate::sponge;
+
+use plonk::prelude::*;
+
+/// Perform a merkle opening of `key` set to `value` in a map and return the
+/// calculated root
+///
+/// The key and the value are given as the witnesses of their encodings, whose
+/// lengths are constants of the circuit.
+pub fn merkle_opening<C, K, V, const DEPTH: usize>(
+    composer: &mut C,
+    opening: &MapOpening<K, V, DEPTH>,
+    key: &[Witness],
+    value: &[Witness],
+) -> Witness
+where
+    C: Composer,
+{
+    let key = sponge::gadget(composer, key);
+    let value = sponge::gadget(composer, value);
+
+    smt::merkle_opening(composer, &opening.opening, key, value)
+}
+
+/// Perform a merkle opening proving that `key` is not in a map and return the
+/// calculated root
+///
+/// The key is given as the witnesses of its encoding, whose length is a
+/// constant of the circuit.
+pub fn non_membership<C, K, V, const DEPTH: usize>(
+    composer: &mut C,
+    opening: &MapOpening<K, V, DEPTH>,
+    key: &[Witness],
+) -> Witness
+where
+    C: Composer,
+{
+    let key = sponge::gadget(composer, key);
+
+    smt::non_membership(composer, &opening.opening, key)
+}
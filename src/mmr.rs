@@ -0,0 +1,206 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Merkle mountain range over the hashes of its leaves.
+//!
+//! The leaves are grouped into perfect binary trees, the mountains, of
+//! strictly decreasing heights given by the bits of the number of leaves, so
+//! leaves can be appended without bounding their number. The roots of the
+//! mountains, the peaks, are bagged from the right to the left, and the root
+//! of the range is the hash of the number of leaves with the bagged peaks. The
+//! root of an empty range is zero.
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod zk;
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use zk::merkle_opening;
+
+use alloc::vec::Vec;
+
+use bls12_381::Scalar as BlsScalar;
+
+use crate::smt::hash_node;
+
+/// Return the heights of the mountains of a range of `size` leaves, from the
+/// left to the right.
+fn heights(size: u64) -> impl Iterator<Item = usize> {
+    (0..u64::BITS as usize)
+        .rev()
+        .filter(move |h| size >> h & 1 == 1)
+}
+
+/// Return the index of the mountain holding the leaf at `pos` in a range of
+/// `size` leaves, along with its height and the offset of the leaf in it.
+fn mountain(size: u64, pos: u64) -> Option<(usize, usize, u64)> {
+    let mut start = 0u64;
+
+    for (index, height) in heights(size).enumerate() {
+        let len = 1 << height;
+        if pos < start + len {
+            return Some((index, height, pos - start));
+        }
+        start += len;
+    }
+
+    None
+}
+
+/// Bag `peaks` into the root of a range of `size` leaves.
+fn bag(size: u64, peaks: &[BlsScalar]) -> BlsScalar {
+    match peaks.split_last() {
+        None => BlsScalar::zero(),
+        Some((last, peaks)) => {
+            let bagged = peaks
+                .iter()
+                .rev()
+                .fold(*last, |bagged, peak| hash_node(peak, &bagged));
+            hash_node(&BlsScalar::from(size), &bagged)
+        }
+    }
+}
+
+/// Proof of inclusion of a leaf in a [`MerkleMountainRange`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MmrProof {
+    pos: u64,
+    size: u64,
+    path: Vec<BlsScalar>,
+    peaks: Vec<BlsScalar>,
+}
+
+impl MmrProof {
+    /// Position of the leaf.
+    pub const fn pos(&self) -> u64 {
+        self.pos
+    }
+
+    /// Number of leaves of the range.
+    pub const fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Siblings of the nodes on the path from the leaf up to its peak.
+    pub fn path(&self) -> &[BlsScalar] {
+        &self.path
+    }
+
+    /// Peaks of the range, from the left to the right.
+    pub fn peaks(&self) -> &[BlsScalar] {
+        &self.peaks
+    }
+
+    /// Check that `leaf` is at the position of the proof in the range with
+    /// the given `root`.
+    pub fn verify(&self, root: &BlsScalar, leaf: &BlsScalar) -> bool {
+        let (index, height, offset) = match mountain(self.size, self.pos) {
+            Some(mountain) => mountain,
+            None => return false,
+        };
+
+        if self.path.len() != height
+            || self.peaks.len() != heights(self.size).count()
+        {
+            return false;
+        }
+
+        let peak = self.path.iter().enumerate().fold(
+            *leaf,
+            |node, (height, sibling)| match offset >> height & 1 {
+                1 => hash_node(sibling, &node),
+                _ => hash_node(&node, sibling),
+            },
+        );
+
+        self.peaks[index] == peak && bag(self.size, &self.peaks) == *root
+    }
+}
+
+/// Merkle mountain range of leaf hashes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MerkleMountainRange {
+    levels: Vec<Vec<BlsScalar>>,
+}
+
+impl MerkleMountainRange {
+    /// Create a new, empty, range.
+    pub const fn new() -> Self {
+        Self { levels: Vec::new() }
+    }
+
+    /// Return the number of leaves in the range.
+    pub fn len(&self) -> u64 {
+        self.levels.first().map_or(0, |leaves| leaves.len() as u64)
+    }
+
+    /// Return `true` if the range has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append a leaf to the range, returning its position.
+    pub fn push(&mut self, leaf: BlsScalar) -> u64 {
+        let pos = self.len();
+        let mut node = leaf;
+
+        // Merge the mountains of equal height
+        for height in 0.. {
+            if self.levels.len() == height {
+                self.levels.push(Vec::new());
+            }
+
+            let level = &mut self.levels[height];
+            level.push(node);
+
+            if level.len() % 2 == 1 {
+                break;
+            }
+            node = hash_node(&level[level.len() - 2], &level[level.len() - 1]);
+        }
+
+        pos
+    }
+
+    /// Return the leaf on a provided position.
+    pub fn get(&self, pos: u64) -> Option<BlsScalar> {
+        self.levels.first()?.get(pos as usize).copied()
+    }
+
+    /// Return the peaks of the range, from the left to the right.
+    pub fn peaks(&self) -> Vec<BlsScalar> {
+        let mut start = 0u64;
+
+        heights(self.len())
+            .map(|height| {
+                let peak = self.levels[height][(start >> height) as usize];
+                start += 1 << height;
+                peak
+            })
+            .collect()
+    }
+
+    /// Return the root of the range.
+    pub fn root(&self) -> BlsScalar {
+        bag(self.len(), &self.peaks())
+    }
+
+    /// Return a proof of inclusion of the leaf on a provided position.
+    pub fn proof(&self, pos: u64) -> Option<MmrProof> {
+        let size = self.len();
+        let (_, height, _) = mountain(size, pos)?;
+
+        let path = (0..height)
+            .map(|h| self.levels[h][((pos >> h) ^ 1) as usize])
+            .collect();
+
+        Some(MmrProof {
+            pos,
+            size,
+            path,
+            peaks: self.peaks(),
+        })
+    }
+}
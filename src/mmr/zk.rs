@@ -0,0 +1,73 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::{mountain, MmrProof};
+use crate::smt::hash_node_gadget;
+
+use alloc::vec::Vec;
+
+use plonk::prelude::*;
+
+/// Perform a merkle opening for a given proof of a merkle mountain range and
+/// return the calculated root
+///
+/// The number of leaves of the range is a constant of the circuit, and the
+/// shape of the circuit depends on it and on the mountain holding the leaf, so
+/// the circuit must be compiled with a proof of the same shape.
+pub fn merkle_opening<C>(
+    composer: &mut C,
+    proof: &MmrProof,
+    leaf: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    let (index, offset) = mountain(proof.size, proof.pos)
+        .map_or((0, 0), |(index, _, offset)| (index, offset));
+
+    // Hash the leaf up to its peak
+    let peak =
+        proof
+            .path
+            .iter()
+            .enumerate()
+            .fold(leaf, |node, (height, sibling)| {
+                let bit = BlsScalar::from(offset >> height & 1);
+                let bit = composer.append_witness(bit);
+                composer.component_boolean(bit);
+
+                let sibling = composer.append_witness(*sibling);
+
+                // A set bit puts the node on the right of its sibling
+                let left = composer.component_select(bit, sibling, node);
+                let right = composer.component_select(bit, node, sibling);
+
+                hash_node_gadget(composer, left, right)
+            });
+
+    let peaks: Vec<Witness> = proof
+        .peaks
+        .iter()
+        .enumerate()
+        .map(|(i, p)| match i == index {
+            true => peak,
+            false => composer.append_witness(*p),
+        })
+        .collect();
+
+    // Bag the peaks from the right to the left
+    match peaks.split_last() {
+        None => C::ZERO,
+        Some((last, peaks)) => {
+            let bagged = peaks.iter().rev().fold(*last, |bagged, peak| {
+                hash_node_gadget(composer, *peak, bagged)
+            });
+
+            let size = composer.append_constant(BlsScalar::from(proof.size));
+            hash_node_gadget(composer, size, bagged)
+        }
+    }
+}
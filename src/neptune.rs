@@ -0,0 +1,176 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! The Poseidon hash of the neptune crate, used by Filecoin, over the
+//! BLS12-381 scalar field.
+//!
+//! neptune hashes up to `arity` elements with a permutation of width
+//! `arity + 1`, whose parameters differ from the ones of [`Params::generate`]:
+//!
+//! - the round constants are generated with the Grain LFSR of the reference
+//!   implementation, seeded with the S-box code `1` instead of the code `0`
+//!   of `x^alpha`, and the MDS matrix is the Cauchy matrix `1 / (x_i + y_j)`
+//!   with `x_i = i` and `y_j = width + j`;
+//! - the partial rounds are computed by the round numbers script without
+//!   rounding them to a multiple of the width, and apply the S-box to the
+//!   first element of the state instead of the last one.
+//!
+//! The first element of the state holds the domain tag of the [`HashType`],
+//! the following ones the preimage, padded with zeros, and the hash is the
+//! second element of the state after a single permutation.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use bls12_381::Scalar as BlsScalar;
+
+use crate::params::{Error, Grain, Params};
+use crate::permutation::Permutation;
+
+/// Maximum arity of the hash.
+pub const MAX_ARITY: usize = 36;
+
+/// Full rounds of every width.
+const FULL_ROUNDS: usize = 8;
+
+/// Code of the S-box seeding the Grain LFSR.
+const SBOX: u64 = 1;
+
+/// Partial rounds of the widths from `2` to `MAX_ARITY + 1`.
+const PARTIAL_ROUNDS: [usize; MAX_ARITY] = [
+    55, 55, 56, 56, 56, 56, 57, 57, 57, 57, 57, 57, 57, 57, 59, 59, 59, 59, 59,
+    59, 59, 59, 59, 59, 59, 59, 59, 59, 59, 59, 60, 60, 60, 60, 60, 60,
+];
+
+/// Type of the data hashed, defining the domain tag of the hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashType {
+    /// Node of a merkle tree, of `arity` children.
+    Merkle,
+    /// Preimage of the given length, at most the arity.
+    ConstantLength(usize),
+}
+
+impl HashType {
+    /// Domain tag of the hash type for a hash of the given `arity`.
+    ///
+    /// The tag of [`HashType::Merkle`] is `2^arity - 1`, and the one of
+    /// [`HashType::ConstantLength`] the length times `2^64`.
+    pub fn domain_tag(&self, arity: usize) -> BlsScalar {
+        match self {
+            Self::Merkle => BlsScalar::from((1u64 << arity) - 1),
+            Self::ConstantLength(len) => {
+                BlsScalar::from_raw([0, *len as u64, 0, 0])
+            }
+        }
+    }
+}
+
+/// The Poseidon hash of neptune for a fixed arity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NeptunePoseidon {
+    params: Params,
+}
+
+impl NeptunePoseidon {
+    /// Generate the hash of the given `arity`.
+    ///
+    /// Return an error if `arity` is zero or bigger than [`MAX_ARITY`].
+    pub fn new(arity: usize) -> Result<Self, Error> {
+        if arity == 0 || arity > MAX_ARITY {
+            return Err(Error::InvalidWidth(arity + 1));
+        }
+
+        let width = arity + 1;
+        let partial_rounds = PARTIAL_ROUNDS[arity - 1];
+
+        let mut grain = Grain::with_sbox::<BlsScalar>(
+            SBOX,
+            width,
+            FULL_ROUNDS,
+            partial_rounds,
+        );
+        let round_constants = (0..width * (FULL_ROUNDS + partial_rounds))
+            .map(|_| grain.next_scalar())
+            .collect();
+
+        let mds: Vec<BlsScalar> = (0..width)
+            .flat_map(|i| (0..width).map(move |j| i + width + j))
+            .map(|sum| BlsScalar::from(sum as u64).invert().unwrap())
+            .collect();
+
+        let params = Params::new(
+            width,
+            FULL_ROUNDS,
+            partial_rounds,
+            round_constants,
+            mds,
+        )?;
+
+        Ok(Self { params })
+    }
+
+    /// Parameters of the permutation.
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    /// Arity of the hash.
+    pub fn arity(&self) -> usize {
+        self.params.width() - 1
+    }
+
+    /// Hash `preimage` as a node of a merkle tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `preimage` doesn't match the arity.
+    pub fn hash_merkle(&self, preimage: &[BlsScalar]) -> BlsScalar {
+        assert_eq!(
+            preimage.len(),
+            self.arity(),
+            "The preimage of a merkle node must match the arity"
+        );
+
+        self.hash(HashType::Merkle, preimage)
+    }
+
+    /// Hash `preimage` with the tag of its constant length.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `preimage` exceeds the arity.
+    pub fn hash_constant_length(&self, preimage: &[BlsScalar]) -> BlsScalar {
+        self.hash(HashType::ConstantLength(preimage.len()), preimage)
+    }
+
+    fn hash(&self, hash_type: HashType, preimage: &[BlsScalar]) -> BlsScalar {
+        assert!(
+            preimage.len() <= self.arity(),
+            "The preimage must not exceed the arity"
+        );
+
+        let mut state = vec![BlsScalar::zero(); self.params.width()];
+        state[0] = hash_type.domain_tag(self.arity());
+        state[1..=preimage.len()].copy_from_slice(preimage);
+
+        Permutation::permute(self, &mut state);
+
+        state[1]
+    }
+}
+
+impl Permutation for NeptunePoseidon {
+    type Scalar = BlsScalar;
+
+    fn width(&self) -> usize {
+        self.params.width()
+    }
+
+    fn permute(&self, state: &mut [BlsScalar]) {
+        self.params.permute_with_partial(state, 0);
+    }
+}
@@ -0,0 +1,72 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Derivation of the nullifiers of notes.
+//!
+//! A nullifier is the hash of the secret key owning a note along with either
+//! the position of the note in its tree, or the commitment of the note. Each
+//! derivation hashes under a domain of its own, so nullifiers never collide
+//! with each other, nor with the hash of the same scalars computed for
+//! another purpose.
+
+use bls12_381::Scalar as BlsScalar;
+
+use crate::sponge;
+
+#[cfg(feature = "alloc")]
+use plonk::prelude::*;
+
+/// Domain of the nullifiers derived from the position of a note.
+const POSITION_DOMAIN: u64 = 0x100000004;
+
+/// Domain of the nullifiers derived from the commitment of a note.
+const COMMITMENT_DOMAIN: u64 = 0x100000005;
+
+/// Derive the nullifier of the note at `position`, owned by `sk`.
+///
+/// The position is the one of the note in its tree, such as the one returned
+/// by [`tree::merkle_opening_with_pos`] in a circuit.
+///
+/// [`tree::merkle_opening_with_pos`]: crate::tree::merkle_opening_with_pos
+pub fn derive(sk: &BlsScalar, position: u64) -> BlsScalar {
+    sponge::hash_with_domain(POSITION_DOMAIN, &[*sk, BlsScalar::from(position)])
+}
+
+/// Derive the nullifier of the note of commitment `note_commitment`, owned by
+/// `sk`.
+pub fn derive_from_commitment(
+    sk: &BlsScalar,
+    note_commitment: &BlsScalar,
+) -> BlsScalar {
+    sponge::hash_with_domain(COMMITMENT_DOMAIN, &[*sk, *note_commitment])
+}
+
+/// Mirror the implementation of [`derive`] inside of a PLONK circuit.
+#[cfg(feature = "alloc")]
+pub fn gadget<C>(composer: &mut C, sk: Witness, position: Witness) -> Witness
+where
+    C: Composer,
+{
+    sponge::gadget_with_domain(composer, POSITION_DOMAIN, &[sk, position])
+}
+
+/// Mirror the implementation of [`derive_from_commitment`] inside of a PLONK
+/// circuit.
+#[cfg(feature = "alloc")]
+pub fn gadget_from_commitment<C>(
+    composer: &mut C,
+    sk: Witness,
+    note_commitment: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    sponge::gadget_with_domain(
+        composer,
+        COMMITMENT_DOMAIN,
+        &[sk, note_commitment],
+    )
+}
@@ -0,0 +1,739 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Runtime-loadable parameters for the Poseidon permutation.
+//!
+//! The default instantiation of this crate is the one provided by the hades
+//! [`ScalarStrategy`](hades::ScalarStrategy). [`Params`] allows to experiment
+//! with alternative instantiations: a different width, different round
+//! numbers, or a different set of round constants and MDS matrix. Parameters
+//! can either be loaded from bytes, or generated with the Grain LFSR procedure
+//! described in the Poseidon paper.
+//!
+//! [`Params`] is generic over the prime field of the permutation, the
+//! BLS12-381 scalar field being the default. The circuit counterparts and the
+//! serialization of the parameters are only provided over the BLS12-381
+//! scalar field.
+//!
+//! The permutation follows the same round structure as hades: every round adds
+//! the round constants to the whole state, the partial rounds apply the
+//! quintic S-box to the last element of the state only, and every round ends
+//! with the multiplication by the MDS matrix.
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::{DeserializableSlice, Serializable};
+use ff::PrimeField;
+
+#[cfg(feature = "alloc")]
+use plonk::prelude::{Composer, Constraint, Witness};
+
+/// Partial rounds of the widths from `2` to [`SecurityLevel::MAX_WIDTH`] at
+/// every security level, all of them with eight full rounds.
+const PARTIAL_ROUNDS_80: [usize; 16] = [
+    34, 36, 36, 35, 36, 35, 40, 36, 40, 44, 36, 39, 42, 45, 48, 51,
+];
+const PARTIAL_ROUNDS_128: [usize; 16] = [
+    56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68,
+];
+const PARTIAL_ROUNDS_256: [usize; 16] = [
+    114, 114, 116, 115, 120, 119, 120, 117, 120, 121, 120, 117, 126, 120, 128,
+    119,
+];
+
+/// Security level targeted by a set of [`Params`].
+///
+/// The round numbers are the ones produced by the round numbers script of the
+/// reference implementation of the Poseidon paper for the `x^5` S-box over the
+/// BLS12-381 scalar field, including its security margin of two additional
+/// full rounds and 7.5% additional partial rounds. As in the round numbers
+/// published with the reference implementation, the partial rounds are then
+/// rounded up to a multiple of the width. The script yields the same round
+/// numbers for the other fields of 254 and 255 bits, such as the BN254 and
+/// the Pasta scalar fields.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecurityLevel {
+    /// 80 bits of security.
+    Bits80,
+    /// 128 bits of security.
+    #[default]
+    Bits128,
+    /// 256 bits of security.
+    Bits256,
+}
+
+impl SecurityLevel {
+    /// Maximum width supported by the round numbers of a security level.
+    pub const MAX_WIDTH: usize = 17;
+
+    /// Number of full and partial rounds required for a permutation of the
+    /// given `width` to reach the security level.
+    ///
+    /// Returns `None` if the width is smaller than `2` or bigger than
+    /// [`SecurityLevel::MAX_WIDTH`].
+    pub const fn rounds(&self, width: usize) -> Option<(usize, usize)> {
+        if width < 2 || width > Self::MAX_WIDTH {
+            return None;
+        }
+
+        let partial_rounds = match self {
+            Self::Bits80 => PARTIAL_ROUNDS_80[width - 2],
+            Self::Bits128 => PARTIAL_ROUNDS_128[width - 2],
+            Self::Bits256 => PARTIAL_ROUNDS_256[width - 2],
+        };
+
+        Some((8, partial_rounds))
+    }
+}
+
+/// Errors that can occur while building a set of [`Params`].
+#[derive(Debug)]
+pub enum Error {
+    /// The width of the permutation must be at least `2`.
+    InvalidWidth(usize),
+    /// The number of full rounds must be even.
+    InvalidFullRounds(usize),
+    /// The number of round constants doesn't match the width and rounds.
+    RoundConstants {
+        /// Number of provided round constants.
+        found: usize,
+        /// Number of expected round constants.
+        expected: usize,
+    },
+    /// The number of entries of the MDS matrix doesn't match the width.
+    Mds {
+        /// Number of provided entries.
+        found: usize,
+        /// Number of expected entries.
+        expected: usize,
+    },
+    /// The number of entries of the internal diagonal of a Poseidon2
+    /// permutation doesn't match the width.
+    InternalDiagonal {
+        /// Number of provided entries.
+        found: usize,
+        /// Number of expected entries.
+        expected: usize,
+    },
+    /// The parameters are not correctly encoded.
+    Bytes(bytes::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidWidth(w) => write!(f, "invalid width: {}", w),
+            Self::InvalidFullRounds(r) => {
+                write!(f, "the number of full rounds must be even: {}", r)
+            }
+            Self::RoundConstants { found, expected } => write!(
+                f,
+                "found {} round constants, expected {}",
+                found, expected
+            ),
+            Self::Mds { found, expected } => {
+                write!(f, "found {} MDS entries, expected {}", found, expected)
+            }
+            Self::InternalDiagonal { found, expected } => write!(
+                f,
+                "found {} internal diagonal entries, expected {}",
+                found, expected
+            ),
+            Self::Bytes(e) => write!(f, "invalid parameters bytes: {:?}", e),
+        }
+    }
+}
+
+impl From<bytes::Error> for Error {
+    fn from(e: bytes::Error) -> Self {
+        Self::Bytes(e)
+    }
+}
+
+/// A Poseidon instantiation over the prime field `F`, the BLS12-381 scalar
+/// field by default.
+///
+/// The `x^5` S-box must be a permutation of `F`, that is `5` must not divide
+/// the order of its multiplicative group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Params<F = BlsScalar> {
+    width: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+    round_constants: Vec<F>,
+    mds: Vec<F>,
+}
+
+impl<F> Params<F>
+where
+    F: PrimeField,
+{
+    /// Create a new set of parameters.
+    ///
+    /// `round_constants` must contain `width` constants per round, and `mds`
+    /// is the `width x width` MDS matrix in row-major order.
+    pub fn new(
+        width: usize,
+        full_rounds: usize,
+        partial_rounds: usize,
+        round_constants: Vec<F>,
+        mds: Vec<F>,
+    ) -> Result<Self, Error> {
+        if width < 2 {
+            return Err(Error::InvalidWidth(width));
+        }
+
+        if full_rounds % 2 != 0 {
+            return Err(Error::InvalidFullRounds(full_rounds));
+        }
+
+        let expected = width * (full_rounds + partial_rounds);
+        if round_constants.len() != expected {
+            return Err(Error::RoundConstants {
+                found: round_constants.len(),
+                expected,
+            });
+        }
+
+        if mds.len() != width * width {
+            return Err(Error::Mds {
+                found: mds.len(),
+                expected: width * width,
+            });
+        }
+
+        Ok(Self {
+            width,
+            full_rounds,
+            partial_rounds,
+            round_constants,
+            mds,
+        })
+    }
+
+    /// Generate the parameters with the Grain LFSR, as specified by the
+    /// reference implementation of the Poseidon paper for a prime field and
+    /// the `x^5` S-box.
+    ///
+    /// The round constants are sampled by rejection, and the MDS matrix is the
+    /// Cauchy matrix `1 / (x_i + y_j)` built from the following `2 · width`
+    /// distinct field elements. The additional security checks that the
+    /// reference performs on the MDS matrix are not carried out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width < 2` or `full_rounds` is odd.
+    pub fn generate(
+        width: usize,
+        full_rounds: usize,
+        partial_rounds: usize,
+    ) -> Self {
+        let mut grain = Grain::new::<F>(width, full_rounds, partial_rounds);
+
+        let round_constants = (0..width * (full_rounds + partial_rounds))
+            .map(|_| grain.next_scalar())
+            .collect();
+
+        let mds = loop {
+            let elements: Vec<F> =
+                (0..2 * width).map(|_| grain.next_reduced()).collect();
+
+            let distinct = elements
+                .iter()
+                .enumerate()
+                .all(|(i, e)| !elements[i + 1..].contains(e));
+            if !distinct {
+                continue;
+            }
+
+            let (xs, ys) = elements.split_at(width);
+            let sums: Vec<F> = xs
+                .iter()
+                .flat_map(|x| ys.iter().map(move |y| *x + y))
+                .collect();
+            if sums.iter().any(|s| s.is_zero_vartime()) {
+                continue;
+            }
+
+            break sums.iter().map(|s| s.invert().unwrap()).collect();
+        };
+
+        Self::new(width, full_rounds, partial_rounds, round_constants, mds)
+            .expect("The width and the number of full rounds must be valid")
+    }
+
+    /// Generate, with [`Params::generate`], the parameters of a permutation
+    /// of the given `width` with the round numbers of the security `level`.
+    pub fn with_security(
+        width: usize,
+        level: SecurityLevel,
+    ) -> Result<Self, Error> {
+        let (full_rounds, partial_rounds) =
+            level.rounds(width).ok_or(Error::InvalidWidth(width))?;
+
+        Ok(Self::generate(width, full_rounds, partial_rounds))
+    }
+
+    /// Width of the permutation.
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of full rounds of the permutation.
+    pub const fn full_rounds(&self) -> usize {
+        self.full_rounds
+    }
+
+    /// Number of partial rounds of the permutation.
+    pub const fn partial_rounds(&self) -> usize {
+        self.partial_rounds
+    }
+
+    /// Round constants, `width` per round.
+    pub fn round_constants(&self) -> &[F] {
+        &self.round_constants
+    }
+
+    /// MDS matrix in row-major order.
+    pub fn mds(&self) -> &[F] {
+        &self.mds
+    }
+
+    /// Apply the permutation to `state`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `state` doesn't match the width.
+    pub fn permute(&self, state: &mut [F]) {
+        self.permute_with_partial(state, self.width - 1);
+    }
+
+    /// Apply the permutation to `state`, the partial rounds applying the
+    /// S-box to the element at index `partial` of the state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `state` doesn't match the width.
+    pub(crate) fn permute_with_partial(&self, state: &mut [F], partial: usize) {
+        assert_eq!(
+            state.len(),
+            self.width,
+            "The state must match the width of the parameters"
+        );
+
+        let mut product = vec![F::ZERO; self.width];
+
+        (0..self.full_rounds + self.partial_rounds).for_each(|round| {
+            self.round_with_partial(round, state, &mut product, partial)
+        });
+    }
+
+    /// Return `true` if `round` is a full round, applying the S-box to the
+    /// whole state, and `false` if it's a partial round.
+    pub(crate) const fn is_full_round(&self, round: usize) -> bool {
+        let half = self.full_rounds / 2;
+
+        round < half || round >= half + self.partial_rounds
+    }
+
+    /// Apply the round `round` of the permutation to `state`, using `product`
+    /// as the scratch space of the multiplication by the MDS matrix.
+    pub(crate) fn round(
+        &self,
+        round: usize,
+        state: &mut [F],
+        product: &mut [F],
+    ) {
+        self.round_with_partial(round, state, product, self.width - 1);
+    }
+
+    fn round_with_partial(
+        &self,
+        round: usize,
+        state: &mut [F],
+        product: &mut [F],
+        partial: usize,
+    ) {
+        let constants = &self.round_constants[round * self.width..];
+        state.iter_mut().zip(constants).for_each(|(s, c)| *s += c);
+
+        if self.is_full_round(round) {
+            state.iter_mut().for_each(quintic_s_box);
+        } else {
+            quintic_s_box(&mut state[partial]);
+        }
+
+        product
+            .iter_mut()
+            .zip(self.mds.chunks(self.width))
+            .for_each(|(p, row)| {
+                *p = row
+                    .iter()
+                    .zip(state.iter())
+                    .fold(F::ZERO, |acc, (m, s)| acc + *m * s);
+            });
+        state.copy_from_slice(product);
+    }
+}
+
+impl Params<BlsScalar> {
+    /// Serialize the parameters.
+    ///
+    /// The width and the round numbers are encoded as little-endian `u32`,
+    /// followed by the round constants and the MDS matrix.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            3 * u32::SIZE
+                + (self.round_constants.len() + self.mds.len())
+                    * BlsScalar::SIZE,
+        );
+
+        bytes.extend_from_slice(&(self.width as u32).to_bytes());
+        bytes.extend_from_slice(&(self.full_rounds as u32).to_bytes());
+        bytes.extend_from_slice(&(self.partial_rounds as u32).to_bytes());
+
+        self.round_constants
+            .iter()
+            .chain(self.mds.iter())
+            .for_each(|s| bytes.extend_from_slice(&s.to_bytes()));
+
+        bytes
+    }
+
+    /// Deserialize the parameters from the format of [`Params::to_bytes`].
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, Error> {
+        let width = u32::from_reader(&mut bytes)? as usize;
+        let full_rounds = u32::from_reader(&mut bytes)? as usize;
+        let partial_rounds = u32::from_reader(&mut bytes)? as usize;
+
+        let scalars = width
+            .checked_mul(width + full_rounds + partial_rounds)
+            .ok_or(bytes::Error::InvalidData)?;
+        if bytes.len() != scalars * BlsScalar::SIZE {
+            return Err(bytes::Error::BadLength {
+                found: bytes.len(),
+                expected: scalars * BlsScalar::SIZE,
+            }
+            .into());
+        }
+
+        let round_constants = (0..width * (full_rounds + partial_rounds))
+            .map(|_| BlsScalar::from_reader(&mut bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mds = (0..width * width)
+            .map(|_| BlsScalar::from_reader(&mut bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::new(width, full_rounds, partial_rounds, round_constants, mds)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Params {
+    /// Mirror [`Params::permute`] inside of a PLONK circuit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `state` doesn't match the width.
+    pub fn gadget<C>(&self, composer: &mut C, state: &mut [Witness])
+    where
+        C: Composer,
+    {
+        assert_eq!(
+            state.len(),
+            self.width,
+            "The state must match the width of the parameters"
+        );
+
+        self.round_constants
+            .chunks(self.width)
+            .enumerate()
+            .for_each(|(round, constants)| {
+                state.iter_mut().zip(constants).for_each(|(s, c)| {
+                    let constraint =
+                        Constraint::new().left(1).a(*s).constant(*c);
+                    *s = composer.gate_add(constraint);
+                });
+
+                if self.is_full_round(round) {
+                    state.iter_mut().for_each(|s| {
+                        *s = quintic_s_box_gadget(composer, *s);
+                    });
+                } else {
+                    let last = self.width - 1;
+                    state[last] = quintic_s_box_gadget(composer, state[last]);
+                }
+
+                let product: Vec<Witness> = self
+                    .mds
+                    .chunks(self.width)
+                    .map(|row| {
+                        row.iter().zip(state.iter()).fold(
+                            C::ZERO,
+                            |acc, (m, s)| {
+                                let constraint = Constraint::new()
+                                    .left(1)
+                                    .a(acc)
+                                    .right(*m)
+                                    .b(*s);
+                                composer.gate_add(constraint)
+                            },
+                        )
+                    })
+                    .collect();
+                state.copy_from_slice(&product);
+            });
+    }
+}
+
+#[cfg(feature = "alloc")]
+pub(crate) fn quintic_s_box_gadget<C>(composer: &mut C, s: Witness) -> Witness
+where
+    C: Composer,
+{
+    let constraint = Constraint::new().mult(1).a(s).b(s);
+    let s2 = composer.gate_mul(constraint);
+    let constraint = Constraint::new().mult(1).a(s2).b(s2);
+    let s4 = composer.gate_mul(constraint);
+    let constraint = Constraint::new().mult(1).a(s4).b(s);
+    composer.gate_mul(constraint)
+}
+
+pub(crate) fn quintic_s_box<F>(s: &mut F)
+where
+    F: PrimeField,
+{
+    *s = s.square().square() * *s;
+}
+
+/// The Grain LFSR used to generate the parameters of the permutation.
+pub(crate) struct Grain {
+    bits: [bool; 80],
+    head: usize,
+}
+
+impl Grain {
+    pub(crate) fn new<F>(
+        width: usize,
+        full_rounds: usize,
+        partial_rounds: usize,
+    ) -> Self
+    where
+        F: PrimeField,
+    {
+        Self::with_sbox::<F>(0, width, full_rounds, partial_rounds)
+    }
+
+    /// Seed the LFSR with the given code of the S-box instead of the code of
+    /// `x^alpha`, `0`.
+    pub(crate) fn with_sbox<F>(
+        sbox: u64,
+        width: usize,
+        full_rounds: usize,
+        partial_rounds: usize,
+    ) -> Self
+    where
+        F: PrimeField,
+    {
+        // Field type (prime field), S-box, field size, width, number of full
+        // rounds, number of partial rounds and padding
+        let init: [(u64, usize); 7] = [
+            (1, 2),
+            (sbox, 4),
+            (F::NUM_BITS as u64, 12),
+            (width as u64, 12),
+            (full_rounds as u64, 10),
+            (partial_rounds as u64, 10),
+            ((1 << 30) - 1, 30),
+        ];
+
+        let mut bits = [false; 80];
+        let mut i = 0;
+        for (value, len) in init {
+            for k in (0..len).rev() {
+                bits[i] = (value >> k) & 1 == 1;
+                i += 1;
+            }
+        }
+
+        let mut grain = Self { bits, head: 0 };
+        (0..160).for_each(|_| {
+            grain.next_bit();
+        });
+
+        grain
+    }
+
+    fn next_bit(&mut self) -> bool {
+        let bit = |i: usize| self.bits[(self.head + i) % 80];
+        let new = bit(62) ^ bit(51) ^ bit(38) ^ bit(23) ^ bit(13) ^ bit(0);
+
+        self.bits[self.head] = new;
+        self.head = (self.head + 1) % 80;
+
+        new
+    }
+
+    fn filtered_bit(&mut self) -> bool {
+        loop {
+            let keep = self.next_bit();
+            let bit = self.next_bit();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    /// Next bits of the size of the modulus of `F`, most significant first.
+    fn next_bits<F>(&mut self) -> Vec<bool>
+    where
+        F: PrimeField,
+    {
+        (0..F::NUM_BITS).map(|_| self.filtered_bit()).collect()
+    }
+
+    /// Next field element, sampled by rejection.
+    pub(crate) fn next_scalar<F>(&mut self) -> F
+    where
+        F: PrimeField,
+    {
+        loop {
+            if let Some(scalar) = canonical(&self.next_bits::<F>()) {
+                return scalar;
+            }
+        }
+    }
+
+    /// Next field element, reduced modulo the field order.
+    fn next_reduced<F>(&mut self) -> F
+    where
+        F: PrimeField,
+    {
+        self.next_bits::<F>().iter().fold(F::ZERO, |acc, bit| {
+            let acc = acc.double();
+            match *bit {
+                true => acc + F::ONE,
+                false => acc,
+            }
+        })
+    }
+}
+
+/// Decode the field element with the given bits, most significant first, if
+/// they encode an integer smaller than the modulus.
+fn canonical<F>(bits: &[bool]) -> Option<F>
+where
+    F: PrimeField,
+{
+    let mut repr = F::Repr::default();
+    let bytes = repr.as_mut();
+
+    // The endianness of the representation isn't specified by `PrimeField`
+    let little_endian = F::ONE.to_repr().as_ref()[0] == 1;
+    let len = bytes.len();
+
+    bits.iter()
+        .rev()
+        .enumerate()
+        .filter(|(_, bit)| **bit)
+        .for_each(|(i, _)| {
+            let byte = match little_endian {
+                true => i / 8,
+                false => len - 1 - i / 8,
+            };
+            bytes[byte] |= 1 << (i % 8);
+        });
+
+    F::from_repr(repr).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sponge;
+
+    #[test]
+    fn grain_round_constants() {
+        let params: Params = Params::generate(5, 8, 60);
+
+        // Values produced by the reference Grain LFSR script of the paper
+        let first = BlsScalar::from_raw([
+            0x6a56db98fa925158,
+            0xe42fa9aa510d1158,
+            0x006e97a15a7609dc,
+            0x5ee52b2f39e240a4,
+        ]);
+        let last = BlsScalar::from_raw([
+            0x43d147645c3944ca,
+            0x7d38cb4a0e12a15d,
+            0xd3575eb2380b466f,
+            0x20f955773b13b160,
+        ]);
+
+        assert_eq!(params.round_constants().len(), 5 * 68);
+        assert_eq!(params.round_constants()[0], first);
+        assert_eq!(params.round_constants()[5 * 68 - 1], last);
+    }
+
+    #[test]
+    fn params_bytes() {
+        let params: Params = Params::generate(3, 8, 57);
+        let parsed = Params::from_bytes(&params.to_bytes())
+            .expect("Parsing should succeed");
+
+        assert_eq!(params, parsed);
+
+        let bytes = params.to_bytes();
+        assert!(Params::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(matches!(
+            Params::<BlsScalar>::new(1, 8, 57, vec![], vec![]),
+            Err(Error::InvalidWidth(1))
+        ));
+    }
+
+    #[test]
+    fn security_levels() {
+        // Round numbers published with the reference implementation
+        assert_eq!(SecurityLevel::default().rounds(3), Some((8, 57)));
+        assert_eq!(SecurityLevel::default().rounds(5), Some((8, 60)));
+        assert_eq!(SecurityLevel::Bits128.rounds(9), Some((8, 63)));
+        assert_eq!(SecurityLevel::Bits128.rounds(17), Some((8, 68)));
+        assert_eq!(SecurityLevel::Bits80.rounds(9), Some((8, 36)));
+        assert_eq!(SecurityLevel::Bits256.rounds(1), None);
+        assert_eq!(SecurityLevel::Bits256.rounds(18), None);
+
+        let params: Params = Params::with_security(3, SecurityLevel::Bits256)
+            .expect("The width should be supported");
+        assert_eq!(params.full_rounds(), 8);
+        assert_eq!(params.partial_rounds(), 114);
+
+        for width in 2..=SecurityLevel::MAX_WIDTH {
+            for level in [
+                SecurityLevel::Bits80,
+                SecurityLevel::Bits128,
+                SecurityLevel::Bits256,
+            ] {
+                let (_, partial_rounds) =
+                    level.rounds(width).expect("The width should be supported");
+                assert_eq!(partial_rounds % width, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn hash_with_params() {
+        let params: Params = Params::generate(3, 8, 57);
+        let message = [BlsScalar::from(42u64), BlsScalar::zero()];
+
+        let h = sponge::hash_with_params(&params, &message);
+        assert_eq!(h, sponge::hash_with_params(&params, &message));
+        assert_ne!(h, sponge::hash_with_params(&params, &message[..1]));
+        assert_ne!(h, sponge::hash(&message));
+    }
+}
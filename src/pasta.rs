@@ -0,0 +1,48 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Poseidon instantiations over the base fields of the Pallas and Vesta
+//! curves, the cycle used by the recursive Halo2 proof systems.
+//!
+//! The parameters are generated with the Grain LFSR of the reference
+//! implementation, as [`Params::generate`] does for any field. The sponge of
+//! this crate is computed over them with [`sponge::hash_with_params`], and the
+//! fixed-arity compression with [`compress::jive_with`].
+//!
+//! [`sponge::hash_with_params`]: crate::sponge::hash_with_params
+//! [`compress::jive_with`]: crate::compress::jive_with
+
+use crate::params::{Error, Params, SecurityLevel};
+
+pub use pasta_curves::{Fp as PallasBase, Fq as VestaBase};
+
+/// Parameters of a permutation over the base field of Pallas.
+pub type PallasParams = Params<PallasBase>;
+
+/// Parameters of a permutation over the base field of Vesta.
+pub type VestaParams = Params<VestaBase>;
+
+/// Generate the parameters of a permutation of the given `width` over the
+/// base field of Pallas, with the round numbers of the security `level`.
+///
+/// Return an error if the width isn't supported by the security level.
+pub fn pallas_params(
+    width: usize,
+    level: SecurityLevel,
+) -> Result<PallasParams, Error> {
+    Params::with_security(width, level)
+}
+
+/// Generate the parameters of a permutation of the given `width` over the
+/// base field of Vesta, with the round numbers of the security `level`.
+///
+/// Return an error if the width isn't supported by the security level.
+pub fn vesta_params(
+    width: usize,
+    level: SecurityLevel,
+) -> Result<VestaParams, Error> {
+    Params::with_security(width, level)
+}
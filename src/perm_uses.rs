@@ -6,14 +6,24 @@
 
 //! The `pad` module implements the padding algorithm on the Poseidon hash.
 
+use alloc::vec;
+
 use bls12_381::{Scalar as BlsScalar};
 use hades::{ScalarStrategy, Strategy};
 
+use crate::permutation::Permutation;
+
+#[cfg(feature = "alloc")]
+use hades::{GadgetStrategy, WIDTH};
+#[cfg(feature = "alloc")]
+use plonk::prelude::*;
+
+/// Capacity of the permutation of [`two_outputs`].
+const CAPACITY: BlsScalar = BlsScalar::from_raw([0, 1, 0, 0]);
+
 /// Takes in one BlsScalar and outputs 2.
 /// This function is fixed.
 pub fn two_outputs(message: BlsScalar) -> [BlsScalar; 2] {
-    const CAPACITY: BlsScalar = BlsScalar::from_raw([0, 1, 0, 0]);
-
     let mut words = [BlsScalar::zero(); hades::WIDTH];
 
     words[0] = CAPACITY;
@@ -27,9 +37,59 @@ pub fn two_outputs(message: BlsScalar) -> [BlsScalar; 2] {
     [words[1], words[2]]
 }
 
+/// Perform the same fixed-length hash as [`two_outputs`] with any
+/// [`Permutation`], over the field of the permutation.
+///
+/// # Panics
+///
+/// Panics if the width of `perm` is smaller than `3`, leaving no room for the
+/// two outputs next to the capacity.
+pub fn two_outputs_with<P>(perm: &P, message: P::Scalar) -> [P::Scalar; 2]
+where
+    P: Permutation,
+{
+    assert!(
+        perm.width() >= 3,
+        "the permutation should be at least three elements wide"
+    );
+
+    let mut words = vec![P::Scalar::ZERO; perm.width()];
+
+    // The capacity of `two_outputs`, `2^64`
+    words[0] = P::Scalar::from(u64::MAX) + P::Scalar::ONE;
+    words[1] = message;
+
+    perm.permute(&mut words);
+
+    [words[1], words[2]]
+}
+
+/// Mirror the implementation of [`two_outputs`] inside of a PLONK circuit.
+///
+/// Both outputs are taken from a single permutation, so deriving a pair of
+/// values costs the constraints of one permutation only.
+#[cfg(feature = "alloc")]
+pub fn two_outputs_gadget<C>(
+    composer: &mut C,
+    message: Witness,
+) -> (Witness, Witness)
+where
+    C: Composer,
+{
+    let mut words = [C::ZERO; WIDTH];
+
+    words[0] = composer.append_constant(CAPACITY);
+    words[1] = message;
+
+    GadgetStrategy::gadget(composer, &mut words);
+
+    (words[1], words[2])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::permutation::Hades;
     use ff::Field;
     use rand::rngs::OsRng;
 
@@ -56,4 +116,11 @@ mod tests {
             assert_eq!(h, h_1);
         }
     }
+
+    #[test]
+    fn two_outputs_with_hades() {
+        let m = BlsScalar::random(&mut OsRng);
+
+        assert_eq!(two_outputs(m), two_outputs_with(&Hades, m));
+    }
 }
@@ -0,0 +1,206 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Abstraction over the algebraic permutation used by the sponge.
+//!
+//! [`Permutation`] and [`PermutationGadget`] allow to instantiate the sponge
+//! constructions of this crate with any permutation, such as Rescue-Prime,
+//! Griffin or Anemoi, without re-implementing the padding and absorption
+//! rules. [`Hades`] is the default instantiation, and [`Params`] and
+//! [`Poseidon2`] implement both traits as well.
+//!
+//! A [`Permutation`] may operate on any prime field, and [`hash_with`],
+//! [`two_outputs_with`] and [`compute_root_with`] are computed over the field
+//! of the permutation. The functions backed by `Hades`, such as [`hash`], and
+//! the trees, the branches, the cipher and the circuits are defined over the
+//! BLS12-381 scalar field only.
+//!
+//! The sponge takes a permutation of any width of at least two elements. The
+//! trees and the cipher are laid out for the width of `Hades`, and take any
+//! permutation of that same width: see [`compute_root_with`],
+//! [`PoseidonBranch::verify_with`] and [`PoseidonCipher::encrypt_with`], along
+//! with their gadgets.
+//!
+//! [`hash`]: crate::sponge::hash
+//! [`hash_with`]: crate::sponge::hash_with
+//! [`two_outputs_with`]: crate::perm_uses::two_outputs_with
+//! [`compute_root_with`]: crate::tree::compute_root_with
+//! [`PoseidonBranch::verify_with`]: crate::tree::PoseidonBranch::verify_with
+//! [`PoseidonCipher::encrypt_with`]: crate::cipher::PoseidonCipher::encrypt_with
+
+use bls12_381::Scalar as BlsScalar;
+use ff::PrimeField;
+use hades::{ScalarStrategy, Strategy, WIDTH};
+
+use crate::params::Params;
+use crate::poseidon2::Poseidon2;
+
+#[cfg(feature = "alloc")]
+use hades::GadgetStrategy;
+#[cfg(feature = "alloc")]
+use plonk::prelude::{Composer, Witness};
+
+/// A permutation over a prime field.
+pub trait Permutation {
+    /// Field of the elements of the state.
+    type Scalar: PrimeField;
+
+    /// Number of elements of the state the permutation operates on.
+    fn width(&self) -> usize;
+
+    /// Apply the permutation to `state`, of [`Permutation::width`] elements.
+    fn permute(&self, state: &mut [Self::Scalar]);
+}
+
+/// The circuit counterpart of a [`Permutation`].
+#[cfg(feature = "alloc")]
+pub trait PermutationGadget {
+    /// Number of elements of the state the permutation operates on.
+    fn width(&self) -> usize;
+
+    /// Apply the permutation to `state`, of [`PermutationGadget::width`]
+    /// witnesses, inside of a PLONK circuit.
+    fn permute_gadget<C>(&self, composer: &mut C, state: &mut [Witness])
+    where
+        C: Composer;
+}
+
+/// Assert that a permutation of `width` elements leaves room for a message
+/// next to the capacity of a sponge.
+pub(crate) fn assert_sponge_width(width: usize) {
+    assert!(
+        width >= 2,
+        "the permutation should be at least two elements wide"
+    );
+}
+
+/// The `Hades` permutation provided by the hades crate, used by default
+/// throughout this crate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Hades;
+
+impl Permutation for Hades {
+    type Scalar = BlsScalar;
+
+    fn width(&self) -> usize {
+        WIDTH
+    }
+
+    fn permute(&self, state: &mut [BlsScalar]) {
+        ScalarStrategy::new().perm(state);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PermutationGadget for Hades {
+    fn width(&self) -> usize {
+        WIDTH
+    }
+
+    fn permute_gadget<C>(&self, composer: &mut C, state: &mut [Witness])
+    where
+        C: Composer,
+    {
+        GadgetStrategy::gadget(composer, state);
+    }
+}
+
+impl<F> Permutation for Params<F>
+where
+    F: PrimeField,
+{
+    type Scalar = F;
+
+    fn width(&self) -> usize {
+        Params::width(self)
+    }
+
+    fn permute(&self, state: &mut [F]) {
+        Params::permute(self, state);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PermutationGadget for Params {
+    fn width(&self) -> usize {
+        Params::width(self)
+    }
+
+    fn permute_gadget<C>(&self, composer: &mut C, state: &mut [Witness])
+    where
+        C: Composer,
+    {
+        self.gadget(composer, state);
+    }
+}
+
+impl<F> Permutation for Poseidon2<F>
+where
+    F: PrimeField,
+{
+    type Scalar = F;
+
+    fn width(&self) -> usize {
+        Poseidon2::width(self)
+    }
+
+    fn permute(&self, state: &mut [F]) {
+        Poseidon2::permute(self, state);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PermutationGadget for Poseidon2 {
+    fn width(&self) -> usize {
+        Poseidon2::width(self)
+    }
+
+    fn permute_gadget<C>(&self, composer: &mut C, state: &mut [Witness])
+    where
+        C: Composer,
+    {
+        self.gadget(composer, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sponge;
+    use alloc::vec::Vec;
+
+    /// A permutation too narrow to hold a message next to the capacity.
+    struct Narrow;
+
+    impl Permutation for Narrow {
+        type Scalar = BlsScalar;
+
+        fn width(&self) -> usize {
+            1
+        }
+
+        fn permute(&self, _state: &mut [BlsScalar]) {}
+    }
+
+    #[test]
+    #[should_panic]
+    fn hash_with_narrow() {
+        sponge::hash_with(&Narrow, &[BlsScalar::one()]);
+    }
+
+    #[test]
+    fn hash_with_hades() {
+        let messages: Vec<BlsScalar> =
+            (0..10u64).map(BlsScalar::from).collect();
+
+        for l in 0..messages.len() {
+            assert_eq!(
+                sponge::hash(&messages[..l]),
+                sponge::hash_with(&Hades, &messages[..l])
+            );
+        }
+    }
+}
@@ -0,0 +1,435 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! The Poseidon2 permutation.
+//!
+//! Poseidon2 keeps the quintic S-box and the full and partial rounds of
+//! Poseidon, and replaces the dense MDS matrix with two cheaper linear layers:
+//!
+//! - the external rounds add a round constant to every element, apply the
+//!   S-box to the whole state and multiply it by a matrix built from `4 x 4`
+//!   blocks, which only takes additions and doublings;
+//! - the internal rounds add a single round constant to the first element,
+//!   apply the S-box to it and multiply the state by `J + diag(d)`, where `J`
+//!   is the all-ones matrix, which takes one multiplication per element.
+//!
+//! As in the reference implementation of the Poseidon2 paper, the external
+//! linear layer is also applied once before the first round. The external
+//! matrix is defined for the widths `2`, `3` and the multiples of `4`.
+//!
+//! [`Poseidon2`] implements [`Permutation`] and, over the BLS12-381 scalar
+//! field, [`PermutationGadget`], so it can be selected in place of `Hades` by
+//! [`sponge::hash_with`], [`sponge::gadget_with`] and [`compress::jive_with`].
+//! The trees and the cipher are laid out for the width of `Hades`, which the
+//! external matrix of Poseidon2 isn't defined for.
+//!
+//! [`Permutation`]: crate::permutation::Permutation
+//! [`PermutationGadget`]: crate::permutation::PermutationGadget
+//! [`sponge::hash_with`]: crate::sponge::hash_with
+//! [`sponge::gadget_with`]: crate::sponge::gadget_with
+//! [`compress::jive_with`]: crate::compress::jive_with
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use bls12_381::Scalar as BlsScalar;
+use ff::PrimeField;
+
+use crate::params::{quintic_s_box, Error, Grain};
+
+#[cfg(feature = "alloc")]
+use crate::params::quintic_s_box_gadget;
+#[cfg(feature = "alloc")]
+use plonk::prelude::{Composer, Constraint, Witness};
+
+/// The `4 x 4` block of the external matrix of the widths multiple of `4`.
+#[cfg(feature = "alloc")]
+const M4: [[u64; 4]; 4] =
+    [[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]];
+
+/// Parameters of a Poseidon2 permutation over the prime field `F`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Poseidon2<F = BlsScalar> {
+    width: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+    external_constants: Vec<F>,
+    internal_constants: Vec<F>,
+    internal_diagonal: Vec<F>,
+}
+
+impl<F> Poseidon2<F>
+where
+    F: PrimeField,
+{
+    /// Create a new Poseidon2 permutation.
+    ///
+    /// `external_constants` must contain `width` constants per full round, in
+    /// the order of the rounds, and `internal_constants` one constant per
+    /// partial round. `internal_diagonal` is the diagonal `d` of the internal
+    /// matrix `J + diag(d)`, of `width` entries.
+    pub fn new(
+        width: usize,
+        full_rounds: usize,
+        partial_rounds: usize,
+        external_constants: Vec<F>,
+        internal_constants: Vec<F>,
+        internal_diagonal: Vec<F>,
+    ) -> Result<Self, Error> {
+        if !supported_width(width) {
+            return Err(Error::InvalidWidth(width));
+        }
+
+        if full_rounds % 2 != 0 {
+            return Err(Error::InvalidFullRounds(full_rounds));
+        }
+
+        let expected = width * full_rounds + partial_rounds;
+        let found = external_constants.len() + internal_constants.len();
+        if external_constants.len() != width * full_rounds
+            || internal_constants.len() != partial_rounds
+        {
+            return Err(Error::RoundConstants { found, expected });
+        }
+
+        if internal_diagonal.len() != width {
+            return Err(Error::InternalDiagonal {
+                found: internal_diagonal.len(),
+                expected: width,
+            });
+        }
+
+        Ok(Self {
+            width,
+            full_rounds,
+            partial_rounds,
+            external_constants,
+            internal_constants,
+            internal_diagonal,
+        })
+    }
+
+    /// Generate a permutation of width `2` or `3` with the Grain LFSR, as the
+    /// reference implementation of the Poseidon2 paper does.
+    ///
+    /// The internal matrices of these widths are the fixed matrices of the
+    /// paper, with the diagonals `[1, 2]` and `[1, 1, 2]`. The other widths
+    /// require a diagonal chosen for the field, see
+    /// [`Poseidon2::generate_with_diagonal`].
+    pub fn generate(
+        width: usize,
+        full_rounds: usize,
+        partial_rounds: usize,
+    ) -> Result<Self, Error> {
+        let internal_diagonal = match width {
+            2 => vec![F::ONE, F::ONE.double()],
+            3 => vec![F::ONE, F::ONE, F::ONE.double()],
+            _ => return Err(Error::InvalidWidth(width)),
+        };
+
+        Self::generate_with_diagonal(
+            width,
+            full_rounds,
+            partial_rounds,
+            internal_diagonal,
+        )
+    }
+
+    /// Generate the round constants of a permutation with the Grain LFSR, as
+    /// the reference implementation of the Poseidon2 paper does, using the
+    /// given diagonal of the internal matrix.
+    ///
+    /// The constants are sampled by rejection from a single stream, `width`
+    /// per full round and one per partial round, in the order of the rounds.
+    pub fn generate_with_diagonal(
+        width: usize,
+        full_rounds: usize,
+        partial_rounds: usize,
+        internal_diagonal: Vec<F>,
+    ) -> Result<Self, Error> {
+        if !supported_width(width) {
+            return Err(Error::InvalidWidth(width));
+        }
+
+        if full_rounds % 2 != 0 {
+            return Err(Error::InvalidFullRounds(full_rounds));
+        }
+
+        let mut grain = Grain::new::<F>(width, full_rounds, partial_rounds);
+        let half = full_rounds / 2;
+
+        let mut external_constants: Vec<F> =
+            (0..half * width).map(|_| grain.next_scalar()).collect();
+        let internal_constants =
+            (0..partial_rounds).map(|_| grain.next_scalar()).collect();
+        external_constants
+            .extend((0..half * width).map(|_| grain.next_scalar()));
+
+        Self::new(
+            width,
+            full_rounds,
+            partial_rounds,
+            external_constants,
+            internal_constants,
+            internal_diagonal,
+        )
+    }
+
+    /// Number of elements of the state.
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of full rounds.
+    pub const fn full_rounds(&self) -> usize {
+        self.full_rounds
+    }
+
+    /// Number of partial rounds.
+    pub const fn partial_rounds(&self) -> usize {
+        self.partial_rounds
+    }
+
+    /// Round constants of the full rounds, `width` per round.
+    pub fn external_constants(&self) -> &[F] {
+        &self.external_constants
+    }
+
+    /// Round constants of the partial rounds, one per round.
+    pub fn internal_constants(&self) -> &[F] {
+        &self.internal_constants
+    }
+
+    /// Diagonal `d` of the internal matrix `J + diag(d)`.
+    pub fn internal_diagonal(&self) -> &[F] {
+        &self.internal_diagonal
+    }
+
+    /// Apply the permutation to `state`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `state` doesn't match the width.
+    pub fn permute(&self, state: &mut [F]) {
+        assert_eq!(
+            state.len(),
+            self.width,
+            "The state must match the width of the permutation"
+        );
+
+        let (first, last) = self
+            .external_constants
+            .split_at(self.width * self.full_rounds / 2);
+
+        self.external_layer(state);
+
+        first
+            .chunks(self.width)
+            .for_each(|constants| self.external_round(constants, state));
+
+        self.internal_constants.iter().for_each(|c| {
+            state[0] += c;
+            quintic_s_box(&mut state[0]);
+            self.internal_layer(state);
+        });
+
+        last.chunks(self.width)
+            .for_each(|constants| self.external_round(constants, state));
+    }
+
+    fn external_round(&self, constants: &[F], state: &mut [F]) {
+        state.iter_mut().zip(constants).for_each(|(s, c)| {
+            *s += c;
+            quintic_s_box(s);
+        });
+        self.external_layer(state);
+    }
+
+    fn external_layer(&self, state: &mut [F]) {
+        match self.width {
+            2 | 3 => {
+                let sum = state.iter().fold(F::ZERO, |acc, s| acc + s);
+                state.iter_mut().for_each(|s| *s += sum);
+            }
+            4 => m4(state),
+            _ => {
+                state.chunks_mut(4).for_each(m4);
+
+                let mut sums = [F::ZERO; 4];
+                state.chunks(4).for_each(|block| {
+                    sums.iter_mut().zip(block).for_each(|(acc, s)| *acc += s);
+                });
+                state.chunks_mut(4).for_each(|block| {
+                    block.iter_mut().zip(sums).for_each(|(s, t)| *s += t)
+                });
+            }
+        }
+    }
+
+    fn internal_layer(&self, state: &mut [F]) {
+        let sum = state.iter().fold(F::ZERO, |acc, s| acc + s);
+        state
+            .iter_mut()
+            .zip(self.internal_diagonal.iter())
+            .for_each(|(s, d)| *s = *s * d + sum);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Poseidon2 {
+    /// Mirror [`Poseidon2::permute`] inside of a PLONK circuit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `state` doesn't match the width.
+    pub fn gadget<C>(&self, composer: &mut C, state: &mut [Witness])
+    where
+        C: Composer,
+    {
+        assert_eq!(
+            state.len(),
+            self.width,
+            "The state must match the width of the permutation"
+        );
+
+        let (first, last) = self
+            .external_constants
+            .split_at(self.width * self.full_rounds / 2);
+
+        self.external_layer_gadget(composer, state);
+
+        first.chunks(self.width).for_each(|constants| {
+            self.external_round_gadget(composer, constants, state)
+        });
+
+        self.internal_constants.iter().for_each(|c| {
+            let constraint = Constraint::new().left(1).a(state[0]).constant(*c);
+            state[0] = composer.gate_add(constraint);
+            state[0] = quintic_s_box_gadget(composer, state[0]);
+
+            let sum = sum_gadget(composer, state.iter());
+            state
+                .iter_mut()
+                .zip(self.internal_diagonal.iter())
+                .for_each(|(s, d)| {
+                    let constraint =
+                        Constraint::new().left(*d).a(*s).right(1).b(sum);
+                    *s = composer.gate_add(constraint);
+                });
+        });
+
+        last.chunks(self.width).for_each(|constants| {
+            self.external_round_gadget(composer, constants, state)
+        });
+    }
+
+    fn external_round_gadget<C>(
+        &self,
+        composer: &mut C,
+        constants: &[BlsScalar],
+        state: &mut [Witness],
+    ) where
+        C: Composer,
+    {
+        state.iter_mut().zip(constants).for_each(|(s, c)| {
+            let constraint = Constraint::new().left(1).a(*s).constant(*c);
+            *s = composer.gate_add(constraint);
+            *s = quintic_s_box_gadget(composer, *s);
+        });
+        self.external_layer_gadget(composer, state);
+    }
+
+    fn external_layer_gadget<C>(&self, composer: &mut C, state: &mut [Witness])
+    where
+        C: Composer,
+    {
+        match self.width {
+            2 | 3 => {
+                let sum = sum_gadget(composer, state.iter());
+                state.iter_mut().for_each(|s| {
+                    let constraint =
+                        Constraint::new().left(1).a(*s).right(1).b(sum);
+                    *s = composer.gate_add(constraint);
+                });
+            }
+            4 => m4_gadget(composer, state),
+            _ => {
+                state
+                    .chunks_mut(4)
+                    .for_each(|block| m4_gadget(composer, block));
+
+                let sums: Vec<Witness> = (0..4)
+                    .map(|i| {
+                        sum_gadget(composer, state.iter().skip(i).step_by(4))
+                    })
+                    .collect();
+                state.chunks_mut(4).for_each(|block| {
+                    block.iter_mut().zip(sums.iter()).for_each(|(s, t)| {
+                        let constraint =
+                            Constraint::new().left(1).a(*s).right(1).b(*t);
+                        *s = composer.gate_add(constraint);
+                    });
+                });
+            }
+        }
+    }
+}
+
+/// Return `true` if the external matrix of Poseidon2 is defined for `width`.
+const fn supported_width(width: usize) -> bool {
+    matches!(width, 2 | 3) || (width >= 4 && width % 4 == 0)
+}
+
+/// Multiply a block of four elements by the `4 x 4` matrix of the paper,
+/// with the addition chain of the reference implementation.
+fn m4<F>(block: &mut [F])
+where
+    F: PrimeField,
+{
+    let t0 = block[0] + block[1];
+    let t1 = block[2] + block[3];
+    let t2 = block[1].double() + t1;
+    let t3 = block[3].double() + t0;
+    let t4 = t1.double().double() + t3;
+    let t5 = t0.double().double() + t2;
+    let t6 = t3 + t5;
+    let t7 = t2 + t4;
+
+    block[0] = t6;
+    block[1] = t5;
+    block[2] = t7;
+    block[3] = t4;
+}
+
+#[cfg(feature = "alloc")]
+fn m4_gadget<C>(composer: &mut C, block: &mut [Witness])
+where
+    C: Composer,
+{
+    let product: Vec<Witness> = M4
+        .iter()
+        .map(|row| {
+            row.iter().zip(block.iter()).fold(C::ZERO, |acc, (m, s)| {
+                let constraint =
+                    Constraint::new().left(1).a(acc).right(*m).b(*s);
+                composer.gate_add(constraint)
+            })
+        })
+        .collect();
+    block.copy_from_slice(&product);
+}
+
+#[cfg(feature = "alloc")]
+fn sum_gadget<'a, C, I>(composer: &mut C, witnesses: I) -> Witness
+where
+    C: Composer,
+    I: Iterator<Item = &'a Witness>,
+{
+    witnesses.fold(C::ZERO, |acc, s| {
+        let constraint = Constraint::new().left(1).a(acc).right(1).b(*s);
+        composer.gate_add(constraint)
+    })
+}
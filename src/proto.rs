@@ -0,0 +1,159 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Protobuf messages for the types of this crate.
+//!
+//! The messages are derived with [`prost`] and mirror the definitions found in
+//! `proto/poseidon.proto`, so services written in other languages can generate
+//! compatible bindings from that file. Every scalar is carried as its 32
+//! canonical little-endian bytes.
+
+use alloc::vec::Vec;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::Serializable;
+
+/// Root of a poseidon tree.
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct Root {
+    /// Canonical bytes of the root.
+    #[prost(bytes = "vec", tag = "1")]
+    pub value: Vec<u8>,
+}
+
+impl From<&BlsScalar> for Root {
+    fn from(root: &BlsScalar) -> Self {
+        Self {
+            value: root.to_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<&Root> for BlsScalar {
+    type Error = bytes::Error;
+
+    fn try_from(root: &Root) -> Result<Self, Self::Error> {
+        scalar_from_slice(&root.value)
+    }
+}
+
+fn scalar_from_slice(bytes: &[u8]) -> Result<BlsScalar, bytes::Error> {
+    let mut buf = [0u8; BlsScalar::SIZE];
+    if bytes.len() != buf.len() {
+        return Err(bytes::Error::BadLength {
+            found: bytes.len(),
+            expected: buf.len(),
+        });
+    }
+    buf.copy_from_slice(bytes);
+
+    BlsScalar::from_bytes(&buf)
+}
+
+#[cfg(any(feature = "alloc", feature = "verify-only"))]
+pub use self::tree::{Branch, Level};
+
+#[cfg(any(feature = "alloc", feature = "verify-only"))]
+mod tree {
+    use super::scalar_from_slice;
+
+    use crate::tree::{PoseidonBranch, PoseidonLevel};
+
+    use alloc::vec::Vec;
+
+    use bytes::Serializable;
+
+    /// Level of a merkle opening.
+    #[derive(Clone, PartialEq, Eq, prost::Message)]
+    pub struct Level {
+        /// Position of the opened node in the level.
+        #[prost(uint64, tag = "1")]
+        pub offset: u64,
+        /// Bitflag of the present nodes followed by their hashes.
+        #[prost(bytes = "vec", repeated, tag = "2")]
+        pub level: Vec<Vec<u8>>,
+    }
+
+    /// Merkle opening of a poseidon tree.
+    #[derive(Clone, PartialEq, Eq, prost::Message)]
+    pub struct Branch {
+        /// Canonical bytes of the root.
+        #[prost(bytes = "vec", tag = "1")]
+        pub root: Vec<u8>,
+        /// Levels of the opening, from the leaf up to the root.
+        #[prost(message, repeated, tag = "2")]
+        pub path: Vec<Level>,
+    }
+
+    impl From<&PoseidonLevel> for Level {
+        fn from(level: &PoseidonLevel) -> Self {
+            Self {
+                offset: level.index,
+                level: level
+                    .level
+                    .iter()
+                    .map(|scalar| scalar.to_bytes().to_vec())
+                    .collect(),
+            }
+        }
+    }
+
+    impl TryFrom<&Level> for PoseidonLevel {
+        type Error = bytes::Error;
+
+        fn try_from(proto: &Level) -> Result<Self, Self::Error> {
+            if proto.level.len() != hades::WIDTH {
+                return Err(bytes::Error::BadLength {
+                    found: proto.level.len(),
+                    expected: hades::WIDTH,
+                });
+            }
+
+            if proto.offset == 0 || proto.offset >= hades::WIDTH as u64 {
+                return Err(bytes::Error::InvalidData);
+            }
+
+            let mut level = PoseidonLevel::default();
+            for (scalar, bytes) in level.level.iter_mut().zip(&proto.level) {
+                *scalar = scalar_from_slice(bytes)?;
+            }
+            level.index = proto.offset;
+
+            Ok(level)
+        }
+    }
+
+    impl<const DEPTH: usize> From<&PoseidonBranch<DEPTH>> for Branch {
+        fn from(branch: &PoseidonBranch<DEPTH>) -> Self {
+            Self {
+                root: branch.root.to_bytes().to_vec(),
+                path: branch.path.iter().map(Level::from).collect(),
+            }
+        }
+    }
+
+    impl<const DEPTH: usize> TryFrom<&Branch> for PoseidonBranch<DEPTH> {
+        type Error = bytes::Error;
+
+        fn try_from(proto: &Branch) -> Result<Self, Self::Error> {
+            if proto.path.len() != DEPTH {
+                return Err(bytes::Error::BadLength {
+                    found: proto.path.len(),
+                    expected: DEPTH,
+                });
+            }
+
+            let mut path = [PoseidonLevel::default(); DEPTH];
+            for (level, proto) in path.iter_mut().zip(&proto.path) {
+                *level = PoseidonLevel::try_from(proto)?;
+            }
+
+            let root = scalar_from_slice(&proto.root)?;
+
+            Ok(PoseidonBranch { path, root })
+        }
+    }
+}
@@ -0,0 +1,206 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! R1CS gadgets over `ark-relations`, for the circuits of the arkworks
+//! ecosystem such as the Groth16 ones.
+//!
+//! The gadgets mirror the sponge and the merkle openings of this crate
+//! instantiated with a set of [`Params`]: [`sponge_gadget`] computes the same
+//! hash as [`sponge::hash_with_params`], and [`merkle_opening`] recomputes the
+//! same root as [`PoseidonBranch::verify_with`]. The constants of the `Hades`
+//! permutation aren't exported by the hades crate, so the default
+//! instantiation is reproduced by loading its round constants and MDS matrix
+//! with [`Params::new`].
+//!
+//! The gadgets only append constraints to the [`ConstraintSystemRef`] of the
+//! messages and leaves they're given, so they can be called from any
+//! [`ConstraintSynthesizer`].
+//!
+//! [`sponge::hash_with_params`]: crate::sponge::hash_with_params
+//! [`ConstraintSynthesizer`]: ark_relations::r1cs::ConstraintSynthesizer
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use ark_bls12_381::Fr;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::boolean::Boolean;
+use ark_r1cs_std::eq::EqGadget;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::fields::FieldVar;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+use crate::arkworks::to_ark;
+use crate::params::Params;
+use crate::permutation::assert_sponge_width;
+use crate::tree::{assert_tree_width, PoseidonBranch};
+
+/// Mirror [`Params::permute`] in R1CS.
+///
+/// The round constants and the MDS matrix are multiplied into linear
+/// combinations, so only the S-boxes cost constraints, three per S-box.
+///
+/// # Panics
+///
+/// Panics if the length of `state` doesn't match the width.
+pub fn permute(
+    params: &Params,
+    state: &mut [FpVar<Fr>],
+) -> Result<(), SynthesisError> {
+    let width = params.width();
+    assert_eq!(
+        state.len(),
+        width,
+        "The state must match the width of the parameters"
+    );
+
+    let mds: Vec<Fr> = params.mds().iter().map(to_ark).collect();
+
+    let rounds = params.round_constants().chunks(width).enumerate();
+    for (round, constants) in rounds {
+        for (s, c) in state.iter_mut().zip(constants) {
+            *s += to_ark(c);
+        }
+
+        if params.is_full_round(round) {
+            for s in state.iter_mut() {
+                *s = quintic_s_box(s)?;
+            }
+        } else {
+            state[width - 1] = quintic_s_box(&state[width - 1])?;
+        }
+
+        let product: Vec<FpVar<Fr>> = mds
+            .chunks(width)
+            .map(|row| {
+                row.iter()
+                    .zip(state.iter())
+                    .fold(FpVar::zero(), |acc, (m, s)| acc + s * *m)
+            })
+            .collect();
+        state.clone_from_slice(&product);
+    }
+
+    Ok(())
+}
+
+/// Mirror [`sponge::hash_with_params`] in R1CS.
+///
+/// The circuit is defined by the length of `messages`, as with the PLONK
+/// [`sponge::gadget`], and the padding is a constant of the circuit.
+///
+/// # Panics
+///
+/// Panics if the width of `params` is smaller than `2`.
+///
+/// [`sponge::hash_with_params`]: crate::sponge::hash_with_params
+/// [`sponge::gadget`]: crate::sponge::gadget
+pub fn sponge_gadget(
+    params: &Params,
+    messages: &[FpVar<Fr>],
+) -> Result<FpVar<Fr>, SynthesisError> {
+    let width = params.width();
+    assert_sponge_width(width);
+
+    let mut state = vec![FpVar::zero(); width];
+
+    let l = messages.len();
+    let m = l / (width - 1);
+    let n = m * (width - 1);
+    let last_iteration = if l == n {
+        m.saturating_sub(1)
+    } else {
+        l / (width - 1)
+    };
+
+    for (i, chunk) in messages.chunks(width - 1).enumerate() {
+        for (s, c) in state[1..].iter_mut().zip(chunk.iter()) {
+            *s += c;
+        }
+
+        if i == last_iteration && chunk.len() < width - 1 {
+            state[chunk.len() + 1] += Fr::from(1u64);
+        } else if i == last_iteration {
+            permute(params, &mut state)?;
+
+            state[1] += Fr::from(1u64);
+        }
+
+        permute(params, &mut state)?;
+    }
+
+    Ok(state[1].clone())
+}
+
+/// Mirror [`PoseidonBranch::verify_with`] in R1CS, returning the root
+/// recomputed from `leaf`.
+///
+/// The levels of the branch are allocated as witnesses of the constraint
+/// system of `leaf`, and every level checks that the node computed from the
+/// level below is found at its offset, as the PLONK [`merkle_opening`]
+/// does. The caller is expected to constrain the returned root, usually to
+/// a public input.
+///
+/// # Panics
+///
+/// Panics if the width of `params` isn't one more than the arity of the
+/// tree.
+///
+/// [`merkle_opening`]: crate::tree::merkle_opening
+pub fn merkle_opening<const DEPTH: usize>(
+    params: &Params,
+    branch: &PoseidonBranch<DEPTH>,
+    leaf: &FpVar<Fr>,
+) -> Result<FpVar<Fr>, SynthesisError> {
+    assert_tree_width(params.width());
+
+    let cs = leaf.cs();
+
+    let mut node = leaf.clone();
+    for level in branch.as_ref() {
+        let mut state = level
+            .as_ref()
+            .iter()
+            .map(|n| FpVar::new_witness(cs.clone(), || Ok(to_ark(n))))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        assert_offset(cs.clone(), &state, level.offset(), &node)?;
+
+        permute(params, &mut state)?;
+        node = state[1].clone();
+    }
+
+    Ok(node)
+}
+
+/// Allocate the bits selecting `offset` among the children of a level held
+/// in `state`, and check that `node` is the child they select.
+fn assert_offset(
+    cs: ConstraintSystemRef<Fr>,
+    state: &[FpVar<Fr>],
+    offset: usize,
+    node: &FpVar<Fr>,
+) -> Result<(), SynthesisError> {
+    let mut sum = FpVar::zero();
+
+    for (i, child) in state[1..].iter().enumerate() {
+        let bit = Boolean::new_witness(cs.clone(), || Ok(i == offset))?;
+        sum += FpVar::from(bit.clone());
+
+        // The child must be the node wherever the bit is set
+        child.conditional_enforce_equal(node, &bit)?;
+    }
+
+    sum.enforce_equal(&FpVar::one())
+}
+
+fn quintic_s_box(s: &FpVar<Fr>) -> Result<FpVar<Fr>, SynthesisError> {
+    let s2 = s.square()?;
+    let s4 = s2.square()?;
+
+    Ok(s4 * s)
+}
@@ -0,0 +1,351 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Binary sparse merkle tree mapping [`BlsScalar`] keys to [`BlsScalar`]
+//! values.
+//!
+//! Every key addresses the leaf at the path given by its `DEPTH` least
+//! significant bits, starting from the bottom of the tree: a zero bit puts the
+//! node on the left of its parent, and a one bit on its right. A leaf holding
+//! a key and its value is hashed with [`sponge::hash`](crate::sponge::hash),
+//! while an empty leaf is zero. The subtrees holding no keys are never stored
+//! and are replaced by their precomputed hashes.
+//!
+//! A key is proven to be absent from the tree by opening its leaf as empty.
+//! Since a key sharing the leaf of another key can't be proven absent, trees
+//! storing arbitrary keys, such as nullifiers, should be [`MAX_DEPTH`] deep,
+//! where every key has its own leaf.
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod zk;
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use zk::{merkle_opening, non_membership};
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub(crate) use zk::hash_node_gadget;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+
+use bls12_381::Scalar as BlsScalar;
+use hades::{ScalarStrategy, Strategy};
+
+use crate::sponge;
+
+/// Maximum depth of a sparse merkle tree, the number of bits of a key.
+pub const MAX_DEPTH: usize = 256;
+
+/// Flags both children of a node as present in the first element of the
+/// permutation.
+const NODE_FLAG: u64 = 0b11;
+
+/// Path of a key, and prefix of a node, as the little-endian bytes of a key.
+type Path = [u8; 32];
+
+/// Errors that can occur while mutating a [`SparseMerkleTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The leaf addressed by the key already holds another key sharing the
+    /// same least significant bits.
+    KeyCollision,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KeyCollision => {
+                write!(f, "the leaf of the key holds another key")
+            }
+        }
+    }
+}
+
+/// Hash the children of a binary node.
+pub(crate) fn hash_node(left: &BlsScalar, right: &BlsScalar) -> BlsScalar {
+    let mut perm = [BlsScalar::zero(); hades::WIDTH];
+
+    perm[0] = BlsScalar::from(NODE_FLAG);
+    perm[1] = *left;
+    perm[2] = *right;
+    ScalarStrategy::new().perm(&mut perm);
+
+    perm[1]
+}
+
+pub(crate) fn hash_leaf(key: &BlsScalar, value: &BlsScalar) -> BlsScalar {
+    sponge::hash(&[*key, *value])
+}
+
+/// Hashes of the empty subtrees of every height, from the leaves up to the
+/// root.
+pub(crate) fn empty_hashes(depth: usize) -> Vec<BlsScalar> {
+    let mut hashes = Vec::with_capacity(depth + 1);
+
+    hashes.push(BlsScalar::zero());
+    for height in 0..depth {
+        let empty = hashes[height];
+        hashes.push(hash_node(&empty, &empty));
+    }
+
+    hashes
+}
+
+/// Return the bit of `path` deciding on which side of its parent the node at
+/// `height` is.
+pub(crate) fn bit(path: &Path, height: usize) -> bool {
+    path[height / 8] >> (height % 8) & 1 == 1
+}
+
+/// Clear the bits of `path` outside of `low..high`.
+fn mask(path: &Path, low: usize, high: usize) -> Path {
+    let mut masked = *path;
+
+    masked.iter_mut().enumerate().for_each(|(i, byte)| {
+        let low = low.saturating_sub(i * 8).min(8);
+        let high = high.saturating_sub(i * 8).min(8);
+
+        let below = (1u16 << low) - 1;
+        let above = !((1u16 << high) - 1);
+        *byte &= !(below | above) as u8;
+    });
+
+    masked
+}
+
+/// Return the path of `key` in a tree of the given depth.
+pub(crate) fn path(key: &BlsScalar, depth: usize) -> Path {
+    mask(&key.to_bytes(), 0, depth)
+}
+
+/// Return the prefix of the node at `height` on `path`.
+fn prefix(path: &Path, height: usize) -> Path {
+    mask(path, height, MAX_DEPTH)
+}
+
+/// Return the prefix of the sibling of the node at `height` on `path`.
+fn sibling(path: &Path, height: usize) -> Path {
+    let mut sibling = prefix(path, height);
+    sibling[height / 8] ^= 1 << (height % 8);
+    sibling
+}
+
+/// Sparse merkle tree of a given depth, mapping keys to values.
+///
+/// Only the leaves holding a key and the nodes above them are stored, so the
+/// size of the tree is proportional to the number of keys it holds times its
+/// depth.
+#[derive(Debug, Clone)]
+pub struct SparseMerkleTree<const DEPTH: usize> {
+    leaves: BTreeMap<Path, (BlsScalar, BlsScalar)>,
+    nodes: BTreeMap<(usize, Path), BlsScalar>,
+    empty: Vec<BlsScalar>,
+}
+
+impl<const DEPTH: usize> Default for SparseMerkleTree<DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const DEPTH: usize> SparseMerkleTree<DEPTH> {
+    /// Create a new, empty, sparse merkle tree.
+    ///
+    /// # Panics
+    /// If `DEPTH` is greater than [`MAX_DEPTH`].
+    pub fn new() -> Self {
+        assert!(DEPTH <= MAX_DEPTH, "the depth of the tree is too large");
+
+        Self {
+            leaves: BTreeMap::new(),
+            nodes: BTreeMap::new(),
+            empty: empty_hashes(DEPTH),
+        }
+    }
+
+    /// Return the root of a tree holding no keys.
+    pub fn empty_root() -> BlsScalar {
+        empty_hashes(DEPTH)[DEPTH]
+    }
+
+    /// Return the root of the tree.
+    pub fn root(&self) -> BlsScalar {
+        self.node(DEPTH, &[0; 32])
+    }
+
+    /// Return the number of keys in the tree.
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Return `true` if the tree holds no keys.
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Return the value of `key`, if it is in the tree.
+    pub fn get(&self, key: &BlsScalar) -> Option<BlsScalar> {
+        self.leaves
+            .get(&path(key, DEPTH))
+            .filter(|(k, _)| k == key)
+            .map(|(_, value)| *value)
+    }
+
+    /// Set the value of `key`, returning its previous value if any.
+    ///
+    /// Return [`Error::KeyCollision`] if the leaf of `key` already holds
+    /// another key.
+    pub fn insert(
+        &mut self,
+        key: BlsScalar,
+        value: BlsScalar,
+    ) -> Result<Option<BlsScalar>, Error> {
+        let path = path(&key, DEPTH);
+
+        if matches!(self.leaves.get(&path), Some((k, _)) if *k != key) {
+            return Err(Error::KeyCollision);
+        }
+
+        let previous = self.leaves.insert(path, (key, value));
+        self.update_path(&path);
+
+        Ok(previous.map(|(_, value)| value))
+    }
+
+    /// Remove `key` from the tree, returning its value if it was in the tree.
+    pub fn remove(&mut self, key: &BlsScalar) -> Option<BlsScalar> {
+        let path = path(key, DEPTH);
+
+        match self.leaves.get(&path) {
+            Some((k, _)) if k == key => {}
+            _ => return None,
+        }
+
+        let removed = self.leaves.remove(&path);
+        self.update_path(&path);
+
+        removed.map(|(_, value)| value)
+    }
+
+    /// Return the opening of the leaf addressed by `key`.
+    ///
+    /// The opening is returned regardless of `key` being in the tree.
+    pub fn opening(&self, key: &BlsScalar) -> SmtOpening<DEPTH> {
+        let path = path(key, DEPTH);
+        let mut opening = SmtOpening::default();
+
+        for (height, node) in opening.siblings.iter_mut().enumerate() {
+            *node = self.node(height, &sibling(&path, height));
+        }
+
+        opening
+    }
+
+    /// Return the opening of the empty leaf addressed by `key`, proving that
+    /// `key` is not in the tree.
+    ///
+    /// Return `None` if the leaf of `key` holds a key, be it `key` or another
+    /// key sharing its leaf.
+    pub fn non_membership(&self, key: &BlsScalar) -> Option<SmtOpening<DEPTH>> {
+        match self.leaves.contains_key(&path(key, DEPTH)) {
+            true => None,
+            false => Some(self.opening(key)),
+        }
+    }
+
+    fn node(&self, height: usize, prefix: &Path) -> BlsScalar {
+        let node = match height {
+            0 => self.leaves.get(prefix).map(|(k, v)| hash_leaf(k, v)),
+            _ => self.nodes.get(&(height, *prefix)).copied(),
+        };
+
+        node.unwrap_or(self.empty[height])
+    }
+
+    /// Hash the nodes on `path` again, from the leaf up to the root.
+    fn update_path(&mut self, path: &Path) {
+        for height in 0..DEPTH {
+            let node = self.node(height, &prefix(path, height));
+            let sibling = self.node(height, &sibling(path, height));
+
+            let parent = match bit(path, height) {
+                true => hash_node(&sibling, &node),
+                false => hash_node(&node, &sibling),
+            };
+
+            let key = (height + 1, prefix(path, height + 1));
+            match parent == self.empty[height + 1] {
+                true => self.nodes.remove(&key),
+                false => self.nodes.insert(key, parent),
+            };
+        }
+    }
+}
+
+/// Opening of a leaf of a [`SparseMerkleTree`], holding the siblings of the
+/// nodes on its path, from the bottom up to the root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmtOpening<const DEPTH: usize> {
+    siblings: [BlsScalar; DEPTH],
+}
+
+impl<const DEPTH: usize> Default for SmtOpening<DEPTH> {
+    fn default() -> Self {
+        Self {
+            siblings: [BlsScalar::zero(); DEPTH],
+        }
+    }
+}
+
+impl<const DEPTH: usize> SmtOpening<DEPTH> {
+    /// Create an opening from the siblings of the nodes on its path.
+    pub const fn new(siblings: [BlsScalar; DEPTH]) -> Self {
+        Self { siblings }
+    }
+
+    /// Return the siblings of the nodes on the path of the opening.
+    pub const fn siblings(&self) -> &[BlsScalar; DEPTH] {
+        &self.siblings
+    }
+
+    /// Compute the root of the tree with `key` set to `value`.
+    pub fn root(&self, key: &BlsScalar, value: &BlsScalar) -> BlsScalar {
+        self.path_root(key, hash_leaf(key, value))
+    }
+
+    /// Check that `key` is set to `value` in the tree with the given `root`.
+    pub fn verify(
+        &self,
+        root: &BlsScalar,
+        key: &BlsScalar,
+        value: &BlsScalar,
+    ) -> bool {
+        self.root(key, value) == *root
+    }
+
+    /// Check that the leaf of `key` is empty in the tree with the given
+    /// `root`, and therefore that `key` is not in the tree.
+    pub fn verify_non_membership(
+        &self,
+        root: &BlsScalar,
+        key: &BlsScalar,
+    ) -> bool {
+        self.path_root(key, BlsScalar::zero()) == *root
+    }
+
+    fn path_root(&self, key: &BlsScalar, leaf: BlsScalar) -> BlsScalar {
+        let path = path(key, DEPTH);
+
+        self.siblings.iter().enumerate().fold(
+            leaf,
+            |node, (height, sibling)| match bit(&path, height) {
+                true => hash_node(sibling, &node),
+                false => hash_node(&node, sibling),
+            },
+        )
+    }
+}
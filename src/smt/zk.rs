@@ -0,0 +1,126 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::{SmtOpening, MAX_DEPTH, NODE_FLAG};
+use crate::sponge;
+use hades::GadgetStrategy;
+
+use plonk::prelude::*;
+
+/// Perform a merkle opening of `key` set to `value` in a sparse merkle tree
+/// and return the calculated root
+pub fn merkle_opening<C, const DEPTH: usize>(
+    composer: &mut C,
+    opening: &SmtOpening<DEPTH>,
+    key: Witness,
+    value: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    // Since the hash of the leaf binds the key, a key can only be opened on
+    // its own path, even with a non-canonical decomposition
+    let bits = composer.component_decomposition::<MAX_DEPTH>(key);
+    let leaf = sponge::gadget(composer, &[key, value]);
+
+    path_root(composer, opening, &bits, leaf)
+}
+
+/// Perform a merkle opening of the empty leaf of `key` in a sparse merkle
+/// tree and return the calculated root, proving that `key` is not in the
+/// tree with that root
+pub fn non_membership<C, const DEPTH: usize>(
+    composer: &mut C,
+    opening: &SmtOpening<DEPTH>,
+    key: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    // An empty leaf doesn't bind the key, so its decomposition must be
+    // canonical to prevent opening the empty leaf of another path
+    let bits = composer.component_decomposition::<MAX_DEPTH>(key);
+    assert_canonical(composer, &bits);
+
+    path_root(composer, opening, &bits, C::ZERO)
+}
+
+/// Constrain the little-endian `bits` to encode an integer smaller than the
+/// modulus of the scalar field.
+fn assert_canonical<C>(composer: &mut C, bits: &[Witness; MAX_DEPTH])
+where
+    C: Composer,
+{
+    let max = (-BlsScalar::one()).to_bytes();
+
+    // Compare the bits with the largest scalar from the most significant
+    // one, tracking whether they are all equal so far, or already lower
+    let mut eq = composer.append_constant(BlsScalar::one());
+    let mut lt = C::ZERO;
+
+    for (i, bit) in bits.iter().enumerate().rev() {
+        let constraint =
+            Constraint::new().mult(-BlsScalar::one()).a(eq).b(*bit);
+
+        eq = match max[i / 8] >> (i % 8) & 1 {
+            1 => {
+                let constraint = constraint.left(1).fourth(1).d(lt);
+                lt = composer.gate_add(constraint);
+
+                let constraint = Constraint::new().mult(1).a(eq).b(*bit);
+                composer.gate_mul(constraint)
+            }
+            _ => composer.gate_add(constraint.left(1)),
+        };
+    }
+
+    let constraint = Constraint::new().left(1).a(lt).right(1).b(eq);
+    let le = composer.gate_add(constraint);
+    composer.assert_equal_constant(le, BlsScalar::one(), None);
+}
+
+/// Hash `leaf` up to the root along the path given by the bits of a key.
+fn path_root<C, const DEPTH: usize>(
+    composer: &mut C,
+    opening: &SmtOpening<DEPTH>,
+    bits: &[Witness; MAX_DEPTH],
+    leaf: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    opening.siblings.iter().zip(bits.iter()).fold(
+        leaf,
+        |node, (sibling, bit)| {
+            let sibling = composer.append_witness(*sibling);
+
+            // A set bit puts the node on the right of its sibling
+            let left = composer.component_select(*bit, sibling, node);
+            let right = composer.component_select(*bit, node, sibling);
+
+            hash_node_gadget(composer, left, right)
+        },
+    )
+}
+
+/// Hash the children of a binary node.
+pub(crate) fn hash_node_gadget<C>(
+    composer: &mut C,
+    left: Witness,
+    right: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    let mut container = [C::ZERO; hades::WIDTH];
+
+    container[0] = composer.append_constant(BlsScalar::from(NODE_FLAG));
+    container[1] = left;
+    container[2] = right;
+
+    GadgetStrategy::gadget(composer, &mut container);
+    container[1]
+}
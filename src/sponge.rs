@@ -9,9 +9,19 @@ mod hash;
 #[cfg(feature = "alloc")]
 mod gadget;
 
+pub mod duplex;
 pub mod truncated;
 
-pub use hash::hash;
+pub use hash::{
+    hash, hash_n, hash_points, hash_with, hash_with_domain, hash_with_params,
+};
+
+#[cfg(any(feature = "transcript", feature = "rs-merkle"))]
+pub(crate) use hash::byte_scalars;
 
 #[cfg(feature = "alloc")]
-pub use gadget::gadget;
+pub use gadget::{
+    gadget, gadget_mixed, gadget_n, gadget_points, gadget_public,
+    gadget_var_len, gadget_with, gadget_with_domain, gadget_with_params,
+    Message,
+};
@@ -0,0 +1,241 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Duplex sponge over the `Hades` permutation.
+//!
+//! Contrary to [`hash`](crate::sponge::hash), the duplex construction allows
+//! to interleave the absorption of inputs with the extraction of outputs,
+//! which makes it suitable as the Fiat-Shamir sponge of interactive protocols
+//! or as a stream of pseudo-random scalars.
+
+use bls12_381::Scalar as BlsScalar;
+use hades::{ScalarStrategy, Strategy, WIDTH};
+
+#[cfg(feature = "alloc")]
+use hades::GadgetStrategy;
+#[cfg(feature = "alloc")]
+use plonk::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Absorbing(usize),
+    Squeezing(usize),
+}
+
+/// A duplex sponge with a capacity of one element and a rate of
+/// [`Duplex::RATE`] elements.
+///
+/// Inputs are added to the rate elements of the state, and the permutation is
+/// applied lazily: only when the rate is exhausted or when the sponge switches
+/// from absorbing to squeezing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Duplex {
+    state: [BlsScalar; WIDTH],
+    mode: Mode,
+}
+
+impl Duplex {
+    /// Number of elements absorbed or squeezed between two permutations.
+    pub const RATE: usize = WIDTH - 1;
+
+    /// Number of bytes extracted from every scalar by
+    /// [`Duplex::squeeze_bytes`].
+    pub const USABLE_BYTES: usize = 31;
+
+    /// Create a new duplex sponge, setting its capacity to `domain`.
+    ///
+    /// Sponges created with different domains produce independent outputs.
+    pub fn new(domain: BlsScalar) -> Self {
+        let mut state = [BlsScalar::zero(); WIDTH];
+        state[0] = domain;
+
+        Self {
+            state,
+            mode: Mode::Absorbing(0),
+        }
+    }
+
+    /// Absorb `input` into the sponge.
+    pub fn absorb(&mut self, input: &[BlsScalar]) {
+        let mut pos = match self.mode {
+            Mode::Absorbing(pos) => pos,
+            Mode::Squeezing(_) => 0,
+        };
+
+        for scalar in input {
+            if pos == Self::RATE {
+                self.permute();
+                pos = 0;
+            }
+
+            self.state[pos + 1] += scalar;
+            pos += 1;
+        }
+
+        self.mode = Mode::Absorbing(pos);
+    }
+
+    /// Squeeze a single scalar out of the sponge.
+    pub fn squeeze(&mut self) -> BlsScalar {
+        let mut pos = match self.mode {
+            Mode::Absorbing(_) => {
+                self.permute();
+                0
+            }
+            Mode::Squeezing(pos) => pos,
+        };
+
+        if pos == Self::RATE {
+            self.permute();
+            pos = 0;
+        }
+
+        self.mode = Mode::Squeezing(pos + 1);
+        self.state[pos + 1]
+    }
+
+    /// Fill `output` with scalars squeezed out of the sponge.
+    pub fn squeeze_into(&mut self, output: &mut [BlsScalar]) {
+        output.iter_mut().for_each(|s| *s = self.squeeze());
+    }
+
+    /// Fill `output` with bytes squeezed out of the sponge.
+    ///
+    /// Every squeezed scalar provides [`Duplex::USABLE_BYTES`] bytes of its
+    /// little-endian representation, so the bytes are uniformly distributed.
+    pub fn squeeze_bytes(&mut self, output: &mut [u8]) {
+        output.chunks_mut(Self::USABLE_BYTES).for_each(|chunk| {
+            let bytes = self.squeeze().to_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        });
+    }
+
+    fn permute(&mut self) {
+        ScalarStrategy::new().perm(&mut self.state);
+    }
+}
+
+/// Mirror the implementation of [`Duplex`] inside of a PLONK circuit.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy)]
+pub struct DuplexGadget {
+    state: [Witness; WIDTH],
+    mode: Mode,
+}
+
+#[cfg(feature = "alloc")]
+impl DuplexGadget {
+    /// Create a new duplex sponge gadget, setting its capacity to the
+    /// constant `domain`.
+    pub fn new<C>(composer: &mut C, domain: BlsScalar) -> Self
+    where
+        C: Composer,
+    {
+        let mut state = [C::ZERO; WIDTH];
+        state[0] = composer.append_constant(domain);
+
+        Self {
+            state,
+            mode: Mode::Absorbing(0),
+        }
+    }
+
+    /// Absorb `input` into the sponge.
+    pub fn absorb<C>(&mut self, composer: &mut C, input: &[Witness])
+    where
+        C: Composer,
+    {
+        let mut pos = match self.mode {
+            Mode::Absorbing(pos) => pos,
+            Mode::Squeezing(_) => 0,
+        };
+
+        for w in input {
+            if pos == Duplex::RATE {
+                GadgetStrategy::gadget(composer, &mut self.state);
+                pos = 0;
+            }
+
+            let constraint = Constraint::new()
+                .left(1)
+                .a(self.state[pos + 1])
+                .right(1)
+                .b(*w);
+            self.state[pos + 1] = composer.gate_add(constraint);
+            pos += 1;
+        }
+
+        self.mode = Mode::Absorbing(pos);
+    }
+
+    /// Squeeze a single witness out of the sponge.
+    pub fn squeeze<C>(&mut self, composer: &mut C) -> Witness
+    where
+        C: Composer,
+    {
+        let mut pos = match self.mode {
+            Mode::Absorbing(_) => {
+                GadgetStrategy::gadget(composer, &mut self.state);
+                0
+            }
+            Mode::Squeezing(pos) => pos,
+        };
+
+        if pos == Duplex::RATE {
+            GadgetStrategy::gadget(composer, &mut self.state);
+            pos = 0;
+        }
+
+        self.mode = Mode::Squeezing(pos + 1);
+        self.state[pos + 1]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn duplex_determinism() {
+        let input: Vec<BlsScalar> = (0..7u64).map(BlsScalar::from).collect();
+
+        let mut a = Duplex::new(BlsScalar::zero());
+        let mut b = Duplex::new(BlsScalar::zero());
+
+        a.absorb(&input);
+        b.absorb(&input[..3]);
+        b.absorb(&input[3..]);
+        assert_eq!(a.squeeze(), b.squeeze());
+
+        let mut out_a = [BlsScalar::zero(); 9];
+        let mut out_b = [BlsScalar::zero(); 9];
+        a.squeeze_into(&mut out_a);
+        b.squeeze_into(&mut out_b);
+        assert_eq!(out_a, out_b);
+        assert_ne!(out_a[0], out_a[1]);
+    }
+
+    #[test]
+    fn duplex_separation() {
+        let one = [BlsScalar::one()];
+
+        let mut a = Duplex::new(BlsScalar::zero());
+        let mut b = Duplex::new(BlsScalar::one());
+        a.absorb(&one);
+        b.absorb(&one);
+        assert_ne!(a.squeeze(), b.squeeze());
+
+        // Squeezing in between absorptions must affect the outputs
+        let mut c = Duplex::new(BlsScalar::zero());
+        let mut d = Duplex::new(BlsScalar::zero());
+        c.absorb(&[BlsScalar::one(), BlsScalar::one()]);
+        d.absorb(&one);
+        d.squeeze();
+        d.absorb(&one);
+        assert_ne!(c.squeeze(), d.squeeze());
+    }
+}
@@ -4,10 +4,40 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use alloc::vec;
+use alloc::vec::Vec;
+
 use hades::{GadgetStrategy, WIDTH};
 
+use crate::params::Params;
+use crate::permutation::{assert_sponge_width, PermutationGadget};
+
 use plonk::prelude::*;
 
+/// Element of a message hashed by [`gadget_mixed`].
+///
+/// Constant elements are part of the circuit description, and are absorbed
+/// into the constants of the gates instead of being appended as witnesses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Message {
+    /// Element known only to the prover.
+    Witness(Witness),
+    /// Element fixed by the circuit.
+    Constant(BlsScalar),
+}
+
+impl From<Witness> for Message {
+    fn from(witness: Witness) -> Self {
+        Self::Witness(witness)
+    }
+}
+
+impl From<BlsScalar> for Message {
+    fn from(constant: BlsScalar) -> Self {
+        Self::Constant(constant)
+    }
+}
+
 /// Mirror the implementation of [`hash`] inside of a PLONK circuit.
 ///
 /// The circuit will be defined by the length of `messages`. This means that a
@@ -27,39 +57,369 @@ where
 {
     let mut state = [C::ZERO; WIDTH];
 
+    absorb(composer, &mut state, messages, |composer, state| {
+        GadgetStrategy::gadget(composer, state)
+    });
+
+    state[1]
+}
+
+/// Mirror the implementation of [`hash`] inside of a PLONK circuit, for a
+/// message mixing witnesses and constants.
+///
+/// Every constant of `messages` is folded into the gate absorbing it, costing
+/// no witness of its own, and a constant absorbed into the zero state shares
+/// its gate with the other occurrences of it in the first chunk, padding
+/// included. The same considerations of [`gadget`] regarding the length of
+/// `messages` apply, and the constants are part of the circuit description as
+/// well.
+///
+/// [`hash`]: crate::sponge::hash
+pub fn gadget_mixed<C>(composer: &mut C, messages: &[Message]) -> Witness
+where
+    C: Composer,
+{
+    let mut state = [C::ZERO; WIDTH];
+
+    absorb(composer, &mut state, messages, |composer, state| {
+        GadgetStrategy::gadget(composer, state)
+    });
+
+    state[1]
+}
+
+/// Mirror the implementation of [`hash_points`] inside of a PLONK circuit.
+///
+/// The coordinates of every point are absorbed in the same order as the
+/// native function. The same considerations of [`gadget`] regarding the
+/// length of the message apply, the length being twice the number of
+/// `points`.
+///
+/// [`hash_points`]: crate::sponge::hash_points
+pub fn gadget_points<C>(composer: &mut C, points: &[WitnessPoint]) -> Witness
+where
+    C: Composer,
+{
+    let messages: Vec<Witness> = points
+        .iter()
+        .flat_map(|point| [*point.x(), *point.y()])
+        .collect();
+
+    gadget(composer, &messages)
+}
+
+/// Mirror the implementation of [`hash_n`] inside of a PLONK circuit.
+///
+/// Every output past the first one costs an additional permutation. The same
+/// considerations of [`gadget`] regarding the length of `messages` apply.
+///
+/// [`hash_n`]: crate::sponge::hash_n
+pub fn gadget_n<C, const OUT: usize>(
+    composer: &mut C,
+    messages: &[Witness],
+) -> [Witness; OUT]
+where
+    C: Composer,
+{
+    let mut state = [C::ZERO; WIDTH];
+
+    absorb(composer, &mut state, messages, |composer, state| {
+        GadgetStrategy::gadget(composer, state)
+    });
+
+    let mut output = [C::ZERO; OUT];
+    output.iter_mut().enumerate().for_each(|(i, o)| {
+        if i > 0 {
+            GadgetStrategy::gadget(composer, &mut state);
+        }
+        *o = state[1];
+    });
+
+    output
+}
+
+/// Mirror the implementation of [`hash_with_domain`] inside of a PLONK
+/// circuit.
+///
+/// The `domain` tag is appended to the circuit as a constant in the capacity
+/// of the state, so it's part of the circuit description.
+///
+/// [`hash_with_domain`]: crate::sponge::hash_with_domain
+pub fn gadget_with_domain<C>(
+    composer: &mut C,
+    domain: u64,
+    messages: &[Witness],
+) -> Witness
+where
+    C: Composer,
+{
+    let mut state = [C::ZERO; WIDTH];
+    state[0] = composer.append_constant(BlsScalar::from(domain));
+
+    absorb(composer, &mut state, messages, |composer, state| {
+        GadgetStrategy::gadget(composer, state)
+    });
+
+    state[1]
+}
+
+/// Mirror the implementation of [`hash`] inside of a PLONK circuit, appending
+/// the `messages` as public inputs.
+///
+/// The verifier must provide the messages as public inputs, in the order they
+/// are appended by the circuit, binding the hash to publicly known data. The
+/// same considerations of [`gadget`] regarding the length of `messages`
+/// apply.
+///
+/// [`hash`]: crate::sponge::hash
+pub fn gadget_public<C>(composer: &mut C, messages: &[BlsScalar]) -> Witness
+where
+    C: Composer,
+{
+    let messages: Vec<Witness> = messages
+        .iter()
+        .map(|message| composer.append_public(*message))
+        .collect();
+
+    gadget(composer, &messages)
+}
+
+/// Mirror the implementation of [`hash`] inside of a PLONK circuit for a
+/// message whose length is a witness.
+///
+/// Only the first `len` scalars of `messages` are hashed, and the padding is
+/// appended after them in the circuit, so the returned value is the [`hash`]
+/// of these scalars. The circuit is defined by the length of `messages`, which
+/// is the maximum length of the message, and `len` is constrained to be at
+/// most this length.
+///
+/// [`hash`]: crate::sponge::hash
+pub fn gadget_var_len<C>(
+    composer: &mut C,
+    messages: &[Witness],
+    len: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    let rate = WIDTH - 1;
+    let max_len = messages.len();
+
+    // Select the position of the padding with a single set bit, constraining
+    // the position to be the length of the message
+    let len_value = composer[len];
+    let mut sum = C::ZERO;
+    let mut position = C::ZERO;
+    let ends: Vec<Witness> = (0..=max_len)
+        .map(|i| {
+            let i = BlsScalar::from(i as u64);
+            let bit = BlsScalar::from((i == len_value) as u64);
+            let bit = composer.append_witness(bit);
+            composer.component_boolean(bit);
+
+            let constraint = Constraint::new().left(1).a(sum).right(1).b(bit);
+            sum = composer.gate_add(constraint);
+
+            let constraint =
+                Constraint::new().left(1).a(position).right(i).b(bit);
+            position = composer.gate_add(constraint);
+
+            bit
+        })
+        .collect();
+    composer.assert_equal_constant(sum, BlsScalar::one(), None);
+    composer.assert_equal(position, len);
+
+    // Absorb every chunk up to the maximum length, keeping the output of the
+    // chunk holding the padding. The scalars before the padding are the ones
+    // of the message, and the ones after it are zero
+    let mut state = [C::ZERO; WIDTH];
+    let mut padded = C::ZERO;
+    let mut output = C::ZERO;
+
+    for chunk in 0..max_len / rate + 1 {
+        let mut last = C::ZERO;
+
+        for i in 0..rate {
+            let message = messages.get(chunk * rate + i).copied();
+            let message = message.unwrap_or(C::ZERO);
+            let end = ends.get(chunk * rate + i).copied().unwrap_or(C::ZERO);
+
+            let constraint =
+                Constraint::new().left(1).a(padded).right(1).b(end);
+            padded = composer.gate_add(constraint);
+
+            // `message` before the padding, `1` on it and `0` after it
+            let constraint = Constraint::new().mult(1).a(padded).b(message);
+            let cleared = composer.gate_mul(constraint);
+            let constraint = Constraint::new()
+                .left(1)
+                .a(message)
+                .right(-BlsScalar::one())
+                .b(cleared)
+                .fourth(1)
+                .d(end);
+            let message = composer.gate_add(constraint);
+
+            let constraint = Constraint::new()
+                .left(1)
+                .a(state[i + 1])
+                .right(1)
+                .b(message);
+            state[i + 1] = composer.gate_add(constraint);
+
+            let constraint = Constraint::new().left(1).a(last).right(1).b(end);
+            last = composer.gate_add(constraint);
+        }
+
+        GadgetStrategy::gadget(composer, &mut state);
+
+        let constraint = Constraint::new().mult(1).a(last).b(state[1]);
+        let selected = composer.gate_mul(constraint);
+        let constraint =
+            Constraint::new().left(1).a(output).right(1).b(selected);
+        output = composer.gate_add(constraint);
+    }
+
+    // The hash of an empty message is zero
+    let constraint = Constraint::new().mult(1).a(ends[0]).b(output);
+    let cleared = composer.gate_mul(constraint);
+    let constraint = Constraint::new()
+        .left(1)
+        .a(output)
+        .right(-BlsScalar::one())
+        .b(cleared);
+    composer.gate_add(constraint)
+}
+
+/// Mirror the implementation of [`hash_with`] inside of a PLONK circuit.
+///
+/// The same considerations of [`gadget`] regarding the length of `messages`
+/// apply.
+///
+/// # Panics
+///
+/// Panics if the width of `perm` is smaller than `2`, as [`hash_with`] does.
+///
+/// [`hash_with`]: crate::sponge::hash_with
+pub fn gadget_with<C, P>(
+    composer: &mut C,
+    perm: &P,
+    messages: &[Witness],
+) -> Witness
+where
+    C: Composer,
+    P: PermutationGadget,
+{
+    assert_sponge_width(perm.width());
+
+    let mut state = vec![C::ZERO; perm.width()];
+
+    absorb(composer, &mut state, messages, |composer, state| {
+        perm.permute_gadget(composer, state)
+    });
+
+    state[1]
+}
+
+/// Mirror the implementation of [`hash_with_params`] inside of a PLONK
+/// circuit.
+///
+/// The same considerations of [`gadget`] regarding the length of `messages`
+/// apply.
+///
+/// [`hash_with_params`]: crate::sponge::hash_with_params
+pub fn gadget_with_params<C>(
+    composer: &mut C,
+    params: &Params,
+    messages: &[Witness],
+) -> Witness
+where
+    C: Composer,
+{
+    gadget_with(composer, params, messages)
+}
+
+/// Absorb `messages` into `state`, applying `perm` after every chunk.
+///
+/// The sum of an element of the state and a message is computed once per
+/// call: when it's repeated, the witness of the first sum is reused instead
+/// of appending another constraint. This happens for the constants absorbed
+/// into the zero state, whether repeated in the message or equal to the
+/// padding. Only the witnesses and the constants are compared, never the
+/// values of the witnesses, so the shape of the circuit still only depends on
+/// its description.
+fn absorb<C, M, P>(
+    composer: &mut C,
+    state: &mut [Witness],
+    messages: &[M],
+    mut perm: P,
+) where
+    C: Composer,
+    M: Copy + Into<Message>,
+    P: FnMut(&mut C, &mut [Witness]),
+{
+    let width = state.len();
+
     let l = messages.len();
-    let m = l / (WIDTH - 1);
-    let n = m * (WIDTH - 1);
-    let last_iteration = if l == n { m - 1 } else { l / (WIDTH - 1) };
+    let m = l / (width - 1);
+    let n = m * (width - 1);
+    let last_iteration = if l == n { m - 1 } else { l / (width - 1) };
+
+    let mut sums = Vec::new();
 
     messages
-        .chunks(WIDTH - 1)
+        .chunks(width - 1)
         .enumerate()
         .for_each(|(i, chunk)| {
             state[1..].iter_mut().zip(chunk.iter()).for_each(|(s, c)| {
-                let constraint = Constraint::new().left(1).a(*s).right(1).b(*c);
-
-                *s = composer.gate_add(constraint);
+                *s = sum(composer, &mut sums, *s, (*c).into());
             });
 
-            if i == last_iteration && chunk.len() < WIDTH - 1 {
-                let constraint = Constraint::new()
-                    .left(1)
-                    .a(state[chunk.len() + 1])
-                    .constant(1);
-
-                state[chunk.len() + 1] = composer.gate_add(constraint);
+            let padding = Message::Constant(BlsScalar::one());
+            if i == last_iteration && chunk.len() < width - 1 {
+                let s = state[chunk.len() + 1];
+                state[chunk.len() + 1] = sum(composer, &mut sums, s, padding);
             } else if i == last_iteration {
-                GadgetStrategy::gadget(composer, &mut state);
+                perm(composer, state);
 
-                let constraint =
-                    Constraint::new().left(1).a(state[1]).constant(1);
-
-                state[1] = composer.gate_add(constraint);
+                state[1] = sum(composer, &mut sums, state[1], padding);
             }
 
-            GadgetStrategy::gadget(composer, &mut state);
+            perm(composer, state);
         });
+}
 
-    state[1]
+/// Add `message` to the element `s` of the state, reusing the witness of the
+/// same sum if it's found in `sums`.
+fn sum<C>(
+    composer: &mut C,
+    sums: &mut Vec<(Witness, Message, Witness)>,
+    s: Witness,
+    message: Message,
+) -> Witness
+where
+    C: Composer,
+{
+    let cached = sums.iter().find(|(a, m, _)| *a == s && *m == message);
+    if let Some((_, _, sum)) = cached {
+        return *sum;
+    }
+
+    let sum = match message {
+        // A witness absorbed into the zero witness is the new state itself,
+        // and doesn't need a constraint
+        Message::Witness(c) if s == C::ZERO => return c,
+        Message::Witness(c) => {
+            let constraint = Constraint::new().left(1).a(s).right(1).b(c);
+            composer.gate_add(constraint)
+        }
+        Message::Constant(c) => {
+            let constraint = Constraint::new().left(1).a(s).constant(c);
+            composer.gate_add(constraint)
+        }
+    };
+    sums.push((s, message, sum));
+
+    sum
 }
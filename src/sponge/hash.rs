@@ -6,8 +6,16 @@
 
 //! Sponge hash and gadget definition
 
+use alloc::vec;
+use alloc::vec::Vec;
+
 use bls12_381::{Scalar as BlsScalar};
+use ff::PrimeField;
 use hades::{ScalarStrategy, Strategy, WIDTH};
+use jubjub::AffinePoint as JubJubAffine;
+
+use crate::params::Params;
+use crate::permutation::{assert_sponge_width, Permutation};
 
 /// The `hash` function takes an arbitrary number of Scalars and returns the
 /// hash, using the `Hades` ScalarStrategy.
@@ -26,22 +34,137 @@ pub fn hash(messages: &[BlsScalar]) -> BlsScalar {
     let mut h = ScalarStrategy::new();
     let mut state = [BlsScalar::zero(); WIDTH];
 
-    // If exists an `m` such as `m · (WIDTH - 1) == l`, then the last iteration
+    absorb(&mut state, messages, |state| h.perm(state));
+
+    state[1]
+}
+
+/// The `hash_n` function performs the same sponge construction as [`hash`],
+/// squeezing `OUT` scalars out of the sponge.
+///
+/// The first output is the result of [`hash`], and every following output is
+/// the first rate element of the state after an additional permutation.
+pub fn hash_n<const OUT: usize>(messages: &[BlsScalar]) -> [BlsScalar; OUT] {
+    let mut h = ScalarStrategy::new();
+    let mut state = [BlsScalar::zero(); WIDTH];
+
+    absorb(&mut state, messages, |state| h.perm(state));
+
+    let mut output = [BlsScalar::zero(); OUT];
+    output.iter_mut().enumerate().for_each(|(i, o)| {
+        if i > 0 {
+            h.perm(&mut state);
+        }
+        *o = state[1];
+    });
+
+    output
+}
+
+/// The `hash_with_domain` function performs the same sponge construction as
+/// [`hash`], setting the capacity to the `domain` tag instead of zero.
+///
+/// Messages hashed under different domains produce independent outputs, and
+/// the domain `0` yields the same output as [`hash`].
+pub fn hash_with_domain(domain: u64, messages: &[BlsScalar]) -> BlsScalar {
+    let mut h = ScalarStrategy::new();
+    let mut state = [BlsScalar::zero(); WIDTH];
+    state[0] = BlsScalar::from(domain);
+
+    absorb(&mut state, messages, |state| h.perm(state));
+
+    state[1]
+}
+
+/// The `hash_points` function performs the same sponge construction as
+/// [`hash`] over the coordinates of the given `points`.
+///
+/// Every point is absorbed as its `u` coordinate followed by its `v`
+/// coordinate, in the order of `points`.
+pub fn hash_points(points: &[JubJubAffine]) -> BlsScalar {
+    let messages: Vec<BlsScalar> = points
+        .iter()
+        .flat_map(|point| [point.get_u(), point.get_v()])
+        .collect();
+
+    hash(&messages)
+}
+
+/// The `hash_with` function performs the same sponge construction as
+/// [`hash`], using any [`Permutation`] instead of the `Hades` ScalarStrategy.
+///
+/// The capacity is the first element of a state of [`Permutation::width`]
+/// elements, and `r` is set to the remainder of the state. The messages and
+/// the hash are elements of the field of the permutation.
+///
+/// # Panics
+///
+/// Panics if the width of `perm` is smaller than `2`, leaving no room for the
+/// message next to the capacity.
+pub fn hash_with<P>(perm: &P, messages: &[P::Scalar]) -> P::Scalar
+where
+    P: Permutation,
+{
+    assert_sponge_width(perm.width());
+
+    let mut state = vec![P::Scalar::ZERO; perm.width()];
+
+    absorb(&mut state, messages, |state| perm.permute(state));
+
+    state[1]
+}
+
+/// The `hash_with_params` function performs the same sponge construction as
+/// [`hash`], using the permutation defined by a custom set of [`Params`]
+/// instead of the `Hades` ScalarStrategy, over the field of the parameters.
+pub fn hash_with_params<F>(params: &Params<F>, messages: &[F]) -> F
+where
+    F: PrimeField,
+{
+    hash_with(params, messages)
+}
+
+/// Encode `bytes` as scalars: their length followed by chunks of `31`
+/// little-endian bytes, each one fitting in a single scalar.
+#[cfg(any(feature = "transcript", feature = "rs-merkle"))]
+pub(crate) fn byte_scalars(
+    bytes: &[u8],
+) -> impl Iterator<Item = BlsScalar> + '_ {
+    let len = BlsScalar::from(bytes.len() as u64);
+
+    core::iter::once(len).chain(bytes.chunks(31).map(|chunk| {
+        let mut wide = [0u8; 64];
+        wide[..chunk.len()].copy_from_slice(chunk);
+
+        BlsScalar::from_bytes_wide(&wide)
+    }))
+}
+
+/// Absorb `messages` into `state` with the padding rules of [`hash`], calling
+/// `perm` on every permutation of the sponge.
+pub(crate) fn absorb<F, P>(state: &mut [F], messages: &[F], mut perm: P)
+where
+    F: PrimeField,
+    P: FnMut(&mut [F]),
+{
+    let width = state.len();
+
+    // If exists an `m` such as `m · (width - 1) == l`, then the last iteration
     // index should be `m - 1`.
     //
-    // In other words, if `l` is a multiple of `WIDTH - 1`, then the last
+    // In other words, if `l` is a multiple of `width - 1`, then the last
     // iteration of the chunk should have an extra appended padding `1`.
     let l = messages.len();
-    let m = l / (WIDTH - 1);
-    let n = m * (WIDTH - 1);
+    let m = l / (width - 1);
+    let n = m * (width - 1);
     let last_iteration = if l == n {
         m.saturating_sub(1)
     } else {
-        l / (WIDTH - 1)
+        l / (width - 1)
     };
 
     messages
-        .chunks(WIDTH - 1)
+        .chunks(width - 1)
         .enumerate()
         .for_each(|(i, chunk)| {
             state[1..].iter_mut().zip(chunk.iter()).for_each(|(s, c)| {
@@ -50,20 +173,18 @@ pub fn hash(messages: &[BlsScalar]) -> BlsScalar {
 
             // Last chunk should have an added `1` followed by zeroes, if there
             // is room for such
-            if i == last_iteration && chunk.len() < WIDTH - 1 {
-                state[chunk.len() + 1] += BlsScalar::one();
+            if i == last_iteration && chunk.len() < width - 1 {
+                state[chunk.len() + 1] += F::ONE;
 
             // If its the last iteration and there is no available room to
             // append `1`, then there must be an extra permutation
             // for the padding
             } else if i == last_iteration {
-                h.perm(&mut state);
+                perm(state);
 
-                state[1] += BlsScalar::one();
+                state[1] += F::ONE;
             }
 
-            h.perm(&mut state);
+            perm(state);
         });
-
-    state[1]
 }
@@ -10,6 +10,9 @@ use crate::sponge;
 use bls12_381::{Scalar as BlsScalar};
 use jubjub::{Scalar as JubJubScalar};
 
+#[cfg(feature = "alloc")]
+use plonk::prelude::*;
+
 /// The constant represents the bitmask used to truncate the hashing results of
 /// a sponge application so that they fit inside of a
 /// [`JubJubScalar`] and it's equal to `2^250 - 1`.
@@ -41,8 +44,29 @@ pub fn hash(messages: &[BlsScalar]) -> JubJubScalar {
     let sponge_result = sponge::hash(messages).to_bytes();
     let trunc_lim = TRUNCATION_LIMIT.to_bytes();
     let mut result = [0u8; 32];
-    result.copy_from_slice(&sponge_result.iter().zip(trunc_lim.iter()).map(|(res, lim)| res & lim).collect::<Vec<u8>>());
-    JubJubScalar::from_bytes(
-        &result
-    ).unwrap()
+    result
+        .iter_mut()
+        .zip(sponge_result.iter().zip(trunc_lim.iter()))
+        .for_each(|(r, (res, lim))| *r = res & lim);
+    JubJubScalar::from_bytes(&result).unwrap()
+}
+
+/// Mirror the implementation of [`hash`] inside of a PLONK circuit.
+///
+/// The output of [`sponge::gadget`] is truncated with a logic gate against
+/// the truncation bitmask, over the full `256` bits of the scalar. The
+/// returned witness is bounded by the bitmask, so it's guaranteed to fit
+/// inside a `JubJubScalar`.
+///
+/// The same considerations of [`sponge::gadget`] regarding the length of
+/// `messages` apply.
+#[cfg(feature = "alloc")]
+pub fn gadget<C>(composer: &mut C, messages: &[Witness]) -> Witness
+where
+    C: Composer,
+{
+    let h = sponge::gadget(composer, messages);
+    let limit = composer.append_constant(TRUNCATION_LIMIT);
+
+    composer.append_logic_and::<128>(h, limit)
 }
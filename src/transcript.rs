@@ -0,0 +1,214 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Fiat-Shamir transcript over the Poseidon [`Duplex`] sponge.
+//!
+//! [`PoseidonTranscript`] exposes the same interface as `merlin::Transcript`,
+//! so protocols can swap their byte-oriented transcript for an algebraic one
+//! without changing their call sites.
+//!
+//! Bytes are absorbed as their length followed by chunks of
+//! [`Duplex::USABLE_BYTES`] little-endian bytes, each one fitting in a single
+//! scalar. Every message is preceded by its label.
+//!
+//! [`TranscriptGadget`] is the circuit counterpart of [`PoseidonTranscript`]:
+//! absorbing the same labels, scalars and points on both sides yields the same
+//! challenges, natively and in-circuit.
+
+use bls12_381::Scalar as BlsScalar;
+use jubjub::AffinePoint as JubJubAffine;
+
+use crate::sponge::byte_scalars;
+use crate::sponge::duplex::Duplex;
+
+#[cfg(feature = "alloc")]
+use crate::sponge::duplex::DuplexGadget;
+#[cfg(feature = "alloc")]
+use plonk::prelude::*;
+
+/// A Fiat-Shamir transcript backed by the Poseidon [`Duplex`] sponge.
+#[derive(Debug, Clone)]
+pub struct PoseidonTranscript {
+    sponge: Duplex,
+}
+
+impl PoseidonTranscript {
+    /// Create a new transcript for the protocol identified by `label`.
+    pub fn new(label: &'static [u8]) -> Self {
+        let mut transcript = Self {
+            sponge: Duplex::new(BlsScalar::zero()),
+        };
+        transcript.append_message(b"dom-sep", label);
+
+        transcript
+    }
+
+    /// Append a `message` to the transcript under the given `label`.
+    pub fn append_message(&mut self, label: &'static [u8], message: &[u8]) {
+        self.absorb_bytes(label);
+        self.absorb_bytes(message);
+    }
+
+    /// Append a `u64` to the transcript under the given `label`.
+    pub fn append_u64(&mut self, label: &'static [u8], x: u64) {
+        self.append_message(label, &x.to_le_bytes());
+    }
+
+    /// Fill `dest` with challenge bytes derived from the transcript and the
+    /// given `label`.
+    pub fn challenge_bytes(&mut self, label: &'static [u8], dest: &mut [u8]) {
+        self.absorb_bytes(label);
+        self.sponge.absorb(&[BlsScalar::from(dest.len() as u64)]);
+        self.sponge.squeeze_bytes(dest);
+    }
+
+    /// Absorb a `scalar` into the transcript under the given `label`.
+    pub fn absorb_scalar(&mut self, label: &'static [u8], scalar: &BlsScalar) {
+        self.absorb_bytes(label);
+        self.sponge.absorb(&[*scalar]);
+    }
+
+    /// Absorb the coordinates of a `point` into the transcript under the
+    /// given `label`.
+    pub fn absorb_point(&mut self, label: &'static [u8], point: &JubJubAffine) {
+        self.absorb_bytes(label);
+        self.sponge.absorb(&[point.get_u(), point.get_v()]);
+    }
+
+    /// Derive a challenge scalar from the transcript and the given `label`.
+    pub fn challenge_scalar(&mut self, label: &'static [u8]) -> BlsScalar {
+        self.absorb_bytes(label);
+        self.sponge.squeeze()
+    }
+
+    fn absorb_bytes(&mut self, bytes: &[u8]) {
+        byte_scalars(bytes).for_each(|s| self.sponge.absorb(&[s]));
+    }
+}
+
+/// Mirror the implementation of [`PoseidonTranscript`] inside of a PLONK
+/// circuit.
+///
+/// Labels and messages are part of the circuit description, and are appended
+/// as constants.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Copy)]
+pub struct TranscriptGadget {
+    sponge: DuplexGadget,
+}
+
+#[cfg(feature = "alloc")]
+impl TranscriptGadget {
+    /// Create a new transcript gadget for the protocol identified by `label`.
+    pub fn new<C>(composer: &mut C, label: &'static [u8]) -> Self
+    where
+        C: Composer,
+    {
+        let mut transcript = Self {
+            sponge: DuplexGadget::new(composer, BlsScalar::zero()),
+        };
+        transcript.append_message(composer, b"dom-sep", label);
+
+        transcript
+    }
+
+    /// Append a constant `message` to the transcript under the given `label`.
+    pub fn append_message<C>(
+        &mut self,
+        composer: &mut C,
+        label: &'static [u8],
+        message: &[u8],
+    ) where
+        C: Composer,
+    {
+        self.absorb_bytes(composer, label);
+        self.absorb_bytes(composer, message);
+    }
+
+    /// Absorb a `scalar` into the transcript under the given `label`.
+    pub fn absorb_scalar<C>(
+        &mut self,
+        composer: &mut C,
+        label: &'static [u8],
+        scalar: Witness,
+    ) where
+        C: Composer,
+    {
+        self.absorb_bytes(composer, label);
+        self.sponge.absorb(composer, &[scalar]);
+    }
+
+    /// Absorb the coordinates of a `point` into the transcript under the
+    /// given `label`.
+    pub fn absorb_point<C>(
+        &mut self,
+        composer: &mut C,
+        label: &'static [u8],
+        point: WitnessPoint,
+    ) where
+        C: Composer,
+    {
+        self.absorb_bytes(composer, label);
+        self.sponge.absorb(composer, &[*point.x(), *point.y()]);
+    }
+
+    /// Derive a challenge scalar from the transcript and the given `label`.
+    pub fn challenge_scalar<C>(
+        &mut self,
+        composer: &mut C,
+        label: &'static [u8],
+    ) -> Witness
+    where
+        C: Composer,
+    {
+        self.absorb_bytes(composer, label);
+        self.sponge.squeeze(composer)
+    }
+
+    fn absorb_bytes<C>(&mut self, composer: &mut C, bytes: &[u8])
+    where
+        C: Composer,
+    {
+        byte_scalars(bytes).for_each(|s| {
+            let s = composer.append_constant(s);
+            self.sponge.absorb(composer, &[s]);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transcript_challenges() {
+        let mut a = PoseidonTranscript::new(b"protocol");
+        let mut b = PoseidonTranscript::new(b"protocol");
+        let mut c = PoseidonTranscript::new(b"other-protocol");
+
+        for t in [&mut a, &mut b, &mut c] {
+            t.append_message(b"commitment", &[0xfa; 70]);
+            t.append_u64(b"round", 3);
+        }
+
+        let challenge = a.challenge_scalar(b"challenge");
+        assert_eq!(challenge, b.challenge_scalar(b"challenge"));
+        assert_ne!(challenge, c.challenge_scalar(b"challenge"));
+
+        let mut bytes_a = [0u8; 40];
+        let mut bytes_b = [0u8; 40];
+        a.challenge_bytes(b"bytes", &mut bytes_a);
+        b.challenge_bytes(b"bytes", &mut bytes_b);
+        assert_eq!(bytes_a, bytes_b);
+
+        // Messages are framed by their length
+        let mut d = PoseidonTranscript::new(b"protocol");
+        let mut e = PoseidonTranscript::new(b"protocol");
+        d.append_message(b"m", &[1, 2]);
+        e.append_message(b"m", &[1, 2, 0]);
+        assert_ne!(d.challenge_scalar(b"c"), e.challenge_scalar(b"c"));
+    }
+}
@@ -6,80 +6,306 @@
 
 //! Implementation of a Merkle Tree with a Dusk-Poseidon backend and
 //! zero-knowledge opening proof powered by PLONK.
+//!
+//! With the `verify-only` feature only the openings, the compact openings, the
+//! consistency proofs, the forest openings, the multiproofs, the maintained
+//! openings, the frontier trees and the root history are compiled, leaving out
+//! the tree storage and the circuits even if `alloc` is enabled. Disabling the
+//! default features as well drops the storage dependencies.
 
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 mod annotation;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod arity;
 mod branch;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod circuit;
+mod compact;
+mod consistency;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod diff;
+mod dynamic;
+mod forest;
+mod frontier;
+mod hex;
+mod history;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 mod leaf;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod leaves;
+mod maintained;
+mod multi;
+mod nodes;
+#[cfg(all(feature = "alloc", feature = "std", not(feature = "verify-only")))]
+mod persist;
+#[cfg(all(feature = "alloc", feature = "sled", not(feature = "verify-only")))]
+mod sled_store;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod stats;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod stored;
+#[cfg(all(feature = "alloc", feature = "std", not(feature = "verify-only")))]
+mod sync;
+#[cfg(all(feature = "alloc", feature = "mmap", not(feature = "verify-only")))]
+mod view;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod visit;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+mod walker;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 mod zk;
 
-pub use annotation::PoseidonAnnotation;
+#[cfg(feature = "interchange")]
+pub mod interchange;
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use annotation::{Aggregate, PoseidonAnnotation};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use arity::ArityBranch;
 
 #[cfg(feature = "rkyv-impl")]
 pub use branch::{
     ArchivedPoseidonBranch, ArchivedPoseidonLevel, PoseidonBranchResolver,
     PoseidonLevelResolver,
 };
-pub use branch::{PoseidonBranch, PoseidonLevel};
+pub use branch::{BranchError, PoseidonBranch, PoseidonLevel, TOMBSTONE};
+pub use compact::{CompactBranch, CompactLevel};
+pub use consistency::ConsistencyProof;
+pub use dynamic::DynPoseidonBranch;
+pub use forest::ForestOpening;
+pub use frontier::FrontierTree;
+pub use history::RootHistory;
+pub use maintained::MaintainedOpening;
+pub use multi::PoseidonMultiProof;
+pub use nodes::{compute_root, compute_root_with};
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use circuit::{OpeningCircuit, OpeningProver, OpeningVerifier};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use diff::{diff, TreeDiff};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use dynamic::DynPoseidonTree;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use forest::Forest;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use leaf::{PoseidonLeaf, PoseidonLeafExt, PoseidonTombstone};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use leaves::{HashedLeaf, KeyedScalarLeaf, ScalarLeaf};
+#[cfg(all(
+    feature = "alloc",
+    feature = "sled",
+    not(feature = "verify-only")
+))]
+pub use sled_store::SledStore;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use stats::TreeStats;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use stored::{MemoryStore, NStackStore, TreeStore};
+#[cfg(all(
+    feature = "alloc",
+    feature = "std",
+    not(feature = "verify-only")
+))]
+pub use sync::{SyncPoseidonTree, SyncWriteGuard};
+#[cfg(all(
+    feature = "alloc",
+    feature = "mmap",
+    not(feature = "verify-only")
+))]
+pub use view::PoseidonTreeView;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use visit::{Node, Visit};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use walker::{AggregateFilter, KeyRange};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub use zk::{
+    arity_merkle_opening, dyn_merkle_opening, forest_opening, merkle_insert,
+    merkle_multi_opening, merkle_opening, merkle_opening_preimage,
+    merkle_opening_public, merkle_opening_with, merkle_opening_with_pos,
+    merkle_openings, merkle_update,
+};
 
-pub use leaf::PoseidonLeaf;
-pub use zk::merkle_opening;
+#[cfg(any(feature = "r1cs", feature = "halo2", feature = "bellman"))]
+pub(crate) use nodes::assert_tree_width;
 
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use crate::permutation::Hades;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use history::RootRecorder;
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use alloc::boxed::Box;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use alloc::collections::BTreeMap;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use alloc::vec;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use alloc::vec::Vec;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 use core::borrow::Borrow;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use core::fmt;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use core::marker::PhantomData;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use core::mem;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use core::ops::{ControlFlow, RangeBounds};
 
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 use bls12_381::{Scalar as BlsScalar};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 use microkelvin::{Branch, Walker};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 use nstack::annotation::{Cardinality, Keyed};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 use nstack::NStack;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 use ranno::Annotation;
+#[cfg(all(
+    feature = "alloc",
+    feature = "parallel",
+    not(feature = "verify-only")
+))]
+use rayon::prelude::*;
+
+/// Callback notified of the mutations of a tree, with the roots before and
+/// after the mutation and the positions of the mutated leaves.
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+type Hook = Box<dyn FnMut(&BlsScalar, &BlsScalar, &[u64]) + Send + Sync>;
 
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 /// Represents a Merkle Tree with a given depth that will be calculated using
 /// the Poseidon Hash technique.
-#[derive(Debug, Default)]
-pub struct PoseidonTree<L, K, const DEPTH: usize> {
-    inner: NStack<L, PoseidonAnnotation<K>>,
+///
+/// The internal nodes of the tree can hold a custom [`Aggregate`] of their
+/// leaves, `A`, queried with [`PoseidonTree::aggregate`] and
+/// [`PoseidonTree::leaves_by_aggregate`].
+///
+/// The leaves and the hashes of the nodes are held in the storage `S`, an
+/// [`NStackStore`] in memory by default. A tree whose storage is a
+/// [`TreeStore`] reads and writes them through the store instead, as
+/// [`PoseidonTree::open`] describes, and its nodes have `ARITY` children
+/// hashed with the permutation `P`. Every access to such a tree may fail with
+/// the error of its store, and the aggregates, the walks, the callbacks of
+/// [`PoseidonTree::on_change`] and the history of the roots are only
+/// supported in an [`NStackStore`], whose arity is `4`.
+#[derive(Default)]
+pub struct PoseidonTree<
+    L,
+    K,
+    const DEPTH: usize,
+    A = (),
+    S = NStackStore<L, K, A>,
+    const ARITY: usize = { hades::WIDTH - 1 },
+    P = Hades,
+> {
+    inner: S,
+    perm: P,
+    hooks: Vec<Hook>,
+    history: Option<Box<dyn RootRecorder>>,
+    _marker: PhantomData<(L, K, A)>,
 }
 
-impl<L, K, const DEPTH: usize> Clone for PoseidonTree<L, K, DEPTH>
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, A, S, const ARITY: usize, P> fmt::Debug
+    for PoseidonTree<L, K, DEPTH, A, S, ARITY, P>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoseidonTree")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, A> Clone for PoseidonTree<L, K, DEPTH, A>
 where
     L: Clone + PoseidonLeaf + Keyed<K>,
     K: Clone + PartialOrd,
+    A: Aggregate<L>,
 {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
+            perm: Hades,
+            hooks: Vec::new(),
+            history: self.history.as_ref().map(|h| h.boxed_clone()),
+            _marker: PhantomData,
         }
     }
 }
 
-impl<L, K, const DEPTH: usize> AsRef<NStack<L, PoseidonAnnotation<K>>>
-    for PoseidonTree<L, K, DEPTH>
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, A> AsRef<NStack<L, PoseidonAnnotation<K, A>>>
+    for PoseidonTree<L, K, DEPTH, A>
 {
-    fn as_ref(&self) -> &NStack<L, PoseidonAnnotation<K>> {
+    fn as_ref(&self) -> &NStack<L, PoseidonAnnotation<K, A>> {
         &self.inner
     }
 }
 
-impl<L, K, const DEPTH: usize> AsMut<NStack<L, PoseidonAnnotation<K>>>
-    for PoseidonTree<L, K, DEPTH>
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, A> AsMut<NStack<L, PoseidonAnnotation<K, A>>>
+    for PoseidonTree<L, K, DEPTH, A>
 {
-    fn as_mut(&mut self) -> &mut NStack<L, PoseidonAnnotation<K>> {
+    fn as_mut(&mut self) -> &mut NStack<L, PoseidonAnnotation<K, A>> {
         &mut self.inner
     }
 }
 
-impl<L, K, const DEPTH: usize> PoseidonTree<L, K, DEPTH> {
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, A> PoseidonTree<L, K, DEPTH, A> {
     /// Creates a new poseidon tree
     pub const fn new() -> Self {
         Self {
             inner: NStack::new(),
+            perm: Hades,
+            hooks: Vec::new(),
+            history: None,
+            _marker: PhantomData,
         }
     }
+
+    /// Register a callback notified after every mutation of the tree, with
+    /// the roots before and after the mutation and the positions of the
+    /// mutated leaves.
+    ///
+    /// A batch of mutations made with [`PoseidonTree::defer_hashing`] is
+    /// notified once, at its end, and a rollback is notified without
+    /// positions. Mutations made through the underlying `NStack`, with
+    /// [`AsMut`], are not notified. The callbacks are neither cloned with the
+    /// tree nor stored in its checkpoints.
+    pub fn on_change<F>(&mut self, hook: F)
+    where
+        F: FnMut(&BlsScalar, &BlsScalar, &[u64]) + Send + Sync + 'static,
+    {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Return the history of the roots of the tree, if tracked with
+    /// [`PoseidonTree::track_roots`] with the same length `N`.
+    pub fn root_history<const N: usize>(&self) -> Option<&RootHistory<N>> {
+        self.history.as_ref()?.as_any().downcast_ref()
+    }
+
+    /// Return `true` if the roots of the tree are tracked with
+    /// [`PoseidonTree::track_roots`], and `root` is one of the recent ones.
+    pub fn is_recent_root(&self, root: &BlsScalar) -> bool {
+        self.history
+            .as_ref()
+            .map_or(false, |history| history.is_recent_root(root))
+    }
 }
 
-impl<L, K, const DEPTH: usize> PoseidonTree<L, K, DEPTH>
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, A> PoseidonTree<L, K, DEPTH, A>
 where
     L: PoseidonLeaf + Keyed<K>,
     K: Clone + PartialOrd,
+    A: Aggregate<L>,
 {
     /// Append a leaf to the tree. Return the index of the appended leaf.
     pub fn push(&mut self, mut leaf: L) -> u64 {
@@ -87,16 +313,282 @@ where
         let cardinality: &Cardinality = anno.borrow();
 
         let pos = **cardinality;
+        let old_root = self.observed_root();
 
         leaf.set_pos(pos);
         self.inner.push(leaf);
 
+        self.notify(old_root, &[pos]);
+
         pos
     }
 
+    /// Build the tree holding `leaves`, in order, hashing the leaves and the
+    /// nodes of every level in parallel.
+    ///
+    /// The nodes are hashed bottom-up as [`compute_root`] does, and their
+    /// hashes are cached in the annotations of the tree, as when it's
+    /// restored with [`PoseidonTree::restore`], so they're not computed again
+    /// when the root is read or a branch is opened.
+    #[cfg(feature = "parallel")]
+    pub fn from_leaves_parallel(leaves: &[L]) -> Self
+    where
+        L: Clone + Send + Sync,
+    {
+        let leaves: Vec<L> = (0..)
+            .zip(leaves)
+            .map(|(pos, leaf)| {
+                let mut leaf = leaf.clone();
+                leaf.set_pos(pos);
+                leaf
+            })
+            .collect();
+        let hashes: Vec<BlsScalar> =
+            leaves.par_iter().map(leaf::leaf_hash).collect();
+
+        // The annotations below the top of the stack are cached, and restored
+        // from the levels holding more than a single node
+        let roots = nodes::levels(&hashes, DEPTH)
+            .into_iter()
+            .take_while(|level| level.len() > 1)
+            .flatten()
+            .collect();
+
+        let mut tree = Self::new();
+        tree.inner = persist::restore_stack(leaves, roots);
+
+        tree
+    }
+
+    /// Start recording the last `N` roots of the tree, the current one
+    /// included, replacing any previously tracked history.
+    ///
+    /// The root is recorded after every mutation notified to the callbacks of
+    /// [`PoseidonTree::on_change`], a batch of
+    /// [`PoseidonTree::defer_hashing`] recording a single root. The history
+    /// is cloned with the tree, and can be read with
+    /// [`PoseidonTree::root_history`] and [`PoseidonTree::is_recent_root`].
+    pub fn track_roots<const N: usize>(&mut self) {
+        let mut history = RootHistory::<N>::new();
+        history.record(self.root());
+
+        self.history = Some(Box::new(history));
+    }
+
     /// Fetch, remove and return the last inserted leaf, if present.
     pub fn pop(&mut self) -> Option<L> {
-        self.inner.pop()
+        let old_root = self.observed_root();
+        let leaf = self.inner.pop()?;
+
+        self.notify(old_root, &[*leaf.pos()]);
+
+        Some(leaf)
+    }
+
+    /// Take a snapshot of the tree, that can later be restored with
+    /// [`PoseidonTree::rollback`].
+    ///
+    /// The snapshot holds a copy of the leaves and of the cached hashes of the
+    /// tree, so rolling back doesn't require any hashing.
+    pub fn checkpoint(&self) -> Checkpoint<L, K, DEPTH, A>
+    where
+        L: Clone,
+    {
+        Checkpoint { tree: self.clone() }
+    }
+
+    /// Restore the tree to the state it had when `checkpoint` was taken,
+    /// discarding every change made since.
+    pub fn rollback(&mut self, checkpoint: Checkpoint<L, K, DEPTH, A>) {
+        let old_root = self.observed_root();
+
+        self.inner = checkpoint.tree.inner;
+
+        self.notify(old_root, &[]);
+    }
+
+    /// Mutate the tree in a batch, deferring the hashing of the internal nodes
+    /// to the end of the batch.
+    ///
+    /// Every mutation marks the nodes on its path as dirty, and the hashes of
+    /// the dirty nodes are recomputed lazily, once, when the tree is next
+    /// read. The ancestors shared by many mutated leaves are then only hashed
+    /// once, instead of once per mutation.
+    pub fn defer_hashing<F, T>(&mut self, f: F) -> T
+    where
+        F: FnOnce(&mut Deferred<'_, L, K, DEPTH, A>) -> T,
+    {
+        let anno = PoseidonAnnotation::from_child(&self.inner);
+        let cardinality: &Cardinality = anno.borrow();
+        let len = **cardinality;
+
+        // The hooks are notified, and the root recorded, once for the whole
+        // batch
+        let old_root = self.observed_root();
+        let hooks = mem::take(&mut self.hooks);
+        let history = self.history.take();
+
+        let mut deferred = Deferred {
+            tree: self,
+            len,
+            changed: Vec::new(),
+        };
+        let result = f(&mut deferred);
+        let changed = deferred.changed;
+
+        self.hooks = hooks;
+        self.history = history;
+        self.notify(old_root, &changed);
+
+        result
+    }
+
+    /// Append all the leaves of `other` to the tree, in order. Return the
+    /// mapping of the positions of the leaves, the leaf on index `i` of
+    /// `other` being on index `mapping[i]` of the tree.
+    ///
+    /// Merging into an empty tree takes `other` in place, reusing all of its
+    /// cached hashes. Otherwise the leaves are appended in a single batch with
+    /// [`PoseidonTree::defer_hashing`], hashing every new internal node once.
+    /// The subtrees of `other` are not grafted even when they are aligned:
+    /// their leaves move to new positions, and the hash of a leaf may depend
+    /// on its position, so their cached hashes can't be trusted.
+    pub fn merge(&mut self, other: Self) -> Vec<u64>
+    where
+        L: Clone,
+    {
+        let anno = PoseidonAnnotation::from_child(&self.inner);
+        let cardinality: &Cardinality = anno.borrow();
+
+        if **cardinality > 0 {
+            return self.defer_hashing(|tree| {
+                other.leaves().map(|leaf| tree.push(leaf.clone())).collect()
+            });
+        }
+
+        let old_root = self.observed_root();
+
+        self.inner = other.inner;
+        let mapping: Vec<u64> = self.leaves().map(|leaf| *leaf.pos()).collect();
+
+        self.notify(old_root, &mapping);
+
+        mapping
+    }
+
+    /// Remove the leaf on a provided index, returning it if present.
+    ///
+    /// The slot is not freed, so the index of every other leaf is preserved:
+    /// it is overwritten with a [`PoseidonTombstone::tombstone`], which is
+    /// hashed to [`TOMBSTONE`] and can't be opened. The hashes on the path
+    /// from the slot to the root are recomputed.
+    pub fn remove(&mut self, n: u64) -> Option<L>
+    where
+        L: PoseidonTombstone,
+    {
+        self.update(n, L::tombstone())
+    }
+
+    /// Replace the leaf on a provided index, returning the previous one.
+    ///
+    /// Only the hashes on the path from the leaf to the root are recomputed.
+    /// If there is no leaf on the index, the tree is left untouched and `None`
+    /// is returned.
+    pub fn update(&mut self, n: u64, mut leaf: L) -> Option<L> {
+        let old_root = self.observed_root();
+
+        let previous = {
+            let mut branch = self.inner.nth_mut(n)?;
+
+            leaf.set_pos(n);
+
+            mem::replace(&mut *branch, leaf)
+        };
+
+        self.notify(old_root, &[n]);
+
+        Some(previous)
+    }
+
+    /// Return the root of the tree, if there are hooks to notify of its
+    /// change.
+    fn observed_root(&self) -> Option<BlsScalar> {
+        match self.hooks.is_empty() {
+            true => None,
+            false => Some(self.root()),
+        }
+    }
+
+    /// Record the new root in the history, and notify the hooks of a
+    /// mutation of the leaves on `positions` if the root before the mutation
+    /// was observed.
+    fn notify(&mut self, old_root: Option<BlsScalar>, positions: &[u64]) {
+        if old_root.is_none() && self.history.is_none() {
+            return;
+        }
+
+        let new_root = self.root();
+        if let Some(history) = &mut self.history {
+            history.record(new_root);
+        }
+        if let Some(old_root) = old_root {
+            self.hooks
+                .iter_mut()
+                .for_each(|hook| hook(&old_root, &new_root, positions));
+        }
+    }
+
+    /// Append a leaf to the tree, after checking that it can be hashed.
+    /// Return the index of the appended leaf.
+    ///
+    /// Once appended, the leaf is notified of its position and of the new
+    /// root of the tree with [`PoseidonLeafExt::on_inserted`]. If the leaf
+    /// can't be hashed, the tree is left untouched and the error is returned.
+    pub fn try_push(&mut self, leaf: L) -> Result<u64, L::Error>
+    where
+        L: PoseidonLeafExt,
+    {
+        let hash = leaf.try_poseidon_hash()?;
+        debug_assert!(
+            hash == leaf.poseidon_hash(),
+            "the hash of the leaf should match its fallible hash"
+        );
+
+        let pos = self.push(leaf);
+        self.notify_inserted(pos);
+
+        Ok(pos)
+    }
+
+    /// Replace the leaf on a provided index, after checking that it can be
+    /// hashed. Return the previous leaf, if any.
+    ///
+    /// Once inserted, the leaf is notified as with
+    /// [`PoseidonTree::try_push`]. If the leaf can't be hashed, the tree is
+    /// left untouched and the error is returned.
+    pub fn try_update(&mut self, n: u64, leaf: L) -> Result<Option<L>, L::Error>
+    where
+        L: PoseidonLeafExt,
+    {
+        leaf.try_poseidon_hash()?;
+
+        let previous = self.update(n, leaf);
+        if previous.is_some() {
+            self.notify_inserted(n);
+        }
+
+        Ok(previous)
+    }
+
+    /// Notify the leaf on `pos` of its insertion.
+    fn notify_inserted(&self, pos: u64)
+    where
+        L: PoseidonLeafExt,
+    {
+        let root = self.root();
+        if let Some(leaf) = self.inner.nth(pos) {
+            leaf.on_inserted(pos, &root);
+        }
     }
 
     /// Fetch a leaf on a provided index.
@@ -112,6 +604,203 @@ where
         self.inner.nth(n).as_ref().map(PoseidonBranch::from)
     }
 
+    /// Return a merkle opening for a given index, that can be kept up to date
+    /// with the leaves later appended to the tree.
+    ///
+    /// See [`MaintainedOpening::append`].
+    pub fn maintained_opening(
+        &self,
+        n: u64,
+    ) -> Option<MaintainedOpening<DEPTH>> {
+        let anno = PoseidonAnnotation::from_child(&self.inner);
+        let cardinality: &Cardinality = anno.borrow();
+        let len = **cardinality;
+
+        let last = self.branch(len.checked_sub(1)?)?;
+        MaintainedOpening::new(n, self.branch(n)?, len, &last)
+    }
+
+    /// Return a merkle opening of the root of a subtree, from the subtree up
+    /// to the root of the tree.
+    ///
+    /// The subtree is the one of height `level` holding the leaf on index
+    /// `n`, a subtree of height `0` being the leaf itself. The root of the
+    /// subtree is the node opened by the branch, and the branch can be opened
+    /// in a circuit with [`dyn_merkle_opening`]. Return `None` if there is no
+    /// leaf on the index, or if `level` is not lower than the depth of the
+    /// tree.
+    pub fn subtree_branch(
+        &self,
+        n: u64,
+        level: usize,
+    ) -> Option<DynPoseidonBranch> {
+        if level >= DEPTH {
+            return None;
+        }
+
+        let branch = self.branch(n)?;
+
+        Some(DynPoseidonBranch {
+            path: branch.path[level..].to_vec(),
+            root: branch.root,
+        })
+    }
+
+    /// Return a merkle opening of the leaves on the provided indexes at once,
+    /// holding the levels shared by their paths only once.
+    ///
+    /// The indexes are sorted and deduplicated. Return `None` if `positions`
+    /// is empty, or if there is no leaf on one of them.
+    pub fn multi_branch(
+        &self,
+        positions: &[u64],
+    ) -> Option<PoseidonMultiProof<DEPTH>> {
+        let mut positions = positions.to_vec();
+        positions.sort_unstable();
+        positions.dedup();
+
+        let mut levels = vec![BTreeMap::new(); DEPTH];
+        let mut root = None;
+
+        for pos in positions.iter() {
+            let branch = self.branch(*pos)?;
+            root = Some(branch.root);
+
+            let mut group = *pos;
+            for (level, levels) in branch.path.iter().zip(levels.iter_mut()) {
+                group /= nodes::ARITY as u64;
+                levels.insert(group, level.level);
+            }
+        }
+
+        Some(PoseidonMultiProof {
+            positions,
+            levels: levels
+                .into_iter()
+                .map(|levels| levels.into_values().collect())
+                .collect(),
+            root: root?,
+        })
+    }
+
+    /// Return the hashes of the internal nodes of the tree, level by level from
+    /// the bottom up to the root, each level holding the nodes from left to
+    /// right.
+    ///
+    /// The hashes are read from the cache of the tree, and only the levels
+    /// above the top of the underlying stack, holding a single node each, are
+    /// hashed. The last node is the root of the tree, and the nodes of an
+    /// empty tree are empty.
+    pub fn export_nodes(&self) -> Vec<BlsScalar> {
+        self.node_levels().concat()
+    }
+
+    /// Return the hashes of the internal nodes of the tree, level by level,
+    /// as in [`PoseidonTree::export_nodes`].
+    pub(crate) fn node_levels(&self) -> Vec<Vec<BlsScalar>> {
+        if self.leaves().next().is_none() {
+            return Vec::new();
+        }
+
+        let mut levels = Vec::with_capacity(DEPTH);
+        let top = nodes::collect(&self.inner, &mut levels);
+
+        let anno = PoseidonAnnotation::from_child(&self.inner);
+        let root: &BlsScalar = anno.borrow();
+        levels.resize(top + 1, Vec::new());
+        levels[top].push(*root);
+
+        let mut node = *root;
+        while levels.len() < DEPTH {
+            node = nodes::hash_node(&[node]);
+            levels.push(vec![node]);
+        }
+
+        levels
+    }
+
+    /// Create a tree from its `leaves` and the hashes of its internal nodes,
+    /// as returned by [`PoseidonTree::export_nodes`].
+    ///
+    /// The tree is only returned if its nodes match the given ones, allowing
+    /// to check hashes received from an external system. Return `None`
+    /// otherwise.
+    pub fn import_nodes<I>(leaves: I, nodes: &[BlsScalar]) -> Option<Self>
+    where
+        I: IntoIterator<Item = L>,
+    {
+        let tree: Self = leaves.into_iter().collect();
+
+        if tree.export_nodes() != nodes {
+            return None;
+        }
+
+        Some(tree)
+    }
+
+    /// Return the number of leaves of the tree, tombstones included.
+    pub fn len(&self) -> u64 {
+        let anno = PoseidonAnnotation::from_child(&self.inner);
+        let cardinality: &Cardinality = anno.borrow();
+
+        **cardinality
+    }
+
+    /// Return `true` if the tree has no leaves.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return the maximum number of leaves of the tree, saturating at
+    /// `u64::MAX`.
+    pub const fn capacity(&self) -> u64 {
+        (nodes::ARITY as u64).saturating_pow(DEPTH as u32)
+    }
+
+    /// Return `true` if no more leaves can be appended to the tree.
+    pub fn is_full(&self) -> bool {
+        self.len() >= self.capacity()
+    }
+
+    /// Return the number of levels of internal nodes holding the leaves of
+    /// the tree, up to the first level with a single node.
+    ///
+    /// The levels above it hold a single node each, hashed up to the root
+    /// when an opening is requested, and aren't stored by the tree.
+    pub fn depth_used(&self) -> usize {
+        let len = self.len();
+        if len == 0 {
+            return 0;
+        }
+
+        nodes::level_lens(len, DEPTH)
+            .position(|len| len == 1)
+            .map_or(DEPTH, |level| level + 1)
+    }
+
+    /// Return statistics on the storage of the tree.
+    pub fn stats(&self) -> TreeStats {
+        let leaves = self.len();
+        let levels: Vec<u64> = nodes::level_lens(leaves, DEPTH).collect();
+
+        let internal = levels.iter().sum();
+        let cached_hashes = levels[..self.depth_used()].iter().sum();
+
+        // Every stored node holds its annotation and a pointer to each of its
+        // children
+        let node_size = mem::size_of::<PoseidonAnnotation<K, A>>()
+            + nodes::ARITY * mem::size_of::<usize>();
+        let memory = leaves as usize * mem::size_of::<L>()
+            + cached_hashes as usize * node_size;
+
+        TreeStats {
+            leaves,
+            nodes: internal,
+            cached_hashes,
+            memory,
+        }
+    }
+
     /// Return the current root/state of the tree.
     pub fn root(&self) -> BlsScalar {
         self.branch(0).map(|b| *b.root()).unwrap_or_default()
@@ -127,6 +816,113 @@ where
         self.inner.nth(start)
     }
 
+    /// Provides an iterator over all the leaves of the tree, in order.
+    pub fn leaves(&self) -> impl Iterator<Item = &L> {
+        self.iter_walk(0).into_iter().flatten()
+    }
+
+    /// Provides an iterator over all the leaves of the tree, in order, paired
+    /// with their positions.
+    pub fn leaves_with_pos(&self) -> impl Iterator<Item = (u64, &L)> {
+        (0..).zip(self.leaves())
+    }
+
+    /// Provides an iterator over the leaves of the tree whose key is contained
+    /// in `range`.
+    ///
+    /// The iteration uses the [`KeyRange`] walker, skipping the subtrees whose
+    /// maximum key is below the start of the range.
+    pub fn leaves_in_range<R>(&self, range: R) -> impl Iterator<Item = &L>
+    where
+        R: RangeBounds<K>,
+    {
+        self.annotated_iter_walk(KeyRange::new(range))
+            .into_iter()
+            .flatten()
+    }
+
+    /// Return the position of the first leaf with the given `key`.
+    ///
+    /// The search uses the [`KeyRange`] walker, skipping the subtrees whose
+    /// maximum key is below `key`.
+    pub fn pos_of_key(&self, key: &K) -> Option<u64> {
+        self.leaves_in_range(key..=key).next().map(|l| *l.pos())
+    }
+
+    /// Return the position of the first leaf with the given `key`, along with
+    /// its merkle opening.
+    ///
+    /// The leaf is found with the [`KeyRange`] walker, as in
+    /// [`PoseidonTree::pos_of_key`], and the opening is read from the same
+    /// walk.
+    pub fn branch_by_key(
+        &self,
+        key: &K,
+    ) -> Option<(u64, PoseidonBranch<DEPTH>)> {
+        let branch = Branch::walk(&self.inner, KeyRange::new(key..=key))?;
+
+        Some((*branch.pos(), PoseidonBranch::from(&branch)))
+    }
+
+    /// Return the custom aggregate of all the leaves of the tree.
+    pub fn aggregate(&self) -> A {
+        let anno = PoseidonAnnotation::from_child(&self.inner);
+        anno.aggregate().clone()
+    }
+
+    /// Provides an iterator over the leaves of the tree whose [`Aggregate`]
+    /// satisfies `filter`.
+    ///
+    /// The iteration uses the [`AggregateFilter`] walker, skipping the
+    /// subtrees whose aggregate doesn't satisfy `filter`.
+    pub fn leaves_by_aggregate<F>(&self, filter: F) -> impl Iterator<Item = &L>
+    where
+        F: FnMut(&A) -> bool,
+    {
+        self.annotated_iter_walk(AggregateFilter::new(filter))
+            .into_iter()
+            .flatten()
+    }
+
+    /// Walk the tree depth-first, calling `visitor` on every internal node
+    /// and every leaf, in order.
+    ///
+    /// An internal node is visited before its children, which are skipped
+    /// when `visitor` returns [`Visit::Prune`], deciding on the annotation of
+    /// the node. The walk stops on the first [`Visit::Break`], returning its
+    /// value.
+    ///
+    /// Only the stored nodes are visited, up to the level returned by
+    /// [`PoseidonTree::depth_used`].
+    pub fn walk<B, F>(&self, mut visitor: F) -> ControlFlow<B>
+    where
+        F: FnMut(Node<'_, L, K, A>) -> Visit<B>,
+    {
+        if self.is_empty() {
+            return ControlFlow::Continue(());
+        }
+
+        let anno = PoseidonAnnotation::from_child(&self.inner);
+        let level = visit::level(&self.inner);
+
+        visit::visit(&self.inner, &anno, level, 0, &mut visitor)
+    }
+
+    /// Return the position of the first leaf whose hash is `hash`.
+    ///
+    /// # Cost
+    /// The leaves aren't indexed by their hash, so this walks them in order,
+    /// hashing every one of them, and takes `O(n)` hashes for a tree of `n`
+    /// leaves when `hash` isn't in it. Applications checking many hashes
+    /// should keep their own index from hashes to positions instead.
+    pub fn contains_hash(&self, hash: &BlsScalar) -> Option<u64> {
+        self.leaves_with_pos()
+            .find(|(_, leaf)| {
+                !leaf.is_tombstone() && leaf.poseidon_hash() == *hash
+            })
+            .map(|(pos, _)| pos)
+    }
+
     /// Provides an iterator over the leaves of the tree which have been
     /// previously annotated via a custom `Walker` passed as argument.
     ///
@@ -138,8 +934,117 @@ where
         walker: W,
     ) -> Option<impl IntoIterator<Item = &L>>
     where
-        W: Walker<NStack<L, PoseidonAnnotation<K>>, PoseidonAnnotation<K>>,
+        W: Walker<
+            NStack<L, PoseidonAnnotation<K, A>>,
+            PoseidonAnnotation<K, A>,
+        >,
     {
         Branch::walk(&self.inner, walker)
     }
 }
+
+/// Snapshot of a [`PoseidonTree`], taken with [`PoseidonTree::checkpoint`].
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+#[derive(Debug)]
+pub struct Checkpoint<L, K, const DEPTH: usize, A = ()> {
+    tree: PoseidonTree<L, K, DEPTH, A>,
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, A> Checkpoint<L, K, DEPTH, A>
+where
+    L: PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    /// Return the root of the tree at the time of the snapshot.
+    pub fn root(&self) -> BlsScalar {
+        self.tree.root()
+    }
+}
+
+/// Handle to a [`PoseidonTree`] being mutated in a batch, given by
+/// [`PoseidonTree::defer_hashing`].
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+#[derive(Debug)]
+pub struct Deferred<'a, L, K, const DEPTH: usize, A = ()> {
+    tree: &'a mut PoseidonTree<L, K, DEPTH, A>,
+    len: u64,
+    changed: Vec<u64>,
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, A> Deferred<'_, L, K, DEPTH, A>
+where
+    L: PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    /// Append a leaf to the tree. Return the index of the appended leaf.
+    pub fn push(&mut self, mut leaf: L) -> u64 {
+        let pos = self.len;
+
+        leaf.set_pos(pos);
+        self.tree.inner.push(leaf);
+        self.len += 1;
+        self.changed.push(pos);
+
+        pos
+    }
+
+    /// Replace the leaf on a provided index, returning the previous one.
+    ///
+    /// Finding the leaf requires the hashes of the nodes on its path, so the
+    /// dirty ones among them are recomputed.
+    pub fn update(&mut self, n: u64, leaf: L) -> Option<L> {
+        let previous = self.tree.update(n, leaf)?;
+        self.changed.push(n);
+
+        Some(previous)
+    }
+
+    /// Return the number of leaves in the tree.
+    pub const fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Return `true` if the tree has no leaves.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Append the leaves to the tree, setting their positions in order.
+///
+/// The leaves are appended with [`PoseidonTree::defer_hashing`], so the hashes
+/// of the internal nodes are only computed once for the whole batch.
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, A> Extend<L> for PoseidonTree<L, K, DEPTH, A>
+where
+    L: PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    fn extend<I: IntoIterator<Item = L>>(&mut self, leaves: I) {
+        self.defer_hashing(|tree| {
+            leaves.into_iter().for_each(|leaf| {
+                tree.push(leaf);
+            })
+        })
+    }
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, A> FromIterator<L>
+    for PoseidonTree<L, K, DEPTH, A>
+where
+    L: PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    fn from_iter<I: IntoIterator<Item = L>>(leaves: I) -> Self {
+        let mut tree = Self::new();
+        tree.extend(leaves);
+        tree
+    }
+}
@@ -10,9 +10,15 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use crate::tree::leaf::leaf_hash;
 use crate::tree::PoseidonLeaf;
 
 use core::borrow::Borrow;
+#[cfg(feature = "std")]
+use core::cell::RefCell;
+
+#[cfg(feature = "std")]
+use alloc::vec::{self, Vec};
 
 use bls12_381::BlsScalar;
 use hades::{ScalarStrategy, Strategy};
@@ -21,58 +27,139 @@ use nstack::annotation::{Cardinality, Keyed, MaxKey};
 use nstack::NStack;
 use ranno::Annotation;
 
-/// Annotation holding the root, cardinality, and the maximum value of a generic
-/// key.
+/// Custom aggregate of the leaves of a subtree, held in the annotations of a
+/// tree along with their root, cardinality and maximum key.
+///
+/// The aggregate of a node is computed from the aggregates of its children,
+/// combined from left to right, and cached with the rest of its annotation.
+/// The default value is the aggregate of no leaves, and must be left
+/// unchanged by [`Aggregate::combine`]. The unit type aggregates nothing,
+/// and is the aggregate of the trees that don't define one.
+pub trait Aggregate<L>: Clone + Default {
+    /// Aggregate of a single leaf.
+    fn from_leaf(leaf: &L) -> Self;
+
+    /// Combine the aggregate of the subtree to the right of this one.
+    fn combine(&mut self, other: &Self);
+}
+
+impl<L> Aggregate<L> for () {
+    fn from_leaf(_: &L) -> Self {}
+
+    fn combine(&mut self, _: &Self) {}
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// Roots of the annotations restored with [`with_restored_roots`], in the
+    /// order the annotations are computed.
+    static RESTORED: RefCell<Option<vec::IntoIter<BlsScalar>>> =
+        RefCell::new(None);
+}
+
+/// Run `f`, taking the roots of the annotations it computes from `roots`
+/// instead of hashing their children, until `roots` are exhausted.
+///
+/// The annotations must be computed in the order of `roots`, and the roots
+/// are trusted to be the hashes of the children.
+#[cfg(feature = "std")]
+pub(crate) fn with_restored_roots<T, F>(roots: Vec<BlsScalar>, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    struct Reset;
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            RESTORED.with(|restored| restored.borrow_mut().take());
+        }
+    }
+
+    RESTORED.with(|restored| {
+        *restored.borrow_mut() = Some(roots.into_iter());
+    });
+    let _reset = Reset;
+
+    f()
+}
+
+#[cfg(feature = "std")]
+fn restored_root() -> Option<BlsScalar> {
+    RESTORED.with(|restored| restored.borrow_mut().as_mut()?.next())
+}
+
+#[cfg(not(feature = "std"))]
+fn restored_root() -> Option<BlsScalar> {
+    None
+}
+
+/// Annotation holding the root, cardinality, the maximum value of a generic
+/// key, and a custom [`Aggregate`] of the leaves.
 #[derive(Debug, Clone, Default)]
-pub struct PoseidonAnnotation<K> {
+pub struct PoseidonAnnotation<K, A = ()> {
     poseidon_root: BlsScalar,
     cardinality: Cardinality,
     max_key: MaxKey<K>,
+    aggregate: A,
 }
 
-impl<K> Borrow<BlsScalar> for PoseidonAnnotation<K> {
+impl<K, A> PoseidonAnnotation<K, A> {
+    /// Return the custom aggregate of the leaves of the annotated subtree.
+    pub const fn aggregate(&self) -> &A {
+        &self.aggregate
+    }
+}
+
+impl<K, A> Borrow<BlsScalar> for PoseidonAnnotation<K, A> {
     fn borrow(&self) -> &BlsScalar {
         &self.poseidon_root
     }
 }
 
-impl<K> Borrow<Cardinality> for PoseidonAnnotation<K> {
+impl<K, A> Borrow<Cardinality> for PoseidonAnnotation<K, A> {
     fn borrow(&self) -> &Cardinality {
         &self.cardinality
     }
 }
 
-impl<K> Borrow<MaxKey<K>> for PoseidonAnnotation<K> {
+impl<K, A> Borrow<MaxKey<K>> for PoseidonAnnotation<K, A> {
     fn borrow(&self) -> &MaxKey<K> {
         &self.max_key
     }
 }
 
-impl<L, K> Annotation<NStack<L, PoseidonAnnotation<K>>>
-    for PoseidonAnnotation<K>
+impl<L, K, A> Annotation<NStack<L, PoseidonAnnotation<K, A>>>
+    for PoseidonAnnotation<K, A>
 where
     L: PoseidonLeaf + Keyed<K>,
     K: Clone + PartialOrd,
+    A: Aggregate<L>,
 {
-    fn from_child(stack: &NStack<L, PoseidonAnnotation<K>>) -> Self {
+    fn from_child(stack: &NStack<L, PoseidonAnnotation<K, A>>) -> Self {
         let mut perm = [BlsScalar::zero(); hades::WIDTH];
         let mut flag = 1;
         let mut mask = 0;
         let mut cardinality = 0;
         let mut max_key = MaxKey::<K>::NegativeInfinity;
+        let mut aggregate = A::default();
+        let restored = restored_root();
 
         match stack {
             NStack::Leaf(leaf) => {
                 for (i, l) in leaf.iter().enumerate() {
                     if let Some(l) = l {
                         mask |= flag;
-                        perm[i + 1] = l.poseidon_hash();
+                        if restored.is_none() {
+                            perm[i + 1] = leaf_hash(l);
+                        }
                         cardinality += 1;
 
                         let key = l.key();
                         if &max_key < key {
                             max_key = MaxKey::Maximum(key.clone());
                         }
+
+                        aggregate.combine(&A::from_leaf(l));
                     }
                     flag <<= 1;
                 }
@@ -90,20 +177,25 @@ where
                         if max_key < anno.max_key {
                             max_key = anno.max_key.clone();
                         }
+
+                        aggregate.combine(&anno.aggregate);
                     }
                     flag <<= 1;
                 }
             }
         }
 
-        perm[0] = BlsScalar::from(mask);
-        ScalarStrategy::new().perm(&mut perm);
-        let poseidon_root = perm[1];
+        let poseidon_root = restored.unwrap_or_else(|| {
+            perm[0] = BlsScalar::from(mask);
+            ScalarStrategy::new().perm(&mut perm);
+            perm[1]
+        });
 
         Self {
             cardinality: cardinality.into(),
             poseidon_root,
             max_key,
+            aggregate,
         }
     }
 }
@@ -0,0 +1,187 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Openings of trees whose nodes have any number of children.
+//!
+//! The nodes of a tree of arity `ARITY` are hashed as the ones of a
+//! [`PoseidonTree`](super::PoseidonTree), with a permutation of width
+//! `ARITY + 1`: the first element of the state flags the children present,
+//! the following ones hold the children, and the hash is the second element
+//! of the permuted state. A tree of arity `4` hashed with `Hades` is then the
+//! same tree as a `PoseidonTree`.
+
+use super::{BranchError, TOMBSTONE};
+use crate::permutation::Permutation;
+
+use alloc::vec;
+
+use bls12_381::Scalar as BlsScalar;
+
+/// Opening of a leaf in a tree of `DEPTH` levels whose nodes have `ARITY`
+/// children, hashed with a permutation of width `ARITY + 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArityBranch<const ARITY: usize, const DEPTH: usize> {
+    pub(crate) siblings: [[BlsScalar; ARITY]; DEPTH],
+    pub(crate) offsets: [usize; DEPTH],
+    pub(crate) lens: [usize; DEPTH],
+    pub(crate) root: BlsScalar,
+}
+
+impl<const ARITY: usize, const DEPTH: usize> Default
+    for ArityBranch<ARITY, DEPTH>
+{
+    fn default() -> Self {
+        Self {
+            siblings: [[BlsScalar::zero(); ARITY]; DEPTH],
+            offsets: [0; DEPTH],
+            lens: [0; DEPTH],
+            root: BlsScalar::zero(),
+        }
+    }
+}
+
+impl<const ARITY: usize, const DEPTH: usize> ArityBranch<ARITY, DEPTH> {
+    /// Create a branch opening `leaf` from the nodes of its path, hashing the
+    /// levels with the permutation `perm`.
+    ///
+    /// The nodes of every level are given as for
+    /// [`PoseidonBranch::from_path`](super::PoseidonBranch::from_path): the
+    /// node on the offset of a level is replaced by the node computed from the
+    /// level below, and the nodes past the length of the level are ignored.
+    ///
+    /// Return [`BranchError::InvalidLength`] if a length is zero or larger
+    /// than the arity, and [`BranchError::InvalidOffset`] if an offset is out
+    /// of its level.
+    ///
+    /// # Panics
+    /// If the width of `perm` isn't one more than the arity of the tree.
+    pub fn from_path_with<P>(
+        perm: &P,
+        leaf: BlsScalar,
+        siblings: &[[BlsScalar; ARITY]; DEPTH],
+        offsets: &[usize; DEPTH],
+        lens: &[usize; DEPTH],
+    ) -> Result<Self, BranchError>
+    where
+        P: Permutation<Scalar = BlsScalar>,
+    {
+        assert_arity_width(perm.width(), ARITY);
+
+        let mut branch = Self {
+            offsets: *offsets,
+            lens: *lens,
+            root: leaf,
+            ..Self::default()
+        };
+
+        let levels = siblings.iter().zip(branch.siblings.iter_mut());
+        for (height, (nodes, level)) in levels.enumerate() {
+            let (offset, len) = (offsets[height], lens[height]);
+            if len == 0 || len > ARITY {
+                return Err(BranchError::InvalidLength { level: height, len });
+            }
+            if offset >= len {
+                return Err(BranchError::InvalidOffset {
+                    level: height,
+                    offset,
+                });
+            }
+
+            level[..len].copy_from_slice(&nodes[..len]);
+            level[offset] = branch.root;
+            branch.root = hash_arity_node(perm, &level[..len]);
+        }
+
+        Ok(branch)
+    }
+
+    /// Represents the root for a given path of an opening over a subtree
+    pub fn root(&self) -> &BlsScalar {
+        &self.root
+    }
+
+    /// Return the nodes of every level of the branch, from the level of the
+    /// leaf up to the children of the root.
+    ///
+    /// The nodes past the length of a level are zero.
+    pub fn siblings(&self) -> &[[BlsScalar; ARITY]; DEPTH] {
+        &self.siblings
+    }
+
+    /// Return the offset of the opened node in every level of the branch.
+    pub fn offsets(&self) -> &[usize; DEPTH] {
+        &self.offsets
+    }
+
+    /// Return the number of nodes of every level of the branch.
+    pub fn lens(&self) -> &[usize; DEPTH] {
+        &self.lens
+    }
+
+    /// Check that the branch is a valid opening of `leaf` against `root`, for
+    /// a tree hashed with the permutation `perm`.
+    ///
+    /// The checks are the ones of
+    /// [`PoseidonBranch::verify`](super::PoseidonBranch::verify).
+    ///
+    /// # Panics
+    /// If the width of `perm` isn't one more than the arity of the tree.
+    pub fn verify_with<P>(
+        &self,
+        perm: &P,
+        root: &BlsScalar,
+        leaf: &BlsScalar,
+    ) -> bool
+    where
+        P: Permutation<Scalar = BlsScalar>,
+    {
+        assert_arity_width(perm.width(), ARITY);
+
+        if *leaf == TOMBSTONE {
+            return false;
+        }
+
+        let mut node = *leaf;
+        let levels = self.siblings.iter().zip(self.offsets.iter());
+        for ((level, offset), len) in levels.zip(self.lens.iter()) {
+            if *len == 0 || *len > ARITY || offset >= len {
+                return false;
+            }
+            if level[*offset] != node {
+                return false;
+            }
+
+            node = hash_arity_node(perm, &level[..*len]);
+        }
+
+        node == *root && self.root == *root
+    }
+}
+
+/// Hash the children of a node of a tree of any arity, with a permutation one
+/// element wider than the arity.
+pub(super) fn hash_arity_node<P>(perm: &P, children: &[P::Scalar]) -> P::Scalar
+where
+    P: Permutation,
+{
+    let mut state = vec![P::Scalar::ZERO; perm.width()];
+
+    state[0] = P::Scalar::from((1u64 << children.len()) - 1);
+    state[1..=children.len()].copy_from_slice(children);
+    perm.permute(&mut state);
+
+    state[1]
+}
+
+/// Assert that a permutation of `width` elements hashes the levels of a tree
+/// of the given `arity`.
+pub(super) fn assert_arity_width(width: usize, arity: usize) {
+    assert_eq!(
+        width,
+        arity + 1,
+        "the permutation should hash the levels of a tree"
+    );
+}
@@ -4,18 +4,31 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use super::PoseidonLeaf;
+use super::hex::{decode_hex, write_hex};
+use super::nodes::assert_tree_width;
+use crate::permutation::{Hades, Permutation};
 
-use crate::tree::PoseidonAnnotation;
-
-use core::borrow::Borrow;
-use core::ops::Deref;
+use core::fmt;
+use core::ops::{Deref, Index};
+use core::slice;
+use core::str::FromStr;
 
 use bls12_381::{Scalar as BlsScalar};
 use bytes::{DeserializableSlice, Serializable};
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use super::leaf::leaf_hash;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use super::{Aggregate, PoseidonAnnotation, PoseidonLeaf};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use core::borrow::Borrow;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 use hades::{ScalarStrategy, Strategy};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 use microkelvin::Branch;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 use nstack::annotation::Keyed;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
 use nstack::NStack;
 
 #[cfg(feature = "rkyv-impl")]
@@ -23,6 +36,58 @@ use bytecheck::CheckBytes;
 #[cfg(feature = "rkyv-impl")]
 use rkyv::{Archive, Deserialize, Serialize};
 
+#[cfg(feature = "scale-codec")]
+use parity_scale_codec::{
+    Decode, Encode, EncodeLike, Error as CodecError, Input, MaxEncodedLen,
+    Output,
+};
+
+/// Hash of the tombstone left in the slot of a leaf removed from a tree.
+///
+/// The native verification of an opening always fails for a leaf with this
+/// hash, so the slot of a removed leaf can't be opened, while the positions
+/// of the other leaves are preserved. Circuits opening a leaf that isn't the
+/// hash of a preimage should reject it as well.
+pub const TOMBSTONE: BlsScalar = BlsScalar::from_raw([
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0xffff_ffff_ffff_ffff,
+    0x3fff_ffff_ffff_ffff,
+]);
+
+/// Errors that can occur while building a [`PoseidonBranch`] from raw data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchError {
+    /// The offset of the opened node in a level is out of the level.
+    InvalidOffset {
+        /// Height of the level, `0` being the level of the leaf.
+        level: usize,
+        /// Offset of the opened node.
+        offset: usize,
+    },
+    /// The number of nodes of a level is zero or exceeds the arity of the
+    /// tree.
+    InvalidLength {
+        /// Height of the level, `0` being the level of the leaf.
+        level: usize,
+        /// Number of nodes of the level.
+        len: usize,
+    },
+}
+
+impl fmt::Display for BranchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidOffset { level, offset } => {
+                write!(f, "invalid offset {} on level {}", offset, level)
+            }
+            Self::InvalidLength { level, len } => {
+                write!(f, "invalid number of nodes {} on level {}", len, level)
+            }
+        }
+    }
+}
+
 /// Represents a level of a branch on a given depth
 #[derive(Debug, Default, Clone, Copy)]
 #[cfg_attr(
@@ -32,7 +97,7 @@ use rkyv::{Archive, Deserialize, Serialize};
 )]
 pub struct PoseidonLevel {
     pub(crate) level: [BlsScalar; hades::WIDTH],
-    index: u64,
+    pub(crate) index: u64,
 }
 
 impl PoseidonLevel {
@@ -53,6 +118,37 @@ impl PoseidonLevel {
     pub const fn offset_flag(&self) -> u64 {
         1 << (self.index - 1)
     }
+
+    /// Offset of the opened node in the group of nodes of the level, the
+    /// first node being on offset `0`.
+    pub const fn offset(&self) -> usize {
+        self.index as usize - 1
+    }
+
+    /// Return the group of nodes of the level, the opened node included,
+    /// without the bitflags prepended to them.
+    pub fn nodes(&self) -> &[BlsScalar] {
+        &self.level[1..]
+    }
+
+    /// Whether the index points to one of the nodes of the level, rather than
+    /// to the bitflags or past the end of it.
+    const fn has_valid_index(&self) -> bool {
+        self.index >= 1 && self.index < hades::WIDTH as u64
+    }
+
+    /// Provides an iterator over the siblings of the opened node, from left
+    /// to right.
+    pub fn siblings(&self) -> impl Iterator<Item = &BlsScalar> {
+        let offset = self.index as usize;
+
+        self.level
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter(move |(i, _)| *i != offset)
+            .map(|(_, node)| node)
+    }
 }
 
 impl Serializable<{ BlsScalar::SIZE * hades::WIDTH + u64::SIZE }>
@@ -91,6 +187,78 @@ impl Serializable<{ BlsScalar::SIZE * hades::WIDTH + u64::SIZE }>
     }
 }
 
+/// Formats the level as the hexadecimal representation of its bytes.
+///
+/// The alternate flag (`{:#}`) will pretty-print the index and every scalar
+/// of the level in a separate line instead.
+impl fmt::Display for PoseidonLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "index: {}", self.index)?;
+            for scalar in self.level.iter() {
+                f.write_str("\n  ")?;
+                write_hex(f, &scalar.to_bytes())?;
+            }
+            Ok(())
+        } else {
+            write_hex(f, &self.to_bytes())
+        }
+    }
+}
+
+impl FromStr for PoseidonLevel {
+    type Err = bytes::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut buf = [0u8; Self::SIZE];
+        decode_hex(s, &mut buf)?;
+
+        let level = Self::from_bytes(&buf)?;
+        if !level.has_valid_index() {
+            return Err(bytes::Error::InvalidData);
+        }
+
+        Ok(level)
+    }
+}
+
+#[cfg(feature = "scale-codec")]
+impl Encode for PoseidonLevel {
+    fn size_hint(&self) -> usize {
+        Self::SIZE
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.to_bytes());
+    }
+}
+
+#[cfg(feature = "scale-codec")]
+impl EncodeLike for PoseidonLevel {}
+
+#[cfg(feature = "scale-codec")]
+impl Decode for PoseidonLevel {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let mut buf = [0u8; Self::SIZE];
+        input.read(&mut buf)?;
+
+        let level = Self::from_bytes(&buf)
+            .map_err(|_| CodecError::from("Invalid PoseidonLevel"))?;
+        if !level.has_valid_index() {
+            return Err(CodecError::from("Invalid PoseidonLevel index"));
+        }
+
+        Ok(level)
+    }
+}
+
+#[cfg(feature = "scale-codec")]
+impl MaxEncodedLen for PoseidonLevel {
+    fn max_encoded_len() -> usize {
+        Self::SIZE
+    }
+}
+
 impl Deref for PoseidonLevel {
     type Target = BlsScalar;
 
@@ -106,6 +274,10 @@ impl AsRef<[BlsScalar]> for PoseidonLevel {
 }
 
 /// Represents a full path for a merkle opening
+///
+/// The levels of the branch are laid out for the width of `Hades`, so it opens
+/// trees of arity `4` only. A tree of any other arity is opened with an
+/// [`ArityBranch`](super::ArityBranch).
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(
     feature = "rkyv-impl",
@@ -114,7 +286,7 @@ impl AsRef<[BlsScalar]> for PoseidonLevel {
 )]
 pub struct PoseidonBranch<const DEPTH: usize> {
     pub(crate) path: [PoseidonLevel; DEPTH],
-    root: BlsScalar,
+    pub(crate) root: BlsScalar,
 }
 
 // This macro is necessary due to the fact that `generic_const_exprs`, is still
@@ -156,6 +328,22 @@ macro_rules! serializable_branch {
 
                 buf
             }
+        }
+
+        impl FromStr for PoseidonBranch<$depth> {
+            type Err = bytes::Error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let mut buf = [0u8; Self::SIZE];
+                decode_hex(s, &mut buf)?;
+
+                let branch = Self::from_bytes(&buf)?;
+                if !branch.path.iter().all(PoseidonLevel::has_valid_index) {
+                    return Err(bytes::Error::InvalidData);
+                }
+
+                Ok(branch)
+            }
         })*
     };
 }
@@ -173,6 +361,235 @@ impl<const DEPTH: usize> PoseidonBranch<DEPTH> {
     pub fn root(&self) -> &BlsScalar {
         &self.root
     }
+
+    /// Return the number of levels of the branch, its depth.
+    pub const fn len(&self) -> usize {
+        DEPTH
+    }
+
+    /// Return `true` if the branch has no levels.
+    pub const fn is_empty(&self) -> bool {
+        DEPTH == 0
+    }
+
+    /// Provides an iterator over the levels of the branch, from the level of
+    /// the leaf up to the root.
+    pub fn iter(&self) -> slice::Iter<'_, PoseidonLevel> {
+        self.path.iter()
+    }
+
+    /// Create a branch opening `leaf` from the nodes of its path, as received
+    /// from an external system.
+    ///
+    /// For every level, from the bottom up, `siblings` holds the group of
+    /// nodes hashed into the level above, `offsets` the offset of the opened
+    /// node in the group, and `lens` the number of nodes of the group, which
+    /// is smaller than the arity for the rightmost nodes of a tree. The node on
+    /// the offset is ignored, and replaced by the node computed from the level
+    /// below, so the root of the branch is the root recomputed from `leaf`.
+    /// The nodes past the length of a group are absent, and are ignored as
+    /// well.
+    ///
+    /// Return [`BranchError::InvalidLength`] if a length is zero or larger
+    /// than the arity, and [`BranchError::InvalidOffset`] if an offset is out
+    /// of its group.
+    pub fn from_path(
+        leaf: BlsScalar,
+        siblings: &[[BlsScalar; hades::WIDTH - 1]; DEPTH],
+        offsets: &[usize; DEPTH],
+        lens: &[usize; DEPTH],
+    ) -> Result<Self, BranchError> {
+        Self::from_path_with(&Hades, leaf, siblings, offsets, lens)
+    }
+
+    /// Create a branch opening `leaf` from the nodes of its path, as
+    /// [`PoseidonBranch::from_path`] does, hashing the levels with the
+    /// permutation `perm`.
+    ///
+    /// # Panics
+    /// If the width of `perm` isn't one more than the arity of the tree.
+    pub fn from_path_with<P>(
+        perm: &P,
+        leaf: BlsScalar,
+        siblings: &[[BlsScalar; hades::WIDTH - 1]; DEPTH],
+        offsets: &[usize; DEPTH],
+        lens: &[usize; DEPTH],
+    ) -> Result<Self, BranchError>
+    where
+        P: Permutation<Scalar = BlsScalar>,
+    {
+        assert_tree_width(perm.width());
+
+        let mut state = [BlsScalar::zero(); hades::WIDTH];
+
+        let mut path = [PoseidonLevel::default(); DEPTH];
+        let mut node = leaf;
+
+        let levels = siblings
+            .iter()
+            .zip(offsets.iter().zip(lens.iter()))
+            .zip(path.iter_mut());
+        for (height, ((nodes, (offset, len)), level)) in levels.enumerate() {
+            let (offset, len) = (*offset, *len);
+            if len == 0 || len > nodes.len() {
+                return Err(BranchError::InvalidLength { level: height, len });
+            }
+            if offset >= len {
+                return Err(BranchError::InvalidOffset {
+                    level: height,
+                    offset,
+                });
+            }
+
+            level.level[0] = BlsScalar::from((1u64 << len) - 1);
+            level.level[1..=len].copy_from_slice(&nodes[..len]);
+            level.level[offset + 1] = node;
+            level.index = offset as u64 + 1;
+
+            state.copy_from_slice(&level.level);
+            perm.permute(&mut state);
+            node = state[1];
+        }
+
+        Ok(Self { path, root: node })
+    }
+
+    /// Check that the branch is a valid opening of `leaf` against `root`.
+    ///
+    /// The path is recomputed natively, performing the same checks as
+    /// `merkle_opening` does in a circuit: at every level the hash computed
+    /// from the previous one must be found at the level's offset. The
+    /// branch's own root must match `root` as well, and the [`TOMBSTONE`] of a
+    /// removed leaf never verifies.
+    pub fn verify(&self, root: &BlsScalar, leaf: &BlsScalar) -> bool {
+        self.verify_with(&Hades, root, leaf)
+    }
+
+    /// Check that the branch is a valid opening of `leaf` against `root`, as
+    /// [`PoseidonBranch::verify`] does, for a tree hashed with the
+    /// permutation `perm`.
+    ///
+    /// # Panics
+    /// If the width of `perm` isn't one more than the arity of the tree.
+    pub fn verify_with<P>(
+        &self,
+        perm: &P,
+        root: &BlsScalar,
+        leaf: &BlsScalar,
+    ) -> bool
+    where
+        P: Permutation<Scalar = BlsScalar>,
+    {
+        path_root_with(perm, &self.path, leaf) == Some(*root)
+            && self.root == *root
+    }
+}
+
+/// Recompute the root of `path` from `leaf`, checking that the hash computed
+/// at every level is found at the level's offset.
+///
+/// Return `None` for the [`TOMBSTONE`] of a removed leaf.
+pub(crate) fn path_root(
+    path: &[PoseidonLevel],
+    leaf: &BlsScalar,
+) -> Option<BlsScalar> {
+    path_root_with(&Hades, path, leaf)
+}
+
+/// Recompute the root of `path` from `leaf` as [`path_root`] does, with the
+/// permutation `perm`.
+pub(crate) fn path_root_with<P>(
+    perm: &P,
+    path: &[PoseidonLevel],
+    leaf: &BlsScalar,
+) -> Option<BlsScalar>
+where
+    P: Permutation<Scalar = BlsScalar>,
+{
+    assert_tree_width(perm.width());
+
+    if *leaf == TOMBSTONE {
+        return None;
+    }
+
+    let mut state = [BlsScalar::zero(); hades::WIDTH];
+
+    path.iter().try_fold(*leaf, |needle, level| {
+        let index = level.index as usize;
+        if index == 0 || index >= hades::WIDTH || level.level[index] != needle {
+            return None;
+        }
+
+        state.copy_from_slice(&level.level);
+        perm.permute(&mut state);
+
+        Some(state[1])
+    })
+}
+
+/// Formats the branch as the hexadecimal representation of its bytes.
+///
+/// The alternate flag (`{:#}`) will pretty-print the root followed by every
+/// level of the path instead, which is convenient to debug mismatching
+/// openings.
+impl<const DEPTH: usize> fmt::Display for PoseidonBranch<DEPTH> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("root: ")?;
+            write_hex(f, &self.root.to_bytes())?;
+            for (depth, level) in self.path.iter().enumerate() {
+                write!(f, "\nlevel {}, {:#}", depth, level)?;
+            }
+            Ok(())
+        } else {
+            self.path
+                .iter()
+                .try_for_each(|level| write_hex(f, &level.to_bytes()))?;
+            write_hex(f, &self.root.to_bytes())
+        }
+    }
+}
+
+// The SCALE encoding of a branch is identical to its `Serializable`
+// representation, but it is available for any depth.
+#[cfg(feature = "scale-codec")]
+impl<const DEPTH: usize> Encode for PoseidonBranch<DEPTH> {
+    fn size_hint(&self) -> usize {
+        Self::max_encoded_len()
+    }
+
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        self.path.iter().for_each(|level| level.encode_to(dest));
+        dest.write(&self.root.to_bytes());
+    }
+}
+
+#[cfg(feature = "scale-codec")]
+impl<const DEPTH: usize> EncodeLike for PoseidonBranch<DEPTH> {}
+
+#[cfg(feature = "scale-codec")]
+impl<const DEPTH: usize> Decode for PoseidonBranch<DEPTH> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let mut path = [PoseidonLevel::default(); DEPTH];
+        for level in path.iter_mut() {
+            *level = PoseidonLevel::decode(input)?;
+        }
+
+        let mut buf = [0u8; BlsScalar::SIZE];
+        input.read(&mut buf)?;
+
+        let root = BlsScalar::from_bytes(&buf)
+            .map_err(|_| CodecError::from("Invalid branch root"))?;
+
+        Ok(Self { path, root })
+    }
+}
+
+#[cfg(feature = "scale-codec")]
+impl<const DEPTH: usize> MaxEncodedLen for PoseidonBranch<DEPTH> {
+    fn max_encoded_len() -> usize {
+        PoseidonLevel::SIZE * DEPTH + BlsScalar::SIZE
+    }
 }
 
 impl<const DEPTH: usize> Default for PoseidonBranch<DEPTH> {
@@ -198,103 +615,161 @@ impl<const DEPTH: usize> AsRef<[PoseidonLevel]> for PoseidonBranch<DEPTH> {
     }
 }
 
-impl<L, K, const DEPTH: usize>
-    From<&Branch<'_, NStack<L, PoseidonAnnotation<K>>, PoseidonAnnotation<K>>>
-    for PoseidonBranch<DEPTH>
+impl<const DEPTH: usize> Index<usize> for PoseidonBranch<DEPTH> {
+    type Output = PoseidonLevel;
+
+    fn index(&self, level: usize) -> &Self::Output {
+        &self.path[level]
+    }
+}
+
+impl<'a, const DEPTH: usize> IntoIterator for &'a PoseidonBranch<DEPTH> {
+    type Item = &'a PoseidonLevel;
+    type IntoIter = slice::Iter<'a, PoseidonLevel>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.path.iter()
+    }
+}
+
+impl<const DEPTH: usize> IntoIterator for PoseidonBranch<DEPTH> {
+    type Item = PoseidonLevel;
+    type IntoIter = core::array::IntoIter<PoseidonLevel, DEPTH>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.path.into_iter()
+    }
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, A, const DEPTH: usize>
+    From<
+        &Branch<
+            '_,
+            NStack<L, PoseidonAnnotation<K, A>>,
+            PoseidonAnnotation<K, A>,
+        >,
+    > for PoseidonBranch<DEPTH>
 where
     L: PoseidonLeaf + Keyed<K>,
     K: Clone + PartialOrd,
+    A: Aggregate<L>,
 {
     fn from(
-        b: &Branch<'_, NStack<L, PoseidonAnnotation<K>>, PoseidonAnnotation<K>>,
+        b: &Branch<
+            '_,
+            NStack<L, PoseidonAnnotation<K, A>>,
+            PoseidonAnnotation<K, A>,
+        >,
     ) -> Self {
         let mut path = [PoseidonLevel::default(); DEPTH];
+        let root = fill_path(b, &mut path);
 
-        b.levels().iter().rev().zip(path.iter_mut()).for_each(
-            |(nstack_level, poseidon_level)| {
-                poseidon_level.index = nstack_level.index() as u64 + 1;
-
-                let mut flag = 1;
-                let mut mask = 0;
-
-                match &**nstack_level {
-                    NStack::Leaf(l) => l
-                        .iter()
-                        .zip(poseidon_level.level.iter_mut().skip(1))
-                        .for_each(|(leaf, l)| {
-                            if let Some(leaf) = leaf {
-                                mask |= flag;
-                                *l = leaf.poseidon_hash();
-                            }
-
-                            flag <<= 1;
-                        }),
-                    NStack::Node(n) => n
-                        .iter()
-                        .zip(poseidon_level.level.iter_mut().skip(1))
-                        .for_each(|(node, l)| {
-                            if let Some(annotated) = node {
-                                let anno = annotated.anno();
-                                let anno = &*anno;
-
-                                mask |= flag;
-
-                                *l = *anno.borrow();
-                            }
-
-                            flag <<= 1;
-                        }),
-                }
+        PoseidonBranch { path, root }
+    }
+}
 
-                poseidon_level.level[0] = BlsScalar::from(mask);
-            },
-        );
+/// Fill `path` with the levels of the nstack branch `b`, from the bottom up,
+/// and return the root of the opening.
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub(crate) fn fill_path<L, K, A>(
+    b: &Branch<
+        '_,
+        NStack<L, PoseidonAnnotation<K, A>>,
+        PoseidonAnnotation<K, A>,
+    >,
+    path: &mut [PoseidonLevel],
+) -> BlsScalar
+where
+    L: PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    let depth = path.len();
+
+    b.levels().iter().rev().zip(path.iter_mut()).for_each(
+        |(nstack_level, poseidon_level)| {
+            poseidon_level.index = nstack_level.index() as u64 + 1;
+
+            let mut flag = 1;
+            let mut mask = 0;
+
+            match &**nstack_level {
+                NStack::Leaf(l) => l
+                    .iter()
+                    .zip(poseidon_level.level.iter_mut().skip(1))
+                    .for_each(|(leaf, l)| {
+                        if let Some(leaf) = leaf {
+                            mask |= flag;
+                            *l = leaf_hash(leaf);
+                        }
+
+                        flag <<= 1;
+                    }),
+                NStack::Node(n) => n
+                    .iter()
+                    .zip(poseidon_level.level.iter_mut().skip(1))
+                    .for_each(|(node, l)| {
+                        if let Some(annotated) = node {
+                            let anno = annotated.anno();
+                            let anno = &*anno;
+
+                            mask |= flag;
+
+                            *l = *anno.borrow();
+                        }
+
+                        flag <<= 1;
+                    }),
+            }
 
-        // If the nstack is smaller than the poseidon tree then the we need to
-        // populate the remaining levels of the tree.
-        let nstack_depth = b.levels().len();
-        let flag = BlsScalar::one();
+            poseidon_level.level[0] = BlsScalar::from(mask);
+        },
+    );
 
-        if nstack_depth < DEPTH {
-            let level = path[nstack_depth - 1].level;
-            let mut perm = [BlsScalar::zero(); hades::WIDTH];
+    // If the nstack is smaller than the poseidon tree then the we need to
+    // populate the remaining levels of the tree.
+    let nstack_depth = b.levels().len();
+    let flag = BlsScalar::one();
 
-            let mut h = ScalarStrategy::new();
+    if nstack_depth < depth {
+        let level = path[nstack_depth - 1].level;
+        let mut perm = [BlsScalar::zero(); hades::WIDTH];
 
-            path.iter_mut().skip(nstack_depth).fold(level, |l, b| {
-                perm.copy_from_slice(&l);
-                h.perm(&mut perm);
+        let mut h = ScalarStrategy::new();
 
-                b.index = 1;
-                b.level[0] = flag;
-                b.level[1] = perm[1];
+        path.iter_mut().skip(nstack_depth).fold(level, |l, b| {
+            perm.copy_from_slice(&l);
+            h.perm(&mut perm);
 
-                b.level
-            });
-        }
+            b.index = 1;
+            b.level[0] = flag;
+            b.level[1] = perm[1];
 
-        // TODO: The amount of repetition here hints at the fact that hashing
-        //  should be more ergonomic.
+            b.level
+        });
+    }
 
-        // Calculate the root
-        let mut perm = [BlsScalar::zero(); hades::WIDTH];
-        let mut h = ScalarStrategy::new();
+    // TODO: The amount of repetition here hints at the fact that hashing
+    //  should be more ergonomic.
 
-        perm.copy_from_slice(&path[DEPTH - 1].level);
-        perm[0] = flag;
-        h.perm(&mut perm);
+    // Calculate the root
+    let mut perm = [BlsScalar::zero(); hades::WIDTH];
+    let mut h = ScalarStrategy::new();
 
-        PoseidonBranch {
-            path,
-            root: perm[1],
-        }
-    }
+    perm.copy_from_slice(&path[depth - 1].level);
+    perm[0] = flag;
+    h.perm(&mut perm);
+
+    perm[1]
 }
 
 #[cfg(test)]
 mod tests {
     use crate::tree::{PoseidonBranch, PoseidonLevel};
 
+    use alloc::format;
+
     use bls12_381::BlsScalar;
     use bytes::Serializable;
 
@@ -330,4 +805,93 @@ mod tests {
             }
         }
     }
+
+    #[cfg(feature = "scale-codec")]
+    #[test]
+    fn branch_scale_codec() {
+        use parity_scale_codec::{Decode, Encode};
+
+        type Branch = PoseidonBranch<17>;
+
+        let mut poseidon_level = PoseidonLevel {
+            index: 2,
+            ..Default::default()
+        };
+        poseidon_level.level[2] = BlsScalar::from(42);
+
+        let mut branch = Branch {
+            root: BlsScalar::from(4),
+            ..Default::default()
+        };
+        branch.path.iter_mut().for_each(|level| level.index = 1);
+        branch.path[3] = poseidon_level;
+
+        let encoded = branch.encode();
+        assert_eq!(encoded.len(), Branch::SIZE);
+        assert_eq!(encoded, branch.to_bytes().to_vec());
+
+        let decoded =
+            Branch::decode(&mut &encoded[..]).expect("Decoding should succeed");
+
+        assert_eq!(branch.root, decoded.root);
+        assert_eq!(branch.path[3].index, decoded.path[3].index);
+        assert_eq!(branch.path[3].level, decoded.path[3].level);
+
+        assert!(Branch::decode(&mut &encoded[1..]).is_err());
+
+        // Indices pointing to the bitflags or past the level are rejected
+        for index in [0, hades::WIDTH as u64] {
+            let mut invalid = branch;
+            invalid.path[3].index = index;
+
+            let encoded = invalid.encode();
+            assert!(Branch::decode(&mut &encoded[..]).is_err());
+
+            let encoded = invalid.path[3].encode();
+            assert!(PoseidonLevel::decode(&mut &encoded[..]).is_err());
+        }
+    }
+
+    #[test]
+    fn branch_hex() {
+        type Branch = PoseidonBranch<4>;
+
+        let mut branch = Branch {
+            root: BlsScalar::from(4),
+            ..Default::default()
+        };
+        branch.path.iter_mut().for_each(|level| level.index = 1);
+        branch.path[1].index = 3;
+        branch.path[1].level[3] = BlsScalar::from(42);
+
+        let hex = format!("{}", branch);
+        assert_eq!(hex.len(), Branch::SIZE * 2);
+
+        let parsed: Branch = hex.parse().expect("Parsing should succeed");
+        assert_eq!(branch.to_bytes(), parsed.to_bytes());
+
+        let prefixed: Branch = format!("0x{}", hex)
+            .parse()
+            .expect("Parsing with prefix should succeed");
+        assert_eq!(branch.to_bytes(), prefixed.to_bytes());
+
+        let level: PoseidonLevel = format!("{}", branch.path[1])
+            .parse()
+            .expect("Parsing a level should succeed");
+        assert_eq!(branch.path[1].to_bytes(), level.to_bytes());
+
+        assert!(hex[1..].parse::<Branch>().is_err());
+        assert!(hex.replacen('0', "z", 1).parse::<Branch>().is_err());
+
+        // Indices pointing to the bitflags or past the level are rejected
+        for index in [0, hades::WIDTH as u64] {
+            let mut invalid = branch;
+            invalid.path[1].index = index;
+
+            assert!(format!("{}", invalid).parse::<Branch>().is_err());
+            assert!(format!("{}", invalid.path[1])
+                .parse::<PoseidonLevel>()
+                .is_err());
+        }
+    }
 }
@@ -0,0 +1,101 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::{merkle_opening_public, PoseidonBranch};
+
+use plonk::error::Error as PlonkError;
+use plonk::prelude::*;
+use rand_core::{CryptoRng, RngCore};
+
+/// Label of the transcripts of the proofs of [`OpeningCircuit`].
+const LABEL: &[u8] = b"poseidon-merkle-opening";
+
+/// Circuit proving the membership of a leaf in a tree of the given `DEPTH`.
+///
+/// The leaf is kept private, and the root of the tree is the only public
+/// input of the circuit. The circuit is compiled with
+/// [`OpeningCircuit::compile`], returning the [`OpeningProver`] and
+/// [`OpeningVerifier`] of the proofs.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OpeningCircuit<const DEPTH: usize> {
+    branch: PoseidonBranch<DEPTH>,
+}
+
+impl<const DEPTH: usize> OpeningCircuit<DEPTH> {
+    /// Create a new circuit opening the leaf of `branch`.
+    pub const fn new(branch: PoseidonBranch<DEPTH>) -> Self {
+        Self { branch }
+    }
+
+    /// Compile the circuit, returning its prover and verifier.
+    ///
+    /// The public parameters must have a capacity of at least
+    /// [`constraint_count::merkle_opening_public`] constraints.
+    ///
+    /// [`constraint_count::merkle_opening_public`]:
+    /// crate::constraint_count::merkle_opening_public
+    pub fn compile(
+        pp: &PublicParameters,
+    ) -> Result<(OpeningProver<DEPTH>, OpeningVerifier<DEPTH>), PlonkError>
+    {
+        let (prover, verifier) = Compiler::compile::<Self>(pp, LABEL)?;
+
+        Ok((OpeningProver { prover }, OpeningVerifier { verifier }))
+    }
+}
+
+impl<const DEPTH: usize> Circuit for OpeningCircuit<DEPTH> {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaf = composer.append_witness(*self.branch);
+        merkle_opening_public(composer, &self.branch, leaf);
+
+        Ok(())
+    }
+}
+
+/// Prover of the membership proofs of an [`OpeningCircuit`].
+pub struct OpeningProver<const DEPTH: usize> {
+    prover: Prover<OpeningCircuit<DEPTH>>,
+}
+
+impl<const DEPTH: usize> OpeningProver<DEPTH> {
+    /// Prove the membership of the leaf of `branch` in the tree of its root.
+    ///
+    /// Return the proof along with the root it's verified against.
+    pub fn prove<R>(
+        &self,
+        rng: &mut R,
+        branch: &PoseidonBranch<DEPTH>,
+    ) -> Result<(Proof, BlsScalar), PlonkError>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let circuit = OpeningCircuit::new(*branch);
+        let (proof, _) = self.prover.prove(rng, &circuit)?;
+
+        Ok((proof, *branch.root()))
+    }
+}
+
+/// Verifier of the membership proofs of an [`OpeningCircuit`].
+pub struct OpeningVerifier<const DEPTH: usize> {
+    verifier: Verifier<OpeningCircuit<DEPTH>>,
+}
+
+impl<const DEPTH: usize> OpeningVerifier<DEPTH> {
+    /// Verify that `proof` proves the membership of a leaf in the tree of the
+    /// given `root`.
+    pub fn verify(
+        &self,
+        proof: &Proof,
+        root: &BlsScalar,
+    ) -> Result<(), PlonkError> {
+        self.verifier.verify(proof, &[*root])
+    }
+}
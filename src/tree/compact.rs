@@ -0,0 +1,235 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::nodes::ARITY;
+use super::{PoseidonBranch, PoseidonLevel, TOMBSTONE};
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::{DeserializableSlice, Serializable};
+use hades::{ScalarStrategy, Strategy};
+
+/// Level of a [`CompactBranch`], holding the siblings of the opened node
+/// without the node itself.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompactLevel {
+    pub(crate) siblings: [BlsScalar; ARITY - 1],
+    pub(crate) offset: u8,
+    pub(crate) flags: u8,
+}
+
+impl CompactLevel {
+    /// Offset of the opened node in the group of nodes of the level, the
+    /// first node being on offset `0`.
+    pub const fn offset(&self) -> usize {
+        self.offset as usize
+    }
+
+    /// Bitflags of the nodes present in the group of nodes of the level.
+    pub const fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Return the siblings of the opened node, from left to right.
+    pub const fn siblings(&self) -> &[BlsScalar; ARITY - 1] {
+        &self.siblings
+    }
+
+    /// Return the level of a full branch, with `node` on the offset.
+    fn expand(&self, node: BlsScalar) -> PoseidonLevel {
+        let offset = self.offset();
+        let mut level = [BlsScalar::zero(); hades::WIDTH];
+
+        level[0] = BlsScalar::from(self.flags as u64);
+        level[1..=offset].copy_from_slice(&self.siblings[..offset]);
+        level[offset + 1] = node;
+        level[offset + 2..].copy_from_slice(&self.siblings[offset..]);
+
+        PoseidonLevel {
+            level,
+            index: offset as u64 + 1,
+        }
+    }
+}
+
+impl From<&PoseidonLevel> for CompactLevel {
+    fn from(level: &PoseidonLevel) -> Self {
+        // The index of a hand-built level may be out of its nodes
+        let offset = (level.index as usize).clamp(1, ARITY) - 1;
+
+        let mut siblings = [BlsScalar::zero(); ARITY - 1];
+        siblings[..offset].copy_from_slice(&level.level[1..=offset]);
+        siblings[offset..].copy_from_slice(&level.level[offset + 2..]);
+
+        // The flags of a level never exceed the arity of the tree, so they
+        // fit in the least significant byte
+        let flags = level.level[0].to_bytes()[0];
+
+        Self {
+            siblings,
+            offset: offset as u8,
+            flags,
+        }
+    }
+}
+
+impl Serializable<{ BlsScalar::SIZE * (ARITY - 1) + 2 }> for CompactLevel {
+    type Error = bytes::Error;
+
+    fn from_bytes(buf: &[u8; Self::SIZE]) -> Result<Self, Self::Error>
+    where
+        Self: Sized,
+    {
+        let mut bytes = &buf[..];
+
+        let mut siblings = [BlsScalar::zero(); ARITY - 1];
+        for sibling in siblings.iter_mut() {
+            *sibling = BlsScalar::from_reader(&mut bytes)?;
+        }
+
+        let offset = buf[Self::SIZE - 2];
+        let flags = buf[Self::SIZE - 1];
+
+        // The opened node is on the offset, so it must be flagged
+        if offset as usize >= ARITY
+            || flags >> ARITY != 0
+            || flags & (1 << offset) == 0
+        {
+            return Err(bytes::Error::InvalidData);
+        }
+
+        Ok(Self {
+            siblings,
+            offset,
+            flags,
+        })
+    }
+
+    fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut buf = [0u8; Self::SIZE];
+
+        for (i, sibling) in self.siblings.iter().enumerate() {
+            buf[i * BlsScalar::SIZE..(i + 1) * BlsScalar::SIZE]
+                .copy_from_slice(&sibling.to_bytes());
+        }
+
+        buf[Self::SIZE - 2] = self.offset;
+        buf[Self::SIZE - 1] = self.flags;
+
+        buf
+    }
+}
+
+/// Merkle opening holding only the siblings of the nodes on its path.
+///
+/// Every node on the path is recomputed from the level below it, starting
+/// from the opened leaf, so a compact branch can't hold a node that doesn't
+/// match its child, unlike a hand-built [`PoseidonBranch`]. The root is
+/// recomputed as well, and isn't held by the branch. Its serialized form is
+/// less than two thirds of the size of the one of a [`PoseidonBranch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompactBranch<const DEPTH: usize> {
+    pub(crate) path: [CompactLevel; DEPTH],
+}
+
+impl<const DEPTH: usize> Default for CompactBranch<DEPTH> {
+    fn default() -> Self {
+        Self {
+            path: [CompactLevel::default(); DEPTH],
+        }
+    }
+}
+
+impl<const DEPTH: usize> CompactBranch<DEPTH> {
+    /// Return the levels of the branch, from the level of the leaf up to the
+    /// root.
+    pub const fn levels(&self) -> &[CompactLevel; DEPTH] {
+        &self.path
+    }
+
+    /// Rebuild the full branch opening `leaf`.
+    pub fn expand(&self, leaf: &BlsScalar) -> PoseidonBranch<DEPTH> {
+        let mut h = ScalarStrategy::new();
+        let mut perm = [BlsScalar::zero(); hades::WIDTH];
+
+        let mut path = [PoseidonLevel::default(); DEPTH];
+        let mut node = *leaf;
+
+        for (level, compact) in path.iter_mut().zip(self.path.iter()) {
+            *level = compact.expand(node);
+
+            perm.copy_from_slice(&level.level);
+            h.perm(&mut perm);
+            node = perm[1];
+        }
+
+        PoseidonBranch { path, root: node }
+    }
+
+    /// Return the root of the tree of the opening of `leaf`.
+    pub fn root(&self, leaf: &BlsScalar) -> BlsScalar {
+        self.expand(leaf).root
+    }
+
+    /// Check that the branch is a valid opening of `leaf` against `root`.
+    ///
+    /// The [`TOMBSTONE`] of a removed leaf never verifies.
+    pub fn verify(&self, root: &BlsScalar, leaf: &BlsScalar) -> bool {
+        *leaf != TOMBSTONE && self.root(leaf) == *root
+    }
+}
+
+impl<const DEPTH: usize> From<&PoseidonBranch<DEPTH>> for CompactBranch<DEPTH> {
+    fn from(branch: &PoseidonBranch<DEPTH>) -> Self {
+        let mut path = [CompactLevel::default(); DEPTH];
+        path.iter_mut()
+            .zip(branch.path.iter())
+            .for_each(|(compact, level)| *compact = level.into());
+
+        Self { path }
+    }
+}
+
+// This macro is necessary for the same reason as the one serializing a
+// `PoseidonBranch`, until `generic_const_exprs` is stable.
+macro_rules! serializable_compact_branch {
+    ($($depth:literal),+) => {
+        $(impl Serializable<{ CompactLevel::SIZE * $depth }>
+            for CompactBranch<$depth>
+        {
+            type Error = bytes::Error;
+
+            fn from_bytes(buf: &[u8; Self::SIZE]) -> Result<Self, Self::Error>
+            where
+                Self: Sized,
+            {
+                let mut bytes = &buf[..];
+
+                let mut path = [CompactLevel::default(); $depth];
+                for level in path.iter_mut() {
+                    *level = CompactLevel::from_reader(&mut bytes)?;
+                }
+
+                Ok(Self { path })
+            }
+
+            fn to_bytes(&self) -> [u8; Self::SIZE] {
+                let mut buf = [0u8; Self::SIZE];
+
+                for (i, level) in self.path.iter().enumerate() {
+                    buf[i * CompactLevel::SIZE..(i + 1) * CompactLevel::SIZE]
+                        .copy_from_slice(&level.to_bytes());
+                }
+
+                buf
+            }
+        })*
+    };
+}
+
+serializable_compact_branch!(
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21,
+    22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 64, 128, 256, 512, 1024, 2048
+);
@@ -0,0 +1,167 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::nodes::{hash_node, ARITY};
+use super::PoseidonBranch;
+
+use alloc::vec::Vec;
+
+use bls12_381::Scalar as BlsScalar;
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use super::leaf::leaf_hash;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use super::{nodes, Aggregate, PoseidonLeaf, PoseidonTree};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use nstack::annotation::Keyed;
+
+/// Proof that a tree is an append-only extension of an older version of
+/// itself.
+///
+/// Since leaves are only ever appended to the right of a tree, the nodes to
+/// the left of the path of the last leaf of the old tree are shared by both
+/// versions. The proof holds the hash of that leaf and, at every level of its
+/// path, its left siblings, shared by both trees, followed by its right
+/// siblings in the new tree. Both roots are then recomputed from the same
+/// nodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyProof<const DEPTH: usize> {
+    old_size: u64,
+    new_size: u64,
+    nodes: Vec<BlsScalar>,
+}
+
+impl<const DEPTH: usize> ConsistencyProof<DEPTH> {
+    /// Number of leaves of the old tree.
+    pub const fn old_size(&self) -> u64 {
+        self.old_size
+    }
+
+    /// Number of leaves of the new tree.
+    pub const fn new_size(&self) -> u64 {
+        self.new_size
+    }
+
+    /// Check that the tree with `new_root` is an append-only extension of the
+    /// tree with `old_root`.
+    pub fn verify(&self, old_root: &BlsScalar, new_root: &BlsScalar) -> bool {
+        if self.old_size > self.new_size {
+            return false;
+        }
+
+        // Any tree is an extension of the empty tree
+        if self.old_size == 0 {
+            return self.nodes.is_empty()
+                && *old_root == PoseidonBranch::<DEPTH>::NULL_ROOT;
+        }
+
+        let mut nodes = self.nodes.iter().copied();
+        let leaf = match nodes.next() {
+            Some(leaf) => leaf,
+            None => return false,
+        };
+
+        let mut old_node = leaf;
+        let mut new_node = leaf;
+        let mut children = [BlsScalar::zero(); ARITY];
+
+        let mut child = self.old_size - 1;
+        let mut new_len = self.new_size;
+
+        for _ in 0..DEPTH {
+            let offset = (child % ARITY as u64) as usize;
+            let group = child - offset as u64;
+            let count = (new_len - group).min(ARITY as u64) as usize;
+
+            for left in children[..offset].iter_mut() {
+                match nodes.next() {
+                    Some(node) => *left = node,
+                    None => return false,
+                }
+            }
+            for right in children[offset + 1..count].iter_mut() {
+                match nodes.next() {
+                    Some(node) => *right = node,
+                    None => return false,
+                }
+            }
+
+            children[offset] = old_node;
+            old_node = hash_node(&children[..=offset]);
+
+            children[offset] = new_node;
+            new_node = hash_node(&children[..count]);
+
+            child /= ARITY as u64;
+            new_len = (new_len + ARITY as u64 - 1) / ARITY as u64;
+        }
+
+        nodes.next().is_none() && old_node == *old_root && new_node == *new_root
+    }
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, A> PoseidonTree<L, K, DEPTH, A>
+where
+    L: PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    /// Return a proof that the tree holding the first `new_size` leaves of
+    /// this tree is an append-only extension of the tree holding its first
+    /// `old_size` leaves.
+    ///
+    /// Since both versions of the tree may be older than the current one, the
+    /// proof is computed by hashing the first `new_size` leaves again.
+    ///
+    /// Return `None` if `old_size` is greater than `new_size`, or if
+    /// `new_size` is greater than the number of leaves of the tree.
+    pub fn consistency_proof(
+        &self,
+        old_size: u64,
+        new_size: u64,
+    ) -> Option<ConsistencyProof<DEPTH>> {
+        let hashes: Vec<BlsScalar> = self
+            .leaves()
+            .take(new_size as usize)
+            .map(leaf_hash)
+            .collect();
+
+        if old_size > new_size || new_size > hashes.len() as u64 {
+            return None;
+        }
+
+        let mut proof = ConsistencyProof {
+            old_size,
+            new_size,
+            nodes: Vec::new(),
+        };
+        if old_size == 0 {
+            return Some(proof);
+        }
+
+        let levels = nodes::levels(&hashes, DEPTH);
+        let mut child = old_size as usize - 1;
+
+        proof.nodes.push(hashes[child]);
+        for depth in 0..DEPTH {
+            let children = match depth {
+                0 => &hashes,
+                _ => &levels[depth - 1],
+            };
+
+            let group = child - child % ARITY;
+            let end = children.len().min(group + ARITY);
+
+            proof.nodes.extend_from_slice(&children[group..child]);
+            proof.nodes.extend_from_slice(&children[child + 1..end]);
+
+            child /= ARITY;
+        }
+
+        Some(proof)
+    }
+}
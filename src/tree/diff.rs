@@ -0,0 +1,103 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::{Aggregate, PoseidonLeaf, PoseidonTree};
+
+use alloc::vec::Vec;
+
+use bls12_381::Scalar as BlsScalar;
+use nstack::annotation::Keyed;
+
+/// Differences between two snapshots of a tree, as returned by [`diff`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TreeDiff {
+    inserted: Vec<u64>,
+    updated: Vec<u64>,
+    removed: Vec<u64>,
+    nodes: Vec<(usize, u64)>,
+}
+
+impl TreeDiff {
+    /// Positions of the leaves only in the new snapshot, in ascending order.
+    pub fn inserted(&self) -> &[u64] {
+        &self.inserted
+    }
+
+    /// Positions of the leaves in both snapshots, with different hashes, in
+    /// ascending order.
+    pub fn updated(&self) -> &[u64] {
+        &self.updated
+    }
+
+    /// Positions of the leaves only in the old snapshot, in ascending order.
+    pub fn removed(&self) -> &[u64] {
+        &self.removed
+    }
+
+    /// Internal nodes whose hash changed between the snapshots, or that are
+    /// only in one of them, as pairs of their level and their index in the
+    /// level.
+    ///
+    /// The levels are numbered as in
+    /// [`PoseidonTree::export_nodes`], from `0` for the parents of the leaves
+    /// up to the root, and the nodes are ordered by level, then by index.
+    pub fn nodes(&self) -> &[(usize, u64)] {
+        &self.nodes
+    }
+
+    /// Return `true` if the snapshots are the same.
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty()
+            && self.updated.is_empty()
+            && self.removed.is_empty()
+            && self.nodes.is_empty()
+    }
+}
+
+/// Compute the differences between two snapshots of a tree, from `a` to `b`.
+///
+/// The leaves are compared by their hashes, and the internal nodes by the
+/// hashes cached in both trees, so the only nodes hashed are the ones above
+/// the top of the underlying stacks.
+pub fn diff<L, K, const DEPTH: usize, A>(
+    a: &PoseidonTree<L, K, DEPTH, A>,
+    b: &PoseidonTree<L, K, DEPTH, A>,
+) -> TreeDiff
+where
+    L: PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    let a_leaves: Vec<BlsScalar> = a.leaves().map(L::poseidon_hash).collect();
+    let b_leaves: Vec<BlsScalar> = b.leaves().map(L::poseidon_hash).collect();
+
+    let mut diff = TreeDiff::default();
+
+    for pos in 0..a_leaves.len().max(b_leaves.len()) {
+        match (a_leaves.get(pos), b_leaves.get(pos)) {
+            (Some(a), Some(b)) if a != b => diff.updated.push(pos as u64),
+            (None, Some(_)) => diff.inserted.push(pos as u64),
+            (Some(_), None) => diff.removed.push(pos as u64),
+            _ => {}
+        }
+    }
+
+    let a_levels = a.node_levels();
+    let b_levels = b.node_levels();
+
+    for level in 0..a_levels.len().max(b_levels.len()) {
+        let a_nodes = a_levels.get(level).map(Vec::as_slice).unwrap_or(&[]);
+        let b_nodes = b_levels.get(level).map(Vec::as_slice).unwrap_or(&[]);
+
+        for index in 0..a_nodes.len().max(b_nodes.len()) {
+            if a_nodes.get(index) != b_nodes.get(index) {
+                diff.nodes.push((level, index as u64));
+            }
+        }
+    }
+
+    diff
+}
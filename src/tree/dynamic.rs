@@ -0,0 +1,251 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::branch::path_root;
+use super::{PoseidonBranch, PoseidonLevel};
+
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+use bls12_381::Scalar as BlsScalar;
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use super::branch::fill_path;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use super::nodes::ARITY;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use super::{PoseidonAnnotation, PoseidonLeaf};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use alloc::vec;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use core::borrow::Borrow;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use core::mem;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use nstack::annotation::{Cardinality, Keyed};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use nstack::NStack;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use ranno::Annotation;
+
+/// Represents a full path for a merkle opening of a tree whose depth is only
+/// known at runtime
+#[derive(Debug, Default, Clone)]
+pub struct DynPoseidonBranch {
+    pub(crate) path: Vec<PoseidonLevel>,
+    pub(crate) root: BlsScalar,
+}
+
+impl DynPoseidonBranch {
+    /// Return the depth of the tree of the opening.
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    /// Represents the root for a given path of an opening over a subtree
+    pub fn root(&self) -> &BlsScalar {
+        &self.root
+    }
+
+    /// Check that the branch is a valid opening of `leaf` against `root`.
+    ///
+    /// See [`PoseidonBranch::verify`].
+    pub fn verify(&self, root: &BlsScalar, leaf: &BlsScalar) -> bool {
+        path_root(&self.path, leaf) == Some(*root) && self.root == *root
+    }
+
+    /// Extend the opening with the `levels` above its depth, bringing it to
+    /// a tree deepened from the one of the opening.
+    ///
+    /// The tree of the opening is the first subtree of the deepened tree, so
+    /// the levels are the ones above the previous depth of any opening of the
+    /// deepened tree within that subtree, such as the opening of its first
+    /// leaf. Since the siblings in the levels are the current ones, the
+    /// opening must have been taken when its tree was already full.
+    ///
+    /// Return `false`, leaving the opening unchanged, if the root of the
+    /// opening isn't found at the offset of the first of the `levels`, or if
+    /// the levels don't chain up to a root.
+    pub fn upgrade(&mut self, levels: &[PoseidonLevel]) -> bool {
+        let leaf = match self.path.first() {
+            Some(level) => **level,
+            None => return false,
+        };
+
+        let len = self.path.len();
+        self.path.extend_from_slice(levels);
+
+        match path_root(&self.path, &leaf) {
+            Some(root) => {
+                self.root = root;
+                true
+            }
+            None => {
+                self.path.truncate(len);
+                false
+            }
+        }
+    }
+}
+
+impl<const DEPTH: usize> From<PoseidonBranch<DEPTH>> for DynPoseidonBranch {
+    fn from(branch: PoseidonBranch<DEPTH>) -> Self {
+        Self {
+            path: branch.path.to_vec(),
+            root: branch.root,
+        }
+    }
+}
+
+impl Deref for DynPoseidonBranch {
+    type Target = BlsScalar;
+
+    fn deref(&self) -> &Self::Target {
+        self.path[0].deref()
+    }
+}
+
+impl AsRef<[PoseidonLevel]> for DynPoseidonBranch {
+    fn as_ref(&self) -> &[PoseidonLevel] {
+        &self.path
+    }
+}
+
+/// Represents a Merkle Tree whose depth is chosen at construction time, that
+/// will be calculated using the Poseidon Hash technique.
+///
+/// The roots and openings of the tree are the same as the ones of a
+/// [`PoseidonTree`](super::PoseidonTree) of the same depth.
+///
+/// A tree created with [`DynPoseidonTree::growing`] deepens by one level
+/// every time a leaf is pushed past its capacity, and the openings taken
+/// before can be brought to the new depth with
+/// [`DynPoseidonBranch::upgrade`].
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+#[derive(Debug)]
+pub struct DynPoseidonTree<L, K> {
+    inner: NStack<L, PoseidonAnnotation<K>>,
+    depth: usize,
+    growing: bool,
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K> Clone for DynPoseidonTree<L, K>
+where
+    L: Clone + PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            depth: self.depth,
+            growing: self.growing,
+        }
+    }
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K> DynPoseidonTree<L, K> {
+    /// Creates a new poseidon tree of the given depth
+    ///
+    /// # Panics
+    /// If `depth` is zero.
+    pub fn new(depth: usize) -> Self {
+        assert!(depth > 0, "the depth of the tree must not be zero");
+
+        Self {
+            inner: NStack::new(),
+            depth,
+            growing: false,
+        }
+    }
+
+    /// Creates a new poseidon tree of the given initial depth, deepening as
+    /// leaves are pushed past its capacity
+    ///
+    /// # Panics
+    /// If `depth` is zero.
+    pub fn growing(depth: usize) -> Self {
+        Self {
+            growing: true,
+            ..Self::new(depth)
+        }
+    }
+
+    /// Return the depth of the tree.
+    pub const fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K> DynPoseidonTree<L, K>
+where
+    L: PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+{
+    /// Append a leaf to the tree. Return the index of the appended leaf.
+    pub fn push(&mut self, mut leaf: L) -> u64 {
+        let anno = PoseidonAnnotation::from_child(&self.inner);
+        let cardinality: &Cardinality = anno.borrow();
+
+        let pos = **cardinality;
+
+        let capacity = (ARITY as u64).checked_pow(self.depth as u32);
+        if self.growing && capacity.map_or(false, |c| pos >= c) {
+            self.depth += 1;
+        }
+
+        leaf.set_pos(pos);
+        self.inner.push(leaf);
+
+        pos
+    }
+
+    /// Fetch, remove and return the last inserted leaf, if present.
+    pub fn pop(&mut self) -> Option<L> {
+        self.inner.pop()
+    }
+
+    /// Replace the leaf on a provided index, returning the previous one.
+    ///
+    /// See [`PoseidonTree::update`](super::PoseidonTree::update).
+    pub fn update(&mut self, n: u64, mut leaf: L) -> Option<L> {
+        let mut branch = self.inner.nth_mut(n)?;
+
+        leaf.set_pos(n);
+
+        Some(mem::replace(&mut *branch, leaf))
+    }
+
+    /// Fetch a leaf on a provided index.
+    pub fn get(&self, n: u64) -> Option<L>
+    where
+        L: Clone,
+    {
+        self.inner.nth(n).map(|b| (*b).clone())
+    }
+
+    /// Provides an iterator over all the leaves of the tree, in order.
+    pub fn leaves(&self) -> impl Iterator<Item = &L> {
+        self.inner.nth(0).into_iter().flatten()
+    }
+
+    /// Return a full merkle opening for this poseidon tree for a given index.
+    pub fn branch(&self, n: u64) -> Option<DynPoseidonBranch> {
+        self.inner.nth(n).as_ref().map(|b| {
+            let mut path = vec![PoseidonLevel::default(); self.depth];
+            let root = fill_path(b, &mut path);
+
+            DynPoseidonBranch { path, root }
+        })
+    }
+
+    /// Return the current root/state of the tree.
+    pub fn root(&self) -> BlsScalar {
+        self.branch(0).map(|b| *b.root()).unwrap_or_default()
+    }
+}
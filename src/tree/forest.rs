@@ -0,0 +1,170 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::PoseidonBranch;
+use crate::sponge;
+
+use bls12_381::Scalar as BlsScalar;
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use super::{PoseidonLeaf, PoseidonTree};
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use nstack::annotation::Keyed;
+
+/// Merkle opening of a leaf in one of the trees of a [`Forest`].
+///
+/// The opening holds the index of the tree, the branch of the leaf in the
+/// tree and the roots of every tree of the forest, hashed into its root.
+#[derive(Debug, Clone, Copy)]
+pub struct ForestOpening<const DEPTH: usize, const N: usize> {
+    pub(crate) index: usize,
+    pub(crate) branch: PoseidonBranch<DEPTH>,
+    pub(crate) roots: [BlsScalar; N],
+}
+
+impl<const DEPTH: usize, const N: usize> Default for ForestOpening<DEPTH, N> {
+    fn default() -> Self {
+        Self {
+            index: 0,
+            branch: PoseidonBranch::default(),
+            roots: [BlsScalar::zero(); N],
+        }
+    }
+}
+
+impl<const DEPTH: usize, const N: usize> ForestOpening<DEPTH, N> {
+    /// Index of the tree holding the leaf.
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Branch of the leaf in its tree.
+    pub const fn branch(&self) -> &PoseidonBranch<DEPTH> {
+        &self.branch
+    }
+
+    /// Roots of the trees of the forest.
+    pub const fn roots(&self) -> &[BlsScalar; N] {
+        &self.roots
+    }
+
+    /// Return the root of the forest of the opening.
+    pub fn root(&self) -> BlsScalar {
+        sponge::hash(&self.roots)
+    }
+
+    /// Check that the opening is a valid opening of `leaf` against the root
+    /// of a forest.
+    pub fn verify(&self, root: &BlsScalar, leaf: &BlsScalar) -> bool {
+        match self.roots.get(self.index) {
+            Some(tree_root) => {
+                self.branch.verify(tree_root, leaf) && self.root() == *root
+            }
+            None => false,
+        }
+    }
+}
+
+/// Set of `N` poseidon trees of the same depth, committed to under a single
+/// root.
+///
+/// The root of the forest is the [`sponge::hash`] of the roots of its trees,
+/// in order.
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+#[derive(Debug)]
+pub struct Forest<L, K, const DEPTH: usize, const N: usize> {
+    trees: [PoseidonTree<L, K, DEPTH>; N],
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, const N: usize> Clone for Forest<L, K, DEPTH, N>
+where
+    L: Clone + PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+{
+    fn clone(&self) -> Self {
+        Self {
+            trees: self.trees.clone(),
+        }
+    }
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, const N: usize> Default
+    for Forest<L, K, DEPTH, N>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, const N: usize> Forest<L, K, DEPTH, N> {
+    /// Creates a new forest of empty trees
+    pub fn new() -> Self {
+        Self {
+            trees: [(); N].map(|_| PoseidonTree::new()),
+        }
+    }
+
+    /// Return the tree on a provided index.
+    pub fn tree(&self, index: usize) -> Option<&PoseidonTree<L, K, DEPTH>> {
+        self.trees.get(index)
+    }
+
+    /// Return a mutable reference to the tree on a provided index.
+    pub fn tree_mut(
+        &mut self,
+        index: usize,
+    ) -> Option<&mut PoseidonTree<L, K, DEPTH>> {
+        self.trees.get_mut(index)
+    }
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<L, K, const DEPTH: usize, const N: usize> Forest<L, K, DEPTH, N>
+where
+    L: PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+{
+    /// Append a leaf to the tree on a provided index. Return the index of the
+    /// appended leaf in the tree, or `None` if there is no such tree.
+    pub fn push(&mut self, index: usize, leaf: L) -> Option<u64> {
+        self.tree_mut(index).map(|tree| tree.push(leaf))
+    }
+
+    /// Return the roots of the trees of the forest.
+    pub fn roots(&self) -> [BlsScalar; N] {
+        let mut roots = [BlsScalar::zero(); N];
+        roots
+            .iter_mut()
+            .zip(self.trees.iter())
+            .for_each(|(root, tree)| *root = tree.root());
+
+        roots
+    }
+
+    /// Return the current root/state of the forest.
+    pub fn root(&self) -> BlsScalar {
+        sponge::hash(&self.roots())
+    }
+
+    /// Return a merkle opening of the leaf on index `pos` of the tree on
+    /// index `index`.
+    pub fn opening(
+        &self,
+        index: usize,
+        pos: u64,
+    ) -> Option<ForestOpening<DEPTH, N>> {
+        let branch = self.tree(index)?.branch(pos)?;
+
+        Some(ForestOpening {
+            index,
+            branch,
+            roots: self.roots(),
+        })
+    }
+}
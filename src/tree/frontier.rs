@@ -0,0 +1,137 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::nodes::{hash_node, ARITY};
+use super::PoseidonBranch;
+
+use bls12_381::Scalar as BlsScalar;
+
+/// Append-only tree storing only its right frontier.
+///
+/// At every level, only the complete nodes of the rightmost group of siblings
+/// are kept, so the memory used by the tree doesn't grow with its leaves. The
+/// root of the tree is the same as the root of a
+/// [`PoseidonTree`](super::PoseidonTree) of the same depth, with leaves of
+/// the same hashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrontierTree<const DEPTH: usize> {
+    frontier: [[BlsScalar; ARITY]; DEPTH],
+    len: u64,
+    root: BlsScalar,
+}
+
+impl<const DEPTH: usize> Default for FrontierTree<DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const DEPTH: usize> FrontierTree<DEPTH> {
+    /// Create a new, empty, frontier tree.
+    pub const fn new() -> Self {
+        Self {
+            frontier: [[BlsScalar::zero(); ARITY]; DEPTH],
+            len: 0,
+            root: PoseidonBranch::<DEPTH>::NULL_ROOT,
+        }
+    }
+
+    /// Create a frontier tree of `len` leaves from the complete nodes of the
+    /// rightmost group of siblings of every level.
+    pub(crate) fn from_frontier(
+        frontier: [[BlsScalar; ARITY]; DEPTH],
+        len: u64,
+    ) -> Self {
+        let mut tree = Self {
+            frontier,
+            len,
+            root: PoseidonBranch::<DEPTH>::NULL_ROOT,
+        };
+        tree.root = tree.compute_root();
+
+        tree
+    }
+
+    /// Return the complete nodes of the rightmost group of siblings of every
+    /// level, from the bottom up.
+    pub(crate) const fn frontier(&self) -> &[[BlsScalar; ARITY]; DEPTH] {
+        &self.frontier
+    }
+
+    /// Return the number of leaves appended to the tree.
+    pub const fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Return `true` if no leaf was appended to the tree.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the root of the tree.
+    pub const fn root(&self) -> &BlsScalar {
+        &self.root
+    }
+
+    /// Append the hash of a leaf to the tree, returning the new root.
+    ///
+    /// # Panics
+    /// If the tree is full.
+    pub fn append(&mut self, leaf: BlsScalar) -> BlsScalar {
+        let capacity = (ARITY as u64).checked_pow(DEPTH as u32);
+        assert!(capacity.map_or(true, |c| self.len < c), "the tree is full");
+
+        // Complete groups are hashed into the level above, as long as they
+        // are complete
+        let mut node = leaf;
+        let mut pos = self.len;
+        for level in self.frontier.iter_mut() {
+            let offset = (pos % ARITY as u64) as usize;
+            level[offset] = node;
+
+            if offset < ARITY - 1 {
+                break;
+            }
+            node = hash_node(level);
+            pos /= ARITY as u64;
+        }
+
+        self.len += 1;
+        self.root = self.compute_root();
+
+        self.root
+    }
+
+    /// Hash the incomplete groups of every level up to the root.
+    fn compute_root(&self) -> BlsScalar {
+        let mut node = None;
+        let mut len = self.len;
+
+        for (depth, level) in self.frontier.iter().enumerate() {
+            // Number of complete nodes in the rightmost group of the level,
+            // the top level being a single group
+            let complete = match depth == DEPTH - 1 {
+                true => len as usize,
+                false => (len % ARITY as u64) as usize,
+            };
+
+            node = match node {
+                Some(node) => {
+                    let mut children = [BlsScalar::zero(); ARITY];
+                    children[..complete].copy_from_slice(&level[..complete]);
+                    children[complete] = node;
+                    Some(hash_node(&children[..=complete]))
+                }
+                None if complete > 0 => Some(hash_node(&level[..complete])),
+                None => None,
+            };
+
+            len /= ARITY as u64;
+        }
+
+        node.unwrap_or(PoseidonBranch::<DEPTH>::NULL_ROOT)
+    }
+}
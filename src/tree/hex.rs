@@ -0,0 +1,54 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Hexadecimal helpers for the human-readable representation of the tree
+//! types.
+
+use core::fmt;
+
+/// Write `bytes` to the formatter as lowercase hexadecimal digits.
+pub(crate) fn write_hex(
+    f: &mut fmt::Formatter<'_>,
+    bytes: &[u8],
+) -> fmt::Result {
+    bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+}
+
+/// Displays the wrapped bytes as lowercase hexadecimal digits.
+pub(crate) struct Hex<'a>(pub &'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_hex(f, self.0)
+    }
+}
+
+/// Decode the hexadecimal string `s` into `buf`.
+///
+/// An optional `0x` prefix is accepted. The string must encode exactly
+/// `buf.len()` bytes.
+pub(crate) fn decode_hex(s: &str, buf: &mut [u8]) -> Result<(), bytes::Error> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+
+    if s.len() != buf.len() * 2 {
+        return Err(bytes::Error::BadLength {
+            found: s.len() / 2,
+            expected: buf.len(),
+        });
+    }
+
+    let nibble = |index: usize| {
+        let ch = s.as_bytes()[index] as char;
+        ch.to_digit(16)
+            .map(|d| d as u8)
+            .ok_or(bytes::Error::InvalidChar { ch, index })
+    };
+
+    buf.iter_mut().enumerate().try_for_each(|(i, byte)| {
+        *byte = (nibble(2 * i)? << 4) | nibble(2 * i + 1)?;
+        Ok(())
+    })
+}
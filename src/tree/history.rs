@@ -0,0 +1,139 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use bls12_381::Scalar as BlsScalar;
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use alloc::boxed::Box;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use core::any::Any;
+
+/// Ring buffer of the last `N` roots of a tree.
+///
+/// Protocols accepting openings against slightly stale roots can have a tree
+/// record its root after every mutation with
+/// [`PoseidonTree::track_roots`](super::PoseidonTree::track_roots), or record
+/// the roots themselves, and check the root of an opening with
+/// [`RootHistory::is_recent_root`]. Recording the same root as the latest one
+/// is a no-op, so mutations leaving the tree unchanged don't evict older
+/// roots.
+#[derive(Debug, Clone, Copy)]
+pub struct RootHistory<const N: usize> {
+    roots: [BlsScalar; N],
+    len: usize,
+    next: usize,
+}
+
+impl<const N: usize> Default for RootHistory<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> RootHistory<N> {
+    /// Create a new, empty, root history.
+    pub const fn new() -> Self {
+        Self {
+            roots: [BlsScalar::zero(); N],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    /// Record `root` as the latest root, evicting the oldest one if the
+    /// history is full.
+    pub fn record(&mut self, root: BlsScalar) {
+        if N == 0 || self.latest() == Some(&root) {
+            return;
+        }
+
+        self.roots[self.next] = root;
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    /// Return the latest recorded root, if any.
+    pub fn latest(&self) -> Option<&BlsScalar> {
+        self.iter().next()
+    }
+
+    /// Return `true` if `root` is one of the recorded roots.
+    pub fn is_recent_root(&self, root: &BlsScalar) -> bool {
+        self.iter().any(|r| r == root)
+    }
+
+    /// Return the number of recorded roots.
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return `true` if no root was recorded.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Provides an iterator over the recorded roots, from the latest to the
+    /// oldest.
+    pub fn iter(&self) -> impl Iterator<Item = &BlsScalar> {
+        (1..=self.len).map(move |i| &self.roots[(self.next + N - i) % N])
+    }
+}
+
+/// A [`RootHistory`] of any length, as held by a tree.
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub(crate) trait RootRecorder: Send + Sync {
+    fn record(&mut self, root: BlsScalar);
+
+    fn is_recent_root(&self, root: &BlsScalar) -> bool;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn boxed_clone(&self) -> Box<dyn RootRecorder>;
+}
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+impl<const N: usize> RootRecorder for RootHistory<N> {
+    fn record(&mut self, root: BlsScalar) {
+        RootHistory::record(self, root)
+    }
+
+    fn is_recent_root(&self, root: &BlsScalar) -> bool {
+        RootHistory::is_recent_root(self, root)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn boxed_clone(&self) -> Box<dyn RootRecorder> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_history() {
+        let mut history = RootHistory::<3>::new();
+        assert!(history.is_empty());
+        assert_eq!(history.latest(), None);
+
+        for i in 0..5 {
+            history.record(BlsScalar::from(i));
+        }
+        history.record(BlsScalar::from(4));
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.latest(), Some(&BlsScalar::from(4)));
+        assert!(history.is_recent_root(&BlsScalar::from(2)));
+        assert!(!history.is_recent_root(&BlsScalar::from(1)));
+
+        let roots = [4, 3, 2].map(BlsScalar::from);
+        assert!(history.iter().eq(roots.iter()));
+    }
+}
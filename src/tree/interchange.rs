@@ -0,0 +1,215 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Versioned JSON interchange format for merkle openings.
+//!
+//! The format is meant to be consumed by tools that are not written in Rust,
+//! such as block explorers or auditing scripts. An opening is represented as:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "root": "<scalar>",
+//!   "leaf": "<scalar>",
+//!   "path": [
+//!     { "offset": 1, "level": ["<scalar>", "<scalar>", ...] },
+//!     ...
+//!   ]
+//! }
+//! ```
+//!
+//! Every scalar is encoded as the hexadecimal representation of its 32
+//! canonical little-endian bytes. The `path` starts at the level that contains
+//! the leaf and ends at the level right below the root. Each level contains
+//! [`hades::WIDTH`] scalars, where the first one is the bitflag of the present
+//! nodes, and `offset` is the position of the opened node in the level.
+
+use super::hex::{decode_hex, Hex};
+use super::{PoseidonBranch, PoseidonLevel};
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::Serializable;
+use serde::{Deserialize, Serialize};
+
+/// Current version of the interchange format.
+pub const VERSION: u32 = 1;
+
+/// JSON representation of a merkle opening.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Opening {
+    /// Version of the format, see [`VERSION`].
+    pub version: u32,
+    /// Root of the tree the opening is performed against.
+    pub root: String,
+    /// Hash of the opened leaf.
+    pub leaf: String,
+    /// Levels of the opening, from the leaf up to the root.
+    pub path: Vec<Level>,
+}
+
+/// JSON representation of a level of a merkle opening.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Level {
+    /// Position of the opened node in the level.
+    pub offset: u64,
+    /// Bitflag of the present nodes followed by their hashes.
+    pub level: Vec<String>,
+}
+
+/// Errors that can occur while reading an opening.
+#[derive(Debug)]
+pub enum Error {
+    /// The input is not a valid JSON opening.
+    Json(serde_json::Error),
+    /// The opening was produced with an unsupported format version.
+    UnsupportedVersion(u32),
+    /// The depth of the opening doesn't match the expected one.
+    BadDepth {
+        /// Depth of the opening.
+        found: usize,
+        /// Depth of the branch.
+        expected: usize,
+    },
+    /// A level has the wrong number of scalars or an out of range offset.
+    BadLevel(usize),
+    /// A scalar is not correctly encoded.
+    InvalidScalar(bytes::Error),
+    /// The leaf is not present at the offset of the first level.
+    LeafMismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "invalid JSON opening: {}", e),
+            Self::UnsupportedVersion(v) => {
+                write!(f, "unsupported opening version: {}", v)
+            }
+            Self::BadDepth { found, expected } => {
+                write!(f, "opening depth is {}, expected {}", found, expected)
+            }
+            Self::BadLevel(depth) => {
+                write!(f, "malformed opening level at depth {}", depth)
+            }
+            Self::InvalidScalar(e) => write!(f, "invalid scalar: {:?}", e),
+            Self::LeafMismatch => {
+                f.write_str("the leaf is not part of the opening")
+            }
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+impl From<bytes::Error> for Error {
+    fn from(e: bytes::Error) -> Self {
+        Self::InvalidScalar(e)
+    }
+}
+
+fn scalar_to_hex(scalar: &BlsScalar) -> String {
+    Hex(&scalar.to_bytes()).to_string()
+}
+
+fn scalar_from_hex(s: &str) -> Result<BlsScalar, Error> {
+    let mut buf = [0u8; BlsScalar::SIZE];
+    decode_hex(s, &mut buf)?;
+
+    Ok(BlsScalar::from_bytes(&buf)?)
+}
+
+impl<const DEPTH: usize> From<&PoseidonBranch<DEPTH>> for Opening {
+    fn from(branch: &PoseidonBranch<DEPTH>) -> Self {
+        let path = branch
+            .path
+            .iter()
+            .map(|level| Level {
+                offset: level.index,
+                level: level.level.iter().map(scalar_to_hex).collect(),
+            })
+            .collect();
+
+        Self {
+            version: VERSION,
+            root: scalar_to_hex(&branch.root),
+            leaf: scalar_to_hex(branch),
+            path,
+        }
+    }
+}
+
+impl<const DEPTH: usize> TryFrom<&Opening> for PoseidonBranch<DEPTH> {
+    type Error = Error;
+
+    fn try_from(opening: &Opening) -> Result<Self, Self::Error> {
+        if opening.version != VERSION {
+            return Err(Error::UnsupportedVersion(opening.version));
+        }
+
+        if opening.path.len() != DEPTH {
+            return Err(Error::BadDepth {
+                found: opening.path.len(),
+                expected: DEPTH,
+            });
+        }
+
+        let mut path = [PoseidonLevel::default(); DEPTH];
+        for (depth, (level, json)) in
+            path.iter_mut().zip(opening.path.iter()).enumerate()
+        {
+            let offset = usize::try_from(json.offset)
+                .map_err(|_| Error::BadLevel(depth))?;
+            if json.level.len() != hades::WIDTH
+                || offset == 0
+                || offset >= hades::WIDTH
+            {
+                return Err(Error::BadLevel(depth));
+            }
+
+            for (scalar, s) in level.level.iter_mut().zip(json.level.iter()) {
+                *scalar = scalar_from_hex(s)?;
+            }
+            level.index = json.offset;
+        }
+
+        let branch = PoseidonBranch {
+            path,
+            root: scalar_from_hex(&opening.root)?,
+        };
+
+        if DEPTH > 0 && *branch != scalar_from_hex(&opening.leaf)? {
+            return Err(Error::LeafMismatch);
+        }
+
+        Ok(branch)
+    }
+}
+
+/// Serialize a branch into the JSON interchange format.
+pub fn to_json<const DEPTH: usize>(branch: &PoseidonBranch<DEPTH>) -> String {
+    serde_json::to_string(&Opening::from(branch))
+        .expect("An opening is always representable as JSON")
+}
+
+/// Deserialize a branch from the JSON interchange format.
+///
+/// The version and the shape of the opening are validated, as well as the
+/// presence of the leaf in the first level of the path. The opening itself is
+/// not verified against the root.
+pub fn from_json<const DEPTH: usize>(
+    json: &str,
+) -> Result<PoseidonBranch<DEPTH>, Error> {
+    let opening: Opening = serde_json::from_str(json)?;
+    PoseidonBranch::try_from(&opening)
+}
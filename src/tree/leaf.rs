@@ -4,6 +4,12 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use super::TOMBSTONE;
+use crate::sponge;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
 use bls12_381::{Scalar as BlsScalar};
 
 /// A struct that will be used as a poseidon tree leaf must implement this
@@ -25,4 +31,68 @@ pub trait PoseidonLeaf {
     /// This method is internally used to set the index after the data has been
     /// inserted in the merkle tree.
     fn set_pos(&mut self, pos: u64);
+
+    /// Return `true` if the leaf is the tombstone left in the slot of a leaf
+    /// removed with [`PoseidonTree::remove`](super::PoseidonTree::remove).
+    ///
+    /// A tombstone is hashed to [`TOMBSTONE`] in the tree, whatever its
+    /// [`PoseidonLeaf::poseidon_hash`], so it can't be opened. No leaf is a
+    /// tombstone by default: the leaves that can be removed implement
+    /// [`PoseidonTombstone`].
+    fn is_tombstone(&self) -> bool {
+        false
+    }
+}
+
+/// Leaves that can be removed from a tree with
+/// [`PoseidonTree::remove`](super::PoseidonTree::remove), which leaves a
+/// tombstone in their slot.
+pub trait PoseidonTombstone: PoseidonLeaf {
+    /// Return a tombstone, for which [`PoseidonLeaf::is_tombstone`] returns
+    /// `true`.
+    fn tombstone() -> Self;
+}
+
+/// Return the hash of `leaf` in a tree, which is [`TOMBSTONE`] for the
+/// tombstone of a removed leaf.
+pub(crate) fn leaf_hash<L: PoseidonLeaf>(leaf: &L) -> BlsScalar {
+    match leaf.is_tombstone() {
+        true => TOMBSTONE,
+        false => leaf.poseidon_hash(),
+    }
+}
+
+/// Extension of [`PoseidonLeaf`] for leaves that may fail to hash, and that
+/// are separated from the leaves of other types by a domain tag.
+///
+/// The hash of a leaf is the [`sponge::hash`] of its domain tag followed by
+/// its encoding, so leaves of types with distinct tags never share a hash,
+/// even with identical contents. [`PoseidonLeaf::poseidon_hash`] must return
+/// the same hash as [`PoseidonLeafExt::try_poseidon_hash`] when the latter
+/// succeeds, which is checked on insertion with
+/// [`PoseidonTree::try_push`](super::PoseidonTree::try_push) in debug builds.
+pub trait PoseidonLeafExt: PoseidonLeaf {
+    /// Domain tag of the leaf type, mixed into the hash of its leaves.
+    const DOMAIN: u64;
+
+    /// Error returned when a leaf can't be hashed.
+    type Error;
+
+    /// Encode the contents of the leaf into scalars, failing if the leaf is
+    /// malformed.
+    fn try_poseidon_encode(&self) -> Result<Vec<BlsScalar>, Self::Error>;
+
+    /// Hash the leaf, mixing the domain tag of its type with its encoding.
+    fn try_poseidon_hash(&self) -> Result<BlsScalar, Self::Error> {
+        let mut messages = vec![BlsScalar::from(Self::DOMAIN)];
+        messages.extend(self.try_poseidon_encode()?);
+
+        Ok(sponge::hash(&messages))
+    }
+
+    /// Hook called once the leaf is inserted in a tree on `pos`, with the root
+    /// of the tree after the insertion.
+    ///
+    /// Does nothing by default.
+    fn on_inserted(&self, _pos: u64, _root: &BlsScalar) {}
 }
@@ -0,0 +1,221 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::{PoseidonLeaf, PoseidonTombstone};
+use crate::map::PoseidonEncode;
+use crate::sponge;
+
+use bls12_381::Scalar as BlsScalar;
+use nstack::annotation::Keyed;
+
+/// Leaf holding a bare scalar, which is its own hash.
+///
+/// The leaves are keyed by their position in the tree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScalarLeaf {
+    scalar: BlsScalar,
+    pos: u64,
+    removed: bool,
+}
+
+impl ScalarLeaf {
+    /// Create a new leaf holding `scalar`.
+    pub const fn new(scalar: BlsScalar) -> Self {
+        Self {
+            scalar,
+            pos: 0,
+            removed: false,
+        }
+    }
+
+    /// Return the scalar held by the leaf.
+    pub const fn scalar(&self) -> &BlsScalar {
+        &self.scalar
+    }
+}
+
+impl From<BlsScalar> for ScalarLeaf {
+    fn from(scalar: BlsScalar) -> Self {
+        Self::new(scalar)
+    }
+}
+
+impl Keyed<u64> for ScalarLeaf {
+    fn key(&self) -> &u64 {
+        &self.pos
+    }
+}
+
+impl PoseidonLeaf for ScalarLeaf {
+    fn poseidon_hash(&self) -> BlsScalar {
+        self.scalar
+    }
+
+    fn pos(&self) -> &u64 {
+        &self.pos
+    }
+
+    fn set_pos(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+
+    fn is_tombstone(&self) -> bool {
+        self.removed
+    }
+}
+
+impl PoseidonTombstone for ScalarLeaf {
+    fn tombstone() -> Self {
+        Self {
+            removed: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Leaf holding a payload, hashed with [`sponge::hash`] from its
+/// [`PoseidonEncode`] encoding.
+///
+/// The hash is computed once, when the leaf is created, and the payload can't
+/// be mutated afterwards. The leaves are keyed by their position in the tree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HashedLeaf<T> {
+    payload: T,
+    hash: BlsScalar,
+    pos: u64,
+    removed: bool,
+}
+
+impl<T: PoseidonEncode> HashedLeaf<T> {
+    /// Create a new leaf holding `payload`.
+    pub fn new(payload: T) -> Self {
+        let hash = sponge::hash(&payload.poseidon_encode());
+
+        Self {
+            payload,
+            hash,
+            pos: 0,
+            removed: false,
+        }
+    }
+}
+
+impl<T> HashedLeaf<T> {
+    /// Return the payload held by the leaf.
+    pub const fn payload(&self) -> &T {
+        &self.payload
+    }
+
+    /// Return the payload held by the leaf, consuming it.
+    pub fn into_payload(self) -> T {
+        self.payload
+    }
+}
+
+impl<T: PoseidonEncode> From<T> for HashedLeaf<T> {
+    fn from(payload: T) -> Self {
+        Self::new(payload)
+    }
+}
+
+impl<T> Keyed<u64> for HashedLeaf<T> {
+    fn key(&self) -> &u64 {
+        &self.pos
+    }
+}
+
+impl<T> PoseidonLeaf for HashedLeaf<T> {
+    fn poseidon_hash(&self) -> BlsScalar {
+        self.hash
+    }
+
+    fn pos(&self) -> &u64 {
+        &self.pos
+    }
+
+    fn set_pos(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+
+    fn is_tombstone(&self) -> bool {
+        self.removed
+    }
+}
+
+/// The tombstone holds the default payload.
+impl<T: Default> PoseidonTombstone for HashedLeaf<T> {
+    fn tombstone() -> Self {
+        Self {
+            removed: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Leaf holding a bare scalar, which is its own hash, along with a key.
+///
+/// The key isn't part of the hash of the leaf, and only serves the searches
+/// of the tree, such as [`PoseidonTree::leaves_in_range`].
+///
+/// [`PoseidonTree::leaves_in_range`]: super::PoseidonTree::leaves_in_range
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KeyedScalarLeaf<K> {
+    scalar: BlsScalar,
+    key: K,
+    pos: u64,
+    removed: bool,
+}
+
+impl<K> KeyedScalarLeaf<K> {
+    /// Create a new leaf holding `scalar`, with the given `key`.
+    pub const fn new(scalar: BlsScalar, key: K) -> Self {
+        Self {
+            scalar,
+            key,
+            pos: 0,
+            removed: false,
+        }
+    }
+
+    /// Return the scalar held by the leaf.
+    pub const fn scalar(&self) -> &BlsScalar {
+        &self.scalar
+    }
+}
+
+impl<K> Keyed<K> for KeyedScalarLeaf<K> {
+    fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K> PoseidonLeaf for KeyedScalarLeaf<K> {
+    fn poseidon_hash(&self) -> BlsScalar {
+        self.scalar
+    }
+
+    fn pos(&self) -> &u64 {
+        &self.pos
+    }
+
+    fn set_pos(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+
+    fn is_tombstone(&self) -> bool {
+        self.removed
+    }
+}
+
+/// The tombstone holds the default key.
+impl<K: Default> PoseidonTombstone for KeyedScalarLeaf<K> {
+    fn tombstone() -> Self {
+        Self {
+            removed: true,
+            ..Self::default()
+        }
+    }
+}
@@ -0,0 +1,127 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::nodes::{hash_node, ARITY};
+use super::{FrontierTree, PoseidonBranch};
+
+use bls12_381::Scalar as BlsScalar;
+
+/// Merkle opening of a leaf kept up to date with the leaves appended to its
+/// tree.
+///
+/// Besides the branch of the leaf, the opening holds the right frontier of
+/// the tree, the complete nodes of the rightmost group of siblings of every
+/// level, as a [`FrontierTree`]. Appending the hashes of the leaves appended
+/// to the tree is then enough to update the branch, without requesting it
+/// again.
+#[derive(Debug, Clone, Copy)]
+pub struct MaintainedOpening<const DEPTH: usize> {
+    pub(crate) pos: u64,
+    pub(crate) branch: PoseidonBranch<DEPTH>,
+    pub(crate) frontier: FrontierTree<DEPTH>,
+}
+
+impl<const DEPTH: usize> MaintainedOpening<DEPTH> {
+    /// Create an opening from the branch of the leaf on index `pos` and the
+    /// branch of the last leaf of a tree of `len` leaves.
+    ///
+    /// Return `None` if `pos` is not lower than `len`.
+    pub fn new(
+        pos: u64,
+        branch: PoseidonBranch<DEPTH>,
+        len: u64,
+        last: &PoseidonBranch<DEPTH>,
+    ) -> Option<Self> {
+        if pos >= len {
+            return None;
+        }
+
+        // The levels of the last leaf hold the rightmost groups of siblings
+        let mut frontier = [[BlsScalar::zero(); ARITY]; DEPTH];
+        let mut right = len - 1;
+
+        for (nodes, level) in frontier.iter_mut().zip(last.path.iter()) {
+            let offset = (right % ARITY as u64) as usize;
+            nodes[..=offset].copy_from_slice(&level.level[1..=offset + 1]);
+
+            right /= ARITY as u64;
+        }
+
+        Some(Self {
+            pos,
+            branch,
+            frontier: FrontierTree::from_frontier(frontier, len),
+        })
+    }
+
+    /// Return the index of the opened leaf.
+    pub const fn pos(&self) -> u64 {
+        self.pos
+    }
+
+    /// Return the number of leaves of the tree.
+    pub const fn len(&self) -> u64 {
+        self.frontier.len()
+    }
+
+    /// Return `true` if the tree has no leaves.
+    pub const fn is_empty(&self) -> bool {
+        self.frontier.is_empty()
+    }
+
+    /// Return the branch of the leaf in the latest tree.
+    pub const fn branch(&self) -> &PoseidonBranch<DEPTH> {
+        &self.branch
+    }
+
+    /// Return the root of the latest tree.
+    pub const fn root(&self) -> &BlsScalar {
+        &self.branch.root
+    }
+
+    /// Update the opening with the hash of a leaf appended to the tree,
+    /// returning the new root.
+    ///
+    /// # Panics
+    /// If the tree is full.
+    pub fn append(&mut self, leaf: BlsScalar) -> BlsScalar {
+        let root = self.frontier.append(leaf);
+        self.update_path();
+
+        root
+    }
+
+    /// Rebuild the levels of the branch in the rightmost groups of siblings,
+    /// from the bottom up, and the root of the branch.
+    fn update_path(&mut self) {
+        let mut right = self.frontier.len() - 1;
+        let mut pos = self.pos;
+        let mut partial = None;
+
+        let frontier = self.frontier.frontier();
+        let levels = self.branch.path.iter_mut().zip(frontier.iter());
+        for (level, nodes) in levels {
+            let offset = (right % ARITY as u64) as usize;
+
+            // The rightmost node of a level above the leaves is the hash of
+            // the rightmost group of the level below
+            let mut children = [BlsScalar::zero(); ARITY];
+            children[..offset].copy_from_slice(&nodes[..offset]);
+            children[offset] = partial.unwrap_or(nodes[offset]);
+
+            if pos / ARITY as u64 == right / ARITY as u64 {
+                level.level[0] = BlsScalar::from((1u64 << (offset + 1)) - 1);
+                level.level[1..].copy_from_slice(&children);
+            }
+
+            partial = Some(hash_node(&children[..=offset]));
+            right /= ARITY as u64;
+            pos /= ARITY as u64;
+        }
+
+        self.branch.root = *self.frontier.root();
+    }
+}
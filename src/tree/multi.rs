@@ -0,0 +1,106 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::nodes::ARITY;
+use super::TOMBSTONE;
+
+use alloc::vec::Vec;
+
+use bls12_381::Scalar as BlsScalar;
+use hades::{ScalarStrategy, Strategy};
+
+/// Represents a merkle opening of many leaves of the same tree at once
+///
+/// The levels shared by the paths of many leaves are only held once: at every
+/// depth, the proof holds the levels of the groups of siblings on the path of
+/// any of the leaves, ordered by their position in the depth. The positions
+/// of the leaves are sorted, and deduplicated.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PoseidonMultiProof<const DEPTH: usize> {
+    pub(crate) positions: Vec<u64>,
+    pub(crate) levels: Vec<Vec<[BlsScalar; hades::WIDTH]>>,
+    pub(crate) root: BlsScalar,
+}
+
+impl<const DEPTH: usize> PoseidonMultiProof<DEPTH> {
+    /// Return the positions of the opened leaves, in ascending order.
+    pub fn positions(&self) -> &[u64] {
+        &self.positions
+    }
+
+    /// Return the levels of the proof, from the bottom up to the root.
+    pub fn levels(&self) -> &[Vec<[BlsScalar; hades::WIDTH]>] {
+        &self.levels
+    }
+
+    /// Represents the root of the tree of the opening
+    pub fn root(&self) -> &BlsScalar {
+        &self.root
+    }
+
+    /// Check that the proof is a valid opening of `leaves` against `root`,
+    /// the leaves being given in the order of [`Self::positions`].
+    ///
+    /// The paths of all the leaves are recomputed natively, with the same
+    /// checks as [`PoseidonBranch::verify`](super::PoseidonBranch::verify),
+    /// hashing every shared level once.
+    pub fn verify(&self, root: &BlsScalar, leaves: &[BlsScalar]) -> bool {
+        if leaves.len() != self.positions.len()
+            || leaves.contains(&TOMBSTONE)
+            || self.positions.windows(2).any(|w| w[0] >= w[1])
+            || self.levels.len() != DEPTH
+            || self.root != *root
+        {
+            return false;
+        }
+
+        let mut h = ScalarStrategy::new();
+        let mut perm = [BlsScalar::zero(); hades::WIDTH];
+
+        let mut nodes: Vec<(u64, BlsScalar)> = self
+            .positions
+            .iter()
+            .copied()
+            .zip(leaves.iter().copied())
+            .collect();
+
+        for levels in self.levels.iter() {
+            let mut levels = levels.iter();
+            let mut level = None;
+            let mut parents: Vec<(u64, BlsScalar)> = Vec::new();
+
+            for (pos, node) in nodes {
+                let group = pos / ARITY as u64;
+                let offset = (pos % ARITY as u64) as usize;
+
+                // Every group of siblings is hashed once, when its first node
+                // is reached
+                if parents.last().map(|(g, _)| *g) != Some(group) {
+                    level = levels.next();
+                    let level = match level {
+                        Some(level) => level,
+                        None => return false,
+                    };
+
+                    perm.copy_from_slice(level);
+                    h.perm(&mut perm);
+                    parents.push((group, perm[1]));
+                }
+
+                if level.map(|l| l[offset + 1]) != Some(node) {
+                    return false;
+                }
+            }
+
+            if levels.next().is_some() {
+                return false;
+            }
+            nodes = parents;
+        }
+
+        nodes == [(0, *root)]
+    }
+}
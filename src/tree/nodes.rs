@@ -0,0 +1,200 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Computation of the internal nodes of a tree directly from the hashes of
+//! its leaves, without building the underlying `NStack`.
+//!
+//! The leaves are grouped by [`ARITY`] into nodes, and so are the nodes of
+//! every level, which yields the same hashes as the annotations of the
+//! `NStack`. Once a single node is left, every upper level holds a single
+//! node as well, until the tree reaches its depth, exactly like the path of a
+//! [`PoseidonBranch`](super::PoseidonBranch).
+
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use super::PoseidonAnnotation;
+
+use alloc::vec::Vec;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use core::borrow::Borrow;
+
+use crate::permutation::{Hades, Permutation};
+
+use bls12_381::Scalar as BlsScalar;
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+use nstack::NStack;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Number of children of every node of the tree.
+pub(crate) const ARITY: usize = hades::WIDTH - 1;
+
+/// Hash the children of a node, flagging them as present in the first element
+/// of the permutation.
+pub(crate) fn hash_node(children: &[BlsScalar]) -> BlsScalar {
+    hash_node_with(&Hades, children)
+}
+
+/// Hash the children of a node as [`hash_node`] does, with the permutation
+/// `perm`.
+pub(crate) fn hash_node_with<P>(perm: &P, children: &[P::Scalar]) -> P::Scalar
+where
+    P: Permutation,
+{
+    let mut state = [P::Scalar::ZERO; hades::WIDTH];
+
+    state[0] = P::Scalar::from((1u64 << children.len()) - 1);
+    state[1..=children.len()].copy_from_slice(children);
+    perm.permute(&mut state);
+
+    state[1]
+}
+
+/// Assert that a permutation of `width` elements hashes the levels of a tree,
+/// one element wider than its arity.
+pub(crate) fn assert_tree_width(width: usize) {
+    assert_eq!(
+        width,
+        hades::WIDTH,
+        "the permutation should hash the levels of a tree"
+    );
+}
+
+/// Hash a level of the tree into the level above it.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn hash_level<P>(perm: &P, level: &[P::Scalar]) -> Vec<P::Scalar>
+where
+    P: Permutation + Sync,
+{
+    level
+        .chunks(ARITY)
+        .map(|children| hash_node_with(perm, children))
+        .collect()
+}
+
+/// Hash a level of the tree into the level above it, in parallel.
+#[cfg(feature = "parallel")]
+pub(crate) fn hash_level<P>(perm: &P, level: &[P::Scalar]) -> Vec<P::Scalar>
+where
+    P: Permutation + Sync,
+{
+    level
+        .par_chunks(ARITY)
+        .map(|children| hash_node_with(perm, children))
+        .collect()
+}
+
+/// Compute the root of the tree of the given depth with the given leaf
+/// hashes, without building the tree.
+///
+/// Return `None` if there are more leaves than the tree can hold. With the
+/// `parallel` feature, the nodes of every level are hashed in parallel.
+pub fn compute_root<const DEPTH: usize>(
+    leaves: &[BlsScalar],
+) -> Option<BlsScalar> {
+    compute_root_with::<_, DEPTH>(&Hades, leaves)
+}
+
+/// Compute the root of the tree of the given depth with the given leaf
+/// hashes, as [`compute_root`] does, hashing the nodes with the permutation
+/// `perm`.
+///
+/// The leaves and the root are elements of the field of the permutation, the
+/// root of an empty tree being zero.
+///
+/// # Panics
+/// If the width of `perm` isn't one more than the arity of the tree.
+pub fn compute_root_with<P, const DEPTH: usize>(
+    perm: &P,
+    leaves: &[P::Scalar],
+) -> Option<P::Scalar>
+where
+    P: Permutation + Sync,
+{
+    assert_tree_width(perm.width());
+
+    let capacity = u32::try_from(DEPTH)
+        .ok()
+        .and_then(|depth| ARITY.checked_pow(depth))
+        .unwrap_or(usize::MAX);
+
+    if leaves.len() > capacity {
+        return None;
+    }
+
+    if leaves.is_empty() {
+        return Some(P::Scalar::ZERO);
+    }
+
+    (1..DEPTH)
+        .fold(hash_level(perm, leaves), |level, _| {
+            hash_level(perm, &level)
+        })
+        .first()
+        .copied()
+}
+
+/// Number of nodes in every level of a tree holding `len` leaves, from the
+/// bottom up to the root.
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub(crate) fn level_lens(len: u64, depth: usize) -> impl Iterator<Item = u64> {
+    let arity = ARITY as u64;
+
+    (0..depth).scan(len, move |len, _| {
+        *len = (*len + arity - 1) / arity;
+        Some(*len)
+    })
+}
+
+/// Compute the `depth` levels of internal nodes of the tree with the given
+/// leaves, from the bottom up to the root.
+///
+/// The levels of an empty tree are empty.
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub(crate) fn levels(
+    leaves: &[BlsScalar],
+    depth: usize,
+) -> Vec<Vec<BlsScalar>> {
+    let mut levels: Vec<Vec<BlsScalar>> = Vec::with_capacity(depth);
+
+    for _ in 0..depth {
+        let level = match levels.last() {
+            Some(level) => hash_level(&Hades, level),
+            None => hash_level(&Hades, leaves),
+        };
+        levels.push(level);
+    }
+
+    levels
+}
+
+/// Collect the cached hashes of the nodes below the top of `stack` into
+/// `levels`, without hashing, and return the level of the top of `stack`.
+#[cfg(all(feature = "alloc", not(feature = "verify-only")))]
+pub(crate) fn collect<L, K, A>(
+    stack: &NStack<L, PoseidonAnnotation<K, A>>,
+    levels: &mut Vec<Vec<BlsScalar>>,
+) -> usize {
+    match stack {
+        NStack::Leaf(_) => 0,
+        NStack::Node(node) => {
+            let mut level = 0;
+
+            for annotated in node.iter().flatten() {
+                level = collect(annotated.child(), levels);
+
+                let anno = annotated.anno();
+                let anno = &*anno;
+
+                if levels.len() <= level {
+                    levels.resize(level + 1, Vec::new());
+                }
+                levels[level].push(*anno.borrow());
+            }
+
+            level + 1
+        }
+    }
+}
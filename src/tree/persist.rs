@@ -0,0 +1,215 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! On-disk persistence of a [`PoseidonTree`].
+//!
+//! A persisted tree is laid out as follows, with every integer encoded in
+//! little-endian:
+//!
+//! - the magic bytes `b"PTRE"`;
+//! - the version of the format, as a `u32`;
+//! - the depth of the tree, as a `u32`;
+//! - the size of a serialized leaf, as a `u32`;
+//! - the number of leaves, as a `u64`;
+//! - the serialized leaves, in order;
+//! - the hashes of the internal nodes, level by level from the bottom up to
+//!   the root, each level holding the nodes from left to right.
+//!
+//! Persisting the internal nodes allows to serve openings from the file
+//! without rebuilding the tree, and to restore the cached hashes of a tree
+//! instead of hashing its leaves again. The root is used to check the
+//! integrity of a restored tree.
+
+use super::annotation::with_restored_roots;
+use super::{nodes, Aggregate, PoseidonAnnotation, PoseidonLeaf, PoseidonTree};
+
+use alloc::vec::Vec;
+use core::array;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::Serializable;
+use nstack::annotation::Keyed;
+use nstack::NStack;
+use ranno::Annotated;
+
+const MAGIC: [u8; 4] = *b"PTRE";
+const VERSION: u32 = 1;
+
+/// Size of the header preceding the leaves of a persisted tree.
+#[cfg(feature = "mmap")]
+pub(super) const HEADER_SIZE: usize = 24;
+
+pub(super) fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Read the header of a persisted tree, checking that it was written for a
+/// tree of the given depth and leaf size, and return its number of leaves.
+pub(super) fn read_header<R, const DEPTH: usize, const N: usize>(
+    reader: &mut R,
+) -> io::Result<u64>
+where
+    R: Read,
+{
+    if read_array(reader)? != MAGIC {
+        return Err(invalid_data("not a persisted poseidon tree"));
+    }
+    if u32::from_le_bytes(read_array(reader)?) != VERSION {
+        return Err(invalid_data("unsupported version"));
+    }
+    if u32::from_le_bytes(read_array(reader)?) != DEPTH as u32 {
+        return Err(invalid_data("mismatching tree depth"));
+    }
+    if u32::from_le_bytes(read_array(reader)?) != N as u32 {
+        return Err(invalid_data("mismatching leaf size"));
+    }
+
+    Ok(u64::from_le_bytes(read_array(reader)?))
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+impl<L, K, const DEPTH: usize, A> PoseidonTree<L, K, DEPTH, A>
+where
+    L: PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    /// Write the tree to the file at `path`, creating it if it doesn't exist
+    /// and truncating it otherwise.
+    pub fn persist<P, const N: usize>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+        L: Serializable<N>,
+    {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&(DEPTH as u32).to_le_bytes())?;
+        writer.write_all(&(N as u32).to_le_bytes())?;
+
+        let len = self.leaves().count() as u64;
+        writer.write_all(&len.to_le_bytes())?;
+        for leaf in self.leaves() {
+            writer.write_all(&leaf.to_bytes())?;
+        }
+
+        for node in self.export_nodes() {
+            writer.write_all(&node.to_bytes())?;
+        }
+
+        writer.flush()
+    }
+
+    /// Read a tree from the file at `path`, previously written with
+    /// [`PoseidonTree::persist`].
+    ///
+    /// The cached hashes of the tree are restored from the persisted internal
+    /// nodes, without hashing the leaves. Only the root is recomputed, from
+    /// the restored nodes of the top of the tree, to check the integrity of
+    /// the file.
+    ///
+    /// An error of kind [`io::ErrorKind::InvalidData`] is returned if the file
+    /// wasn't written for a tree of the same depth and leaf type, or if the
+    /// root of the restored tree doesn't match the persisted one.
+    pub fn restore<P, const N: usize>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        L: Serializable<N>,
+    {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let len = read_header::<_, DEPTH, N>(&mut reader)?;
+        let leaves = (0..len)
+            .map(|_| {
+                L::from_bytes(&read_array(&mut reader)?)
+                    .map_err(|_| invalid_data("invalid leaf"))
+            })
+            .collect::<io::Result<Vec<L>>>()?;
+
+        let mut tree = Self::new();
+        if len == 0 {
+            return Ok(tree);
+        }
+
+        let nodes = nodes::level_lens(len, DEPTH).sum::<u64>();
+        let mut roots = (0..nodes)
+            .map(|_| {
+                BlsScalar::from_bytes(&read_array(&mut reader)?)
+                    .map_err(|_| invalid_data("invalid node"))
+            })
+            .collect::<io::Result<Vec<BlsScalar>>>()?;
+        let root = roots.pop().ok_or_else(|| invalid_data("invalid root"))?;
+
+        // The annotations below the top of the stack are cached, and restored
+        // from the levels holding more than a single node
+        let cached = nodes::level_lens(len, DEPTH)
+            .take_while(|&len| len > 1)
+            .sum::<u64>();
+        roots.truncate(cached as usize);
+
+        tree.inner = restore_stack(leaves, roots);
+        if tree.root() != root {
+            return Err(invalid_data("mismatching root"));
+        }
+
+        Ok(tree)
+    }
+}
+
+/// Build the stack holding `leaves`, with the cached annotations of its nodes
+/// taken from `roots`, level by level from the bottom up.
+pub(super) fn restore_stack<L, K, A>(
+    leaves: Vec<L>,
+    roots: Vec<BlsScalar>,
+) -> NStack<L, PoseidonAnnotation<K, A>>
+where
+    L: PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    let mut leaves = (0..)
+        .zip(leaves)
+        .map(|(pos, mut leaf)| {
+            leaf.set_pos(pos);
+            leaf
+        })
+        .peekable();
+    let mut stacks = Vec::new();
+    while leaves.peek().is_some() {
+        stacks.push(NStack::Leaf(array::from_fn(|_| leaves.next())));
+    }
+
+    // The annotations are computed as the nodes are created, in the order
+    // the roots are persisted
+    with_restored_roots(roots, || {
+        while stacks.len() > 1 {
+            let mut children = stacks
+                .into_iter()
+                .map(|stack| {
+                    let child = Annotated::new(stack);
+                    let _ = child.anno();
+                    child
+                })
+                .peekable();
+
+            stacks = Vec::new();
+            while children.peek().is_some() {
+                stacks.push(NStack::Node(array::from_fn(|_| children.next())));
+            }
+        }
+    });
+
+    stacks.pop().unwrap_or_default()
+}
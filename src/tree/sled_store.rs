@@ -0,0 +1,154 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::persist::invalid_data;
+use super::stored::TreeStore;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use std::path::Path;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::{DeserializableSlice, Serializable};
+
+/// Key of the number of leaves of the tree.
+const LEN_KEY: &[u8] = b"len";
+
+/// [`TreeStore`] backed by a [`sled::Tree`], storing leaves `L` of `N` bytes.
+///
+/// The writes of a mutation are held in memory until it is committed. They
+/// are then applied to the sled tree in a single atomic batch, along with the
+/// new number of leaves, and flushed to disk. A crash can't leave the tree
+/// with a root that doesn't match its leaves: reopening it yields the last
+/// committed tree.
+#[derive(Debug, Clone)]
+pub struct SledStore<L, const N: usize> {
+    tree: sled::Tree,
+    pending: BTreeMap<Vec<u8>, Vec<u8>>,
+    _leaf: PhantomData<L>,
+}
+
+impl<L, const N: usize> SledStore<L, N> {
+    /// Open the sled database at `path`, storing the tree in its default
+    /// tree.
+    pub fn open<P: AsRef<Path>>(path: P) -> sled::Result<Self> {
+        let db = sled::open(path)?;
+
+        Ok(Self::from_tree((*db).clone()))
+    }
+
+    /// Store the tree in the given sled tree, which shouldn't be used for
+    /// anything else.
+    pub fn from_tree(tree: sled::Tree) -> Self {
+        Self {
+            tree,
+            pending: BTreeMap::new(),
+            _leaf: PhantomData,
+        }
+    }
+
+    /// Return the sled tree of the store.
+    pub fn tree(&self) -> &sled::Tree {
+        &self.tree
+    }
+
+    fn get(&self, key: &[u8]) -> sled::Result<Option<Vec<u8>>> {
+        match self.pending.get(key) {
+            Some(value) => Ok(Some(value.clone())),
+            None => Ok(self.tree.get(key)?.map(|value| value.to_vec())),
+        }
+    }
+}
+
+fn leaf_key(pos: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(9);
+    key.push(b'l');
+    key.extend_from_slice(&pos.to_be_bytes());
+    key
+}
+
+fn node_key(height: usize, index: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(13);
+    key.push(b'n');
+    key.extend_from_slice(&(height as u32).to_be_bytes());
+    key.extend_from_slice(&index.to_be_bytes());
+    key
+}
+
+impl<L, const N: usize> TreeStore for SledStore<L, N>
+where
+    L: Serializable<N>,
+{
+    type Leaf = L;
+    type Error = sled::Error;
+
+    fn get_leaf(&self, pos: u64) -> sled::Result<Option<L>> {
+        self.get(&leaf_key(pos))?
+            .map(|bytes| {
+                L::from_slice(&bytes).map_err(|_| {
+                    sled::Error::Io(invalid_data("invalid leaf bytes"))
+                })
+            })
+            .transpose()
+    }
+
+    fn put_leaf(&mut self, pos: u64, leaf: L) -> sled::Result<()> {
+        self.pending.insert(leaf_key(pos), leaf.to_bytes().to_vec());
+        Ok(())
+    }
+
+    fn get_node(
+        &self,
+        height: usize,
+        index: u64,
+    ) -> sled::Result<Option<BlsScalar>> {
+        self.get(&node_key(height, index))?
+            .map(|bytes| {
+                BlsScalar::from_slice(&bytes).map_err(|_| {
+                    sled::Error::Io(invalid_data("invalid node bytes"))
+                })
+            })
+            .transpose()
+    }
+
+    fn put_node(
+        &mut self,
+        height: usize,
+        index: u64,
+        node: BlsScalar,
+    ) -> sled::Result<()> {
+        self.pending
+            .insert(node_key(height, index), node.to_bytes().to_vec());
+        Ok(())
+    }
+
+    fn committed_len(&self) -> sled::Result<u64> {
+        match self.tree.get(LEN_KEY)? {
+            Some(bytes) => {
+                let bytes = <[u8; 8]>::try_from(&*bytes).map_err(|_| {
+                    sled::Error::Io(invalid_data("invalid tree length"))
+                })?;
+                Ok(u64::from_be_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn commit(&mut self, len: u64) -> sled::Result<()> {
+        let mut batch = sled::Batch::default();
+        for (key, value) in &self.pending {
+            batch.insert(key.as_slice(), value.as_slice());
+        }
+        batch.insert(LEN_KEY, &len.to_be_bytes()[..]);
+
+        self.tree.apply_batch(batch)?;
+        self.tree.flush()?;
+        self.pending.clear();
+
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+/// Statistics on the storage of a [`PoseidonTree`](super::PoseidonTree), as
+/// returned by [`PoseidonTree::stats`](super::PoseidonTree::stats).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TreeStats {
+    /// Number of leaves of the tree, tombstones included.
+    pub leaves: u64,
+    /// Number of internal nodes of the tree, up to the root.
+    pub nodes: u64,
+    /// Number of internal nodes whose hash is cached by the tree, the nodes
+    /// above the first level with a single node being hashed on demand.
+    pub cached_hashes: u64,
+    /// Estimate of the memory used by the leaves and the nodes of the tree,
+    /// in bytes.
+    pub memory: usize,
+}
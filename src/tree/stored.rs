@@ -0,0 +1,468 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Trees storing their leaves and the hashes of their nodes in a pluggable
+//! [`TreeStore`].
+//!
+//! A [`PoseidonTree`] keeps its leaves and the hashes of its nodes in the
+//! nodes of an [`NStackStore`] by default, owned in memory. A tree whose
+//! storage `S` is a [`TreeStore`] reads and writes them through the store
+//! instead, one node at a time, so they can live in a database. The roots and
+//! the branches of both trees are the same for the same leaves.
+//!
+//! The nodes are addressed by their height and their index in the level: the
+//! hashes of the leaves are on height `0`, and the root alone on height
+//! `DEPTH`. A store may drop any node hash, which the tree recomputes from
+//! the children of the node when it needs it, down to the leaves.
+
+use super::arity::{assert_arity_width, hash_arity_node};
+use super::leaf::leaf_hash;
+use super::{
+    ArityBranch, PoseidonAnnotation, PoseidonBranch, PoseidonLeaf, PoseidonTree,
+};
+use crate::permutation::Permutation;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::marker::PhantomData;
+
+use bls12_381::Scalar as BlsScalar;
+use nstack::NStack;
+
+/// Storage of a [`PoseidonTree`] in memory, the nodes of the stack caching
+/// their hashes in their annotations.
+///
+/// This is the default storage of a tree, whose arity is the one of the
+/// stack, `4`.
+pub type NStackStore<L, K, A = ()> = NStack<L, PoseidonAnnotation<K, A>>;
+
+/// Storage of the leaves and of the hashes of the nodes of a [`PoseidonTree`].
+///
+/// The writes of a mutation of the tree are followed by a call to
+/// [`TreeStore::commit`], with the new number of leaves of the tree.
+pub trait TreeStore {
+    /// Leaves of the tree.
+    type Leaf;
+
+    /// Error returned by the store.
+    type Error;
+
+    /// Return the leaf on position `pos`, if stored.
+    fn get_leaf(&self, pos: u64) -> Result<Option<Self::Leaf>, Self::Error>;
+
+    /// Store `leaf` on position `pos`.
+    fn put_leaf(
+        &mut self,
+        pos: u64,
+        leaf: Self::Leaf,
+    ) -> Result<(), Self::Error>;
+
+    /// Return the hash of the node on the given `height` and `index`, if
+    /// stored.
+    fn get_node(
+        &self,
+        height: usize,
+        index: u64,
+    ) -> Result<Option<BlsScalar>, Self::Error>;
+
+    /// Store the hash of the node on the given `height` and `index`.
+    fn put_node(
+        &mut self,
+        height: usize,
+        index: u64,
+        node: BlsScalar,
+    ) -> Result<(), Self::Error>;
+
+    /// Return the number of leaves of the tree, as last committed.
+    fn committed_len(&self) -> Result<u64, Self::Error>;
+
+    /// Commit the writes made since the last commit, along with the new
+    /// number of leaves of the tree.
+    fn commit(&mut self, len: u64) -> Result<(), Self::Error>;
+}
+
+/// A [`TreeStore`] keeping the leaves and the nodes in memory.
+///
+/// A store created with [`MemoryStore::with_node_budget`] holds a bounded
+/// number of node hashes, dropping the ones of the lowest levels first, and
+/// backs the trees created with [`PoseidonTree::with_node_budget`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryStore<L> {
+    leaves: BTreeMap<u64, L>,
+    nodes: BTreeMap<(usize, u64), BlsScalar>,
+    node_budget: Option<usize>,
+    len: u64,
+}
+
+impl<L> Default for MemoryStore<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L> MemoryStore<L> {
+    /// Create a new, empty, store.
+    pub const fn new() -> Self {
+        Self {
+            leaves: BTreeMap::new(),
+            nodes: BTreeMap::new(),
+            node_budget: None,
+            len: 0,
+        }
+    }
+
+    /// Create a new, empty, store holding at most `budget` node hashes.
+    ///
+    /// Storing a hash past the budget drops the hash of the leftmost node of
+    /// the lowest level, which is the cheapest to recompute. The root, stored
+    /// last, is kept as long as the budget isn't zero.
+    pub const fn with_node_budget(budget: usize) -> Self {
+        Self {
+            leaves: BTreeMap::new(),
+            nodes: BTreeMap::new(),
+            node_budget: Some(budget),
+            len: 0,
+        }
+    }
+
+    /// Return the number of node hashes held by the store.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+impl<L> TreeStore for MemoryStore<L>
+where
+    L: Clone,
+{
+    type Leaf = L;
+    type Error = Infallible;
+
+    fn get_leaf(&self, pos: u64) -> Result<Option<L>, Infallible> {
+        Ok(self.leaves.get(&pos).cloned())
+    }
+
+    fn put_leaf(&mut self, pos: u64, leaf: L) -> Result<(), Infallible> {
+        self.leaves.insert(pos, leaf);
+        Ok(())
+    }
+
+    fn get_node(
+        &self,
+        height: usize,
+        index: u64,
+    ) -> Result<Option<BlsScalar>, Infallible> {
+        Ok(self.nodes.get(&(height, index)).copied())
+    }
+
+    fn put_node(
+        &mut self,
+        height: usize,
+        index: u64,
+        node: BlsScalar,
+    ) -> Result<(), Infallible> {
+        self.nodes.insert((height, index), node);
+
+        if let Some(budget) = self.node_budget {
+            while self.nodes.len() > budget {
+                self.nodes.pop_first();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn committed_len(&self) -> Result<u64, Infallible> {
+        Ok(self.len)
+    }
+
+    fn commit(&mut self, len: u64) -> Result<(), Infallible> {
+        self.len = len;
+        Ok(())
+    }
+}
+
+impl<L, K, const DEPTH: usize, A, S, const ARITY: usize, P> Clone
+    for PoseidonTree<L, K, DEPTH, A, S, ARITY, P>
+where
+    S: TreeStore<Leaf = L> + Clone,
+    P: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            perm: self.perm.clone(),
+            hooks: Vec::new(),
+            history: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<L, K, const DEPTH: usize, A, S, const ARITY: usize, P>
+    PoseidonTree<L, K, DEPTH, A, S, ARITY, P>
+where
+    L: PoseidonLeaf + Clone,
+    S: TreeStore<Leaf = L>,
+    P: Permutation<Scalar = BlsScalar>,
+{
+    /// Open the tree held by `store`, with the number of leaves it last
+    /// committed.
+    pub fn open(store: S) -> Self
+    where
+        P: Default,
+    {
+        Self::open_with(store, P::default())
+    }
+
+    /// Open the tree held by `store`, with the number of leaves it last
+    /// committed, hashing its nodes with the permutation `perm`.
+    ///
+    /// # Panics
+    /// If the width of `perm` isn't one more than the arity of the tree.
+    pub fn open_with(store: S, perm: P) -> Self {
+        assert_arity_width(perm.width(), ARITY);
+
+        Self {
+            inner: store,
+            perm,
+            hooks: Vec::new(),
+            history: None,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Return the store of the tree.
+    pub const fn store(&self) -> &S {
+        &self.inner
+    }
+
+    /// Consume the tree, returning its store.
+    pub fn into_store(self) -> S {
+        self.inner
+    }
+
+    /// Return the number of leaves of the tree.
+    pub fn len(&self) -> Result<u64, S::Error> {
+        self.inner.committed_len()
+    }
+
+    /// Return `true` if the tree has no leaves.
+    pub fn is_empty(&self) -> Result<bool, S::Error> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Append a leaf to the tree. Return the index of the appended leaf.
+    ///
+    /// # Panics
+    /// If the tree is full.
+    pub fn push(&mut self, mut leaf: L) -> Result<u64, S::Error> {
+        let pos = self.len()?;
+
+        let capacity = (ARITY as u64).checked_pow(DEPTH as u32);
+        assert!(capacity.map_or(true, |c| pos < c), "the tree is full");
+
+        leaf.set_pos(pos);
+        self.write(pos, leaf, pos + 1)?;
+
+        Ok(pos)
+    }
+
+    /// Return the leaf on the given position, if any.
+    pub fn get(&self, pos: u64) -> Result<Option<L>, S::Error> {
+        match pos < self.len()? {
+            true => self.inner.get_leaf(pos),
+            false => Ok(None),
+        }
+    }
+
+    /// Replace the leaf on the given position, returning the previous one.
+    pub fn update(
+        &mut self,
+        pos: u64,
+        mut leaf: L,
+    ) -> Result<Option<L>, S::Error> {
+        let len = self.len()?;
+        if pos >= len {
+            return Ok(None);
+        }
+
+        let previous = self.inner.get_leaf(pos)?;
+
+        leaf.set_pos(pos);
+        self.write(pos, leaf, len)?;
+
+        Ok(previous)
+    }
+
+    /// Return the root of the tree.
+    pub fn root(&self) -> Result<BlsScalar, S::Error> {
+        match self.len()? {
+            0 => Ok(PoseidonBranch::<DEPTH>::NULL_ROOT),
+            len => self.node(len, DEPTH, 0),
+        }
+    }
+
+    /// Return the opening of the leaf on the given position, if any.
+    pub fn opening(
+        &self,
+        pos: u64,
+    ) -> Result<Option<ArityBranch<ARITY, DEPTH>>, S::Error> {
+        let tree_len = self.len()?;
+        if pos >= tree_len {
+            return Ok(None);
+        }
+
+        let mut siblings = [[BlsScalar::zero(); ARITY]; DEPTH];
+        let mut offsets = [0; DEPTH];
+        let mut lens = [0; DEPTH];
+
+        let levels = siblings.iter_mut().zip(offsets.iter_mut().zip(&mut lens));
+        let mut index = pos;
+        let mut level_len = tree_len;
+        for (height, (nodes, (offset, len))) in levels.enumerate() {
+            let start = index - index % ARITY as u64;
+
+            *offset = (index - start) as usize;
+            *len = (level_len - start).min(ARITY as u64) as usize;
+            for (i, node) in nodes[..*len].iter_mut().enumerate() {
+                *node = self.node(tree_len, height, start + i as u64)?;
+            }
+
+            index /= ARITY as u64;
+            level_len = (level_len + ARITY as u64 - 1) / ARITY as u64;
+        }
+
+        let leaf = self.node(tree_len, 0, pos)?;
+        let opening = ArityBranch::from_path_with(
+            &self.perm, leaf, &siblings, &offsets, &lens,
+        )
+        .expect("The path of a leaf of the tree is valid");
+
+        Ok(Some(opening))
+    }
+
+    /// Store `leaf` on position `pos` of the tree of `len` leaves and rehash
+    /// the nodes of its path, then commit the writes.
+    fn write(&mut self, pos: u64, leaf: L, len: u64) -> Result<(), S::Error> {
+        let mut node = leaf_hash(&leaf);
+        self.inner.put_leaf(pos, leaf)?;
+        self.inner.put_node(0, pos, node)?;
+
+        let mut index = pos;
+        let mut level_len = len;
+        for height in 0..DEPTH {
+            let start = index - index % ARITY as u64;
+            let end = (start + ARITY as u64).min(level_len);
+
+            let mut children = [BlsScalar::zero(); ARITY];
+            for (child, i) in children.iter_mut().zip(start..end) {
+                *child = match i == index {
+                    true => node,
+                    false => self.node(len, height, i)?,
+                };
+            }
+
+            index /= ARITY as u64;
+            level_len = (level_len + ARITY as u64 - 1) / ARITY as u64;
+
+            let children = &children[..(end - start) as usize];
+            node = hash_arity_node(&self.perm, children);
+            self.inner.put_node(height + 1, index, node)?;
+        }
+
+        self.inner.commit(len)
+    }
+
+    /// Return the hash of the node on the given height and index of the tree
+    /// of `len` leaves, recomputing it from its children if the store doesn't
+    /// hold it.
+    fn node(
+        &self,
+        len: u64,
+        height: usize,
+        index: u64,
+    ) -> Result<BlsScalar, S::Error> {
+        if let Some(node) = self.inner.get_node(height, index)? {
+            return Ok(node);
+        }
+
+        if height == 0 {
+            let leaf = self.inner.get_leaf(index)?;
+            let leaf = leaf.expect("The store should hold the leaves");
+            return Ok(leaf_hash(&leaf));
+        }
+
+        let children_len = (1..height)
+            .fold(len, |len, _| (len + ARITY as u64 - 1) / ARITY as u64);
+        let start = index * ARITY as u64;
+        let end = (start + ARITY as u64).min(children_len);
+
+        let mut children = [BlsScalar::zero(); ARITY];
+        for (child, i) in children.iter_mut().zip(start..end) {
+            *child = self.node(len, height - 1, i)?;
+        }
+
+        Ok(hash_arity_node(
+            &self.perm,
+            &children[..(end - start) as usize],
+        ))
+    }
+}
+
+impl<L, K, const DEPTH: usize, A, const ARITY: usize, P>
+    PoseidonTree<L, K, DEPTH, A, MemoryStore<L>, ARITY, P>
+where
+    L: PoseidonLeaf + Clone,
+    P: Permutation<Scalar = BlsScalar> + Default,
+{
+    /// Create a new, empty, tree holding at most `budget` node hashes in
+    /// memory.
+    ///
+    /// The hashes dropped past the budget, as
+    /// [`MemoryStore::with_node_budget`] describes, are recomputed by the tree
+    /// from the leaves when it needs them, trading memory for hashing.
+    pub fn with_node_budget(budget: usize) -> Self {
+        Self::open(MemoryStore::with_node_budget(budget))
+    }
+
+    /// Return the number of node hashes held in memory by the tree.
+    pub fn node_count(&self) -> usize {
+        self.inner.node_count()
+    }
+}
+
+impl<L, K, const DEPTH: usize, A, S, P>
+    PoseidonTree<L, K, DEPTH, A, S, { hades::WIDTH - 1 }, P>
+where
+    L: PoseidonLeaf + Clone,
+    S: TreeStore<Leaf = L>,
+    P: Permutation<Scalar = BlsScalar>,
+{
+    /// Return the branch opening the leaf on the given position, if any.
+    pub fn branch(
+        &self,
+        pos: u64,
+    ) -> Result<Option<PoseidonBranch<DEPTH>>, S::Error> {
+        let opening = match self.opening(pos)? {
+            Some(opening) => opening,
+            None => return Ok(None),
+        };
+
+        let leaf = self.node(self.len()?, 0, pos)?;
+        let branch = PoseidonBranch::from_path_with(
+            &self.perm,
+            leaf,
+            opening.siblings(),
+            opening.offsets(),
+            opening.lens(),
+        )
+        .expect("The path of a leaf of the tree is valid");
+
+        Ok(Some(branch))
+    }
+}
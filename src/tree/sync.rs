@@ -0,0 +1,192 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::{
+    Aggregate, PoseidonBranch, PoseidonLeaf, PoseidonTree, ScalarLeaf,
+};
+
+use core::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use arc_swap::ArcSwap;
+use bls12_381::Scalar as BlsScalar;
+use nstack::annotation::Keyed;
+
+/// Poseidon tree shared between threads, serving concurrent reads.
+///
+/// The readers never lock: every read is served by an immutable snapshot of
+/// the tree, an [`Arc`] loaded atomically, so any number of threads may
+/// compute roots and openings in parallel with each other and with the
+/// writes. The writes are serialized on a mutex over the tree, and each
+/// publishes a new snapshot once it ends: a reader sees the tree either
+/// before or after a write, never in between.
+///
+/// Publishing a snapshot copies the tree. Batch the mutations under a single
+/// [`SyncPoseidonTree::write`] guard to publish them with a single copy.
+///
+/// The wrapper is [`Send`] and [`Sync`] whenever the tree is [`Send`] and
+/// [`Sync`], which requires the leaves and keys to be so. A panic while
+/// writing doesn't make the tree unusable: the next accesses go on with the
+/// tree as the panicking thread left it.
+#[derive(Debug)]
+pub struct SyncPoseidonTree<L, K, const DEPTH: usize, A = ()> {
+    tree: Mutex<PoseidonTree<L, K, DEPTH, A>>,
+    snapshot: ArcSwap<PoseidonTree<L, K, DEPTH, A>>,
+}
+
+// Fail to compile if the shared tree of the leaves provided by the crate
+// can't be shared between threads after all.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SyncPoseidonTree<ScalarLeaf, u64, 17>>();
+};
+
+impl<L, K, const DEPTH: usize, A> Default for SyncPoseidonTree<L, K, DEPTH, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<L, K, const DEPTH: usize, A> From<PoseidonTree<L, K, DEPTH, A>>
+    for SyncPoseidonTree<L, K, DEPTH, A>
+where
+    L: Clone + PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    fn from(tree: PoseidonTree<L, K, DEPTH, A>) -> Self {
+        Self {
+            snapshot: ArcSwap::from_pointee(tree.clone()),
+            tree: Mutex::new(tree),
+        }
+    }
+}
+
+impl<L, K, const DEPTH: usize, A> SyncPoseidonTree<L, K, DEPTH, A> {
+    /// Creates a new, empty, shared poseidon tree
+    pub fn new() -> Self {
+        Self {
+            tree: Mutex::new(PoseidonTree::new()),
+            snapshot: ArcSwap::from_pointee(PoseidonTree::new()),
+        }
+    }
+
+    /// Return the inner tree, consuming the wrapper.
+    pub fn into_inner(self) -> PoseidonTree<L, K, DEPTH, A> {
+        self.tree
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Return the last published snapshot of the tree, without locking.
+    ///
+    /// The snapshot doesn't see the writes that end after it is taken, nor
+    /// the callbacks registered on the tree.
+    pub fn snapshot(&self) -> Arc<PoseidonTree<L, K, DEPTH, A>> {
+        self.snapshot.load_full()
+    }
+}
+
+impl<L, K, const DEPTH: usize, A> SyncPoseidonTree<L, K, DEPTH, A>
+where
+    L: Clone + PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    /// Lock the tree for writing, blocking while another write is ongoing.
+    ///
+    /// The reads aren't blocked, and go on with the last published snapshot
+    /// until the guard is dropped, publishing the mutated tree.
+    pub fn write(&self) -> SyncWriteGuard<'_, L, K, DEPTH, A> {
+        SyncWriteGuard {
+            tree: self.tree.lock().unwrap_or_else(PoisonError::into_inner),
+            snapshot: &self.snapshot,
+            mutated: false,
+        }
+    }
+
+    /// Return the current root/state of the tree.
+    pub fn root(&self) -> BlsScalar {
+        self.snapshot.load().root()
+    }
+
+    /// Return a full merkle opening for a given index.
+    pub fn branch(&self, n: u64) -> Option<PoseidonBranch<DEPTH>> {
+        self.snapshot.load().branch(n)
+    }
+
+    /// Fetch a leaf on a provided index.
+    pub fn get(&self, n: u64) -> Option<L> {
+        self.snapshot.load().get(n)
+    }
+
+    /// Append a leaf to the tree. Return the index of the appended leaf.
+    pub fn push(&self, leaf: L) -> u64 {
+        self.write().push(leaf)
+    }
+
+    /// Fetch, remove and return the last inserted leaf, if present.
+    pub fn pop(&self) -> Option<L> {
+        self.write().pop()
+    }
+
+    /// Replace the leaf on a provided index, returning the previous one.
+    pub fn update(&self, n: u64, leaf: L) -> Option<L> {
+        self.write().update(n, leaf)
+    }
+}
+
+/// Exclusive access to a [`SyncPoseidonTree`] for writing, publishing a
+/// snapshot of the tree when dropped if it was mutably borrowed.
+pub struct SyncWriteGuard<'a, L, K, const DEPTH: usize, A>
+where
+    L: Clone + PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    tree: MutexGuard<'a, PoseidonTree<L, K, DEPTH, A>>,
+    snapshot: &'a ArcSwap<PoseidonTree<L, K, DEPTH, A>>,
+    mutated: bool,
+}
+
+impl<L, K, const DEPTH: usize, A> Deref for SyncWriteGuard<'_, L, K, DEPTH, A>
+where
+    L: Clone + PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    type Target = PoseidonTree<L, K, DEPTH, A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tree
+    }
+}
+
+impl<L, K, const DEPTH: usize, A> DerefMut
+    for SyncWriteGuard<'_, L, K, DEPTH, A>
+where
+    L: Clone + PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.mutated = true;
+        &mut self.tree
+    }
+}
+
+impl<L, K, const DEPTH: usize, A> Drop for SyncWriteGuard<'_, L, K, DEPTH, A>
+where
+    L: Clone + PoseidonLeaf + Keyed<K>,
+    K: Clone + PartialOrd,
+    A: Aggregate<L>,
+{
+    fn drop(&mut self) {
+        if self.mutated {
+            self.snapshot.store(Arc::new(self.tree.clone()));
+        }
+    }
+}
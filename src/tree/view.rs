@@ -0,0 +1,176 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::leaf::leaf_hash;
+use super::nodes::{self, ARITY};
+use super::persist::{self, HEADER_SIZE};
+use super::{PoseidonBranch, PoseidonLeaf};
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::{DeserializableSlice, Serializable};
+use memmap2::Mmap;
+
+/// Read-only view over a tree persisted with
+/// [`PoseidonTree::persist`](super::PoseidonTree::persist).
+///
+/// The file is memory-mapped instead of being loaded, and the persisted hashes
+/// of the internal nodes are used to serve the root and the openings, so
+/// opening a view is cheap regardless of the size of the tree.
+///
+/// The file must not be modified while the view is open. Reads of a modified
+/// file may return inconsistent data.
+#[derive(Debug)]
+pub struct PoseidonTreeView<L, const DEPTH: usize> {
+    mmap: Mmap,
+    len: u64,
+    leaf_size: usize,
+    levels: Vec<(u64, u64)>,
+    _leaf: PhantomData<L>,
+}
+
+impl<L, const DEPTH: usize> PoseidonTreeView<L, DEPTH> {
+    /// Memory-map the tree persisted in the file at `path`.
+    ///
+    /// An error of kind [`io::ErrorKind::InvalidData`] is returned if the file
+    /// wasn't written for a tree of the same depth and leaf type, or if its
+    /// size doesn't match the number of leaves.
+    pub fn open<P, const N: usize>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+        L: Serializable<N>,
+    {
+        let file = File::open(path)?;
+
+        // SAFETY: the map is only ever read from, and the documentation of the
+        // type forbids modifying the file while it is mapped.
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let mut header = mmap.get(..HEADER_SIZE).ok_or_else(|| {
+            persist::invalid_data("not a persisted poseidon tree")
+        })?;
+        let len = persist::read_header::<_, DEPTH, N>(&mut header)?;
+
+        // Store the start and length of every level of nodes
+        let mut start = 0;
+        let levels: Vec<(u64, u64)> = nodes::level_lens(len, DEPTH)
+            .map(|level_len| {
+                let level = (start, level_len);
+                start += level_len;
+                level
+            })
+            .collect();
+
+        let size = HEADER_SIZE as u64
+            + len * N as u64
+            + start * BlsScalar::SIZE as u64;
+        if mmap.len() as u64 != size {
+            return Err(persist::invalid_data("mismatching file size"));
+        }
+
+        Ok(Self {
+            mmap,
+            len,
+            leaf_size: N,
+            levels,
+            _leaf: PhantomData,
+        })
+    }
+
+    /// Return the number of leaves in the tree.
+    pub const fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Return `true` if the tree has no leaves.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the root of the tree.
+    pub fn root(&self) -> BlsScalar {
+        if self.is_empty() {
+            return PoseidonBranch::<DEPTH>::NULL_ROOT;
+        }
+
+        self.node(self.levels[DEPTH - 1].0).unwrap_or_default()
+    }
+
+    /// Read the leaf on a provided index.
+    ///
+    /// Return `None` if there is no leaf on the index, or if its bytes are
+    /// invalid.
+    pub fn get<const N: usize>(&self, n: u64) -> Option<L>
+    where
+        L: Serializable<N>,
+    {
+        if n >= self.len {
+            return None;
+        }
+
+        let offset = HEADER_SIZE + n as usize * self.leaf_size;
+        let bytes = self.mmap.get(offset..offset + self.leaf_size)?;
+
+        L::from_slice(bytes).ok()
+    }
+
+    /// Return a full merkle opening for the tree for a given index.
+    ///
+    /// Return `None` if there is no leaf on the index, or if the bytes of the
+    /// opening are invalid.
+    pub fn branch<const N: usize>(
+        &self,
+        n: u64,
+    ) -> Option<PoseidonBranch<DEPTH>>
+    where
+        L: PoseidonLeaf + Serializable<N>,
+    {
+        if n >= self.len {
+            return None;
+        }
+
+        let mut branch = PoseidonBranch::<DEPTH>::default();
+        let mut children_len = self.len;
+        let mut child = n;
+
+        for (depth, level) in branch.path.iter_mut().enumerate() {
+            let group = child / ARITY as u64 * ARITY as u64;
+            let count = (children_len - group).min(ARITY as u64) as usize;
+
+            for (i, scalar) in level.level[1..=count].iter_mut().enumerate() {
+                let pos = group + i as u64;
+                *scalar = match depth {
+                    0 => leaf_hash(&self.get::<N>(pos)?),
+                    _ => self.node(self.levels[depth - 1].0 + pos)?,
+                };
+            }
+
+            level.level[0] = BlsScalar::from((1u64 << count) - 1);
+            level.index = child - group + 1;
+
+            children_len = self.levels[depth].1;
+            child /= ARITY as u64;
+        }
+
+        branch.root = self.node(self.levels[DEPTH - 1].0)?;
+
+        Some(branch)
+    }
+
+    fn node(&self, index: u64) -> Option<BlsScalar> {
+        let offset = HEADER_SIZE
+            + self.len as usize * self.leaf_size
+            + index as usize * BlsScalar::SIZE;
+        let bytes = self.mmap.get(offset..offset + BlsScalar::SIZE)?;
+
+        BlsScalar::from_slice(bytes).ok()
+    }
+}
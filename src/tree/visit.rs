@@ -0,0 +1,111 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use super::nodes::ARITY;
+use super::PoseidonAnnotation;
+
+use core::borrow::Borrow;
+use core::ops::ControlFlow;
+
+use nstack::annotation::Cardinality;
+use nstack::NStack;
+
+/// Node of a tree visited by [`PoseidonTree::walk`](super::PoseidonTree::walk).
+#[derive(Debug)]
+pub enum Node<'a, L, K, A = ()> {
+    /// A leaf of the tree.
+    Leaf(&'a L),
+    /// An internal node of the tree, with its annotation.
+    Internal {
+        /// Level of the node, from `0` for the parents of the leaves.
+        level: usize,
+        /// Index of the node in its level.
+        index: u64,
+        /// Annotation of the node, aggregating the leaves below it.
+        annotation: &'a PoseidonAnnotation<K, A>,
+    },
+}
+
+/// Decision of a visitor after visiting a node of a tree.
+///
+/// This extends [`ControlFlow`] with the pruning of the subtree below an
+/// internal node, deciding on its annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit<B = ()> {
+    /// Go on with the walk, visiting the children of an internal node.
+    Continue,
+    /// Go on with the walk, skipping the children of an internal node.
+    Prune,
+    /// Stop the walk, returning the given value.
+    Break(B),
+}
+
+/// Visit the node of `stack`, of the given `level`, and its descendants in
+/// order. The first leaf below the node is on index `start`.
+pub(crate) fn visit<L, K, A, B, F>(
+    stack: &NStack<L, PoseidonAnnotation<K, A>>,
+    annotation: &PoseidonAnnotation<K, A>,
+    level: usize,
+    start: u64,
+    visitor: &mut F,
+) -> ControlFlow<B>
+where
+    F: FnMut(Node<'_, L, K, A>) -> Visit<B>,
+{
+    let index = (ARITY as u64)
+        .checked_pow(level as u32 + 1)
+        .map_or(0, |width| start / width);
+
+    let node = Node::Internal {
+        level,
+        index,
+        annotation,
+    };
+    match visitor(node) {
+        Visit::Continue => {}
+        Visit::Prune => return ControlFlow::Continue(()),
+        Visit::Break(b) => return ControlFlow::Break(b),
+    }
+
+    match stack {
+        NStack::Leaf(leaves) => {
+            for leaf in leaves.iter().flatten() {
+                if let Visit::Break(b) = visitor(Node::Leaf(leaf)) {
+                    return ControlFlow::Break(b);
+                }
+            }
+        }
+        NStack::Node(node) => {
+            let mut start = start;
+
+            for annotated in node.iter().flatten() {
+                let anno = annotated.anno();
+                let anno = &*anno;
+
+                visit(annotated.child(), anno, level - 1, start, visitor)?;
+
+                let cardinality: &Cardinality = anno.borrow();
+                start += **cardinality;
+            }
+        }
+    }
+
+    ControlFlow::Continue(())
+}
+
+/// Return the level of the node at the top of `stack`.
+pub(crate) fn level<L, K, A>(
+    stack: &NStack<L, PoseidonAnnotation<K, A>>,
+) -> usize {
+    match stack {
+        NStack::Leaf(_) => 0,
+        NStack::Node(node) => node
+            .iter()
+            .flatten()
+            .next()
+            .map_or(0, |annotated| level(annotated.child()) + 1),
+    }
+}
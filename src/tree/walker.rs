@@ -0,0 +1,130 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use core::borrow::Borrow;
+use core::marker::PhantomData;
+use core::ops::{Bound, RangeBounds};
+
+use microkelvin::{Child, Compound, Step, Walk, Walker};
+use nstack::annotation::{Keyed, MaxKey};
+use ranno::Annotation;
+
+use super::{Aggregate, PoseidonAnnotation};
+
+/// Walker over the leaves whose key is contained in a range.
+///
+/// Subtrees whose maximum key is below the start of the range are skipped
+/// entirely, using the `MaxKey` annotation of their nodes.
+#[derive(Debug, Clone)]
+pub struct KeyRange<K, R> {
+    range: R,
+    _key: PhantomData<K>,
+}
+
+impl<K, R> KeyRange<K, R>
+where
+    K: PartialOrd,
+    R: RangeBounds<K>,
+{
+    /// Create a new walker over the leaves with keys in `range`.
+    pub const fn new(range: R) -> Self {
+        Self {
+            range,
+            _key: PhantomData,
+        }
+    }
+
+    fn reaches(&self, max_key: &MaxKey<K>) -> bool {
+        match max_key {
+            MaxKey::NegativeInfinity => false,
+            MaxKey::Maximum(key) => match self.range.start_bound() {
+                Bound::Included(start) => key >= start,
+                Bound::Excluded(start) => key > start,
+                Bound::Unbounded => true,
+            },
+        }
+    }
+}
+
+impl<C, A, K, R> Walker<C, A> for KeyRange<K, R>
+where
+    C: Compound<A>,
+    C::Leaf: Keyed<K>,
+    A: Annotation<C> + Borrow<MaxKey<K>>,
+    K: PartialOrd,
+    R: RangeBounds<K>,
+{
+    fn walk(&mut self, walk: Walk<C, A>) -> Step {
+        for i in 0.. {
+            match walk.child(i) {
+                Child::Leaf(l) => {
+                    if self.range.contains(l.key()) {
+                        return Step::Found(i);
+                    }
+                }
+                Child::Node(n) => {
+                    let anno = n.anno();
+                    let max_key: &MaxKey<K> = (*anno).borrow();
+
+                    if self.reaches(max_key) {
+                        return Step::Into(i);
+                    }
+                }
+                Child::Empty => (),
+                Child::EndOfNode => return Step::Advance,
+            }
+        }
+        unreachable!()
+    }
+}
+
+/// Walker over the leaves whose [`Aggregate`] satisfies a filter.
+///
+/// Subtrees whose aggregate doesn't satisfy the filter are skipped entirely,
+/// so the filter must hold for the aggregate of a subtree whenever it holds
+/// for the aggregate of one of its leaves.
+#[derive(Debug, Clone)]
+pub struct AggregateFilter<F> {
+    filter: F,
+}
+
+impl<F> AggregateFilter<F> {
+    /// Create a new walker over the leaves whose aggregate satisfies
+    /// `filter`.
+    pub const fn new(filter: F) -> Self {
+        Self { filter }
+    }
+}
+
+impl<C, K, A, F> Walker<C, PoseidonAnnotation<K, A>> for AggregateFilter<F>
+where
+    C: Compound<PoseidonAnnotation<K, A>>,
+    PoseidonAnnotation<K, A>: Annotation<C>,
+    A: Aggregate<C::Leaf>,
+    F: FnMut(&A) -> bool,
+{
+    fn walk(&mut self, walk: Walk<C, PoseidonAnnotation<K, A>>) -> Step {
+        for i in 0.. {
+            match walk.child(i) {
+                Child::Leaf(l) => {
+                    if (self.filter)(&A::from_leaf(l)) {
+                        return Step::Found(i);
+                    }
+                }
+                Child::Node(n) => {
+                    let anno = n.anno();
+
+                    if (self.filter)(anno.aggregate()) {
+                        return Step::Into(i);
+                    }
+                }
+                Child::Empty => (),
+                Child::EndOfNode => return Step::Advance,
+            }
+        }
+        unreachable!()
+    }
+}
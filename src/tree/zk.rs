@@ -4,12 +4,26 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use super::PoseidonBranch;
+use super::arity::assert_arity_width;
+use super::nodes::{assert_tree_width, ARITY};
+use super::{
+    ArityBranch, DynPoseidonBranch, ForestOpening, PoseidonBranch,
+    PoseidonLevel, PoseidonMultiProof,
+};
+use crate::permutation::{Hades, PermutationGadget};
+use crate::sponge;
 use hades::GadgetStrategy;
 
+use alloc::vec;
+use alloc::vec::Vec;
+
 use plonk::prelude::*;
 
 /// Perform a merkle opening for a given branch and return the calculated root
+///
+/// The branch is one of a tree of arity `4`, as [`PoseidonBranch`] is. The
+/// openings of trees of any other arity are proven with
+/// [`arity_merkle_opening`].
 pub fn merkle_opening<C, const DEPTH: usize>(
     composer: &mut C,
     branch: &PoseidonBranch<DEPTH>,
@@ -18,58 +32,648 @@ pub fn merkle_opening<C, const DEPTH: usize>(
 where
     C: Composer,
 {
-    // Generate a permutation container
-    let mut container = [C::ZERO; hades::WIDTH];
+    opening(composer, branch.as_ref(), leaf)
+}
 
-    // Recalculate the root for the given branch
-    (0..DEPTH).fold(leaf, |root, depth| {
-        let level = branch.as_ref()[depth];
+/// Perform a merkle opening for a given branch of a tree hashed with the
+/// permutation `perm` and return the calculated root
+///
+/// This mirrors [`PoseidonBranch::verify_with`] as [`merkle_opening`] mirrors
+/// [`PoseidonBranch::verify`].
+///
+/// # Panics
+/// If the width of `perm` isn't one more than the arity of the tree.
+pub fn merkle_opening_with<C, P, const DEPTH: usize>(
+    composer: &mut C,
+    perm: &P,
+    branch: &PoseidonBranch<DEPTH>,
+    leaf: Witness,
+) -> Witness
+where
+    C: Composer,
+    P: PermutationGadget,
+{
+    assert_tree_width(perm.width());
 
-        // Create the bits representation of the offset as witness
-        // and make sure that offset points to a hash in the level
-        let offset_flag = level.offset_flag();
+    opening_with(composer, perm, branch.as_ref(), leaf, |_, _| {})
+}
+
+/// Perform a merkle opening for a given branch of a tree of any arity, hashed
+/// with the permutation `perm`, and return the calculated root
+///
+/// This mirrors [`ArityBranch::verify_with`] as [`merkle_opening_with`]
+/// mirrors [`PoseidonBranch::verify_with`].
+///
+/// # Panics
+/// If the width of `perm` isn't one more than the arity of the tree.
+pub fn arity_merkle_opening<C, P, const ARITY: usize, const DEPTH: usize>(
+    composer: &mut C,
+    perm: &P,
+    branch: &ArityBranch<ARITY, DEPTH>,
+    leaf: Witness,
+) -> Witness
+where
+    C: Composer,
+    P: PermutationGadget,
+{
+    assert_arity_width(perm.width(), ARITY);
+
+    let mut node = leaf;
+    let levels = branch.siblings.iter().zip(branch.offsets.iter());
+    for ((level, offset), len) in levels.zip(branch.lens.iter()) {
+        let flag = BlsScalar::from((1u64 << len) - 1);
+
+        let mut container = vec![C::ZERO; ARITY + 1];
+        container[0] = composer.append_witness(flag);
+
+        // Select the offset of the node with a single set bit, and check that
+        // the node is found there
         let mut sum = C::ZERO;
-        let mut offset_bits = [C::ZERO; hades::WIDTH - 1];
-        offset_bits.iter_mut().fold(1, |mask, bit| {
-            let bit_bls = BlsScalar::from((offset_flag & mask).min(1));
-            *bit = composer.append_witness(bit_bls);
+        for (i, sibling) in level.iter().enumerate() {
+            let bit = BlsScalar::from((i == *offset) as u64);
+            let bit = composer.append_witness(bit);
+            composer.component_boolean(bit);
 
-            let constraint = Constraint::new().left(1).a(sum).right(1).b(*bit);
+            let constraint = Constraint::new().left(1).a(sum).right(1).b(bit);
             sum = composer.gate_add(constraint);
 
-            mask << 1
-        });
+            let child = composer.append_witness(*sibling);
+            let constraint = Constraint::new().mult(1).a(bit).b(child);
+            let expected = composer.gate_mul(constraint);
+            let constraint = Constraint::new().mult(1).a(bit).b(node);
+            let calculated = composer.gate_mul(constraint);
+            composer.assert_equal(expected, calculated);
+
+            container[i + 1] = child;
+        }
         composer.assert_equal_constant(sum, BlsScalar::one(), None);
 
-        // Check that the root of the previous level is the same value as what
-        // is stored in the level at the offset
-        for i in 0..hades::WIDTH {
-            // Load child hashes of the current level into the permutation
-            // container
-            container[i] = composer.append_witness(level.as_ref()[i]);
-            if i > 0 {
-                let bit = offset_bits[i - 1];
-
-                // `expected` and `calculated` will be zero everywhere except
-                // at the level offset
-                let constraint =
-                    Constraint::new().mult(1).a(bit).b(container[i]);
-                let expected = composer.gate_mul(constraint);
-                let constraint = Constraint::new().mult(1).a(bit).b(root);
-                let calculated = composer.gate_mul(constraint);
-
-                // Make sure that the hash at the offset of the current level
-                // matches the hash calculated in the previous level
-                composer.assert_equal(expected, calculated);
+        perm.permute_gadget(composer, &mut container);
+        node = container[1];
+    }
+
+    node
+}
+
+/// Perform a merkle opening for a given branch of a leaf given by its preimage
+/// and return the hash of the leaf along with the calculated root
+///
+/// The leaf is the [`sponge::gadget`] hash of the `preimage`, as the hash of a
+/// [`HashedLeaf`](super::HashedLeaf) is the [`sponge::hash`] of the encoding
+/// of its payload.
+pub fn merkle_opening_preimage<C, const DEPTH: usize>(
+    composer: &mut C,
+    branch: &PoseidonBranch<DEPTH>,
+    preimage: &[Witness],
+) -> (Witness, Witness)
+where
+    C: Composer,
+{
+    let leaf = sponge::gadget(composer, preimage);
+    let root = merkle_opening(composer, branch, leaf);
+
+    (leaf, root)
+}
+
+/// Perform a merkle opening for each of the given branches of the same tree
+/// and return the calculated roots, in the order of the branches
+///
+/// The top level of a branch, holding the children of the root, is the same
+/// for every branch of a tree, so it's loaded and hashed once for all the
+/// openings, which only check that the node calculated from their leaf is
+/// found at their offset of it. The other levels are opened as with
+/// [`merkle_opening`], without sharing the hashes of the levels common to
+/// many paths, since it would make the circuit depend on the positions of
+/// the leaves: when the positions are constants of the circuit,
+/// [`merkle_multi_opening`] hashes them once. The shape of the circuit then
+/// only depends on the number of branches, and the circuit may be compiled
+/// with any branches of the same number.
+///
+/// # Panics
+/// If the number of leaves doesn't match the number of branches, or if the
+/// branches don't share the same root.
+pub fn merkle_openings<C, const DEPTH: usize>(
+    composer: &mut C,
+    branches: &[PoseidonBranch<DEPTH>],
+    leaves: &[Witness],
+) -> Vec<Witness>
+where
+    C: Composer,
+{
+    assert_eq!(
+        branches.len(),
+        leaves.len(),
+        "every branch should open a leaf"
+    );
+    assert!(
+        branches.windows(2).all(|w| w[0].root == w[1].root),
+        "the branches should open the same tree"
+    );
+
+    let top = match branches.first().and_then(|b| b.as_ref().last()) {
+        Some(top) => top,
+        None => return leaves.to_vec(),
+    };
+
+    // The top level is shared by all the openings
+    let mut container = [C::ZERO; hades::WIDTH];
+    for (c, node) in container.iter_mut().zip(top.as_ref()) {
+        *c = composer.append_witness(*node);
+    }
+
+    let mut perm = container;
+    GadgetStrategy::gadget(composer, &mut perm);
+    let root = perm[1];
+
+    branches
+        .iter()
+        .zip(leaves.iter())
+        .map(|(branch, leaf)| {
+            let (top, path) = branch
+                .as_ref()
+                .split_last()
+                .expect("the branch should have a top level");
+
+            let node = opening(composer, path, *leaf);
+            let offset_bits = offset_bits(composer, top);
+            assert_at_offset(composer, &container, &offset_bits, node);
+
+            root
+        })
+        .collect()
+}
+
+/// Perform a merkle opening for a given branch and return the calculated root
+/// along with the position of the opened leaf
+///
+/// The position is composed from the bits selecting the offset of every
+/// level of the branch, so it's authenticated by the opening without any
+/// other witness. It's only meaningful for the branches of trees whose
+/// capacity fits in the scalar field.
+pub fn merkle_opening_with_pos<C, const DEPTH: usize>(
+    composer: &mut C,
+    branch: &PoseidonBranch<DEPTH>,
+    leaf: Witness,
+) -> (Witness, Witness)
+where
+    C: Composer,
+{
+    let mut position = C::ZERO;
+    let mut weight = BlsScalar::one();
+
+    let path = branch.as_ref();
+    let root = opening_with(composer, &Hades, path, leaf, |composer, bits| {
+        // The offset of the level is the sum of the offsets of its nodes,
+        // weighted by their selecting bit
+        for (offset, bit) in bits.iter().enumerate().skip(1) {
+            let coefficient = weight * BlsScalar::from(offset as u64);
+            let constraint = Constraint::new()
+                .left(coefficient)
+                .a(*bit)
+                .right(1)
+                .b(position);
+            position = composer.gate_add(constraint);
+        }
+        weight *= BlsScalar::from(ARITY as u64);
+    });
+
+    (root, position)
+}
+
+/// Perform a merkle opening for a given branch, registering the root of the
+/// branch as a public input, and return the witness of the root
+///
+/// The calculated root is constrained to be equal to the public input, so the
+/// verifier must provide the root of the tree the leaf is opened against, in
+/// the order the public inputs are appended by the circuit.
+pub fn merkle_opening_public<C, const DEPTH: usize>(
+    composer: &mut C,
+    branch: &PoseidonBranch<DEPTH>,
+    leaf: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    let root = composer.append_public(*branch.root());
+    let calculated = merkle_opening(composer, branch, leaf);
+
+    composer.assert_equal(calculated, root);
+
+    root
+}
+
+/// Perform a merkle opening for a given branch of a tree whose depth is only
+/// known at runtime and return the calculated root
+///
+/// The shape of the circuit depends on the depth of the branch, so the circuit
+/// must be compiled with a branch of the same depth.
+///
+/// The branch may also open the root of a subtree, as returned by
+/// [`PoseidonTree::subtree_branch`](super::PoseidonTree::subtree_branch), in
+/// which case `leaf` is the root of the subtree.
+pub fn dyn_merkle_opening<C>(
+    composer: &mut C,
+    branch: &DynPoseidonBranch,
+    leaf: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    opening(composer, branch.as_ref(), leaf)
+}
+
+/// Perform a merkle opening for a given opening of a forest and return the
+/// calculated root of the forest
+///
+/// The index of the tree holding the leaf is kept private, and only the
+/// number of trees of the forest is a constant of the circuit.
+pub fn forest_opening<C, const DEPTH: usize, const N: usize>(
+    composer: &mut C,
+    opening: &ForestOpening<DEPTH, N>,
+    leaf: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    let root = merkle_opening(composer, &opening.branch, leaf);
+
+    // Replace the root of the tree at the index with the calculated one,
+    // selected by a single set bit
+    let mut sum = C::ZERO;
+    let mut roots = Vec::with_capacity(N);
+    for (i, tree_root) in opening.roots.iter().enumerate() {
+        let bit = BlsScalar::from((i == opening.index) as u64);
+        let bit = composer.append_witness(bit);
+        composer.component_boolean(bit);
+
+        let constraint = Constraint::new().left(1).a(sum).right(1).b(bit);
+        sum = composer.gate_add(constraint);
+
+        let tree_root = composer.append_witness(*tree_root);
+        roots.push(composer.component_select(bit, root, tree_root));
+    }
+    composer.assert_equal_constant(sum, BlsScalar::one(), None);
+
+    sponge::gadget(composer, &roots)
+}
+
+/// Perform a merkle opening of many leaves at once for a given multiproof and
+/// return the calculated root
+///
+/// The `leaves` are given in the order of the positions of the proof. Every
+/// level shared by the paths of many leaves is only hashed once, so the
+/// circuit is smaller than the one opening every leaf separately. The
+/// positions of the leaves are constants of the circuit, so the circuit must
+/// be compiled with a proof of the same positions.
+///
+/// # Panics
+///
+/// If the proof is malformed, in the same way
+/// [`PoseidonMultiProof::verify`] rejects it: the number of `leaves` must
+/// match the number of positions, the positions must be sorted and
+/// deduplicated, and the levels must be exactly the ones of their paths,
+/// down to a single root at position zero.
+pub fn merkle_multi_opening<C, const DEPTH: usize>(
+    composer: &mut C,
+    proof: &PoseidonMultiProof<DEPTH>,
+    leaves: &[Witness],
+) -> Witness
+where
+    C: Composer,
+{
+    assert_eq!(
+        leaves.len(),
+        proof.positions.len(),
+        "there should be a leaf for every position"
+    );
+    assert!(
+        proof.positions.windows(2).all(|w| w[0] < w[1]),
+        "the positions should be sorted and deduplicated"
+    );
+    assert_eq!(
+        proof.levels.len(),
+        DEPTH,
+        "the proof should have the levels of every depth"
+    );
+
+    let mut container = [C::ZERO; hades::WIDTH];
+
+    let mut nodes: Vec<(u64, Witness)> = proof
+        .positions
+        .iter()
+        .copied()
+        .zip(leaves.iter().copied())
+        .collect();
+
+    for levels in proof.levels.iter() {
+        let mut levels = levels.iter();
+        let mut parents: Vec<(u64, Witness)> = Vec::new();
+
+        for (pos, node) in nodes {
+            let group = pos / ARITY as u64;
+            let offset = (pos % ARITY as u64) as usize;
+
+            // Every group of siblings is hashed once, when its first node is
+            // reached
+            if parents.last().map(|(g, _)| *g) != Some(group) {
+                let level = levels
+                    .next()
+                    .expect("every group of siblings should have a level");
+                for (c, l) in container.iter_mut().zip(level.iter()) {
+                    *c = composer.append_witness(*l);
+                }
+
+                let mut perm = container;
+                GadgetStrategy::gadget(composer, &mut perm);
+                parents.push((group, perm[1]));
             }
+
+            // The hash calculated in the previous level must be found at the
+            // offset of the node in the current level
+            composer.assert_equal(container[offset + 1], node);
+        }
+
+        assert!(
+            levels.next().is_none(),
+            "every level should be on the path of a leaf"
+        );
+        nodes = parents;
+    }
+
+    match nodes[..] {
+        [(0, root)] => root,
+        _ => panic!("the proof should end in a single root"),
+    }
+}
+
+/// Perform a merkle opening for the branch of a leaf appended to a tree and
+/// return the roots of the tree before and after the append
+///
+/// The branch is the one of the leaf in the tree after the append, and the
+/// leaf must be the last one of the tree: the nodes of every level of the
+/// branch after its offset are constrained to be absent, both unflagged and
+/// zero. The root before the append is calculated from the same levels, with
+/// the leaf removed along with every subtree it was the only leaf of, and is
+/// zero if the leaf was the first one of the tree.
+pub fn merkle_insert<C, const DEPTH: usize>(
+    composer: &mut C,
+    branch: &PoseidonBranch<DEPTH>,
+    leaf: Witness,
+) -> (Witness, Witness)
+where
+    C: Composer,
+{
+    // The node at the offset of a level, in the trees after and before the
+    // append, and whether the node was empty before the append
+    let mut node = leaf;
+    let mut old_node = C::ZERO;
+    let mut empty = composer.append_constant(BlsScalar::one());
+
+    for level in branch.as_ref() {
+        let offset_bits = offset_bits(composer, level);
+        let mut container = load_level(composer, level, &offset_bits, node);
+
+        // The nodes up to the offset are present, and none after it, so the
+        // flags of the level are `2^(offset + 1) - 1`, while the node at the
+        // offset is flagged with `2^offset`
+        let mut flags = C::ZERO;
+        let mut offset_flag = C::ZERO;
+        for (i, bit) in offset_bits.iter().enumerate() {
+            let constraint = Constraint::new()
+                .left(BlsScalar::from((2 << i) - 1))
+                .a(*bit)
+                .right(1)
+                .b(flags);
+            flags = composer.gate_add(constraint);
+
+            let constraint = Constraint::new()
+                .left(BlsScalar::from(1 << i))
+                .a(*bit)
+                .right(1)
+                .b(offset_flag);
+            offset_flag = composer.gate_add(constraint);
+        }
+        composer.assert_equal(container[0], flags);
+
+        // The nodes after the offset are zero, as they are in the tree, so
+        // they can't hold any value the flags don't account for
+        let mut past_offset = C::ZERO;
+        for (bit, node) in offset_bits.iter().zip(container[2..].iter()) {
+            let constraint =
+                Constraint::new().left(1).a(past_offset).right(1).b(*bit);
+            past_offset = composer.gate_add(constraint);
+
+            let constraint = Constraint::new().mult(1).a(past_offset).b(*node);
+            let absent = composer.gate_mul(constraint);
+            composer.assert_equal_constant(absent, BlsScalar::zero(), None);
+        }
+
+        // Before the append, the node at the offset is unflagged and zero if
+        // it was empty
+        let mut old_container = container;
+
+        let constraint = Constraint::new().mult(1).a(empty).b(offset_flag);
+        let unflagged = composer.gate_mul(constraint);
+        let constraint = Constraint::new()
+            .left(1)
+            .a(container[0])
+            .right(-BlsScalar::one())
+            .b(unflagged);
+        old_container[0] = composer.gate_add(constraint);
+
+        let old_child = clear(composer, empty, old_node);
+
+        for (i, bit) in offset_bits.iter().enumerate() {
+            old_container[i + 1] =
+                composer.component_select(*bit, old_child, container[i + 1]);
+        }
+
+        // The node of the next level was empty if it only holds the leaf
+        let constraint = Constraint::new().mult(1).a(empty).b(offset_bits[0]);
+        empty = composer.gate_mul(constraint);
+
+        GadgetStrategy::gadget(composer, &mut container);
+        GadgetStrategy::gadget(composer, &mut old_container);
+
+        node = container[1];
+        old_node = old_container[1];
+    }
+
+    // The root of an empty tree is zero
+    let old_root = clear(composer, empty, old_node);
+
+    (old_root, node)
+}
+
+/// Perform a merkle opening for a given branch under two values of its leaf
+/// and return the roots of the tree before and after the leaf is updated
+///
+/// The branch is the one of `old_leaf`, and `new_leaf` replaces it on the same
+/// position. The bits of the offsets and the siblings of the path are shared
+/// by both openings, so only the hashes of the levels are calculated twice.
+pub fn merkle_update<C, const DEPTH: usize>(
+    composer: &mut C,
+    branch: &PoseidonBranch<DEPTH>,
+    old_leaf: Witness,
+    new_leaf: Witness,
+) -> (Witness, Witness)
+where
+    C: Composer,
+{
+    let mut old_node = old_leaf;
+    let mut new_node = new_leaf;
+
+    for level in branch.as_ref() {
+        let offset_bits = offset_bits(composer, level);
+        let mut container = load_level(composer, level, &offset_bits, old_node);
+
+        // Replace the node at the offset with the updated one
+        let mut new_container = container;
+        for (i, bit) in offset_bits.iter().enumerate() {
+            new_container[i + 1] =
+                composer.component_select(*bit, new_node, container[i + 1]);
         }
 
-        // Calculate the root for the next level
         GadgetStrategy::gadget(composer, &mut container);
+        GadgetStrategy::gadget(composer, &mut new_container);
+
+        old_node = container[1];
+        new_node = new_container[1];
+    }
+
+    (old_node, new_node)
+}
+
+/// Return zero if `bit` is set, and `value` otherwise.
+fn clear<C>(composer: &mut C, bit: Witness, value: Witness) -> Witness
+where
+    C: Composer,
+{
+    let constraint = Constraint::new().mult(1).a(bit).b(value);
+    let cleared = composer.gate_mul(constraint);
+
+    let constraint = Constraint::new()
+        .left(1)
+        .a(value)
+        .right(-BlsScalar::one())
+        .b(cleared);
+    composer.gate_add(constraint)
+}
+
+fn opening<C>(
+    composer: &mut C,
+    path: &[PoseidonLevel],
+    leaf: Witness,
+) -> Witness
+where
+    C: Composer,
+{
+    opening_with(composer, &Hades, path, leaf, |_, _| {})
+}
+
+/// Perform a merkle opening for a given path, hashing the levels with `perm`
+/// and passing the bits of the offset of every level to `offsets`, from the
+/// level of the leaf up to the root.
+fn opening_with<C, P, F>(
+    composer: &mut C,
+    perm: &P,
+    path: &[PoseidonLevel],
+    leaf: Witness,
+    mut offsets: F,
+) -> Witness
+where
+    C: Composer,
+    P: PermutationGadget,
+    F: FnMut(&mut C, &[Witness; hades::WIDTH - 1]),
+{
+    // Recalculate the root for the given branch
+    path.iter().fold(leaf, |root, level| {
+        let offset_bits = offset_bits(composer, level);
+        offsets(composer, &offset_bits);
+
+        let mut container = load_level(composer, level, &offset_bits, root);
+
+        // Calculate the root for the next level
+        perm.permute_gadget(composer, &mut container);
         container[1]
     })
 }
 
+/// Create the bits representation of the offset of `level` as witnesses.
+fn offset_bits<C>(
+    composer: &mut C,
+    level: &PoseidonLevel,
+) -> [Witness; hades::WIDTH - 1]
+where
+    C: Composer,
+{
+    // Make sure that offset points to a hash in the level. Each bit must be
+    // boolean, otherwise a combination of the hashes of the level could be
+    // selected as long as the bits sum to one
+    let offset_flag = level.offset_flag();
+    let mut sum = C::ZERO;
+    let mut offset_bits = [C::ZERO; hades::WIDTH - 1];
+    offset_bits.iter_mut().fold(1, |mask, bit| {
+        let bit_bls = BlsScalar::from((offset_flag & mask).min(1));
+        *bit = composer.append_witness(bit_bls);
+        composer.component_boolean(*bit);
+
+        let constraint = Constraint::new().left(1).a(sum).right(1).b(*bit);
+        sum = composer.gate_add(constraint);
+
+        mask << 1
+    });
+    composer.assert_equal_constant(sum, BlsScalar::one(), None);
+
+    offset_bits
+}
+
+/// Load the hashes of `level` into a permutation container, checking that
+/// `node` is stored in the level at the offset selected by `offset_bits`.
+fn load_level<C>(
+    composer: &mut C,
+    level: &PoseidonLevel,
+    offset_bits: &[Witness; hades::WIDTH - 1],
+    node: Witness,
+) -> [Witness; hades::WIDTH]
+where
+    C: Composer,
+{
+    let mut container = [C::ZERO; hades::WIDTH];
+
+    // Load child hashes of the current level into the permutation container
+    for (c, node) in container.iter_mut().zip(level.as_ref()) {
+        *c = composer.append_witness(*node);
+    }
+    assert_at_offset(composer, &container, offset_bits, node);
+
+    container
+}
+
+/// Assert that `node` is found in the permutation `container` of a level, at
+/// the offset selected by `offset_bits`.
+fn assert_at_offset<C>(
+    composer: &mut C,
+    container: &[Witness; hades::WIDTH],
+    offset_bits: &[Witness; hades::WIDTH - 1],
+    node: Witness,
+) where
+    C: Composer,
+{
+    for (child, bit) in container[1..].iter().zip(offset_bits.iter()) {
+        // `expected` and `calculated` will be zero everywhere except at the
+        // level offset
+        let constraint = Constraint::new().mult(1).a(*bit).b(*child);
+        let expected = composer.gate_mul(constraint);
+        let constraint = Constraint::new().mult(1).a(*bit).b(node);
+        let calculated = composer.gate_mul(constraint);
+
+        // Make sure that the hash at the offset of the current level matches
+        // the hash calculated in the previous level
+        composer.assert_equal(expected, calculated);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,78 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+use poseidon::accumulator::{Accumulator, Witness};
+
+const DEPTH: usize = 17;
+
+type Acc = Accumulator<[u64; 2], DEPTH>;
+
+#[test]
+fn accumulator_add_prove_verify() {
+    let mut acc = Acc::new();
+    assert!(acc.is_empty());
+    assert!(acc.prove(&[0, 0]).is_none());
+
+    let witness = acc.add(&[1, 2]);
+    assert_eq!(witness.root(), &acc.root());
+    assert!(Acc::verify(&acc.root(), &witness, &[1, 2]));
+    assert!(!Acc::verify(&acc.root(), &witness, &[2, 1]));
+
+    for i in 0..20 {
+        acc.add(&[i, i]);
+    }
+    assert_eq!(acc.len(), 21);
+    assert!(acc.contains(&[7, 7]));
+    assert!(!acc.contains(&[7, 8]));
+
+    // The outdated witness is no longer valid for the current root
+    assert!(!Acc::verify(&acc.root(), &witness, &[1, 2]));
+
+    let proof = acc.prove(&[1, 2]).expect("the element was added");
+    assert!(Acc::verify(&acc.root(), &proof, &[1, 2]));
+
+    // Adding an element twice doesn't change the accumulator
+    let root = acc.root();
+    let again = acc.add(&[1, 2]);
+    assert_eq!(acc.root(), root);
+    assert_eq!(acc.len(), 21);
+    assert!(Acc::verify(&root, &again, &[1, 2]));
+}
+
+#[test]
+fn accumulator_witness_updates() {
+    let mut acc = Acc::new();
+    let mut other = Acc::new();
+
+    let mut witnesses: Vec<Witness<DEPTH>> =
+        (0..5).map(|i| acc.add(&[i, 0])).collect();
+    let mut appended = acc.add(&[5, 0]);
+
+    for i in 6..40 {
+        acc.add(&[i, 0]);
+        assert_eq!(appended.append(&[i, 0]), acc.root());
+    }
+
+    // The witnesses are caught up in a batch
+    assert!(acc.update_all(&mut witnesses));
+    for (i, witness) in witnesses.iter().enumerate() {
+        assert_eq!(witness.len(), acc.len());
+        assert!(Acc::verify(&acc.root(), witness, &[i as u64, 0]));
+    }
+    assert!(Acc::verify(&acc.root(), &appended, &[5, 0]));
+
+    // A witness of another accumulator is left untouched
+    let mut foreign = other.add(&[1, 1]);
+    let root = *foreign.root();
+    assert!(acc.update(&mut foreign).is_none());
+    assert_eq!(foreign.root(), &root);
+
+    other.add(&[2, 2]);
+    assert_eq!(other.update(&mut foreign), Some(other.root()));
+    assert!(Acc::verify(&other.root(), &foreign, &[1, 1]));
+}
@@ -0,0 +1,103 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(all(feature = "alloc", feature = "arkworks"))]
+
+mod max_annotation;
+
+use ark_bls12_381::Fr;
+use ark_crypto_primitives::sponge::CryptographicSponge;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use bls12_381::Scalar as BlsScalar;
+use bytes::Serializable;
+use ff::Field;
+use max_annotation::MockLeaf;
+use poseidon::arkworks::{from_ark, to_ark};
+use poseidon::sponge::duplex::Duplex;
+use poseidon::tree::{PoseidonBranch, PoseidonTree};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const DEPTH: usize = 17;
+
+type Tree = PoseidonTree<MockLeaf, u64, DEPTH>;
+
+#[test]
+fn scalar_conversion() {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    for _ in 0..32 {
+        let a = BlsScalar::random(&mut rng);
+        let b = BlsScalar::random(&mut rng);
+
+        assert_eq!(a, from_ark(&to_ark(&a)));
+        assert_eq!(to_ark(&(a * b)), to_ark(&a) * to_ark(&b));
+        assert_eq!(to_ark(&(a + b)), to_ark(&a) + to_ark(&b));
+    }
+}
+
+#[test]
+fn branch_canonical_serialize() {
+    let mut tree = Tree::new();
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+    }
+    let branch = tree.branch(33).expect("The branch should exist");
+
+    let mut bytes = Vec::new();
+    branch
+        .serialize_compressed(&mut bytes)
+        .expect("Serialization should succeed");
+    assert_eq!(bytes.len(), branch.compressed_size());
+
+    let decoded = PoseidonBranch::<DEPTH>::deserialize_compressed(&bytes[..])
+        .expect("Deserialization should succeed");
+    assert_eq!(branch.to_bytes(), decoded.to_bytes());
+}
+
+#[test]
+fn cryptographic_sponge() {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let input: Vec<BlsScalar> =
+        (0..6).map(|_| BlsScalar::random(&mut rng)).collect();
+    let ark_input: Vec<Fr> = input.iter().map(to_ark).collect();
+
+    // Every input is framed by its number of elements and of bytes
+    let frame = BlsScalar::from_raw([6, 6 * 32, 0, 0]);
+    let mut native = Duplex::new(BlsScalar::zero());
+    native.absorb(&[frame]);
+    native.absorb(&input);
+
+    let mut sponge = <Duplex as CryptographicSponge>::new(&BlsScalar::zero());
+    CryptographicSponge::absorb(&mut sponge, &ark_input);
+
+    let expected = native.squeeze().to_bytes();
+    let bytes = sponge.squeeze_bytes(40);
+    assert_eq!(bytes.len(), 40);
+    assert_eq!(bytes[..31], expected[..31]);
+
+    let bits = sponge.squeeze_bits(300);
+    assert_eq!(bits.len(), 300);
+}
+
+#[test]
+fn cryptographic_sponge_framing() {
+    let squeeze = |inputs: &[&[u8]]| {
+        let mut sponge =
+            <Duplex as CryptographicSponge>::new(&BlsScalar::zero());
+        inputs
+            .iter()
+            .for_each(|input| CryptographicSponge::absorb(&mut sponge, input));
+        sponge.squeeze_bytes(31)
+    };
+
+    // Trailing zeros are packed into the same field elements
+    assert_ne!(squeeze(&[&[1]]), squeeze(&[&[1, 0]]));
+
+    // Successive inputs differ from their concatenation
+    assert_ne!(squeeze(&[&[1], &[2]]), squeeze(&[&[1, 2]]));
+    assert_eq!(squeeze(&[&[1], &[2]]), squeeze(&[&[1], &[2]]));
+}
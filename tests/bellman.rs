@@ -0,0 +1,107 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "bellman")]
+
+use bellman::gadgets::num::AllocatedNum;
+use bellman::gadgets::test::TestConstraintSystem;
+use bellman::ConstraintSystem;
+use bls12_381::Scalar as BlsScalar;
+use ff::Field;
+use poseidon::params::{Params, SecurityLevel};
+use poseidon::sponge;
+use poseidon::tree::{self, PoseidonBranch};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn bellman_sponge() {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let params = Params::with_security(3, SecurityLevel::Bits128)
+        .expect("the width is supported");
+
+    let messages: Vec<BlsScalar> =
+        (0..6).map(|_| BlsScalar::random(&mut rng)).collect();
+
+    for len in 0..=messages.len() {
+        let mut cs = TestConstraintSystem::<BlsScalar>::new();
+        let witnesses = messages[..len]
+            .iter()
+            .enumerate()
+            .map(|(i, m)| {
+                AllocatedNum::alloc(cs.namespace(|| format!("{i}")), || Ok(*m))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .expect("the messages should be allocated");
+
+        let hash = poseidon::bellman::sponge_gadget(
+            cs.namespace(|| "sponge"),
+            &params,
+            &witnesses,
+        )
+        .expect("the hash should be synthesized");
+
+        assert_eq!(
+            hash.get_value(),
+            Some(sponge::hash_with_params(&params, &messages[..len]))
+        );
+        assert!(cs.is_satisfied());
+    }
+}
+
+#[test]
+fn bellman_merkle_opening() {
+    let params = Params::with_security(hades::WIDTH, SecurityLevel::Bits128)
+        .expect("the width is supported");
+    let leaves: Vec<BlsScalar> = (0..16).map(BlsScalar::from).collect();
+    let root = tree::compute_root_with::<_, 2>(&params, &leaves)
+        .expect("The leaves should fit");
+
+    // Open the third leaf of the second group
+    let nodes: Vec<BlsScalar> = leaves
+        .chunks(4)
+        .map(|group| tree::compute_root_with::<_, 1>(&params, group))
+        .collect::<Option<_>>()
+        .expect("The groups should fit");
+    let mut siblings = [[BlsScalar::zero(); 4]; 2];
+    siblings[0].copy_from_slice(&leaves[4..8]);
+    siblings[1].copy_from_slice(&nodes);
+    let branch = PoseidonBranch::<2>::from_path_with(
+        &params,
+        leaves[6],
+        &siblings,
+        &[2, 1],
+        &[4, 4],
+    )
+    .expect("The path should be valid");
+
+    let mut cs = TestConstraintSystem::<BlsScalar>::new();
+    let leaf = AllocatedNum::alloc(cs.namespace(|| "leaf"), || Ok(leaves[6]))
+        .expect("the leaf should be allocated");
+    let computed = poseidon::bellman::merkle_opening(
+        cs.namespace(|| "opening"),
+        &params,
+        &branch,
+        &leaf,
+    )
+    .expect("the opening should be synthesized");
+
+    assert_eq!(computed.get_value(), Some(root));
+    assert!(cs.is_satisfied());
+
+    // The leaf must be found at the offset of the branch
+    let mut cs = TestConstraintSystem::<BlsScalar>::new();
+    let leaf = AllocatedNum::alloc(cs.namespace(|| "leaf"), || Ok(leaves[5]))
+        .expect("the leaf should be allocated");
+    poseidon::bellman::merkle_opening(
+        cs.namespace(|| "opening"),
+        &params,
+        &branch,
+        &leaf,
+    )
+    .expect("the opening should be synthesized");
+    assert!(!cs.is_satisfied());
+}
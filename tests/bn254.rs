@@ -0,0 +1,61 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "bn254")]
+
+use poseidon::bn254::{self, Bn254Scalar};
+use poseidon::params::SecurityLevel;
+use poseidon::sponge;
+
+#[test]
+fn bn254_circomlib_constants() {
+    let params = bn254::params(3, SecurityLevel::Bits128)
+        .expect("the width is supported");
+
+    // First round constant and MDS entry of circomlib for `t = 3`
+    let constant = Bn254Scalar::from_raw([
+        0x8d21d47304cd8e6e,
+        0x14c4993c11bb2993,
+        0xd05986d656f40c21,
+        0x0ee9a592ba9a9518,
+    ]);
+    let mds = Bn254Scalar::from_raw([
+        0xfedb68592ba8118b,
+        0x94be7c11ad24378b,
+        0xb2b70caf5c36a7b1,
+        0x109b7f411ba0e4c9,
+    ]);
+
+    assert_eq!(params.full_rounds(), 8);
+    assert_eq!(params.partial_rounds(), 57);
+    assert_eq!(params.round_constants()[0], constant);
+    assert_eq!(params.mds()[0], mds);
+}
+
+#[test]
+fn bn254_sponge() {
+    let params = bn254::params(3, SecurityLevel::Bits128)
+        .expect("the width is supported");
+    let messages: Vec<Bn254Scalar> =
+        (1..=3u64).map(Bn254Scalar::from).collect();
+
+    // Values computed with an independent implementation of the sponge
+    let expected = Bn254Scalar::from_raw([
+        0xdb70deb562b846a4,
+        0x2bafca166c0c1e08,
+        0x81979e823457ecbe,
+        0x251a0fe863cbd9ec,
+    ]);
+    assert_eq!(sponge::hash_with_params(&params, &messages[..2]), expected);
+
+    let expected = Bn254Scalar::from_raw([
+        0x84193fd67314f01b,
+        0xda388b5331775584,
+        0x82dd07777a46e5e5,
+        0x16e4701ace327846,
+    ]);
+    assert_eq!(sponge::hash_with_params(&params, &messages), expected);
+}
@@ -15,6 +15,7 @@ use jubjub::{
 };
 use plonk::error::Error as PlonkError;
 use poseidon::cipher::{self, PoseidonCipher};
+use poseidon::params::{Params, SecurityLevel};
 use rand::rngs::{OsRng, StdRng};
 use rand::{RngCore, SeedableRng};
 
@@ -62,7 +63,8 @@ fn sanity() {
 fn encrypt() {
     let (message, secret, nonce) = gen();
 
-    let cipher = PoseidonCipher::encrypt(&message, &secret, &nonce);
+    let cipher = PoseidonCipher::encrypt(&message, &secret, &nonce)
+        .expect("the message should fit");
     let decrypt = cipher
         .decrypt(&secret, &nonce)
         .expect("decryption should succeed");
@@ -75,7 +77,8 @@ fn single_bit() {
     let (_, secret, nonce) = gen();
     let message = BlsScalar::random(&mut OsRng);
 
-    let cipher = PoseidonCipher::encrypt(&[message], &secret, &nonce);
+    let cipher = PoseidonCipher::encrypt(&[message], &secret, &nonce)
+        .expect("the message should fit");
     let decrypt = cipher
         .decrypt(&secret, &nonce)
         .expect("decryption should succeed");
@@ -89,12 +92,8 @@ fn overflow() {
     let message =
         [BlsScalar::random(&mut OsRng); PoseidonCipher::capacity() + 1];
 
-    let cipher = PoseidonCipher::encrypt(&message, &secret, &nonce);
-    let decrypt = cipher
-        .decrypt(&secret, &nonce)
-        .expect("decryption should succeed");
-
-    assert_eq!(message[0..PoseidonCipher::capacity()], decrypt);
+    // A message longer than the capacity is rejected instead of truncated
+    assert!(PoseidonCipher::encrypt(&message, &secret, &nonce).is_none());
 }
 
 #[test]
@@ -102,7 +101,8 @@ fn wrong_key_fail() {
     let (message, secret, nonce) = gen();
     let (_, wrong_secret, _) = gen();
 
-    let cipher = PoseidonCipher::encrypt(&message, &secret, &nonce);
+    let cipher = PoseidonCipher::encrypt(&message, &secret, &nonce)
+        .expect("the message should fit");
     assert!(cipher.decrypt(&wrong_secret, &nonce).is_none());
 }
 
@@ -110,7 +110,8 @@ fn wrong_key_fail() {
 fn bytes() {
     let (message, secret, nonce) = gen();
 
-    let cipher = PoseidonCipher::encrypt(&message, &secret, &nonce);
+    let cipher = PoseidonCipher::encrypt(&message, &secret, &nonce)
+        .expect("the message should fit");
 
     let bytes = cipher.to_bytes();
     let restored_cipher = PoseidonCipher::from_bytes(&bytes).unwrap();
@@ -124,6 +125,37 @@ fn bytes() {
     assert_eq!(message, decrypt);
 }
 
+#[test]
+fn encrypt_with_params() {
+    let (message, secret, nonce) = gen();
+    let params = Params::with_security(hades::WIDTH, SecurityLevel::Bits128)
+        .expect("the width is supported");
+
+    let cipher =
+        PoseidonCipher::encrypt_with(&params, &message, &secret, &nonce)
+            .expect("the message should fit");
+    let decrypt = cipher
+        .decrypt_with(&params, &secret, &nonce)
+        .expect("decryption should succeed");
+    assert_eq!(message, decrypt);
+
+    // The cipher depends on the permutation
+    let hades = PoseidonCipher::encrypt(&message, &secret, &nonce)
+        .expect("the message should fit");
+    assert_ne!(cipher, hades);
+    assert!(cipher.decrypt(&secret, &nonce).is_none());
+}
+
+#[test]
+#[should_panic]
+fn encrypt_with_narrow_params() {
+    let (message, secret, nonce) = gen();
+    let params = Params::with_security(3, SecurityLevel::Bits128)
+        .expect("the width is supported");
+
+    let _ = PoseidonCipher::encrypt_with(&params, &message, &secret, &nonce);
+}
+
 #[derive(Debug)]
 pub struct TestCipherCircuit<'a> {
     secret: JubJubScalar,
@@ -188,7 +220,7 @@ impl<'a> Circuit for TestCipherCircuit<'a> {
             });
 
         let cipher_gadget =
-            cipher::encrypt(composer, &shared, nonce, &message_circuit);
+            cipher::encrypt_gadget(composer, &shared, nonce, &message_circuit);
 
         self.cipher
             .iter()
@@ -199,7 +231,7 @@ impl<'a> Circuit for TestCipherCircuit<'a> {
             });
 
         let message_gadget =
-            cipher::decrypt(composer, &shared, nonce, &cipher_gadget);
+            cipher::decrypt_gadget(composer, &shared, nonce, &cipher_gadget);
 
         self.message
             .iter()
@@ -232,7 +264,8 @@ fn gadget() -> Result<(), PlonkError> {
 
     // Perform the encryption
     let nonce = BlsScalar::random(&mut OsRng);
-    let cipher = PoseidonCipher::encrypt(&message, &shared_secret, &nonce);
+    let cipher = PoseidonCipher::encrypt(&message, &shared_secret, &nonce)
+        .expect("the message should fit");
 
     let label = b"poseidon-cipher";
     let size = 13;
@@ -254,3 +287,206 @@ fn gadget() -> Result<(), PlonkError> {
 
     verifier.verify(&proof, &public_inputs)
 }
+
+#[derive(Debug, Default)]
+pub struct TestShortMessageCircuit {
+    shared: JubJubAffine,
+    nonce: BlsScalar,
+    message: BlsScalar,
+    cipher: PoseidonCipher,
+}
+
+impl Circuit for TestShortMessageCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let shared = composer.append_point(self.shared);
+        let nonce = composer.append_witness(self.nonce);
+        let message = composer.append_witness(self.message);
+
+        let cipher =
+            cipher::encrypt_gadget(composer, &shared, nonce, &[message]);
+
+        // The cipher is the one of the native encryption, with the absent
+        // scalars of the message replaced by zero
+        for (c, g) in self.cipher.cipher().iter().zip(cipher.iter()) {
+            let c = composer.append_public(*c);
+            composer.assert_equal(c, *g);
+        }
+
+        let decrypted =
+            cipher::decrypt_gadget(composer, &shared, nonce, &cipher);
+        composer.assert_equal(decrypted[0], message);
+        composer.assert_equal_constant(decrypted[1], BlsScalar::zero(), None);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn gadget_short_message() -> Result<(), PlonkError> {
+    let (_, shared, nonce) = gen();
+    let message = BlsScalar::random(&mut OsRng);
+    let cipher = PoseidonCipher::encrypt(&[message], &shared, &nonce)
+        .expect("the message should fit");
+
+    let label = b"poseidon-cipher-short";
+    let pp = PublicParameters::setup(1 << 13, &mut OsRng)?;
+    let (prover, verifier) =
+        Compiler::compile::<TestShortMessageCircuit>(&pp, label)?;
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let circuit = TestShortMessageCircuit {
+        shared,
+        nonce,
+        message,
+        cipher,
+    };
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    assert_eq!(public_inputs, cipher.cipher());
+    verifier.verify(&proof, &public_inputs)?;
+
+    // The proof doesn't verify another cipher
+    let (_, other_shared, _) = gen();
+    let other = PoseidonCipher::encrypt(&[message], &other_shared, &nonce)
+        .expect("the message should fit");
+    assert!(verifier.verify(&proof, other.cipher()).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn tampering() {
+    let (message, secret, nonce) = gen();
+
+    let cipher = PoseidonCipher::encrypt(&message, &secret, &nonce)
+        .expect("the message should fit");
+    assert_eq!(cipher.try_decrypt(&secret, &nonce), Ok(message));
+
+    // Tampering with any scalar of the cipher, including the last one, is
+    // detected without any additional tag
+    for i in 0..PoseidonCipher::cipher_size() {
+        let mut tampered = *cipher.cipher();
+        tampered[i] += BlsScalar::one();
+        let tampered = PoseidonCipher::new(tampered);
+
+        assert!(tampered.decrypt(&secret, &nonce).is_none());
+        assert_eq!(
+            tampered.try_decrypt(&secret, &nonce),
+            Err(cipher::Error::DecryptionFailed)
+        );
+    }
+
+    let (_, wrong_secret, _) = gen();
+    assert_eq!(
+        cipher.try_decrypt(&wrong_secret, &nonce),
+        Err(cipher::Error::DecryptionFailed)
+    );
+}
+
+#[derive(Debug, Default)]
+pub struct TestDecryptCircuit {
+    shared: JubJubAffine,
+    nonce: BlsScalar,
+    message: [BlsScalar; PoseidonCipher::capacity()],
+    cipher: PoseidonCipher,
+}
+
+impl Circuit for TestDecryptCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let shared = composer.append_point(self.shared);
+        let nonce = composer.append_witness(self.nonce);
+
+        let cipher = self.cipher.cipher().map(|c| composer.append_public(c));
+
+        let decrypted =
+            cipher::decrypt_gadget(composer, &shared, nonce, &cipher);
+        for (m, d) in self.message.iter().zip(decrypted.iter()) {
+            let m = composer.append_witness(*m);
+            composer.assert_equal(m, *d);
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn gadget_tampering() -> Result<(), PlonkError> {
+    let (message, shared, nonce) = gen();
+    let cipher = PoseidonCipher::encrypt(&message, &shared, &nonce)
+        .expect("the message should fit");
+
+    let label = b"poseidon-cipher-tampering";
+    let pp = PublicParameters::setup(1 << 13, &mut OsRng)?;
+    let (prover, verifier) =
+        Compiler::compile::<TestDecryptCircuit>(&pp, label)?;
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let circuit = TestDecryptCircuit {
+        shared,
+        nonce,
+        message,
+        cipher,
+    };
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    assert_eq!(public_inputs, cipher.cipher());
+    verifier.verify(&proof, &public_inputs)?;
+
+    // The proof doesn't verify a tampered cipher
+    for i in 0..PoseidonCipher::cipher_size() {
+        let mut tampered = public_inputs.clone();
+        tampered[i] += BlsScalar::one();
+        assert!(verifier.verify(&proof, &tampered).is_err());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn encapsulation() {
+    let (_, secret, nonce) = gen();
+
+    for len in [0, 1, 4, 9] {
+        let message: Vec<BlsScalar> =
+            (0..len).map(|_| BlsScalar::random(&mut OsRng)).collect();
+
+        let encapsulated = cipher::encapsulate(&message, &secret, &nonce);
+        assert_eq!(encapsulated.len(), len + 1);
+
+        // The message is encrypted with the keystream of its length
+        let keystream = cipher::keystream(&secret, &nonce, len);
+        encapsulated
+            .iter()
+            .zip(message.iter().zip(keystream.iter()))
+            .for_each(|(c, (m, k))| assert_eq!(*c, m + k));
+
+        let decapsulated = cipher::decapsulate(&encapsulated, &secret, &nonce);
+        assert_eq!(decapsulated, Ok(message));
+
+        // Tampering with any scalar is detected
+        for i in 0..encapsulated.len() {
+            let mut tampered = encapsulated.clone();
+            tampered[i] += BlsScalar::one();
+            assert_eq!(
+                cipher::decapsulate(&tampered, &secret, &nonce),
+                Err(cipher::Error::DecryptionFailed)
+            );
+        }
+
+        let (_, wrong_secret, _) = gen();
+        assert!(
+            cipher::decapsulate(&encapsulated, &wrong_secret, &nonce).is_err()
+        );
+    }
+
+    assert_eq!(
+        cipher::decapsulate(&[], &secret, &nonce),
+        Err(cipher::Error::DecryptionFailed)
+    );
+}
@@ -0,0 +1,72 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "circom")]
+
+use poseidon::bn254::Bn254Scalar;
+use poseidon::circom::{self, CircomPoseidon, MAX_INPUTS};
+use poseidon::params::Error;
+
+fn inputs(n: u64) -> Vec<Bn254Scalar> {
+    (1..=n).map(Bn254Scalar::from).collect()
+}
+
+#[test]
+fn circomlib_vectors() {
+    // Test vectors of circomlib
+    let expected = Bn254Scalar::from_raw([
+        0x9e19607a4417189a,
+        0x2a3617f274324551,
+        0x3df64c6b9662e9cf,
+        0x115cc0f5e7d69041,
+    ]);
+    assert_eq!(circom::hash(&inputs(2)).unwrap(), expected);
+
+    let expected = Bn254Scalar::from_raw([
+        0x76913a93e1f30996,
+        0x36e37d4efb93efab,
+        0x84b806164531b0cd,
+        0x20a3af0435914ccd,
+    ]);
+    let messages = [Bn254Scalar::from(3u64), Bn254Scalar::from(4u64)];
+    assert_eq!(circom::hash(&messages).unwrap(), expected);
+
+    let expected = Bn254Scalar::from_raw([
+        0xbaa525df65250465,
+        0x37e60ebb1ce0663d,
+        0x9dcefa40e4510b98,
+        0x299c867db6c1fdd7,
+    ]);
+    assert_eq!(circom::hash(&inputs(4)).unwrap(), expected);
+}
+
+#[test]
+fn circom_inputs() {
+    // Values computed with an independent implementation of the permutation
+    let expected = Bn254Scalar::from_raw([
+        0x8b897dc502820133,
+        0x0e96a4d1168b3384,
+        0xc1fe6c654d6a3c13,
+        0x29176100eaa962bd,
+    ]);
+    assert_eq!(circom::hash(&inputs(1)).unwrap(), expected);
+
+    let poseidon = CircomPoseidon::new(MAX_INPUTS).unwrap();
+    let expected = Bn254Scalar::from_raw([
+        0xde2ffb96b919b765,
+        0xae08afd7f1f2ec06,
+        0x8281a48099fff949,
+        0x16159a551cbb6610,
+    ]);
+    assert_eq!(poseidon.inputs(), MAX_INPUTS);
+    assert_eq!(poseidon.hash(&inputs(MAX_INPUTS as u64)), expected);
+
+    assert!(matches!(circom::hash(&[]), Err(Error::InvalidWidth(1))));
+    assert!(matches!(
+        CircomPoseidon::new(MAX_INPUTS + 1),
+        Err(Error::InvalidWidth(18))
+    ));
+}
@@ -0,0 +1,91 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+use plonk::error::Error as PlonkError;
+use poseidon::{commitment, sponge};
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
+
+use plonk::prelude::*;
+
+const CAPACITY: usize = 11;
+
+#[derive(Default, Debug)]
+struct CommitmentCircuit {
+    values: [BlsScalar; 2],
+    blinder: BlsScalar,
+    commitment: BlsScalar,
+}
+
+impl Circuit for CommitmentCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let values = self.values.map(|v| composer.append_witness(v));
+        let blinder = composer.append_witness(self.blinder);
+
+        let computed = commitment::gadget(composer, &values, blinder);
+
+        let commitment = composer.append_public(self.commitment);
+        composer.assert_equal(commitment, computed);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn commitment() {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let values = [(); 2].map(|_| BlsScalar::random(&mut rng));
+    let blinder = BlsScalar::random(&mut rng);
+
+    let c = commitment::commit(&values, blinder);
+
+    // The commitment depends on the blinder, and is domain separated from the
+    // plain hash of the same scalars
+    let other = BlsScalar::random(&mut rng);
+    assert_ne!(c, commitment::commit(&values, other));
+    assert_ne!(c, sponge::hash(&[blinder, values[0], values[1]]));
+}
+
+#[test]
+fn commitment_gadget() -> Result<(), Error> {
+    let label = b"commitment-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<CommitmentCircuit>(&pp, label)?;
+
+    let values = [(); 2].map(|_| BlsScalar::random(&mut rng));
+    let blinder = BlsScalar::random(&mut rng);
+    let commitment = commitment::commit(&values, blinder);
+
+    let circuit = CommitmentCircuit {
+        values,
+        blinder,
+        commitment,
+    };
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    assert_eq!(public_inputs, [commitment]);
+    verifier.verify(&proof, &public_inputs)?;
+
+    // An opening with another blinder isn't accepted
+    let circuit = CommitmentCircuit {
+        values,
+        blinder: BlsScalar::random(&mut rng),
+        commitment,
+    };
+    assert!(prover.prove(&mut rng, &circuit).is_err());
+
+    Ok(())
+}
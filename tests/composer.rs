@@ -0,0 +1,72 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+mod max_annotation;
+
+use max_annotation::MockLeaf;
+use plonk::error::Error as PlonkError;
+use poseidon::composer::PoseidonComposer;
+use poseidon::sponge;
+use poseidon::tree::{PoseidonBranch, PoseidonTree};
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
+
+use plonk::prelude::*;
+
+const DEPTH: usize = 4;
+const CAPACITY: usize = 14;
+
+#[derive(Default)]
+struct ComposerCircuit {
+    messages: [BlsScalar; 3],
+    branch: PoseidonBranch<DEPTH>,
+}
+
+impl Circuit for ComposerCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let messages = self.messages.map(|m| composer.append_witness(m));
+        let hash = composer.poseidon_hash(&messages);
+        composer.assert_equal_constant(
+            hash,
+            sponge::hash(&self.messages),
+            None,
+        );
+
+        let leaf = composer.append_witness(*self.branch);
+        let root = composer.merkle_opening(&self.branch, leaf);
+        composer.assert_equal_constant(root, *self.branch.root(), None);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn composer_extension() -> Result<(), Error> {
+    let label = b"poseidon-composer-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let tree: PoseidonTree<MockLeaf, u64, DEPTH> =
+        (0..42).map(MockLeaf::from).collect();
+    let circuit = ComposerCircuit {
+        messages: [(); 3].map(|_| BlsScalar::random(&mut rng)),
+        branch: tree.branch(21).expect("The branch should exist"),
+    };
+
+    let (prover, verifier) =
+        Compiler::compile_with_circuit(&pp, label, &circuit)?;
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    verifier.verify(&proof, &public_inputs)?;
+
+    Ok(())
+}
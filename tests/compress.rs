@@ -0,0 +1,114 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+use bls12_381::Scalar as BlsScalar;
+use ff::Field;
+use plonk::error::Error as PlonkError;
+use poseidon::compress;
+use poseidon::permutation::Hades;
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
+
+use plonk::prelude::*;
+
+const CAPACITY: usize = 11;
+
+#[test]
+fn jive_domain_separation() {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let a = BlsScalar::random(&mut rng);
+    let b = BlsScalar::random(&mut rng);
+    let zero = BlsScalar::zero();
+
+    assert_eq!(compress::jive2(&[a, b]), compress::jive2(&[a, b]));
+    assert_ne!(compress::jive2(&[a, b]), compress::jive2(&[b, a]));
+    assert_ne!(
+        compress::jive2(&[a, b]),
+        compress::jive4(&[a, b, zero, zero])
+    );
+}
+
+#[test]
+fn jive_with_hades() {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let inputs: [BlsScalar; 4] =
+        core::array::from_fn(|_| BlsScalar::random(&mut rng));
+
+    assert_eq!(
+        compress::jive_with(&Hades, &inputs[..2]),
+        compress::jive2(&[inputs[0], inputs[1]])
+    );
+    assert_eq!(
+        compress::jive_with(&Hades, &inputs),
+        compress::jive4(&inputs)
+    );
+}
+
+#[derive(Debug, Default)]
+struct JiveCircuit {
+    inputs: [BlsScalar; 4],
+    jive2: BlsScalar,
+    jive4: BlsScalar,
+}
+
+impl JiveCircuit {
+    fn new(inputs: [BlsScalar; 4]) -> Self {
+        let jive2 = compress::jive2(&[inputs[0], inputs[1]]);
+        let jive4 = compress::jive4(&inputs);
+
+        Self {
+            inputs,
+            jive2,
+            jive4,
+        }
+    }
+}
+
+impl Circuit for JiveCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let mut inputs = [C::ZERO; 4];
+        inputs
+            .iter_mut()
+            .zip(self.inputs.iter())
+            .for_each(|(w, i)| *w = composer.append_witness(*i));
+
+        let jive2 = compress::jive2_gadget(composer, &[inputs[0], inputs[1]]);
+        let jive4 = compress::jive4_gadget(composer, &inputs);
+
+        let expected = composer.append_witness(self.jive2);
+        composer.assert_equal(jive2, expected);
+
+        let expected = composer.append_witness(self.jive4);
+        composer.assert_equal(jive4, expected);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn jive_gadget() -> Result<(), PlonkError> {
+    let label = b"jive-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) = Compiler::compile::<JiveCircuit>(&pp, label)?;
+
+    let mut inputs = [BlsScalar::zero(); 4];
+    inputs
+        .iter_mut()
+        .for_each(|i| *i = BlsScalar::random(&mut rng));
+    let circuit = JiveCircuit::new(inputs);
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    verifier.verify(&proof, &public_inputs)?;
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+mod max_annotation;
+
+use max_annotation::MockLeaf;
+use plonk::error::Error as PlonkError;
+use poseidon::constraint_count;
+use poseidon::params::{Params, SecurityLevel};
+use poseidon::sponge;
+use poseidon::tree::{self, PoseidonBranch, PoseidonTree};
+use rand::rngs::OsRng;
+
+use plonk::prelude::*;
+
+const DEPTH: usize = 4;
+const CAPACITY: usize = 16;
+
+/// Return the number of constraints appended to `composer` by `gadget`.
+fn count<C, F>(composer: &mut C, gadget: F) -> usize
+where
+    C: Composer,
+    F: FnOnce(&mut C),
+{
+    let before = composer.constraints();
+    gadget(composer);
+    composer.constraints() - before
+}
+
+#[derive(Default)]
+struct CountCircuit {
+    branch: PoseidonBranch<DEPTH>,
+}
+
+impl Circuit for CountCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let messages = [composer.append_witness(BlsScalar::one()); 9];
+        for len in 1..=messages.len() {
+            let counted = count(composer, |composer| {
+                sponge::gadget(composer, &messages[..len]);
+            });
+            assert_eq!(counted, constraint_count::sponge(len));
+        }
+
+        // The constants of a mixed message are absorbed with a constraint,
+        // even in the first chunk, but the ones absorbed into the zero state
+        // share it with their repetitions, including the padding
+        let mixed = [
+            sponge::Message::Constant(BlsScalar::from(7)),
+            sponge::Message::Witness(messages[0]),
+            sponge::Message::Constant(BlsScalar::from(7)),
+        ];
+        let counted = count(composer, |composer| {
+            sponge::gadget_mixed(composer, &mixed);
+        });
+        assert_eq!(counted, constraint_count::sponge(mixed.len()) + 1);
+
+        let mixed = [
+            sponge::Message::Constant(BlsScalar::from(7)),
+            sponge::Message::Witness(messages[0]),
+            sponge::Message::Constant(BlsScalar::one()),
+        ];
+        let counted = count(composer, |composer| {
+            sponge::gadget_mixed(composer, &mixed);
+        });
+        assert_eq!(counted, constraint_count::sponge(mixed.len()) + 1);
+
+        let params = Params::with_security(3, SecurityLevel::Bits128)
+            .expect("the width is supported");
+        let counted = count(composer, |composer| {
+            sponge::gadget_with_params(composer, &params, &messages[..3]);
+        });
+        assert_eq!(counted, constraint_count::sponge_with_params(&params, 3));
+
+        let branch = &self.branch;
+        let leaf = composer.append_witness(**branch);
+
+        let counted = count(composer, |composer| {
+            tree::merkle_opening(composer, branch, leaf);
+        });
+        assert_eq!(counted, constraint_count::merkle_opening(DEPTH));
+
+        let counted = count(composer, |composer| {
+            tree::merkle_opening_public(composer, branch, leaf);
+        });
+        assert_eq!(counted, constraint_count::merkle_opening_public(DEPTH));
+
+        let counted = count(composer, |composer| {
+            tree::merkle_opening_with_pos(composer, branch, leaf);
+        });
+        assert_eq!(counted, constraint_count::merkle_opening_with_pos(DEPTH));
+
+        let counted = count(composer, |composer| {
+            tree::merkle_opening_preimage(composer, branch, &messages[..3]);
+        });
+        assert_eq!(
+            counted,
+            constraint_count::merkle_opening_preimage(DEPTH, 3)
+        );
+
+        let counted = count(composer, |composer| {
+            tree::merkle_openings(composer, &[*branch; 2], &[leaf; 2]);
+        });
+        assert_eq!(counted, constraint_count::merkle_openings(DEPTH, 2));
+
+        // The openings share the permutation of the top level
+        let single = count(composer, |composer| {
+            tree::merkle_opening(composer, branch, leaf);
+        });
+        assert_eq!(counted, 2 * single - constraint_count::permutation());
+
+        Ok(())
+    }
+}
+
+#[test]
+fn constraint_count_gadgets() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+
+    let tree: PoseidonTree<MockLeaf, u64, DEPTH> =
+        (0..42).map(MockLeaf::from).collect();
+    let circuit = CountCircuit {
+        branch: tree.branch(21).expect("The branch should exist"),
+    };
+
+    // The counts are checked while the circuit is built
+    Compiler::compile_with_circuit(&pp, label, &circuit)
+        .expect("Circuit should compile successfully");
+}
+
+#[test]
+fn constraint_count_fits_capacity() {
+    // The opening of the tests of the tree fits their public parameters
+    assert!(constraint_count::merkle_opening(17) < 1 << 15);
+}
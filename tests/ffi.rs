@@ -0,0 +1,67 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(all(feature = "alloc", feature = "uniffi"))]
+
+mod max_annotation;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::Serializable;
+use max_annotation::MockLeaf;
+use poseidon::ffi::{self, FfiError};
+use poseidon::sponge;
+use poseidon::tree::PoseidonTree;
+
+const DEPTH: usize = 17;
+
+type Tree = PoseidonTree<MockLeaf, u64, DEPTH>;
+
+#[test]
+fn ffi_hash() {
+    let messages: Vec<BlsScalar> = (0..5u64).map(BlsScalar::from).collect();
+    let bytes: Vec<Vec<u8>> =
+        messages.iter().map(|m| m.to_bytes().to_vec()).collect();
+
+    let hash =
+        ffi::poseidon_hash(bytes.clone()).expect("Hashing should succeed");
+    assert_eq!(hash, sponge::hash(&messages).to_bytes().to_vec());
+
+    let hash =
+        ffi::poseidon_truncated_hash(bytes).expect("Hashing should succeed");
+    assert_eq!(hash, sponge::truncated::hash(&messages).to_bytes().to_vec());
+
+    assert!(matches!(
+        ffi::poseidon_hash(vec![vec![0xff; 32]]),
+        Err(FfiError::InvalidScalar)
+    ));
+}
+
+#[test]
+fn ffi_verify_branch() {
+    let mut tree = Tree::new();
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+    }
+
+    let branch = tree.branch(33).expect("The branch should exist");
+    let bytes = branch.to_bytes().to_vec();
+    let root = tree.root().to_bytes().to_vec();
+    let leaf = BlsScalar::from(33).to_bytes().to_vec();
+    let other = BlsScalar::from(34).to_bytes().to_vec();
+
+    assert_eq!(
+        ffi::poseidon_verify_branch(bytes.clone(), root.clone(), leaf.clone()),
+        Ok(true)
+    );
+    assert_eq!(
+        ffi::poseidon_verify_branch(bytes.clone(), root.clone(), other),
+        Ok(false)
+    );
+    assert!(matches!(
+        ffi::poseidon_verify_branch(bytes[1..].to_vec(), root, leaf),
+        Err(FfiError::InvalidBranch)
+    ));
+}
@@ -0,0 +1,212 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "halo2")]
+
+use bls12_381::Scalar as BlsScalar;
+use halo2_proofs::circuit::{Layouter, SimpleFloorPlanner, Value};
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::plonk::{
+    Advice, Circuit, Column, ConstraintSystem, Error, Instance,
+};
+use poseidon::halo2::{Cell, PoseidonChip, PoseidonConfig};
+use poseidon::params::{Params, SecurityLevel};
+use poseidon::sponge;
+use poseidon::tree::{self, PoseidonBranch};
+
+const K: u32 = 9;
+
+/// Configuration of the test circuits: the chip, a column to load the
+/// witnesses and the instance the output is constrained to.
+#[derive(Debug, Clone)]
+struct TestConfig<const W: usize> {
+    chip: PoseidonConfig<W>,
+    input: Column<Advice>,
+    instance: Column<Instance>,
+}
+
+fn configure<const W: usize>(
+    meta: &mut ConstraintSystem<BlsScalar>,
+) -> TestConfig<W> {
+    let input = meta.advice_column();
+    let instance = meta.instance_column();
+    meta.enable_equality(input);
+    meta.enable_equality(instance);
+
+    TestConfig {
+        chip: PoseidonChip::configure(meta),
+        input,
+        instance,
+    }
+}
+
+/// Load `values` as witnesses in the input column.
+fn load<const W: usize>(
+    config: &TestConfig<W>,
+    layouter: &mut impl Layouter<BlsScalar>,
+    values: &[Value<BlsScalar>],
+) -> Result<Vec<Cell>, Error> {
+    layouter.assign_region(
+        || "witnesses",
+        |mut region| {
+            values
+                .iter()
+                .enumerate()
+                .map(|(row, value)| {
+                    region.assign_advice(
+                        || "witness",
+                        config.input,
+                        row,
+                        || *value,
+                    )
+                })
+                .collect()
+        },
+    )
+}
+
+fn params(width: usize) -> Params {
+    Params::with_security(width, SecurityLevel::Bits128)
+        .expect("the width is supported")
+}
+
+#[derive(Debug, Clone)]
+struct HashCircuit {
+    messages: Vec<Value<BlsScalar>>,
+}
+
+impl Circuit<BlsScalar> for HashCircuit {
+    type Config = TestConfig<3>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        let messages = vec![Value::unknown(); self.messages.len()];
+        Self { messages }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<BlsScalar>) -> Self::Config {
+        configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<BlsScalar>,
+    ) -> Result<(), Error> {
+        let chip = PoseidonChip::construct(config.chip.clone());
+
+        let messages = load(&config, &mut layouter, &self.messages)?;
+        let hash =
+            chip.hash(layouter.namespace(|| "hash"), &params(3), &messages)?;
+
+        layouter.constrain_instance(hash.cell(), config.instance, 0)
+    }
+}
+
+#[test]
+fn halo2_hash() {
+    let messages: Vec<BlsScalar> = (0..5).map(BlsScalar::from).collect();
+
+    for len in 0..=messages.len() {
+        let circuit = HashCircuit {
+            messages: messages[..len]
+                .iter()
+                .copied()
+                .map(Value::known)
+                .collect(),
+        };
+        let hash = sponge::hash_with_params(&params(3), &messages[..len]);
+
+        let prover = MockProver::run(K, &circuit, vec![vec![hash]])
+            .expect("the circuit should be synthesized");
+        assert_eq!(prover.verify(), Ok(()));
+
+        let wrong = hash + BlsScalar::one();
+        let prover = MockProver::run(K, &circuit, vec![vec![wrong]])
+            .expect("the circuit should be synthesized");
+        assert!(prover.verify().is_err());
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct OpeningCircuit {
+    branch: PoseidonBranch<2>,
+    leaf: Value<BlsScalar>,
+}
+
+impl Circuit<BlsScalar> for OpeningCircuit {
+    type Config = TestConfig<{ hades::WIDTH }>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<BlsScalar>) -> Self::Config {
+        configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<BlsScalar>,
+    ) -> Result<(), Error> {
+        let chip = PoseidonChip::construct(config.chip.clone());
+
+        let leaf = load(&config, &mut layouter, &[self.leaf])?;
+        let root = chip.merkle_opening(
+            layouter.namespace(|| "opening"),
+            &params(hades::WIDTH),
+            &self.branch,
+            &leaf[0],
+        )?;
+
+        layouter.constrain_instance(root.cell(), config.instance, 0)
+    }
+}
+
+#[test]
+fn halo2_merkle_opening() {
+    let params = params(hades::WIDTH);
+    let leaves: Vec<BlsScalar> = (0..16).map(BlsScalar::from).collect();
+    let root = tree::compute_root_with::<_, 2>(&params, &leaves)
+        .expect("The leaves should fit");
+
+    // Open the third leaf of the second group
+    let nodes: Vec<BlsScalar> = leaves
+        .chunks(4)
+        .map(|group| tree::compute_root_with::<_, 1>(&params, group))
+        .collect::<Option<_>>()
+        .expect("The groups should fit");
+    let mut siblings = [[BlsScalar::zero(); 4]; 2];
+    siblings[0].copy_from_slice(&leaves[4..8]);
+    siblings[1].copy_from_slice(&nodes);
+    let branch = PoseidonBranch::<2>::from_path_with(
+        &params,
+        leaves[6],
+        &siblings,
+        &[2, 1],
+        &[4, 4],
+    )
+    .expect("The path should be valid");
+
+    let circuit = OpeningCircuit {
+        branch,
+        leaf: Value::known(leaves[6]),
+    };
+    let prover = MockProver::run(K, &circuit, vec![vec![root]])
+        .expect("the circuit should be synthesized");
+    assert_eq!(prover.verify(), Ok(()));
+
+    // The leaf must be found at the offset of the branch
+    let circuit = OpeningCircuit {
+        branch,
+        leaf: Value::known(leaves[5]),
+    };
+    let prover = MockProver::run(K, &circuit, vec![vec![root]])
+        .expect("the circuit should be synthesized");
+    assert!(prover.verify().is_err());
+}
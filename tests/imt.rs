@@ -0,0 +1,222 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+use plonk::error::Error as PlonkError;
+use poseidon::imt::{
+    self, Error, IndexedMerkleTree, InsertionProof, NonMembershipProof,
+    MAX_VALUE,
+};
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
+
+use plonk::prelude::*;
+
+const DEPTH: usize = 8;
+const CAPACITY: usize = 16;
+
+type Tree = IndexedMerkleTree<DEPTH>;
+
+#[derive(Default)]
+struct NonMembershipCircuit {
+    proof: NonMembershipProof<DEPTH>,
+    value: BlsScalar,
+    root: BlsScalar,
+}
+
+impl Circuit for NonMembershipCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let value = composer.append_witness(self.value);
+        let root = composer.append_public(self.root);
+
+        let root_p = imt::non_membership(composer, &self.proof, value);
+
+        composer.assert_equal(root_p, root);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct InsertionCircuit {
+    proof: InsertionProof<DEPTH>,
+    value: BlsScalar,
+    old_root: BlsScalar,
+    new_root: BlsScalar,
+}
+
+impl Circuit for InsertionCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let value = composer.append_witness(self.value);
+        let old_root = composer.append_public(self.old_root);
+        let new_root = composer.append_public(self.new_root);
+
+        let root_p = imt::insertion(composer, &self.proof, old_root, value);
+
+        composer.assert_equal(root_p, new_root);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn imt_insert() {
+    let mut tree = Tree::new();
+    assert!(tree.is_empty());
+    assert!(tree.contains(&BlsScalar::zero()));
+
+    for value in [30u64, 10, 20] {
+        let old_root = tree.root();
+        let value = BlsScalar::from(value);
+
+        let proof = tree.insert(value).expect("the value should be inserted");
+        assert!(proof.verify(&old_root, &tree.root(), &value));
+        assert!(!proof.verify(&old_root, &old_root, &value));
+    }
+
+    assert_eq!(tree.len(), 3);
+    assert!(tree.contains(&BlsScalar::from(20)));
+
+    // The leaves form a linked list sorted by value
+    let mut leaf = tree.leaf(0).expect("the zero leaf should exist");
+    for value in [10u64, 20, 30] {
+        assert_eq!(leaf.next_value(), &BlsScalar::from(value));
+        leaf = tree.leaf(leaf.next_index()).expect("the leaf should exist");
+    }
+    assert_eq!(leaf.next_value(), &MAX_VALUE);
+
+    assert_eq!(tree.insert(BlsScalar::from(20)), Err(Error::Duplicate));
+    assert_eq!(tree.insert(BlsScalar::zero()), Err(Error::Duplicate));
+    assert_eq!(tree.insert(MAX_VALUE), Err(Error::OutOfRange));
+}
+
+#[test]
+fn imt_full() {
+    let mut tree = IndexedMerkleTree::<2>::new();
+
+    for value in 1..4u64 {
+        tree.insert(BlsScalar::from(value))
+            .expect("the value should be inserted");
+    }
+
+    assert_eq!(tree.insert(BlsScalar::from(4)), Err(Error::Full));
+}
+
+#[test]
+fn imt_non_membership() {
+    let mut tree = Tree::new();
+
+    for value in [10u64, 20, 30] {
+        tree.insert(BlsScalar::from(value))
+            .expect("the value should be inserted");
+    }
+
+    let root = tree.root();
+    let value = BlsScalar::from(15);
+    let proof = tree
+        .non_membership(&value)
+        .expect("the value should not be in the set");
+
+    assert!(proof.verify(&root, &value));
+    assert_eq!(proof.leaf().value(), &BlsScalar::from(10));
+    assert!(!proof.verify(&root, &BlsScalar::from(20)));
+    assert!(!proof.verify(&root, &BlsScalar::from(25)));
+
+    assert!(tree.non_membership(&BlsScalar::from(20)).is_none());
+    assert!(tree.non_membership(&MAX_VALUE).is_none());
+
+    let value = BlsScalar::from(31);
+    let proof = tree
+        .non_membership(&value)
+        .expect("the value should not be in the set");
+    assert!(proof.verify(&root, &value));
+}
+
+#[test]
+fn imt_non_membership_gadget() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<NonMembershipCircuit>(&pp, label)
+            .expect("Circuit should compile successfully");
+
+    let mut tree = Tree::new();
+    for value in [10u64, 20, 30] {
+        tree.insert(BlsScalar::from(value))
+            .expect("the value should be inserted");
+    }
+
+    let value = BlsScalar::from(25);
+    let mut circuit = NonMembershipCircuit {
+        proof: tree
+            .non_membership(&value)
+            .expect("the value should not be in the set"),
+        value,
+        root: tree.root(),
+    };
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proof generation should succeed");
+
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // A value in the set can't be proven absent
+    circuit.value = BlsScalar::from(30);
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
+
+#[test]
+fn imt_insertion_gadget() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) = Compiler::compile::<InsertionCircuit>(&pp, label)
+        .expect("Circuit should compile successfully");
+
+    let mut tree = Tree::new();
+    for value in [10u64, 20, 30] {
+        tree.insert(BlsScalar::from(value))
+            .expect("the value should be inserted");
+    }
+
+    let value = BlsScalar::from(25);
+    let old_root = tree.root();
+    let mut circuit = InsertionCircuit {
+        proof: tree.insert(value).expect("the value should be inserted"),
+        value,
+        old_root,
+        new_root: tree.root(),
+    };
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proof generation should succeed");
+
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // The insertion doesn't yield any other root
+    circuit.new_root = old_root;
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
@@ -0,0 +1,91 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(all(feature = "alloc", feature = "interchange"))]
+
+mod max_annotation;
+
+use bytes::Serializable;
+use max_annotation::MockLeaf;
+use poseidon::tree::interchange::{self, Error, Opening};
+use poseidon::tree::{PoseidonBranch, PoseidonTree};
+
+const DEPTH: usize = 17;
+
+type Tree = PoseidonTree<MockLeaf, u64, DEPTH>;
+
+fn branch() -> PoseidonBranch<DEPTH> {
+    let mut tree = Tree::new();
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+    }
+
+    tree.branch(33).expect("The branch should exist")
+}
+
+#[test]
+fn json_roundtrip() {
+    let branch = branch();
+
+    let json = interchange::to_json(&branch);
+    let parsed: PoseidonBranch<DEPTH> =
+        interchange::from_json(&json).expect("Parsing should succeed");
+
+    assert_eq!(branch.to_bytes(), parsed.to_bytes());
+}
+
+#[test]
+fn json_validation() {
+    let branch = branch();
+    let opening = Opening::from(&branch);
+
+    let mut unsupported = opening.clone();
+    unsupported.version += 1;
+    assert!(matches!(
+        PoseidonBranch::<DEPTH>::try_from(&unsupported),
+        Err(Error::UnsupportedVersion(_))
+    ));
+
+    assert!(matches!(
+        PoseidonBranch::<{ DEPTH + 1 }>::try_from(&opening),
+        Err(Error::BadDepth { .. })
+    ));
+
+    let mut bad_offset = opening.clone();
+    bad_offset.path[3].offset = hades::WIDTH as u64;
+    assert!(matches!(
+        PoseidonBranch::<DEPTH>::try_from(&bad_offset),
+        Err(Error::BadLevel(3))
+    ));
+
+    // An offset that would wrap around to a valid one when narrowed to a
+    // 32-bit `usize` is still rejected
+    let mut wrapping_offset = opening.clone();
+    wrapping_offset.path[3].offset = (1 << 32) + 1;
+    assert!(matches!(
+        PoseidonBranch::<DEPTH>::try_from(&wrapping_offset),
+        Err(Error::BadLevel(3))
+    ));
+
+    let mut bad_leaf = opening.clone();
+    bad_leaf.leaf = bad_leaf.root.clone();
+    assert!(matches!(
+        PoseidonBranch::<DEPTH>::try_from(&bad_leaf),
+        Err(Error::LeafMismatch)
+    ));
+
+    let mut bad_scalar = opening;
+    bad_scalar.root = String::from("0xzz");
+    assert!(matches!(
+        PoseidonBranch::<DEPTH>::try_from(&bad_scalar),
+        Err(Error::InvalidScalar(_))
+    ));
+
+    assert!(matches!(
+        interchange::from_json::<DEPTH>("{}"),
+        Err(Error::Json(_))
+    ));
+}
@@ -0,0 +1,235 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+use plonk::error::Error as PlonkError;
+use poseidon::map::{self, MapOpening, PoseidonMap, VersionedMap};
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
+
+use plonk::prelude::*;
+
+const DEPTH: usize = 16;
+const CAPACITY: usize = 15;
+
+type Map = PoseidonMap<[u64; 2], BlsScalar, DEPTH>;
+
+#[derive(Default)]
+struct MapOpeningCircuit {
+    opening: MapOpening<[u64; 2], BlsScalar, DEPTH>,
+    key: [u64; 2],
+    value: BlsScalar,
+    root: BlsScalar,
+}
+
+impl Circuit for MapOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let key = self.key.map(|k| composer.append_witness(k));
+        let value = composer.append_witness(self.value);
+        let root = composer.append_public(self.root);
+
+        let root_p =
+            map::merkle_opening(composer, &self.opening, &key, &[value]);
+
+        composer.assert_equal(root_p, root);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct MapNonMembershipCircuit {
+    opening: MapOpening<[u64; 2], BlsScalar, DEPTH>,
+    key: [u64; 2],
+    root: BlsScalar,
+}
+
+impl Circuit for MapNonMembershipCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let key = self.key.map(|k| composer.append_witness(k));
+        let root = composer.append_public(self.root);
+
+        let root_p = map::non_membership(composer, &self.opening, &key);
+
+        composer.assert_equal(root_p, root);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn map_insert_get_remove() {
+    let mut map = Map::new();
+    assert!(map.is_empty());
+    let empty = map.root();
+
+    let key = [4, 2];
+    assert_eq!(map.insert(key, BlsScalar::from(1)), Ok(None));
+    assert_eq!(
+        map.insert(key, BlsScalar::from(2)),
+        Ok(Some(BlsScalar::from(1)))
+    );
+    assert_eq!(map.get(&key), Some(&BlsScalar::from(2)));
+    assert!(map.contains_key(&key));
+    assert!(!map.contains_key(&[2, 4]));
+    assert_eq!(map.len(), 1);
+
+    assert_eq!(map.remove(&[2, 4]), None);
+    assert_eq!(map.remove(&key), Some(BlsScalar::from(2)));
+    assert_eq!(map.get(&key), None);
+    assert_eq!(map.root(), empty);
+}
+
+#[test]
+fn map_opening() {
+    let mut map = PoseidonMap::<u64, u64>::new();
+
+    for i in 0..20 {
+        map.insert(i, i * i).expect("keys should not collide");
+    }
+
+    let root = map.root();
+    let opening = map.opening(&5).expect("the key should be in the map");
+
+    assert!(opening.verify(&root, &5, &25));
+    assert!(!opening.verify(&root, &5, &26));
+    assert!(!opening.verify(&root, &6, &25));
+    assert!(map.opening(&20).is_none());
+
+    let opening = map
+        .non_membership(&20)
+        .expect("the key should not be in the map");
+
+    assert!(opening.verify_non_membership(&root, &20));
+    assert!(!opening.verify_non_membership(&root, &5));
+    assert!(map.non_membership(&5).is_none());
+}
+
+#[test]
+fn map_merkle_opening() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) = Compiler::compile::<MapOpeningCircuit>(&pp, label)
+        .expect("Circuit should compile successfully");
+
+    let mut map = Map::new();
+    for i in 0..10 {
+        map.insert([i, i + 1], BlsScalar::random(&mut rng))
+            .expect("keys should not collide");
+    }
+
+    let key = [3, 4];
+    let value = *map.get(&key).expect("the key should be in the map");
+    let mut circuit = MapOpeningCircuit {
+        opening: map.opening(&key).expect("the key should be in the map"),
+        key,
+        value,
+        root: map.root(),
+    };
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proof generation should succeed");
+
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // The key can't be opened with another value
+    circuit.value = BlsScalar::random(&mut rng);
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
+
+#[test]
+fn map_non_membership_gadget() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<MapNonMembershipCircuit>(&pp, label)
+            .expect("Circuit should compile successfully");
+
+    let mut map = Map::new();
+    for i in 0..10 {
+        map.insert([i, i + 1], BlsScalar::random(&mut rng))
+            .expect("keys should not collide");
+    }
+
+    let key = [4, 3];
+    let mut circuit = MapNonMembershipCircuit {
+        opening: map
+            .non_membership(&key)
+            .expect("the key should not be in the map"),
+        key,
+        root: map.root(),
+    };
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proof generation should succeed");
+
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // A key of the map can't be proven absent
+    circuit.key = [3, 4];
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
+
+#[test]
+fn map_versioned() {
+    let mut map = VersionedMap::<u64, u64, DEPTH>::new();
+    let mut roots = vec![map.root()];
+
+    for i in 0..10 {
+        roots.push(map.insert(i, i).expect("keys should not collide"));
+    }
+    roots.push(map.insert(3, 42).expect("keys should not collide"));
+    roots.push(map.remove(&5).expect("the key should be in the map"));
+    assert_eq!(map.remove(&5), None);
+    assert_eq!(map.roots(), roots);
+
+    // Every version matches a map holding the same keys
+    let mut latest = PoseidonMap::<u64, u64, DEPTH>::new();
+    for i in 0..10 {
+        latest.insert(i, i).expect("keys should not collide");
+    }
+    assert_eq!(roots[10], latest.root());
+
+    assert_eq!(map.get(&3), Some(&42));
+    assert_eq!(map.get(&5), None);
+    assert_eq!(map.get_at(&roots[10], &3), Some(&3));
+    assert_eq!(map.get_at(&roots[10], &5), Some(&5));
+    assert_eq!(map.get_at(&roots[4], &5), None);
+    assert_eq!(map.get_at(&BlsScalar::from(42), &3), None);
+
+    let opening = map
+        .opening_at(&roots[10], &3)
+        .expect("the key should be in the version");
+    assert!(opening.verify(&roots[10], &3, &3));
+    assert!(map.opening_at(&roots[4], &5).is_none());
+
+    let opening = map
+        .non_membership_at(&roots[4], &5)
+        .expect("the key should not be in the version");
+    assert!(opening.verify_non_membership(&roots[4], &5));
+    assert!(map.non_membership_at(&roots[10], &5).is_none());
+}
@@ -8,18 +8,30 @@
 
 use core::borrow::Borrow;
 use bls12_381::BlsScalar;
+use bytes::{DeserializableSlice, Serializable};
 use hades::{ScalarStrategy, Strategy};
-use poseidon::tree::{PoseidonLeaf, PoseidonTree};
+use poseidon::tree::{PoseidonLeaf, PoseidonTombstone, PoseidonTree};
 use nstack::annotation::Keyed;
 use rand::{CryptoRng, RngCore};
 
-#[derive(Debug, Default, Clone, Copy, PartialOrd, Ord, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq)]
 pub struct MockLeaf {
     s: BlsScalar,
     pub pos: u64,
     pub expiration: u64,
 }
 
+// The default leaf is the tombstone of a removed leaf, which never expires
+impl Default for MockLeaf {
+    fn default() -> Self {
+        Self {
+            s: BlsScalar::zero(),
+            pos: 0,
+            expiration: u64::MAX,
+        }
+    }
+}
+
 impl Keyed<u64> for MockLeaf {
     fn key(&self) -> &u64 {
         &self.pos
@@ -58,6 +70,42 @@ impl PoseidonLeaf for MockLeaf {
     fn set_pos(&mut self, pos: u64) {
         self.pos = pos;
     }
+
+    fn is_tombstone(&self) -> bool {
+        self.expiration == u64::MAX
+    }
+}
+
+impl PoseidonTombstone for MockLeaf {
+    fn tombstone() -> Self {
+        Self::default()
+    }
+}
+
+impl Serializable<{ BlsScalar::SIZE + 2 * u64::SIZE }> for MockLeaf {
+    type Error = bytes::Error;
+
+    fn from_bytes(buf: &[u8; Self::SIZE]) -> Result<Self, Self::Error> {
+        let mut bytes = &buf[..];
+
+        let s = BlsScalar::from_reader(&mut bytes)?;
+        let pos = u64::from_reader(&mut bytes)?;
+        let expiration = u64::from_reader(&mut bytes)?;
+
+        Ok(Self { s, pos, expiration })
+    }
+
+    fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut buf = [0u8; Self::SIZE];
+
+        buf[..BlsScalar::SIZE].copy_from_slice(&self.s.to_bytes());
+        buf[BlsScalar::SIZE..BlsScalar::SIZE + u64::SIZE]
+            .copy_from_slice(&self.pos.to_bytes());
+        buf[BlsScalar::SIZE + u64::SIZE..]
+            .copy_from_slice(&self.expiration.to_bytes());
+
+        buf
+    }
 }
 
 impl Borrow<u64> for MockLeaf {
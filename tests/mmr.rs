@@ -0,0 +1,124 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+use plonk::error::Error as PlonkError;
+use poseidon::mmr::{self, MerkleMountainRange, MmrProof};
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
+
+use plonk::prelude::*;
+
+const CAPACITY: usize = 15;
+
+#[derive(Default)]
+struct MmrOpeningCircuit {
+    proof: MmrProof,
+    leaf: BlsScalar,
+    root: BlsScalar,
+}
+
+impl Circuit for MmrOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaf = composer.append_witness(self.leaf);
+        let root = composer.append_public(self.root);
+
+        let root_p = mmr::merkle_opening(composer, &self.proof, leaf);
+
+        composer.assert_equal(root_p, root);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn mmr_push() {
+    let mut mmr = MerkleMountainRange::new();
+    assert!(mmr.is_empty());
+    assert_eq!(mmr.root(), BlsScalar::zero());
+
+    let mut roots = Vec::new();
+    for i in 0..11u64 {
+        assert_eq!(mmr.push(BlsScalar::from(i)), i);
+        roots.push(mmr.root());
+    }
+
+    // 11 leaves make mountains of 8, 2 and 1 leaves
+    assert_eq!(mmr.len(), 11);
+    assert_eq!(mmr.peaks().len(), 3);
+    assert_eq!(mmr.get(7), Some(BlsScalar::from(7)));
+    assert_eq!(mmr.get(11), None);
+
+    roots.dedup();
+    assert_eq!(roots.len(), 11);
+}
+
+#[test]
+fn mmr_proof() {
+    let mut mmr = MerkleMountainRange::new();
+
+    for i in 0..23u64 {
+        mmr.push(BlsScalar::from(i));
+
+        let root = mmr.root();
+        for pos in 0..=i {
+            let proof = mmr.proof(pos).expect("the leaf should exist");
+            let leaf = BlsScalar::from(pos);
+
+            assert!(proof.verify(&root, &leaf));
+            assert!(!proof.verify(&root, &(leaf + BlsScalar::one())));
+        }
+    }
+
+    assert!(mmr.proof(23).is_none());
+
+    // A proof is bound to the size of the range
+    let proof = mmr.proof(3).expect("the leaf should exist");
+    mmr.push(BlsScalar::from(23));
+    assert!(!proof.verify(&mmr.root(), &BlsScalar::from(3)));
+}
+
+#[test]
+fn mmr_merkle_opening() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let mut mmr = MerkleMountainRange::new();
+    for _ in 0..27 {
+        mmr.push(BlsScalar::random(&mut rng));
+    }
+
+    let pos = 19;
+    let leaf = mmr.get(pos).expect("the leaf should exist");
+    let mut circuit = MmrOpeningCircuit {
+        proof: mmr.proof(pos).expect("the leaf should exist"),
+        leaf,
+        root: mmr.root(),
+    };
+
+    let (prover, verifier) =
+        Compiler::compile_with_circuit(&pp, label, &circuit)
+            .expect("Circuit should compile successfully");
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proof generation should succeed");
+
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // Another leaf of the same mountain can't be opened on the position
+    circuit.leaf = mmr.get(pos - 1).expect("the leaf should exist");
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
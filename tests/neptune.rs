@@ -0,0 +1,128 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use bls12_381::BlsScalar;
+use poseidon::neptune::{HashType, NeptunePoseidon, MAX_ARITY};
+use poseidon::params::Error;
+
+fn preimage(n: usize) -> Vec<BlsScalar> {
+    (0..n as u64).map(BlsScalar::from).collect()
+}
+
+// Test vectors of neptune 13.0.0, in `hash_values_aux` of `src/poseidon.rs`,
+// for the standard strength
+
+#[test]
+fn neptune_merkle() {
+    let vectors = [
+        (
+            2,
+            [
+                0x2e203c369a02e7ff,
+                0xa6fba9339d05a69d,
+                0x739e0fd902efe161,
+                0x396508d75e76a56b,
+            ],
+        ),
+        (
+            4,
+            [
+                0x019814ff6662075d,
+                0xfb6b4605bf1327ec,
+                0x00db3c6579229399,
+                0x58a54b10a9e5848a,
+            ],
+        ),
+        (
+            8,
+            [
+                0x2a9934f56d38a5e6,
+                0x4b682e9d9cc4aed9,
+                0x1201004211677077,
+                0x2394611da3a5de55,
+            ],
+        ),
+        (
+            11,
+            [
+                0xcee3bbc32b693163,
+                0x09f3dcd8ccb08fc1,
+                0x6ca537e232ebe87a,
+                0x0c0fc1b2e5227f28,
+            ],
+        ),
+        (
+            16,
+            [
+                0x1291c74060266d37,
+                0x5b8dbc6d30680a6f,
+                0xc1c2fb5a6f871e63,
+                0x2d3ae2663381ae8a,
+            ],
+        ),
+        (
+            24,
+            [
+                0xd7ef3569f585b321,
+                0xc3e779f6468815b1,
+                0x066f39bf783f3d9f,
+                0x63beb8831f11ae15,
+            ],
+        ),
+        (
+            36,
+            [
+                0x4473606dfa4e8140,
+                0x75cd368df8a8ac3c,
+                0x540a30e03c10bbaa,
+                0x699303082a6e5d5f,
+            ],
+        ),
+    ];
+
+    for (arity, expected) in vectors {
+        let neptune = NeptunePoseidon::new(arity).unwrap();
+        assert_eq!(
+            neptune.hash_merkle(&preimage(arity)),
+            BlsScalar::from_raw(expected)
+        );
+    }
+}
+
+#[test]
+fn neptune_constant_length() {
+    let neptune = NeptunePoseidon::new(4).unwrap();
+
+    let expected = BlsScalar::from_raw([
+        0x8935b00a07909d45,
+        0x4984de08542c9977,
+        0x39443980077d7593,
+        0x3a21a6ae86754a29,
+    ]);
+    assert_eq!(neptune.hash_constant_length(&preimage(4)), expected);
+
+    assert_eq!(
+        HashType::ConstantLength(3).domain_tag(4),
+        BlsScalar::from_raw([0, 3, 0, 0])
+    );
+    assert_eq!(HashType::Merkle.domain_tag(4), BlsScalar::from(15u64));
+}
+
+#[test]
+fn neptune_arity() {
+    assert!(matches!(
+        NeptunePoseidon::new(0),
+        Err(Error::InvalidWidth(1))
+    ));
+    assert!(matches!(
+        NeptunePoseidon::new(MAX_ARITY + 1),
+        Err(Error::InvalidWidth(38))
+    ));
+
+    let neptune = NeptunePoseidon::new(MAX_ARITY).unwrap();
+    assert_eq!(neptune.arity(), MAX_ARITY);
+    assert_eq!(neptune.params().partial_rounds(), 60);
+}
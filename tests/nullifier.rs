@@ -0,0 +1,110 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+use plonk::error::Error as PlonkError;
+use poseidon::{nullifier, sponge};
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
+
+use plonk::prelude::*;
+
+const CAPACITY: usize = 12;
+
+#[derive(Default, Debug)]
+struct NullifierCircuit {
+    sk: BlsScalar,
+    position: u64,
+    note_commitment: BlsScalar,
+    nullifiers: [BlsScalar; 2],
+}
+
+impl Circuit for NullifierCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let sk = composer.append_witness(self.sk);
+        let position = composer.append_witness(BlsScalar::from(self.position));
+        let note_commitment = composer.append_witness(self.note_commitment);
+
+        let computed = [
+            nullifier::gadget(composer, sk, position),
+            nullifier::gadget_from_commitment(composer, sk, note_commitment),
+        ];
+
+        for (n, c) in self.nullifiers.iter().zip(computed.iter()) {
+            let n = composer.append_public(*n);
+            composer.assert_equal(n, *c);
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn nullifier() {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let sk = BlsScalar::random(&mut rng);
+    let note_commitment = BlsScalar::random(&mut rng);
+
+    // Nullifiers are unique to the key and the note
+    let n = nullifier::derive(&sk, 42);
+    assert_ne!(n, nullifier::derive(&sk, 43));
+    assert_ne!(n, nullifier::derive(&BlsScalar::random(&mut rng), 42));
+
+    // The derivations are domain separated from each other and from the plain
+    // hash of the same scalars
+    let position = BlsScalar::from(42);
+    assert_ne!(n, nullifier::derive_from_commitment(&sk, &position));
+    assert_ne!(n, sponge::hash(&[sk, position]));
+    assert_ne!(
+        nullifier::derive_from_commitment(&sk, &note_commitment),
+        sponge::hash(&[sk, note_commitment])
+    );
+}
+
+#[test]
+fn nullifier_gadget() -> Result<(), Error> {
+    let label = b"nullifier-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) = Compiler::compile::<NullifierCircuit>(&pp, label)?;
+
+    let sk = BlsScalar::random(&mut rng);
+    let position = 42;
+    let note_commitment = BlsScalar::random(&mut rng);
+    let nullifiers = [
+        nullifier::derive(&sk, position),
+        nullifier::derive_from_commitment(&sk, &note_commitment),
+    ];
+
+    let circuit = NullifierCircuit {
+        sk,
+        position,
+        note_commitment,
+        nullifiers,
+    };
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    assert_eq!(public_inputs, nullifiers);
+    verifier.verify(&proof, &public_inputs)?;
+
+    // The nullifiers of another position aren't accepted
+    let circuit = NullifierCircuit {
+        sk,
+        position: position + 1,
+        note_commitment,
+        nullifiers,
+    };
+    assert!(prover.prove(&mut rng, &circuit).is_err());
+
+    Ok(())
+}
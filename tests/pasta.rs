@@ -0,0 +1,101 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "pasta")]
+
+use poseidon::compress;
+use poseidon::params::SecurityLevel;
+use poseidon::pasta::{self, PallasBase, VestaBase};
+use poseidon::sponge;
+
+// Values computed with an independent implementation of the Grain LFSR, of
+// the sponge and of the compression
+
+#[test]
+fn pallas_vectors() {
+    let params = pasta::pallas_params(3, SecurityLevel::Bits128)
+        .expect("the width is supported");
+    let messages: Vec<PallasBase> = (1..=3u64).map(PallasBase::from).collect();
+
+    let hash = PallasBase::from_raw([
+        0x65ea1f95aaf5f873,
+        0x8673c650e2a66ebf,
+        0x16f7c8ddd9ec9536,
+        0x37476ce94855ad6e,
+    ]);
+    assert_eq!(sponge::hash_with_params(&params, &messages), hash);
+
+    let jive = PallasBase::from_raw([
+        0x59ec8b03d66c2bca,
+        0xbd700790a7882b92,
+        0x04d50bbbf5e4502b,
+        0x3eb1279f2e237a45,
+    ]);
+    assert_eq!(compress::jive_with(&params, &messages[..2]), jive);
+
+    let params = pasta::pallas_params(5, SecurityLevel::Bits128)
+        .expect("the width is supported");
+
+    let hash = PallasBase::from_raw([
+        0x97b2978faa5b223d,
+        0xadbb97d3c495e42b,
+        0x3d2c7665bb8271de,
+        0x16dee8c8b8bd8794,
+    ]);
+    assert_eq!(sponge::hash_with_params(&params, &messages), hash);
+
+    let jive = PallasBase::from_raw([
+        0xf0ceb67e6fcfa71b,
+        0x18bc5efffb104ded,
+        0x9ed889831a7fbb45,
+        0x023701ee77395077,
+    ]);
+    let inputs = [messages[0], messages[1], messages[2], 4.into()];
+    assert_eq!(compress::jive_with(&params, &inputs), jive);
+}
+
+#[test]
+fn vesta_vectors() {
+    let params = pasta::vesta_params(3, SecurityLevel::Bits128)
+        .expect("the width is supported");
+    let messages: Vec<VestaBase> = (1..=3u64).map(VestaBase::from).collect();
+
+    let hash = VestaBase::from_raw([
+        0xcb7270f8b343cb84,
+        0x33cd9c0e5e7cb004,
+        0xfbf552d8e156941b,
+        0x11991fe254dda76e,
+    ]);
+    assert_eq!(sponge::hash_with_params(&params, &messages), hash);
+
+    let jive = VestaBase::from_raw([
+        0x8cbcb00e34aceec5,
+        0x231017f92b839cdd,
+        0x64734f77183539cf,
+        0x1b9e037c3c46be48,
+    ]);
+    assert_eq!(compress::jive_with(&params, &messages[..2]), jive);
+
+    let params = pasta::vesta_params(5, SecurityLevel::Bits128)
+        .expect("the width is supported");
+
+    let hash = VestaBase::from_raw([
+        0xd3efa8fab07398ed,
+        0x08e31ed5a9b92010,
+        0x7a750b87fc7bad3d,
+        0x3fc417e710f7b829,
+    ]);
+    assert_eq!(sponge::hash_with_params(&params, &messages), hash);
+
+    let jive = VestaBase::from_raw([
+        0x80df872c93e03216,
+        0xf6473049bc51c4db,
+        0x622344e09ec89202,
+        0x3bacbe302987d74c,
+    ]);
+    let inputs = [messages[0], messages[1], messages[2], 4.into()];
+    assert_eq!(compress::jive_with(&params, &inputs), jive);
+}
@@ -0,0 +1,63 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+use plonk::error::Error as PlonkError;
+use poseidon::perm_uses;
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
+
+use plonk::prelude::*;
+
+const CAPACITY: usize = 11;
+
+#[derive(Default, Debug)]
+struct TwoOutputsCircuit {
+    message: BlsScalar,
+    outputs: [BlsScalar; 2],
+}
+
+impl Circuit for TwoOutputsCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let message = composer.append_witness(self.message);
+        let (a, b) = perm_uses::two_outputs_gadget(composer, message);
+
+        let outputs = self.outputs.map(|o| composer.append_witness(o));
+        composer.assert_equal(a, outputs[0]);
+        composer.assert_equal(b, outputs[1]);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn two_outputs_gadget() -> Result<(), Error> {
+    let label = b"two-outputs-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<TwoOutputsCircuit>(&pp, label)?;
+
+    let message = BlsScalar::random(&mut rng);
+    let outputs = perm_uses::two_outputs(message);
+    let circuit = TwoOutputsCircuit { message, outputs };
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    verifier.verify(&proof, &public_inputs)?;
+
+    // The outputs are ordered
+    let outputs = [outputs[1], outputs[0]];
+    let circuit = TwoOutputsCircuit { message, outputs };
+    assert!(prover.prove(&mut rng, &circuit).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,141 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(all(feature = "alloc", feature = "std"))]
+
+mod max_annotation;
+
+use std::fs;
+use std::io::ErrorKind;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::Serializable;
+use max_annotation::MockLeaf;
+use poseidon::tree::{Aggregate, PoseidonTree};
+
+type Tree = PoseidonTree<MockLeaf, u64, 17>;
+
+/// Number of leaves of a subtree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct LeafCount(u64);
+
+impl Aggregate<MockLeaf> for LeafCount {
+    fn from_leaf(_: &MockLeaf) -> Self {
+        Self(1)
+    }
+
+    fn combine(&mut self, other: &Self) {
+        self.0 += other.0;
+    }
+}
+
+#[test]
+fn tree_persist_restore() {
+    let path = std::env::temp_dir().join("poseidon-tree-persist-restore");
+
+    let tree: Tree = (0..1025).map(MockLeaf::from).collect();
+    tree.persist(&path).expect("Persisting should succeed");
+
+    let restored = Tree::restore(&path).expect("Restoring should succeed");
+    assert_eq!(tree.root(), restored.root());
+    assert!(tree.leaves().eq(restored.leaves()));
+    assert_eq!(tree.export_nodes(), restored.export_nodes());
+
+    let branch = restored.branch(1024).expect("The branch should exist");
+    assert!(branch.verify(&tree.root(), &*branch));
+
+    let shallow = PoseidonTree::<MockLeaf, u64, 16>::restore(&path);
+    assert_eq!(
+        shallow.map(|_| ()).map_err(|e| e.kind()),
+        Err(ErrorKind::InvalidData)
+    );
+
+    // Tampering with the nodes is detected through the root
+    let bytes = fs::read(&path).expect("Reading should succeed");
+    let mut tampered = bytes.clone();
+    let last = tampered.len() - BlsScalar::SIZE;
+    tampered[last] ^= 1;
+    fs::write(&path, tampered).expect("Writing should succeed");
+    let tampered = Tree::restore(&path);
+    assert_eq!(
+        tampered.map(|_| ()).map_err(|e| e.kind()),
+        Err(ErrorKind::InvalidData)
+    );
+
+    // The leaves aren't hashed when restoring, so a tampered leaf is only
+    // detected by its opening
+    let mut tampered = bytes;
+    tampered[24] ^= 1;
+    fs::write(&path, tampered).expect("Writing should succeed");
+    let tampered = Tree::restore(&path).expect("Restoring should succeed");
+    assert_eq!(tampered.root(), tree.root());
+    assert_ne!(tampered.get(0), tree.get(0));
+
+    let branch = tampered.branch(0).expect("The branch should exist");
+    assert!(!branch.verify(&tree.root(), &*branch));
+
+    fs::remove_file(&path).expect("Removing should succeed");
+}
+
+#[test]
+fn tree_persist_restore_aggregate() {
+    let path = std::env::temp_dir().join("poseidon-tree-persist-aggregate");
+
+    let tree: PoseidonTree<MockLeaf, u64, 17, LeafCount> =
+        (0..1025).map(MockLeaf::from).collect();
+    tree.persist(&path).expect("Persisting should succeed");
+
+    // The aggregates are computed again from the leaves
+    let restored = PoseidonTree::<MockLeaf, u64, 17, LeafCount>::restore(&path)
+        .expect("Restoring should succeed");
+    assert_eq!(restored.root(), tree.root());
+    assert_eq!(restored.aggregate(), LeafCount(1025));
+
+    fs::remove_file(&path).expect("Removing should succeed");
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn tree_view() {
+    use bytes::Serializable;
+    use poseidon::tree::PoseidonTreeView;
+
+    let path = std::env::temp_dir().join("poseidon-tree-view");
+
+    let empty = Tree::new();
+    empty.persist(&path).expect("Persisting should succeed");
+    let view = PoseidonTreeView::<MockLeaf, 17>::open(&path)
+        .expect("Opening the view should succeed");
+    assert!(view.is_empty());
+    assert_eq!(view.root(), empty.root());
+    assert!(view.branch(0).is_none());
+
+    let tree: Tree = (0..1025).map(MockLeaf::from).collect();
+    tree.persist(&path).expect("Persisting should succeed");
+
+    let view = PoseidonTreeView::<MockLeaf, 17>::open(&path)
+        .expect("Opening the view should succeed");
+    assert_eq!(view.len(), 1025);
+    assert_eq!(view.root(), tree.root());
+
+    for pos in [0, 1, 4, 17, 1023, 1024] {
+        assert_eq!(view.get(pos), tree.get(pos));
+
+        let branch = view.branch(pos).expect("The branch should exist");
+        let expected = tree.branch(pos).expect("The branch should exist");
+        assert_eq!(branch.to_bytes(), expected.to_bytes());
+    }
+    assert!(view.get(1025).is_none());
+    assert!(view.branch(1025).is_none());
+
+    let shallow = PoseidonTreeView::<MockLeaf, 16>::open(&path);
+    assert_eq!(
+        shallow.map(|_| ()).map_err(|e| e.kind()),
+        Err(ErrorKind::InvalidData)
+    );
+
+    fs::remove_file(&path).expect("Removing should succeed");
+}
@@ -0,0 +1,176 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+use bls12_381::BlsScalar;
+use plonk::error::Error as PlonkError;
+use poseidon::params::Error;
+use poseidon::poseidon2::Poseidon2;
+use poseidon::sponge;
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
+
+use plonk::prelude::*;
+
+const CAPACITY: usize = 13;
+
+#[test]
+fn poseidon2_vectors() {
+    // Test vectors of the reference implementation of the Poseidon2 paper,
+    // permuting the state `[0, 1, ..., t - 1]`
+    let poseidon2: Poseidon2 =
+        Poseidon2::generate(2, 8, 56).expect("the width is supported");
+    let mut state = [BlsScalar::zero(), BlsScalar::one()];
+    poseidon2.permute(&mut state);
+
+    let expected = [
+        BlsScalar::from_raw([
+            0xe189fb945a15ce4e,
+            0xd30fc3d09f16531f,
+            0x7b61d19e67fa1b4e,
+            0x73c46dd530e248a8,
+        ]),
+        BlsScalar::from_raw([
+            0x4578262891b043b4,
+            0x2a3fee32b9dd0b5f,
+            0xd5793b8025140503,
+            0x1f0e305ee21c9366,
+        ]),
+    ];
+    assert_eq!(state, expected);
+
+    let poseidon2: Poseidon2 =
+        Poseidon2::generate(3, 8, 56).expect("the width is supported");
+    let mut state = [BlsScalar::zero(), BlsScalar::one(), 2.into()];
+    poseidon2.permute(&mut state);
+
+    let expected = [
+        BlsScalar::from_raw([
+            0xef3fd45761fbf5f0,
+            0xca5cca5650534e56,
+            0x8ca75ee4407b6e26,
+            0x1b152349b1950b6a,
+        ]),
+        BlsScalar::from_raw([
+            0xc5f36919af5b3891,
+            0x00bd0275868f09eb,
+            0xc08a32108437dc00,
+            0x4c5793c87d51bdc2,
+        ]),
+        BlsScalar::from_raw([
+            0x3b8125f08b50dc6b,
+            0x25318843d1397614,
+            0xa49863159fe5ba63,
+            0x1fc8ed171e67902c,
+        ]),
+    ];
+    assert_eq!(state, expected);
+}
+
+#[test]
+fn poseidon2_invalid() {
+    // The internal diagonal of the other widths isn't fixed by the paper
+    assert!(matches!(
+        Poseidon2::<BlsScalar>::generate(4, 8, 56),
+        Err(Error::InvalidWidth(4))
+    ));
+    assert!(matches!(
+        Poseidon2::<BlsScalar>::generate_with_diagonal(5, 8, 56, vec![]),
+        Err(Error::InvalidWidth(5))
+    ));
+    assert!(matches!(
+        Poseidon2::<BlsScalar>::generate_with_diagonal(4, 8, 56, vec![]),
+        Err(Error::InternalDiagonal {
+            found: 0,
+            expected: 4
+        })
+    ));
+}
+
+#[derive(Default, Debug)]
+pub struct TestPoseidon2Circuit {
+    width: usize,
+    input: Vec<BlsScalar>,
+    output: Vec<BlsScalar>,
+    hash: BlsScalar,
+}
+
+fn test_poseidon2(width: usize) -> Poseidon2 {
+    match width {
+        3 => Poseidon2::generate(3, 8, 56),
+        // The diagonal is arbitrary, it's only used to check the blocks of
+        // the external matrix against the native permutation
+        _ => Poseidon2::generate_with_diagonal(
+            width,
+            8,
+            57,
+            (1..=width as u64).map(BlsScalar::from).collect(),
+        ),
+    }
+    .expect("The width should be supported")
+}
+
+impl Circuit for TestPoseidon2Circuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let poseidon2 = test_poseidon2(self.width);
+
+        let mut state: Vec<Witness> = self
+            .input
+            .iter()
+            .map(|i| composer.append_witness(*i))
+            .collect();
+        let hash = sponge::gadget_with(composer, &poseidon2, &state);
+
+        poseidon2.gadget(composer, &mut state);
+
+        state.iter().zip(self.output.iter()).for_each(|(s, o)| {
+            let o = composer.append_witness(*o);
+            composer.assert_equal(o, *s);
+        });
+
+        let h = composer.append_witness(self.hash);
+        composer.assert_equal(h, hash);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn poseidon2_gadget() -> Result<(), PlonkError> {
+    let label = b"poseidon2-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    for width in [3, 8] {
+        let poseidon2 = test_poseidon2(width);
+        let input: Vec<BlsScalar> =
+            (0..width).map(|_| BlsScalar::random(&mut rng)).collect();
+        let hash = sponge::hash_with(&poseidon2, &input);
+        let mut output = input.clone();
+        poseidon2.permute(&mut output);
+
+        let circuit = TestPoseidon2Circuit {
+            width,
+            input,
+            output,
+            hash,
+        };
+
+        let (prover, verifier) =
+            Compiler::compile_with_circuit(&pp, label, &circuit)?;
+
+        let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+
+        verifier.verify(&proof, &public_inputs)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(all(feature = "alloc", feature = "proto"))]
+
+mod max_annotation;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::Serializable;
+use max_annotation::MockLeaf;
+use poseidon::proto::{Branch, Root};
+use poseidon::tree::{PoseidonBranch, PoseidonTree};
+use prost::Message;
+
+const DEPTH: usize = 17;
+
+type Tree = PoseidonTree<MockLeaf, u64, DEPTH>;
+
+#[test]
+fn proto_roundtrip() {
+    let mut tree = Tree::new();
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+    }
+    let branch = tree.branch(33).expect("The branch should exist");
+
+    let encoded = Branch::from(&branch).encode_to_vec();
+    let proto = Branch::decode(&encoded[..]).expect("Decoding should succeed");
+    let decoded = PoseidonBranch::<DEPTH>::try_from(&proto)
+        .expect("The message should be a valid branch");
+    assert_eq!(branch.to_bytes(), decoded.to_bytes());
+
+    let root = Root::from(&tree.root());
+    let root = Root::decode(&root.encode_to_vec()[..])
+        .expect("Decoding should succeed");
+    let root =
+        BlsScalar::try_from(&root).expect("The message should be a valid root");
+    assert_eq!(root, tree.root());
+
+    let mut bad_depth = proto.clone();
+    bad_depth.path.pop();
+    assert!(PoseidonBranch::<DEPTH>::try_from(&bad_depth).is_err());
+
+    let mut bad_offset = proto;
+    bad_offset.path[0].offset = 0;
+    assert!(PoseidonBranch::<DEPTH>::try_from(&bad_offset).is_err());
+}
@@ -0,0 +1,96 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "r1cs")]
+
+use ark_bls12_381::Fr;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::r1cs::ConstraintSystem;
+use bls12_381::Scalar as BlsScalar;
+use ff::Field;
+use poseidon::arkworks::{from_ark, to_ark};
+use poseidon::params::{Params, SecurityLevel};
+use poseidon::r1cs;
+use poseidon::sponge;
+use poseidon::tree::{self, PoseidonBranch};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn r1cs_sponge() {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let params = Params::with_security(3, SecurityLevel::Bits128)
+        .expect("the width is supported");
+
+    let messages: Vec<BlsScalar> =
+        (0..6).map(|_| BlsScalar::random(&mut rng)).collect();
+
+    for len in 0..=messages.len() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let witnesses = messages[..len]
+            .iter()
+            .map(|m| FpVar::new_witness(cs.clone(), || Ok(to_ark(m))))
+            .collect::<Result<Vec<_>, _>>()
+            .expect("the messages should be allocated");
+
+        let hash = r1cs::sponge_gadget(&params, &witnesses)
+            .expect("the hash should be synthesized");
+        let hash = hash.value().expect("the hash should have a value");
+
+        assert_eq!(
+            from_ark(&hash),
+            sponge::hash_with_params(&params, &messages[..len])
+        );
+        assert!(cs.is_satisfied().expect("the system should be checked"));
+    }
+}
+
+#[test]
+fn r1cs_merkle_opening() {
+    let params = Params::with_security(hades::WIDTH, SecurityLevel::Bits128)
+        .expect("the width is supported");
+    let leaves: Vec<BlsScalar> = (0..16).map(BlsScalar::from).collect();
+    let root = tree::compute_root_with::<_, 2>(&params, &leaves)
+        .expect("The leaves should fit");
+
+    // Open the third leaf of the second group
+    let nodes: Vec<BlsScalar> = leaves
+        .chunks(4)
+        .map(|group| tree::compute_root_with::<_, 1>(&params, group))
+        .collect::<Option<_>>()
+        .expect("The groups should fit");
+    let mut siblings = [[BlsScalar::zero(); 4]; 2];
+    siblings[0].copy_from_slice(&leaves[4..8]);
+    siblings[1].copy_from_slice(&nodes);
+    let branch = PoseidonBranch::<2>::from_path_with(
+        &params,
+        leaves[6],
+        &siblings,
+        &[2, 1],
+        &[4, 4],
+    )
+    .expect("The path should be valid");
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let leaf = FpVar::new_witness(cs.clone(), || Ok(to_ark(&leaves[6])))
+        .expect("the leaf should be allocated");
+    let computed = r1cs::merkle_opening(&params, &branch, &leaf)
+        .expect("the opening should be synthesized");
+
+    let computed = computed.value().expect("the root should have a value");
+    assert_eq!(from_ark(&computed), root);
+    assert!(cs.is_satisfied().expect("the system should be checked"));
+
+    // The leaf must be found at the offset of the branch
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let leaf = FpVar::new_witness(cs.clone(), || Ok(to_ark(&leaves[5])))
+        .expect("the leaf should be allocated");
+    r1cs::merkle_opening(&params, &branch, &leaf)
+        .expect("the opening should be synthesized");
+    assert!(!cs.is_satisfied().expect("the system should be checked"));
+}
@@ -0,0 +1,81 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(all(feature = "alloc", feature = "sled"))]
+
+mod max_annotation;
+
+use std::fs;
+
+use bls12_381::Scalar as BlsScalar;
+use bytes::Serializable;
+use max_annotation::MockLeaf;
+use poseidon::tree::{PoseidonLeaf, PoseidonTree, SledStore, TreeStore};
+
+const DEPTH: usize = 17;
+
+type Store = SledStore<MockLeaf, { MockLeaf::SIZE }>;
+type Tree = PoseidonTree<MockLeaf, u64, DEPTH, (), Store>;
+
+#[test]
+fn sled_store_reopen() {
+    let path = std::env::temp_dir().join("poseidon-tree-sled-reopen");
+    let _ = fs::remove_dir_all(&path);
+
+    let mut expected = PoseidonTree::<MockLeaf, u64, DEPTH>::new();
+
+    let store = Store::open(&path).expect("Opening should succeed");
+    let mut tree = Tree::open(store);
+    assert!(tree.is_empty().expect("Reading should succeed"));
+
+    for i in 0..42 {
+        expected.push(MockLeaf::from(i));
+        tree.push(MockLeaf::from(i))
+            .expect("Pushing should succeed");
+    }
+    tree.update(17, MockLeaf::from(1000))
+        .expect("Updating should succeed");
+    expected.update(17, MockLeaf::from(1000));
+    drop(tree);
+
+    let store = Store::open(&path).expect("Opening should succeed");
+    let tree = Tree::open(store);
+    assert_eq!(tree.len().expect("Reading should succeed"), 42);
+    assert_eq!(tree.root().unwrap(), expected.root());
+
+    let leaf = tree.get(17).unwrap().expect("The leaf should exist");
+    assert_eq!(leaf.pos, 17);
+    assert_eq!(leaf.poseidon_hash(), BlsScalar::from(1000));
+
+    let branch = tree.branch(17).unwrap().expect("The branch should exist");
+    assert!(branch.verify(&expected.root(), &BlsScalar::from(1000)));
+
+    drop(tree);
+    fs::remove_dir_all(&path).expect("Removing should succeed");
+}
+
+#[test]
+fn sled_store_uncommitted() {
+    let path = std::env::temp_dir().join("poseidon-tree-sled-uncommitted");
+    let _ = fs::remove_dir_all(&path);
+
+    let mut store = Store::open(&path).expect("Opening should succeed");
+    TreeStore::put_node(&mut store, 0, 0, BlsScalar::one())
+        .expect("Writing should succeed");
+    assert_eq!(
+        TreeStore::get_node(&store, 0, 0).unwrap(),
+        Some(BlsScalar::one())
+    );
+    drop(store);
+
+    // Writes that were never committed are lost
+    let store = Store::open(&path).expect("Opening should succeed");
+    assert_eq!(TreeStore::get_node(&store, 0, 0).unwrap(), None);
+    assert_eq!(TreeStore::committed_len(&store).unwrap(), 0);
+
+    drop(store);
+    fs::remove_dir_all(&path).expect("Removing should succeed");
+}
@@ -0,0 +1,251 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(feature = "alloc")]
+
+use plonk::error::Error as PlonkError;
+use poseidon::smt::{self, Error, SmtOpening, SparseMerkleTree};
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
+
+use plonk::prelude::*;
+
+const DEPTH: usize = 16;
+const CAPACITY: usize = 15;
+
+type Tree = SparseMerkleTree<DEPTH>;
+
+#[derive(Default)]
+struct SmtOpeningCircuit {
+    opening: SmtOpening<DEPTH>,
+    key: BlsScalar,
+    value: BlsScalar,
+    root: BlsScalar,
+}
+
+impl Circuit for SmtOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let key = composer.append_witness(self.key);
+        let value = composer.append_witness(self.value);
+        let root = composer.append_public(self.root);
+
+        let root_p = smt::merkle_opening(composer, &self.opening, key, value);
+
+        composer.assert_equal(root_p, root);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct SmtNonMembershipCircuit {
+    opening: SmtOpening<DEPTH>,
+    key: BlsScalar,
+    root: BlsScalar,
+}
+
+impl Circuit for SmtNonMembershipCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let key = composer.append_witness(self.key);
+        let root = composer.append_public(self.root);
+
+        let root_p = smt::non_membership(composer, &self.opening, key);
+
+        composer.assert_equal(root_p, root);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn smt_insert_get_remove() {
+    let mut tree = Tree::new();
+    assert!(tree.is_empty());
+    assert_eq!(tree.root(), Tree::empty_root());
+
+    let key = BlsScalar::from(42);
+    assert_eq!(tree.insert(key, BlsScalar::from(1)), Ok(None));
+    assert_eq!(
+        tree.insert(key, BlsScalar::from(2)),
+        Ok(Some(BlsScalar::from(1)))
+    );
+    assert_eq!(tree.get(&key), Some(BlsScalar::from(2)));
+    assert_eq!(tree.len(), 1);
+
+    // Keys sharing their least significant bits address the same leaf
+    let other = BlsScalar::from(42 + (1 << DEPTH));
+    assert_eq!(tree.get(&other), None);
+    assert_eq!(
+        tree.insert(other, BlsScalar::from(3)),
+        Err(Error::KeyCollision)
+    );
+    assert_eq!(tree.remove(&other), None);
+
+    assert_eq!(tree.remove(&key), Some(BlsScalar::from(2)));
+    assert_eq!(tree.get(&key), None);
+    assert_eq!(tree.root(), Tree::empty_root());
+}
+
+#[test]
+fn smt_root_is_order_independent() {
+    let mut tree = Tree::new();
+    let mut other = Tree::new();
+
+    for i in 0..50u64 {
+        tree.insert(BlsScalar::from(i * 7919), BlsScalar::from(i))
+            .expect("keys should not collide");
+    }
+    for i in (0..50u64).rev() {
+        other
+            .insert(BlsScalar::from(i * 7919), BlsScalar::from(i))
+            .expect("keys should not collide");
+    }
+
+    assert_eq!(tree.root(), other.root());
+    assert_ne!(tree.root(), Tree::empty_root());
+}
+
+#[test]
+fn smt_opening() {
+    let mut tree = Tree::new();
+
+    for i in 0..20u64 {
+        tree.insert(BlsScalar::from(i * 31), BlsScalar::from(i))
+            .expect("keys should not collide");
+    }
+
+    let root = tree.root();
+    let key = BlsScalar::from(5 * 31);
+    let opening = tree.opening(&key);
+
+    assert!(opening.verify(&root, &key, &BlsScalar::from(5)));
+    assert!(!opening.verify(&root, &key, &BlsScalar::from(6)));
+    assert!(!opening.verify(&root, &BlsScalar::from(31), &BlsScalar::from(5)));
+
+    // The opening of a missing key shows the root after inserting it
+    let key = BlsScalar::from(1000);
+    let opening = tree.opening(&key);
+    tree.insert(key, BlsScalar::from(7))
+        .expect("keys should not collide");
+
+    assert!(opening.verify(&tree.root(), &key, &BlsScalar::from(7)));
+}
+
+#[test]
+fn smt_merkle_opening() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) = Compiler::compile::<SmtOpeningCircuit>(&pp, label)
+        .expect("Circuit should compile successfully");
+
+    let mut tree = Tree::new();
+    for i in 0..10u64 {
+        tree.insert(BlsScalar::from(i * 3), BlsScalar::random(&mut rng))
+            .expect("keys should not collide");
+    }
+
+    let key = BlsScalar::from(0xbeef);
+    let value = BlsScalar::random(&mut rng);
+    tree.insert(key, value).expect("keys should not collide");
+
+    let mut circuit = SmtOpeningCircuit {
+        opening: tree.opening(&key),
+        key,
+        value,
+        root: tree.root(),
+    };
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proof generation should succeed");
+
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // The key can't be opened with another value
+    circuit.value = BlsScalar::random(&mut rng);
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
+
+#[test]
+fn smt_non_membership() {
+    let mut tree = Tree::new();
+
+    for i in 0..20u64 {
+        tree.insert(BlsScalar::from(i * 31), BlsScalar::from(i))
+            .expect("keys should not collide");
+    }
+
+    let root = tree.root();
+    let key = BlsScalar::from(1000);
+    let opening = tree
+        .non_membership(&key)
+        .expect("the key should not be in the tree");
+
+    assert!(opening.verify_non_membership(&root, &key));
+    assert!(!opening.verify_non_membership(&root, &BlsScalar::from(1001)));
+
+    // Neither a key in the tree nor a key sharing its leaf can be proven absent
+    assert!(tree.non_membership(&BlsScalar::from(31)).is_none());
+    assert!(tree
+        .non_membership(&BlsScalar::from(31 + (1 << DEPTH)))
+        .is_none());
+
+    let opening = tree.opening(&BlsScalar::from(31));
+    assert!(!opening.verify_non_membership(&root, &BlsScalar::from(31)));
+}
+
+#[test]
+fn smt_non_membership_gadget() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<SmtNonMembershipCircuit>(&pp, label)
+            .expect("Circuit should compile successfully");
+
+    let mut tree = Tree::new();
+    for i in 0..10u64 {
+        tree.insert(BlsScalar::from(i * 3), BlsScalar::random(&mut rng))
+            .expect("keys should not collide");
+    }
+
+    let key = BlsScalar::from(0xbeef);
+    let mut circuit = SmtNonMembershipCircuit {
+        opening: tree
+            .non_membership(&key)
+            .expect("the key should not be in the tree"),
+        key,
+        root: tree.root(),
+    };
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proof generation should succeed");
+
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // A key in the tree can't be proven absent
+    circuit.key = BlsScalar::from(3);
+    circuit.opening = tree.opening(&circuit.key);
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
@@ -9,6 +9,7 @@
 use bls12_381::BlsScalar;
 use bytes::ParseHexStr;
 use plonk::error::Error as PlonkError;
+use poseidon::params::{Params, SecurityLevel};
 use poseidon::sponge;
 use rand::rngs::{OsRng, StdRng};
 use rand::SeedableRng;
@@ -119,6 +120,447 @@ fn sponge_gadget() -> Result<(), Error> {
     Ok(())
 }
 
+#[derive(Default, Debug)]
+pub struct TestPublicCircuit {
+    input: [BlsScalar; 3],
+    output: BlsScalar,
+}
+
+impl Circuit for TestPublicCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let computed = sponge::gadget_public(composer, &self.input);
+
+        let o = composer.append_witness(self.output);
+        composer.assert_equal(o, computed);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn sponge_public_gadget() -> Result<(), Error> {
+    let label = b"sponge-public-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<TestPublicCircuit>(&pp, label)?;
+
+    let input = [(); 3].map(|_| BlsScalar::random(&mut rng));
+    let output = sponge::hash(&input);
+    let circuit = TestPublicCircuit { input, output };
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+
+    // The messages are the public inputs of the circuit
+    assert_eq!(public_inputs, input);
+    verifier.verify(&proof, &public_inputs)?;
+
+    let mut other = input;
+    other[1] = BlsScalar::one();
+    assert!(verifier.verify(&proof, &other).is_err());
+
+    Ok(())
+}
+
+#[derive(Default, Debug)]
+pub struct TestPointsCircuit {
+    points: [JubJubAffine; 2],
+    output: BlsScalar,
+}
+
+impl Circuit for TestPointsCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let points = self.points.map(|p| composer.append_point(p));
+        let computed = sponge::gadget_points(composer, &points);
+
+        let o = composer.append_witness(self.output);
+        composer.assert_equal(o, computed);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn sponge_points_gadget() -> Result<(), Error> {
+    let label = b"sponge-points-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<TestPointsCircuit>(&pp, label)?;
+
+    let points = [(); 2].map(|_| {
+        JubJubAffine::from(
+            jubjub::GENERATOR_EXTENDED * JubJubScalar::random(&mut rng),
+        )
+    });
+
+    // The points are hashed as their coordinates
+    let output = sponge::hash_points(&points);
+    let coordinates = [
+        points[0].get_u(),
+        points[0].get_v(),
+        points[1].get_u(),
+        points[1].get_v(),
+    ];
+    assert_eq!(output, sponge::hash(&coordinates));
+
+    let circuit = TestPointsCircuit { points, output };
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    verifier.verify(&proof, &public_inputs)?;
+
+    // The order of the points is part of the hash
+    let output = sponge::hash_points(&[points[1], points[0]]);
+    let circuit = TestPointsCircuit { points, output };
+    assert!(prover.prove(&mut rng, &circuit).is_err());
+
+    Ok(())
+}
+
+const OUT: usize = 3;
+
+#[derive(Default, Debug)]
+pub struct TestMultiOutputCircuit {
+    input: [BlsScalar; 3],
+    output: [BlsScalar; OUT],
+}
+
+impl Circuit for TestMultiOutputCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let i = self.input.map(|i| composer.append_witness(i));
+        let computed = sponge::gadget_n::<_, OUT>(composer, &i);
+
+        let o = self.output.map(|o| composer.append_witness(o));
+        o.iter()
+            .zip(computed.iter())
+            .for_each(|(o, c)| composer.assert_equal(*o, *c));
+
+        Ok(())
+    }
+}
+
+#[test]
+fn sponge_multi_output_gadget() -> Result<(), Error> {
+    let label = b"sponge-multi-output-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<TestMultiOutputCircuit>(&pp, label)?;
+
+    let input = [(); 3].map(|_| BlsScalar::random(&mut rng));
+    let output = sponge::hash_n::<OUT>(&input);
+
+    // The first output is the hash of the message
+    assert_eq!(output[0], sponge::hash(&input));
+    assert_ne!(output[1], output[2]);
+
+    let circuit = TestMultiOutputCircuit { input, output };
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    verifier.verify(&proof, &public_inputs)?;
+
+    let mut output = output;
+    output.swap(1, 2);
+    let circuit = TestMultiOutputCircuit { input, output };
+    assert!(prover.prove(&mut rng, &circuit).is_err());
+
+    Ok(())
+}
+
+const TAG: u64 = 0xbeef;
+
+#[derive(Default, Debug)]
+pub struct TestMixedCircuit {
+    input: [BlsScalar; 2],
+    output: BlsScalar,
+}
+
+impl Circuit for TestMixedCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let a = composer.append_witness(self.input[0]);
+        let b = composer.append_witness(self.input[1]);
+
+        let messages = [
+            sponge::Message::from(BlsScalar::from(TAG)),
+            sponge::Message::from(a),
+            sponge::Message::from(BlsScalar::one()),
+            sponge::Message::from(b),
+        ];
+        let computed = sponge::gadget_mixed(composer, &messages);
+
+        let o = composer.append_witness(self.output);
+        composer.assert_equal(o, computed);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn sponge_mixed_gadget() -> Result<(), Error> {
+    let label = b"sponge-mixed-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) = Compiler::compile::<TestMixedCircuit>(&pp, label)?;
+
+    let a = BlsScalar::random(&mut rng);
+    let b = BlsScalar::random(&mut rng);
+
+    let input = [a, b];
+    let output = sponge::hash(&[BlsScalar::from(TAG), a, BlsScalar::one(), b]);
+    let circuit = TestMixedCircuit { input, output };
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    verifier.verify(&proof, &public_inputs)?;
+
+    // The constants are part of the hashed message
+    let output = sponge::hash(&[a, b]);
+    let circuit = TestMixedCircuit { input, output };
+    assert!(prover.prove(&mut rng, &circuit).is_err());
+
+    Ok(())
+}
+
+#[derive(Default, Debug)]
+pub struct TestMixedRepeatedCircuit {
+    input: BlsScalar,
+    output: BlsScalar,
+}
+
+impl Circuit for TestMixedRepeatedCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let a = composer.append_witness(self.input);
+
+        // The repeated constants and the padding share their gate
+        let messages = [
+            sponge::Message::from(BlsScalar::one()),
+            sponge::Message::from(a),
+            sponge::Message::from(BlsScalar::one()),
+        ];
+        let computed = sponge::gadget_mixed(composer, &messages);
+
+        let o = composer.append_witness(self.output);
+        composer.assert_equal(o, computed);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn sponge_mixed_gadget_repeated() -> Result<(), Error> {
+    let label = b"sponge-mixed-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<TestMixedRepeatedCircuit>(&pp, label)?;
+
+    let input = BlsScalar::random(&mut rng);
+    let output = sponge::hash(&[BlsScalar::one(), input, BlsScalar::one()]);
+    let circuit = TestMixedRepeatedCircuit { input, output };
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    verifier.verify(&proof, &public_inputs)?;
+
+    Ok(())
+}
+
+const DOMAIN: u64 = 0xdead;
+
+#[derive(Default, Debug)]
+pub struct TestDomainCircuit {
+    input: [BlsScalar; 3],
+    output: BlsScalar,
+}
+
+impl Circuit for TestDomainCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let i = self.input.map(|i| composer.append_witness(i));
+        let computed = sponge::gadget_with_domain(composer, DOMAIN, &i);
+
+        let o = composer.append_witness(self.output);
+        composer.assert_equal(o, computed);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn sponge_domain_gadget() -> Result<(), Error> {
+    let label = b"sponge-domain-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<TestDomainCircuit>(&pp, label)?;
+
+    let input = [(); 3].map(|_| BlsScalar::random(&mut rng));
+
+    // The domain `0` is the one of the plain hash
+    assert_eq!(sponge::hash_with_domain(0, &input), sponge::hash(&input));
+    assert_ne!(
+        sponge::hash_with_domain(DOMAIN, &input),
+        sponge::hash(&input)
+    );
+
+    let output = sponge::hash_with_domain(DOMAIN, &input);
+    let circuit = TestDomainCircuit { input, output };
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    verifier.verify(&proof, &public_inputs)?;
+
+    // The hash of another domain isn't accepted
+    let output = sponge::hash_with_domain(DOMAIN + 1, &input);
+    let circuit = TestDomainCircuit { input, output };
+    assert!(prover.prove(&mut rng, &circuit).is_err());
+
+    Ok(())
+}
+
+const VAR_LEN: usize = 6;
+
+#[derive(Default, Debug)]
+pub struct TestVarLenCircuit {
+    input: [BlsScalar; VAR_LEN],
+    len: usize,
+    output: BlsScalar,
+}
+
+impl Circuit for TestVarLenCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let i = self.input.map(|i| composer.append_witness(i));
+        let len = composer.append_witness(BlsScalar::from(self.len as u64));
+
+        let computed = sponge::gadget_var_len(composer, &i, len);
+
+        let o = composer.append_witness(self.output);
+        composer.assert_equal(o, computed);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn sponge_var_len_gadget() -> Result<(), Error> {
+    let label = b"sponge-var-len-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<TestVarLenCircuit>(&pp, label)?;
+
+    // The scalars past the length of the message are ignored
+    let input = [(); VAR_LEN].map(|_| BlsScalar::random(&mut rng));
+
+    for len in 0..=VAR_LEN {
+        let output = sponge::hash(&input[..len]);
+        let circuit = TestVarLenCircuit { input, len, output };
+
+        let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+        verifier.verify(&proof, &public_inputs)?;
+
+        // The hash of a message of another length isn't accepted
+        let output = sponge::hash(&input[..(len + 1) % (VAR_LEN + 1)]);
+        let circuit = TestVarLenCircuit { input, len, output };
+        assert!(prover.prove(&mut rng, &circuit).is_err());
+    }
+
+    // The length can't exceed the number of scalars of the circuit
+    let output = sponge::hash(&input);
+    let len = VAR_LEN + 1;
+    let circuit = TestVarLenCircuit { input, len, output };
+    assert!(prover.prove(&mut rng, &circuit).is_err());
+
+    Ok(())
+}
+
+#[derive(Default, Debug)]
+pub struct TestParamsCircuit {
+    input: Vec<BlsScalar>,
+    output: BlsScalar,
+}
+
+fn test_params() -> Params {
+    Params::with_security(3, SecurityLevel::Bits128)
+        .expect("The width should be supported")
+}
+
+impl Circuit for TestParamsCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let i: Vec<Witness> = self
+            .input
+            .iter()
+            .map(|i| composer.append_witness(*i))
+            .collect();
+
+        let computed = sponge::gadget_with_params(composer, &test_params(), &i);
+
+        let o = composer.append_witness(self.output);
+        composer.assert_equal(o, computed);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn sponge_params_gadget() -> Result<(), Error> {
+    let label = b"sponge-params-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+    let params = test_params();
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    for w in [1, 2, 5] {
+        let input: Vec<BlsScalar> =
+            (0..w).map(|_| BlsScalar::random(&mut rng)).collect();
+        let output = sponge::hash_with_params(&params, &input);
+        let circuit = TestParamsCircuit { input, output };
+
+        let (prover, verifier) =
+            Compiler::compile_with_circuit(&pp, label, &circuit)?;
+
+        let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+
+        verifier.verify(&proof, &public_inputs)?;
+    }
+
+    Ok(())
+}
+
 #[test]
 fn sponge_hash_test() {
     let test_inputs: Vec<BlsScalar> = TEST_INPUTS
@@ -0,0 +1,100 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(all(feature = "alloc", feature = "std"))]
+
+mod max_annotation;
+
+use bls12_381::BlsScalar;
+use max_annotation::MockLeaf;
+use poseidon::tree::{PoseidonTree, SyncPoseidonTree};
+use std::sync::Arc;
+use std::thread;
+
+const DEPTH: usize = 17;
+
+type Tree = SyncPoseidonTree<MockLeaf, u64, DEPTH>;
+
+#[test]
+fn sync_tree_concurrent_reads() {
+    let plain: PoseidonTree<MockLeaf, u64, DEPTH> =
+        (0..100).map(MockLeaf::from).collect();
+    let root = plain.root();
+
+    let tree = Arc::new(Tree::from(plain));
+
+    let readers: Vec<_> = (0..4)
+        .map(|t| {
+            let tree = Arc::clone(&tree);
+            thread::spawn(move || {
+                for pos in (t..100).step_by(4) {
+                    let branch = tree.branch(pos).expect("the leaf exists");
+                    assert!(branch.verify(&root, &BlsScalar::from(pos)));
+                }
+            })
+        })
+        .collect();
+
+    readers
+        .into_iter()
+        .for_each(|r| r.join().expect("the reader shouldn't panic"));
+}
+
+#[test]
+fn sync_tree_serialized_writes() {
+    let tree = Arc::new(Tree::new());
+
+    let writers: Vec<_> = (0..4)
+        .map(|t| {
+            let tree = Arc::clone(&tree);
+            thread::spawn(move || {
+                for i in 0..25 {
+                    tree.push(MockLeaf::from(t * 25 + i));
+                }
+            })
+        })
+        .collect();
+
+    writers
+        .into_iter()
+        .for_each(|w| w.join().expect("the writer shouldn't panic"));
+
+    // Every leaf was appended once, on its own position
+    let tree = Arc::try_unwrap(tree).expect("the writers are done");
+    let tree = tree.into_inner();
+    let mut positions: Vec<u64> = tree.leaves().map(|l| l.pos).collect();
+    positions.sort_unstable();
+    assert_eq!(positions, (0..100).collect::<Vec<_>>());
+
+    let shared = Tree::from(tree);
+    assert_eq!(shared.get(42).map(|l| l.pos), Some(42));
+    assert!(shared.update(42, MockLeaf::from(1000)).is_some());
+    assert_eq!(shared.root(), shared.snapshot().root());
+}
+
+#[test]
+fn sync_tree_snapshots() {
+    let plain: PoseidonTree<MockLeaf, u64, DEPTH> =
+        (0..10).map(MockLeaf::from).collect();
+    let tree = Tree::from(plain);
+    let before = tree.snapshot();
+
+    // The reads go on with the published snapshot during a write
+    let mut writer = tree.write();
+    writer.push(MockLeaf::from(10));
+    writer.push(MockLeaf::from(11));
+    assert_eq!(tree.root(), before.root());
+    assert_eq!(tree.get(10), None);
+
+    let root = writer.root();
+    drop(writer);
+
+    // The whole write is published at once, leaving the old snapshot alone
+    assert_eq!(tree.root(), root);
+    assert_eq!(tree.get(11).map(|l| l.pos), Some(11));
+    assert_eq!(before.get(11), None);
+    assert_ne!(before.root(), root);
+}
@@ -0,0 +1,85 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+#![cfg(all(feature = "alloc", feature = "transcript"))]
+
+use bls12_381::Scalar as BlsScalar;
+use ff::Field;
+use jubjub::{AffinePoint as JubJubAffine, Scalar as JubJubScalar};
+use plonk::error::Error as PlonkError;
+use poseidon::transcript::{PoseidonTranscript, TranscriptGadget};
+use rand::rngs::{OsRng, StdRng};
+use rand::SeedableRng;
+
+use plonk::prelude::*;
+
+const CAPACITY: usize = 14;
+
+#[derive(Debug, Default)]
+struct TranscriptCircuit {
+    scalar: BlsScalar,
+    point: JubJubAffine,
+    challenge: BlsScalar,
+}
+
+impl TranscriptCircuit {
+    fn new(scalar: BlsScalar, point: JubJubAffine) -> Self {
+        let mut transcript = PoseidonTranscript::new(b"transcript-tester");
+        transcript.append_message(b"setup", b"public parameters");
+        transcript.absorb_scalar(b"scalar", &scalar);
+        transcript.absorb_point(b"point", &point);
+        let challenge = transcript.challenge_scalar(b"challenge");
+
+        Self {
+            scalar,
+            point,
+            challenge,
+        }
+    }
+}
+
+impl Circuit for TranscriptCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let scalar = composer.append_witness(self.scalar);
+        let point = composer.append_point(self.point);
+
+        let mut transcript =
+            TranscriptGadget::new(composer, b"transcript-tester");
+        transcript.append_message(composer, b"setup", b"public parameters");
+        transcript.absorb_scalar(composer, b"scalar", scalar);
+        transcript.absorb_point(composer, b"point", point);
+        let challenge = transcript.challenge_scalar(composer, b"challenge");
+
+        let expected = composer.append_witness(self.challenge);
+        composer.assert_equal(challenge, expected);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn transcript_gadget() -> Result<(), PlonkError> {
+    let label = b"transcript-tester";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng)?;
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<TranscriptCircuit>(&pp, label)?;
+
+    let scalar = BlsScalar::random(&mut rng);
+    let point = JubJubAffine::from(
+        jubjub::GENERATOR_EXTENDED * JubJubScalar::random(&mut rng),
+    );
+    let circuit = TranscriptCircuit::new(scalar, point);
+
+    let (proof, public_inputs) = prover.prove(&mut rng, &circuit)?;
+    verifier.verify(&proof, &public_inputs)?;
+
+    Ok(())
+}
@@ -8,9 +8,24 @@
 
 mod max_annotation;
 
+use bytes::{DeserializableSlice, Serializable};
+use core::borrow::Borrow;
+use core::cell::Cell;
+use core::ops::ControlFlow;
+use hades::{ScalarStrategy, Strategy};
 use plonk::error::Error as PlonkError;
-use poseidon::tree::{self, PoseidonBranch, PoseidonTree};
+use poseidon::params::{Params, SecurityLevel};
+use poseidon::permutation::{Hades, Permutation};
+use poseidon::sponge;
+use poseidon::tree::{
+    self, Aggregate, ArityBranch, BranchError, CompactBranch, CompactLevel,
+    DynPoseidonBranch, DynPoseidonTree, Forest, ForestOpening, FrontierTree,
+    HashedLeaf, KeyedScalarLeaf, MemoryStore, Node, OpeningCircuit,
+    PoseidonBranch, PoseidonLeaf, PoseidonLeafExt, PoseidonLevel,
+    PoseidonMultiProof, PoseidonTree, ScalarLeaf, Visit,
+};
 use max_annotation::MockLeaf;
+use nstack::annotation::{Keyed, MaxKey};
 use rand::rngs::{OsRng, StdRng};
 use rand::{CryptoRng, RngCore, SeedableRng};
 
@@ -20,6 +35,7 @@ const DEPTH: usize = 17;
 const CAPACITY: usize = 15;
 
 type Tree = PoseidonTree<MockLeaf, u64, DEPTH>;
+type MemoryTree = PoseidonTree<MockLeaf, u64, DEPTH, (), MemoryStore<MockLeaf>>;
 
 #[derive(Default)]
 struct MerkleOpeningCircuit {
@@ -64,6 +80,77 @@ impl Circuit for MerkleOpeningCircuit {
     }
 }
 
+#[derive(Default)]
+struct DynMerkleOpeningCircuit {
+    branch: DynPoseidonBranch,
+}
+
+impl Circuit for DynMerkleOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaf = composer.append_witness(*self.branch);
+        let root = composer.append_public(*self.branch.root());
+
+        let root_p = tree::dyn_merkle_opening(composer, &self.branch, leaf);
+
+        composer.assert_equal(root_p, root);
+
+        Ok(())
+    }
+}
+
+const TREES: usize = 3;
+
+#[derive(Default)]
+struct ForestOpeningCircuit {
+    opening: ForestOpening<DEPTH, TREES>,
+    leaf: BlsScalar,
+}
+
+impl Circuit for ForestOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaf = composer.append_witness(self.leaf);
+        let root = composer.append_public(self.opening.root());
+
+        let root_p = tree::forest_opening(composer, &self.opening, leaf);
+
+        composer.assert_equal(root_p, root);
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct MultiOpeningCircuit {
+    proof: PoseidonMultiProof<DEPTH>,
+    leaves: Vec<BlsScalar>,
+}
+
+impl Circuit for MultiOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaves: Vec<Witness> = self
+            .leaves
+            .iter()
+            .map(|leaf| composer.append_witness(*leaf))
+            .collect();
+        let root = composer.append_public(*self.proof.root());
+
+        let root_p = tree::merkle_multi_opening(composer, &self.proof, &leaves);
+
+        composer.assert_equal(root_p, root);
+
+        Ok(())
+    }
+}
+
 #[test]
 fn tree_merkle_opening() {
     let label = b"dusk-network";
@@ -85,3 +172,2044 @@ fn tree_merkle_opening() {
         .verify(&proof, &public_inputs)
         .expect("Proof verification should succeed");
 }
+
+#[derive(Default)]
+struct PublicOpeningCircuit {
+    branch: PoseidonBranch<DEPTH>,
+}
+
+impl Circuit for PublicOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaf = composer.append_witness(*self.branch);
+        tree::merkle_opening_public(composer, &self.branch, leaf);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn tree_merkle_opening_public() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<PublicOpeningCircuit>(&pp, label)
+            .expect("Circuit should compile successfully");
+
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+    let branch = tree.branch(21).expect("The branch should exist");
+    let circuit = PublicOpeningCircuit { branch };
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proving the circuit should succeed");
+
+    // The root is the only public input of the circuit
+    assert_eq!(public_inputs, [tree.root()]);
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    verifier
+        .verify(&proof, &[BlsScalar::from(42)])
+        .expect_err("Proof verification should fail with another root");
+}
+
+#[derive(Default)]
+struct PositionOpeningCircuit {
+    branch: PoseidonBranch<DEPTH>,
+    pos: u64,
+}
+
+impl Circuit for PositionOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaf = composer.append_witness(*self.branch);
+        let root = composer.append_public(*self.branch.root());
+        let pos = composer.append_public(BlsScalar::from(self.pos));
+
+        let (root_p, pos_p) =
+            tree::merkle_opening_with_pos(composer, &self.branch, leaf);
+
+        composer.assert_equal(root_p, root);
+        composer.assert_equal(pos_p, pos);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn tree_merkle_opening_with_pos() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<PositionOpeningCircuit>(&pp, label)
+            .expect("Circuit should compile successfully");
+
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+
+    for pos in [0, 7, 33, 41] {
+        let branch = tree.branch(pos).expect("The branch should exist");
+        let circuit = PositionOpeningCircuit { branch, pos };
+
+        let (proof, public_inputs) = prover
+            .prove(&mut rng, &circuit)
+            .expect("Proving the circuit should succeed");
+
+        verifier
+            .verify(&proof, &public_inputs)
+            .expect("Proof verification should succeed");
+    }
+
+    // The position is authenticated by the opening
+    let branch = tree.branch(33).expect("The branch should exist");
+    let circuit = PositionOpeningCircuit { branch, pos: 32 };
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
+
+const OPENINGS: usize = 4;
+
+#[derive(Default)]
+struct BatchOpeningCircuit {
+    branches: [PoseidonBranch<DEPTH>; OPENINGS],
+    leaves: [BlsScalar; OPENINGS],
+}
+
+impl Circuit for BatchOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaves: Vec<Witness> = self
+            .leaves
+            .iter()
+            .map(|leaf| composer.append_witness(*leaf))
+            .collect();
+
+        let roots = tree::merkle_openings(composer, &self.branches, &leaves);
+
+        for (branch, root_p) in self.branches.iter().zip(roots) {
+            let root = composer.append_public(*branch.root());
+            composer.assert_equal(root_p, root);
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn tree_merkle_openings() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << (CAPACITY + 2), &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<BatchOpeningCircuit>(&pp, label)
+            .expect("Circuit should compile successfully");
+
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+    let positions = [2, 3, 17, 41];
+    let branches =
+        positions.map(|pos| tree.branch(pos).expect("The branch should exist"));
+    let leaves = positions.map(BlsScalar::from);
+    let mut circuit = BatchOpeningCircuit { branches, leaves };
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proving the circuit should succeed");
+
+    assert_eq!(public_inputs, [tree.root(); OPENINGS]);
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // Every opening is checked
+    circuit.leaves[2] = BlsScalar::from(18);
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
+
+/// Parameters of a permutation of the width of `Hades`, generated for the
+/// tests of the trees hashed with another permutation.
+fn tree_params() -> Params {
+    Params::with_security(hades::WIDTH, SecurityLevel::Bits128)
+        .expect("the width is supported")
+}
+
+#[derive(Default)]
+struct ParamsOpeningCircuit {
+    branch: PoseidonBranch<2>,
+    leaf: BlsScalar,
+}
+
+impl Circuit for ParamsOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaf = composer.append_witness(self.leaf);
+        let root = composer.append_public(*self.branch.root());
+
+        let root_p = tree::merkle_opening_with(
+            composer,
+            &tree_params(),
+            &self.branch,
+            leaf,
+        );
+        composer.assert_equal(root_p, root);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn tree_opening_with_params() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << 13, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let params = tree_params();
+    let leaves: Vec<BlsScalar> = (0..16).map(BlsScalar::from).collect();
+
+    let root = tree::compute_root_with::<_, 2>(&params, &leaves)
+        .expect("The leaves should fit");
+    assert_ne!(Some(root), tree::compute_root::<2>(&leaves));
+    assert_eq!(
+        tree::compute_root_with::<_, 2>(&Hades, &leaves),
+        tree::compute_root::<2>(&leaves)
+    );
+
+    // Open the third leaf of the second group
+    let nodes: Vec<BlsScalar> = leaves
+        .chunks(4)
+        .map(|group| tree::compute_root_with::<_, 1>(&params, group))
+        .collect::<Option<_>>()
+        .expect("The groups should fit");
+    let mut siblings = [[BlsScalar::zero(); 4]; 2];
+    siblings[0].copy_from_slice(&leaves[4..8]);
+    siblings[1].copy_from_slice(&nodes);
+
+    let branch = PoseidonBranch::<2>::from_path_with(
+        &params,
+        leaves[6],
+        &siblings,
+        &[2, 1],
+        &[4, 4],
+    )
+    .expect("The path should be valid");
+    assert_eq!(*branch.root(), root);
+    assert!(branch.verify_with(&params, &root, &leaves[6]));
+    assert!(!branch.verify_with(&params, &root, &leaves[5]));
+    assert!(!branch.verify(&root, &leaves[6]));
+
+    let circuit = ParamsOpeningCircuit {
+        branch,
+        leaf: leaves[6],
+    };
+    let (prover, verifier) =
+        Compiler::compile_with_circuit(&pp, label, &circuit)
+            .expect("Circuit should compile successfully");
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proving the circuit should succeed");
+    assert_eq!(public_inputs, [root]);
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+}
+
+#[test]
+#[should_panic]
+fn tree_opening_with_narrow_params() {
+    let params = Params::with_security(3, SecurityLevel::Bits128)
+        .expect("the width is supported");
+
+    let _ = tree::compute_root_with::<_, 2>(&params, &[BlsScalar::one()]);
+}
+
+#[test]
+#[should_panic]
+fn tree_merkle_openings_different_trees() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << 4, &mut OsRng).unwrap();
+
+    // The openings share the top level, so they must open the same tree
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+    let other: Tree = (0..43).map(MockLeaf::from).collect();
+    let branch = tree.branch(0).expect("The branch should exist");
+    let mut branches = [branch; OPENINGS];
+    branches[1] = other.branch(0).expect("The branch should exist");
+    let leaves = [BlsScalar::zero(); OPENINGS];
+    let circuit = BatchOpeningCircuit { branches, leaves };
+
+    let _ = Compiler::compile_with_circuit(&pp, label, &circuit);
+}
+
+#[derive(Default)]
+struct PreimageOpeningCircuit {
+    branch: PoseidonBranch<DEPTH>,
+    payload: [u64; 2],
+}
+
+impl Circuit for PreimageOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let preimage = self
+            .payload
+            .map(|p| composer.append_witness(BlsScalar::from(p)));
+        let root = composer.append_public(*self.branch.root());
+        let leaf = composer.append_public(*self.branch);
+
+        let (leaf_p, root_p) =
+            tree::merkle_opening_preimage(composer, &self.branch, &preimage);
+
+        composer.assert_equal(root_p, root);
+        composer.assert_equal(leaf_p, leaf);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn tree_merkle_opening_preimage() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<PreimageOpeningCircuit>(&pp, label)
+            .expect("Circuit should compile successfully");
+
+    let tree: PoseidonTree<HashedLeaf<[u64; 2]>, u64, DEPTH> =
+        (0..42).map(|i| HashedLeaf::new([i, i * i])).collect();
+
+    let branch = tree.branch(7).expect("The branch should exist");
+    let circuit = PreimageOpeningCircuit {
+        branch,
+        payload: [7, 49],
+    };
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proving the circuit should succeed");
+
+    // The hash of the leaf is a public input along with the root
+    assert_eq!(public_inputs, [tree.root(), *branch]);
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // The preimage of another leaf can't be opened
+    let circuit = PreimageOpeningCircuit {
+        branch,
+        payload: [8, 64],
+    };
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
+
+#[test]
+fn tree_opening_circuit() {
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) = OpeningCircuit::<DEPTH>::compile(&pp)
+        .expect("Circuit should compile successfully");
+
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+    let branch = tree.branch(21).expect("The branch should exist");
+
+    let (proof, root) = prover
+        .prove(&mut rng, &branch)
+        .expect("Proving the circuit should succeed");
+    assert_eq!(root, tree.root());
+
+    verifier
+        .verify(&proof, &root)
+        .expect("Proof verification should succeed");
+    verifier
+        .verify(&proof, &BlsScalar::from(42))
+        .expect_err("Proof verification should fail with another root");
+}
+
+#[derive(Default)]
+struct InsertCircuit {
+    branch: PoseidonBranch<DEPTH>,
+    old_root: BlsScalar,
+}
+
+impl Circuit for InsertCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaf = composer.append_witness(*self.branch);
+        let old_root = composer.append_public(self.old_root);
+        let new_root = composer.append_public(*self.branch.root());
+
+        let (old_root_p, new_root_p) =
+            tree::merkle_insert(composer, &self.branch, leaf);
+
+        composer.assert_equal(old_root_p, old_root);
+        composer.assert_equal(new_root_p, new_root);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn tree_merkle_insert() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << (CAPACITY + 1), &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) = Compiler::compile::<InsertCircuit>(&pp, label)
+        .expect("Circuit should compile successfully");
+
+    // Appending to an empty tree, to a full group of leaves, and to a group
+    // with other leaves
+    for len in [0, 4, 16, 41] {
+        let mut tree: Tree = (0..len).map(MockLeaf::from).collect();
+        let old_root = tree.root();
+
+        let pos = tree.push(MockLeaf::from(len));
+        let branch = tree.branch(pos).expect("The branch should exist");
+        let circuit = InsertCircuit { branch, old_root };
+
+        let (proof, public_inputs) = prover
+            .prove(&mut rng, &circuit)
+            .expect("Proving the circuit should succeed");
+
+        assert_eq!(public_inputs, [old_root, tree.root()]);
+        verifier
+            .verify(&proof, &public_inputs)
+            .expect("Proof verification should succeed");
+    }
+
+    // Only the last leaf of a tree can be proven to be appended
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+    let old_tree: Tree = (0..41).map(MockLeaf::from).collect();
+    let circuit = InsertCircuit {
+        branch: tree.branch(40).expect("The branch should exist"),
+        old_root: old_tree.root(),
+    };
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
+
+#[derive(Default)]
+struct InsertedRootCircuit {
+    branch: PoseidonBranch<DEPTH>,
+}
+
+impl Circuit for InsertedRootCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaf = composer.append_witness(*self.branch);
+        let new_root = composer.append_public(*self.branch.root());
+
+        let (_, new_root_p) = tree::merkle_insert(composer, &self.branch, leaf);
+        composer.assert_equal(new_root_p, new_root);
+
+        Ok(())
+    }
+}
+
+/// Set the last node of the first level of `branch` to one, rehashing the
+/// levels above it up to the root.
+fn fill_absent_node(branch: &PoseidonBranch<DEPTH>) -> PoseidonBranch<DEPTH> {
+    const SCALAR: usize = BlsScalar::SIZE;
+
+    let mut bytes = branch.to_bytes();
+    bytes[(hades::WIDTH - 1) * SCALAR] ^= 1;
+
+    let mut node = None;
+    for depth in 0..DEPTH {
+        let start = depth * PoseidonLevel::SIZE;
+        let end = start + PoseidonLevel::SIZE;
+
+        let level = PoseidonLevel::from_slice(&bytes[start..end]).unwrap();
+        if let Some(node) = node {
+            let offset = start + level.index() as usize * SCALAR;
+            bytes[offset..offset + SCALAR].copy_from_slice(&node.to_bytes());
+        }
+
+        let level = PoseidonLevel::from_slice(&bytes[start..end]).unwrap();
+        let mut perm = [BlsScalar::zero(); hades::WIDTH];
+        perm.copy_from_slice(level.as_ref());
+        ScalarStrategy::new().perm(&mut perm);
+        node = Some(perm[1]);
+    }
+
+    let root = node.expect("the branch has levels").to_bytes();
+    bytes[DEPTH * PoseidonLevel::SIZE..].copy_from_slice(&root);
+
+    PoseidonBranch::from_bytes(&bytes).unwrap()
+}
+
+#[test]
+fn tree_merkle_insert_absent_nodes() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << (CAPACITY + 1), &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, _) = Compiler::compile::<InsertedRootCircuit>(&pp, label)
+        .expect("Circuit should compile successfully");
+
+    let mut tree: Tree = (0..41).map(MockLeaf::from).collect();
+    let pos = tree.push(MockLeaf::from(41));
+    let branch = tree.branch(pos).expect("The branch should exist");
+
+    prover
+        .prove(&mut rng, &InsertedRootCircuit { branch })
+        .expect("Proving the circuit should succeed");
+
+    // A node after the offset that is unflagged but not zero is rejected,
+    // even though the root is consistent with it
+    let branch = fill_absent_node(&branch);
+    prover
+        .prove(&mut rng, &InsertedRootCircuit { branch })
+        .expect_err("Proof generation should fail");
+}
+
+#[derive(Default)]
+struct UpdateCircuit {
+    branch: PoseidonBranch<DEPTH>,
+    new_leaf: BlsScalar,
+    new_root: BlsScalar,
+}
+
+impl Circuit for UpdateCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let old_leaf = composer.append_witness(*self.branch);
+        let new_leaf = composer.append_witness(self.new_leaf);
+        let old_root = composer.append_public(*self.branch.root());
+        let new_root = composer.append_public(self.new_root);
+
+        let (old_root_p, new_root_p) =
+            tree::merkle_update(composer, &self.branch, old_leaf, new_leaf);
+
+        composer.assert_equal(old_root_p, old_root);
+        composer.assert_equal(new_root_p, new_root);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn tree_merkle_update() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << (CAPACITY + 1), &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) = Compiler::compile::<UpdateCircuit>(&pp, label)
+        .expect("Circuit should compile successfully");
+
+    let mut tree: Tree = (0..42).map(MockLeaf::from).collect();
+    let branch = tree.branch(21).expect("The branch should exist");
+    tree.update(21, MockLeaf::from(1000));
+
+    let circuit = UpdateCircuit {
+        branch,
+        new_leaf: BlsScalar::from(1000),
+        new_root: tree.root(),
+    };
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proving the circuit should succeed");
+
+    assert_eq!(public_inputs, [*branch.root(), tree.root()]);
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // The new root must be the one of the updated leaf
+    let circuit = UpdateCircuit {
+        new_leaf: BlsScalar::from(1001),
+        ..circuit
+    };
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
+
+#[test]
+fn tree_branch_verify() {
+    let mut tree = Tree::default();
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+    }
+
+    let root = tree.root();
+    let leaf = BlsScalar::from(33);
+
+    let branch = tree.branch(33).expect("The branch should exist");
+    assert!(branch.verify(&root, &leaf));
+
+    assert!(!branch.verify(&root, &BlsScalar::from(34)));
+    assert!(!branch.verify(&BlsScalar::from(34), &leaf));
+
+    let other = tree.branch(34).expect("The branch should exist");
+    assert!(!other.verify(&root, &leaf));
+}
+
+#[test]
+fn tree_branch_levels() {
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+    let branch = tree.branch(33).expect("The branch should exist");
+
+    assert_eq!(branch.len(), DEPTH);
+    assert_eq!(branch.iter().count(), DEPTH);
+    assert_eq!((&branch).into_iter().count(), DEPTH);
+
+    // Leaf 33 is on offset 1 of its group, which is on offset 0 of its own
+    assert_eq!(branch[0].offset(), 1);
+    assert_eq!(branch[1].offset(), 0);
+    assert_eq!(branch[0].nodes()[1], BlsScalar::from(33));
+
+    let siblings: Vec<BlsScalar> = branch[0].siblings().copied().collect();
+    assert_eq!(siblings, [32, 34, 35].map(BlsScalar::from));
+
+    for (level, owned) in branch.iter().zip(branch) {
+        assert_eq!(level.offset(), owned.offset());
+        assert_eq!(level.nodes(), owned.nodes());
+    }
+}
+
+#[test]
+fn tree_compact_branch() {
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+    let root = tree.root();
+
+    for pos in [0, 33, 41] {
+        let branch = tree.branch(pos).expect("The branch should exist");
+        let leaf = BlsScalar::from(pos);
+
+        let compact = CompactBranch::from(&branch);
+        assert!(compact.verify(&root, &leaf));
+        assert!(!compact.verify(&root, &BlsScalar::from(pos + 1)));
+        assert_eq!(compact.expand(&leaf).to_string(), branch.to_string());
+
+        let bytes = compact.to_bytes();
+        assert!(bytes.len() < branch.to_bytes().len());
+        assert_eq!(CompactBranch::from_bytes(&bytes).ok(), Some(compact));
+    }
+
+    // The opened node must be flagged on every level
+    let branch = tree.branch(33).expect("The branch should exist");
+    let mut bytes = CompactBranch::from(&branch).to_bytes();
+    bytes[CompactLevel::SIZE - 1] = 0;
+    assert!(CompactBranch::<DEPTH>::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn tree_branch_from_path() {
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+
+    for pos in [0, 33, 41] {
+        let branch = tree.branch(pos).expect("The branch should exist");
+        let (siblings, offsets, lens) = path_of(&branch);
+
+        let leaf = BlsScalar::from(pos);
+        let rebuilt =
+            PoseidonBranch::from_path(leaf, &siblings, &offsets, &lens)
+                .expect("The path should be valid");
+
+        assert_eq!(rebuilt.to_string(), branch.to_string());
+        assert!(rebuilt.verify(&tree.root(), &leaf));
+    }
+
+    // A present node may be zero, even when it's the last one of its group
+    let tree: Tree = [1, 2, 0].into_iter().map(MockLeaf::from).collect();
+    let branch = tree.branch(0).expect("The branch should exist");
+    let (siblings, offsets, lens) = path_of(&branch);
+    assert_eq!(lens[0], 3);
+
+    let leaf = BlsScalar::one();
+    let rebuilt = PoseidonBranch::from_path(leaf, &siblings, &offsets, &lens)
+        .expect("The path should be valid");
+    assert!(rebuilt.verify(&tree.root(), &leaf));
+
+    let siblings = [[BlsScalar::zero(); 4]; DEPTH];
+    let lens = [4; DEPTH];
+    let mut offsets = [0; DEPTH];
+    offsets[3] = 4;
+    assert_eq!(
+        PoseidonBranch::from_path(BlsScalar::one(), &siblings, &offsets, &lens)
+            .map(|_| ()),
+        Err(BranchError::InvalidOffset {
+            level: 3,
+            offset: 4
+        })
+    );
+
+    let offsets = [0; DEPTH];
+    for len in [0, 5] {
+        let mut lens = [4; DEPTH];
+        lens[2] = len;
+        assert_eq!(
+            PoseidonBranch::from_path(
+                BlsScalar::one(),
+                &siblings,
+                &offsets,
+                &lens
+            )
+            .map(|_| ()),
+            Err(BranchError::InvalidLength { level: 2, len })
+        );
+    }
+}
+
+/// Groups of nodes, offsets and number of nodes of the levels of a branch, as
+/// taken by `PoseidonBranch::from_path`.
+type Path = ([[BlsScalar; 4]; DEPTH], [usize; DEPTH], [usize; DEPTH]);
+
+/// Split `branch` into the groups of nodes, the offsets and the number of
+/// nodes of its levels.
+fn path_of(branch: &PoseidonBranch<DEPTH>) -> Path {
+    let mut siblings = [[BlsScalar::zero(); 4]; DEPTH];
+    let mut offsets = [0; DEPTH];
+    let mut lens = [0; DEPTH];
+
+    for (i, level) in branch.as_ref().iter().enumerate() {
+        offsets[i] = level.index() as usize - 1;
+        siblings[i].copy_from_slice(&level.as_ref()[1..]);
+        lens[i] = level.as_ref()[0].to_bytes()[0].count_ones() as usize;
+
+        // The opened node is recomputed from the level below
+        siblings[i][offsets[i]] = BlsScalar::zero();
+    }
+
+    (siblings, offsets, lens)
+}
+
+#[test]
+fn tree_remove() {
+    let mut tree = Tree::default();
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+    }
+    let root = tree.root();
+
+    let removed = tree.remove(33).expect("The leaf should exist");
+    let mut leaf = MockLeaf::from(33);
+    leaf.pos = 33;
+    assert_eq!(removed, leaf);
+    assert!(tree.remove(42).is_none());
+
+    let new_root = tree.root();
+    assert_ne!(root, new_root);
+
+    // The other leaves keep their positions
+    let tombstone = tree.get(33).expect("The slot should be kept");
+    assert_eq!(tombstone.pos, 33);
+    let branch = tree.branch(34).expect("The branch should exist");
+    assert!(branch.verify(&new_root, &BlsScalar::from(34)));
+
+    // The removed leaf is hashed to the tombstone, which can't be opened
+    let mut hashes: Vec<BlsScalar> = (0..42).map(BlsScalar::from).collect();
+    hashes[33] = tree::TOMBSTONE;
+    assert_eq!(tree::compute_root::<DEPTH>(&hashes), Some(new_root));
+
+    let branch = tree.branch(33).expect("The branch should exist");
+    assert_eq!(*branch, tree::TOMBSTONE);
+    assert!(!branch.verify(&new_root, &tree::TOMBSTONE));
+    assert!(!branch.verify(&new_root, &BlsScalar::zero()));
+    assert!(!branch.verify(&new_root, &BlsScalar::from(33)));
+    assert!(!CompactBranch::from(&branch).verify(&new_root, &tree::TOMBSTONE));
+    assert_eq!(tree.contains_hash(&tree::TOMBSTONE), None);
+}
+
+#[test]
+fn tree_update() {
+    let mut tree = Tree::default();
+    let mut other = Tree::default();
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+        other.push(MockLeaf::from(if i == 17 { 1000 } else { i }));
+    }
+
+    let previous = tree
+        .update(17, MockLeaf::from(1000))
+        .expect("The leaf should exist");
+    assert_eq!(previous.pos, 17);
+    assert_eq!(tree.get(17).map(|l| l.pos), Some(17));
+    assert!(tree.update(42, MockLeaf::from(1000)).is_none());
+
+    // Updating a leaf is the same as having appended it in the first place
+    let root = tree.root();
+    assert_eq!(root, other.root());
+
+    let branch = tree.branch(17).expect("The branch should exist");
+    assert!(branch.verify(&root, &BlsScalar::from(1000)));
+}
+
+#[test]
+fn tree_extend() {
+    let mut tree = Tree::default();
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+    }
+
+    let mut extended: Tree = (0..20).map(MockLeaf::from).collect();
+    assert_eq!(extended.get(19).map(|l| l.pos), Some(19));
+
+    extended.extend((20..42).map(MockLeaf::from));
+    assert_eq!(extended.get(41).map(|l| l.pos), Some(41));
+    assert!(extended.get(42).is_none());
+
+    assert_eq!(tree.root(), extended.root());
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn tree_from_leaves_parallel() {
+    for len in [0, 1, 4, 42, 300] {
+        let leaves: Vec<MockLeaf> = (0..len).map(MockLeaf::from).collect();
+
+        let tree: Tree = leaves.iter().copied().collect();
+        let mut parallel = Tree::from_leaves_parallel(&leaves);
+
+        assert_eq!(tree.len(), parallel.len());
+        assert_eq!(tree.root(), parallel.root());
+        assert_eq!(
+            parallel.get(len.saturating_sub(1)).map(|l| l.pos),
+            len.checked_sub(1)
+        );
+        for pos in (0..len).step_by(7) {
+            assert_eq!(
+                tree.branch(pos).map(|b| b.to_string()),
+                parallel.branch(pos).map(|b| b.to_string())
+            );
+        }
+
+        // The cached hashes must be the ones of the tree
+        let mut tree = tree;
+        tree.push(MockLeaf::from(len));
+        parallel.push(MockLeaf::from(len));
+        assert_eq!(tree.root(), parallel.root());
+    }
+}
+
+#[test]
+fn tree_stored() {
+    let mut tree = Tree::default();
+    let mut stored = MemoryTree::default();
+    assert_eq!(stored.root(), Ok(tree.root()));
+
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+        assert_eq!(stored.push(MockLeaf::from(i)), Ok(i));
+        assert_eq!(stored.root(), Ok(tree.root()));
+    }
+
+    assert_eq!(stored.len(), Ok(42));
+    assert_eq!(stored.get(41).unwrap().map(|l| l.pos), Some(41));
+    assert!(stored.get(42).unwrap().is_none());
+    assert!(stored.branch(42).unwrap().is_none());
+    for pos in 0..42 {
+        assert_eq!(
+            stored.branch(pos).unwrap().map(|b| b.to_string()),
+            tree.branch(pos).map(|b| b.to_string())
+        );
+    }
+
+    tree.update(17, MockLeaf::from(1000));
+    let previous = stored.update(17, MockLeaf::from(1000)).unwrap();
+    assert_eq!(previous.map(|l| l.pos), Some(17));
+    assert_eq!(stored.get(17).unwrap().map(|l| l.pos), Some(17));
+    assert_eq!(stored.update(42, MockLeaf::from(1000)), Ok(None));
+    assert_eq!(stored.root(), Ok(tree.root()));
+
+    // A tree opened from the store of another is the same tree
+    let stored = MemoryTree::open(stored.into_store());
+    assert_eq!(stored.len(), Ok(42));
+
+    let root = stored.root().unwrap();
+    let branch = stored.branch(17).unwrap().expect("The branch should exist");
+    assert!(branch.verify(&root, &BlsScalar::from(1000)));
+}
+
+#[test]
+fn tree_stored_node_budget() {
+    let mut tree = Tree::default();
+    let mut stored = MemoryTree::with_node_budget(8);
+
+    for i in 0..100 {
+        tree.push(MockLeaf::from(i));
+        stored.push(MockLeaf::from(i)).unwrap();
+    }
+    assert_eq!(stored.node_count(), 8);
+    assert_eq!(stored.root(), Ok(tree.root()));
+
+    // The dropped hashes are recomputed to open the leaves
+    for pos in (0..100).step_by(3) {
+        assert_eq!(
+            stored.branch(pos).unwrap().map(|b| b.to_string()),
+            tree.branch(pos).map(|b| b.to_string())
+        );
+    }
+
+    tree.update(17, MockLeaf::from(1000));
+    stored.update(17, MockLeaf::from(1000)).unwrap();
+    assert_eq!(stored.root(), Ok(tree.root()));
+    assert!(stored.node_count() <= 8);
+}
+
+/// Binary tree of depth 4, hashed with a permutation of width 3.
+type BinaryTree =
+    PoseidonTree<MockLeaf, u64, 4, (), MemoryStore<MockLeaf>, 2, Params>;
+
+fn binary_params() -> Params {
+    Params::with_security(3, SecurityLevel::Bits128)
+        .expect("the width is supported")
+}
+
+/// Compute the root of a binary tree of the given depth with `params`.
+fn binary_root(
+    params: &Params,
+    leaves: &[BlsScalar],
+    depth: usize,
+) -> BlsScalar {
+    (0..depth).fold(leaves.to_vec(), |level, _| {
+        level
+            .chunks(2)
+            .map(|children| {
+                let mut state = [BlsScalar::zero(); 3];
+                state[0] = BlsScalar::from((1u64 << children.len()) - 1);
+                state[1..=children.len()].copy_from_slice(children);
+                params.permute(&mut state);
+                state[1]
+            })
+            .collect()
+    })[0]
+}
+
+#[derive(Default)]
+struct ArityOpeningCircuit {
+    branch: ArityBranch<2, 4>,
+    leaf: BlsScalar,
+}
+
+impl Circuit for ArityOpeningCircuit {
+    fn circuit<C>(&self, composer: &mut C) -> Result<(), PlonkError>
+    where
+        C: Composer,
+    {
+        let leaf = composer.append_witness(self.leaf);
+        let root = composer.append_public(*self.branch.root());
+
+        let root_p = tree::arity_merkle_opening(
+            composer,
+            &binary_params(),
+            &self.branch,
+            leaf,
+        );
+        composer.assert_equal(root_p, root);
+
+        Ok(())
+    }
+}
+
+#[test]
+fn tree_stored_arity() {
+    // The default arity opens the same branches as a `PoseidonTree`
+    let mut stored = MemoryTree::default();
+    for i in 0..42 {
+        stored.push(MockLeaf::from(i)).unwrap();
+    }
+    let root = stored.root().unwrap();
+    let opening = stored.opening(17).unwrap().expect("The leaf should exist");
+    assert_eq!(*opening.root(), root);
+    assert!(opening.verify_with(&Hades, &root, &BlsScalar::from(17)));
+    assert!(!opening.verify_with(&Hades, &root, &BlsScalar::from(18)));
+
+    let mut tree = BinaryTree::open_with(MemoryStore::new(), binary_params());
+    for i in 0..11 {
+        tree.push(MockLeaf::from(i)).unwrap();
+    }
+
+    let leaves: Vec<BlsScalar> = (0..11).map(BlsScalar::from).collect();
+    let root = binary_root(&binary_params(), &leaves, 4);
+    assert_eq!(tree.root(), Ok(root));
+
+    let opening = tree.opening(10).unwrap().expect("The leaf should exist");
+    assert_eq!(opening.offsets(), &[0, 1, 0, 1]);
+    assert_eq!(opening.lens(), &[1, 2, 1, 2]);
+    assert!(opening.verify_with(&binary_params(), &root, &leaves[10]));
+    assert!(!opening.verify_with(&binary_params(), &root, &leaves[9]));
+    assert!(tree.opening(11).unwrap().is_none());
+}
+
+#[test]
+fn tree_arity_opening() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << 13, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let mut tree = BinaryTree::open_with(MemoryStore::new(), binary_params());
+    for i in 0..11 {
+        tree.push(MockLeaf::from(i)).unwrap();
+    }
+    let root = tree.root().unwrap();
+
+    let circuit = ArityOpeningCircuit {
+        branch: tree.opening(6).unwrap().expect("The leaf should exist"),
+        leaf: BlsScalar::from(6),
+    };
+    let (prover, verifier) =
+        Compiler::compile_with_circuit(&pp, label, &circuit)
+            .expect("Circuit should compile successfully");
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proving the circuit should succeed");
+    assert_eq!(public_inputs, [root]);
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // A leaf that isn't on the offset of the branch can't be opened
+    let circuit = ArityOpeningCircuit {
+        leaf: BlsScalar::from(7),
+        ..circuit
+    };
+    assert!(prover.prove(&mut rng, &circuit).is_err());
+}
+
+#[test]
+#[should_panic]
+fn tree_stored_arity_width() {
+    let store = MemoryStore::<MockLeaf>::new();
+    let _ = BinaryTree::open_with(store, tree_params());
+}
+
+#[test]
+fn tree_merge() {
+    let tree: Tree = (0..50).map(MockLeaf::from).collect();
+
+    let mut master: Tree = (0..30).map(MockLeaf::from).collect();
+    let epoch: Tree = (30..50).map(MockLeaf::from).collect();
+
+    let mapping = master.merge(epoch);
+    assert_eq!(mapping, (30..50).collect::<Vec<u64>>());
+    assert_eq!(master.get(49).map(|l| l.pos), Some(49));
+    assert_eq!(master.root(), tree.root());
+
+    // Merging into an empty tree keeps the positions
+    let mut empty = Tree::default();
+    let mapping = empty.merge(tree.clone());
+    assert_eq!(mapping, (0..50).collect::<Vec<u64>>());
+    assert_eq!(empty.root(), tree.root());
+
+    assert!(master.merge(Tree::default()).is_empty());
+    assert_eq!(master.root(), tree.root());
+}
+
+#[test]
+fn tree_diff() {
+    let old: Tree = (0..20).map(MockLeaf::from).collect();
+    assert!(tree::diff(&old, &old.clone()).is_empty());
+
+    let mut new = old.clone();
+    new.update(5, MockLeaf::from(100));
+    new.extend((20..22).map(MockLeaf::from));
+
+    let diff = tree::diff(&old, &new);
+    assert_eq!(diff.inserted(), &[20, 21]);
+    assert_eq!(diff.updated(), &[5]);
+    assert!(diff.removed().is_empty());
+
+    // The paths of the updated and inserted leaves changed, along with the
+    // nodes of the levels above
+    let nodes = diff.nodes();
+    assert!(nodes.contains(&(0, 1)));
+    assert!(nodes.contains(&(0, 5)));
+    assert!(nodes.contains(&(1, 0)));
+    assert!(nodes.contains(&(1, 1)));
+    assert!(!nodes.contains(&(0, 0)));
+    assert!(!nodes.contains(&(0, 2)));
+    assert!(nodes.contains(&(DEPTH - 1, 0)));
+
+    let back = tree::diff(&new, &old);
+    assert_eq!(back.removed(), &[20, 21]);
+    assert_eq!(back.updated(), &[5]);
+    assert_eq!(back.nodes(), diff.nodes());
+}
+
+#[test]
+fn tree_stats() {
+    let mut tree = Tree::default();
+    assert!(tree.is_empty());
+    assert_eq!(tree.depth_used(), 0);
+    assert_eq!(tree.stats().cached_hashes, 0);
+    assert_eq!(tree.capacity(), 1 << (2 * DEPTH));
+
+    tree.extend((0..42).map(MockLeaf::from));
+    assert_eq!(tree.len(), 42);
+    assert!(!tree.is_full());
+
+    // The 42 leaves are held by 11, then 3, then a single node
+    assert_eq!(tree.depth_used(), 3);
+
+    let stats = tree.stats();
+    assert_eq!(stats.leaves, 42);
+    assert_eq!(stats.cached_hashes, 11 + 3 + 1);
+    assert_eq!(stats.nodes, 11 + 3 + (DEPTH as u64 - 2));
+    assert!(stats.memory > 0);
+
+    let mut small: PoseidonTree<MockLeaf, u64, 2> = PoseidonTree::new();
+    small.extend((0..16).map(MockLeaf::from));
+    assert!(small.is_full());
+}
+
+#[test]
+fn tree_walk() {
+    let mut tree = Tree::default();
+    assert!(tree.walk(|_| Visit::<()>::Break(())).is_continue());
+
+    tree.extend((0..42).map(MockLeaf::from));
+
+    // Every stored node and leaf is visited, in order
+    let mut internal = 0;
+    let mut positions = vec![];
+    let flow = tree.walk(|node| {
+        match node {
+            Node::Leaf(leaf) => positions.push(leaf.pos),
+            Node::Internal { .. } => internal += 1,
+        }
+        Visit::<()>::Continue
+    });
+    assert!(flow.is_continue());
+    assert_eq!(internal, 11 + 3 + 1);
+    assert_eq!(positions, (0..42).collect::<Vec<_>>());
+
+    // Collect the leaves expiring on epoch 5, pruning the subtrees expiring
+    // before it and stopping on the first leaf expiring after it
+    let mut visited = 0;
+    let mut expiring = vec![];
+    let flow = tree.walk(|node| match node {
+        Node::Leaf(leaf) if leaf.expiration > 5 => Visit::Break(leaf.pos),
+        Node::Leaf(leaf) => {
+            visited += 1;
+            if leaf.expiration == 5 {
+                expiring.push(leaf.pos);
+            }
+            Visit::Continue
+        }
+        Node::Internal { annotation, .. } => {
+            let max_key: &MaxKey<u64> = annotation.borrow();
+            match max_key {
+                MaxKey::Maximum(key) if *key >= 5 => Visit::Continue,
+                _ => Visit::Prune,
+            }
+        }
+    });
+    assert_eq!(flow, ControlFlow::Break(18));
+    assert_eq!(expiring, [15, 16, 17]);
+    assert_eq!(visited, 6);
+
+    // The positions of the internal nodes follow the ones of their leaves
+    let mut nodes = vec![];
+    tree.walk(|node| {
+        if let Node::Internal { level, index, .. } = node {
+            nodes.push((level, index));
+        }
+        Visit::<()>::Continue
+    });
+    assert_eq!(nodes[..3], [(2, 0), (1, 0), (0, 0)]);
+    assert!(nodes.contains(&(0, 10)));
+    assert!(nodes.contains(&(1, 2)));
+}
+
+#[test]
+fn tree_leaves() {
+    let mut tree = Tree::default();
+    assert_eq!(tree.leaves().count(), 0);
+
+    tree.extend((0..42).map(MockLeaf::from));
+
+    let leaves: Vec<MockLeaf> = tree.leaves().copied().collect();
+    assert_eq!(leaves.len(), 42);
+    for (pos, leaf) in tree.leaves_with_pos() {
+        assert_eq!(leaf.pos, pos);
+        assert_eq!(leaves[pos as usize], *leaf);
+    }
+}
+
+#[test]
+fn tree_leaves_in_range() {
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+
+    let pos: Vec<u64> = tree.leaves_in_range(10..20).map(|l| l.pos).collect();
+    assert_eq!(pos, (10..20).collect::<Vec<_>>());
+
+    let pos: Vec<u64> = tree.leaves_in_range(40..).map(|l| l.pos).collect();
+    assert_eq!(pos, vec![40, 41]);
+
+    assert_eq!(tree.leaves_in_range(..).count(), 42);
+    assert_eq!(tree.leaves_in_range(50..).count(), 0);
+}
+
+/// Maximum expiration of the leaves of a subtree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct MaxExpiration(u64);
+
+impl Aggregate<MockLeaf> for MaxExpiration {
+    fn from_leaf(leaf: &MockLeaf) -> Self {
+        Self(leaf.expiration)
+    }
+
+    fn combine(&mut self, other: &Self) {
+        self.0 = self.0.max(other.0);
+    }
+}
+
+#[test]
+fn tree_aggregate() {
+    let tree: PoseidonTree<MockLeaf, u64, DEPTH, MaxExpiration> =
+        (0..100).map(MockLeaf::from).collect();
+    let plain: Tree = (0..100).map(MockLeaf::from).collect();
+
+    // The aggregate doesn't change the hashes of the tree
+    assert_eq!(tree.root(), plain.root());
+    assert_eq!(tree.aggregate(), MaxExpiration(33));
+
+    let pos: Vec<u64> = tree
+        .leaves_by_aggregate(|max| max.0 >= 30)
+        .map(|l| l.pos)
+        .collect();
+    assert_eq!(pos, (90..100).collect::<Vec<_>>());
+
+    assert_eq!(tree.leaves_by_aggregate(|max| max.0 > 33).count(), 0);
+
+    let branch = tree.branch(95).expect("The branch should exist");
+    assert!(branch.verify(&tree.root(), &BlsScalar::from(95)));
+
+    let old: PoseidonTree<MockLeaf, u64, DEPTH, MaxExpiration> =
+        (0..42).map(MockLeaf::from).collect();
+    let proof = tree
+        .consistency_proof(42, 100)
+        .expect("The sizes should be valid");
+    assert!(proof.verify(&old.root(), &tree.root()));
+
+    let diff = tree::diff(&old, &tree);
+    assert_eq!(diff.inserted(), (42..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn tree_pos_of_key() {
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+
+    assert_eq!(tree.pos_of_key(&0), Some(0));
+    assert_eq!(tree.pos_of_key(&42), None);
+
+    let pos = tree.pos_of_key(&33).expect("The key should be found");
+    assert_eq!(pos, 33);
+
+    let branch = tree.branch(pos).expect("The branch should exist");
+    assert!(branch.verify(&tree.root(), &BlsScalar::from(33)));
+}
+
+#[test]
+fn tree_branch_by_key() {
+    let tree: Tree = (0..42).map(MockLeaf::from).collect();
+
+    let (pos, branch) = tree.branch_by_key(&33).expect("The key should exist");
+    assert_eq!(pos, 33);
+    let expected = tree.branch(33).expect("The branch should exist");
+    assert_eq!(branch.to_string(), expected.to_string());
+    assert!(branch.verify(&tree.root(), &BlsScalar::from(33)));
+
+    assert!(tree.branch_by_key(&42).is_none());
+}
+
+#[test]
+fn tree_contains_hash() {
+    let mut tree: Tree = (0..42).map(MockLeaf::from).collect();
+
+    assert_eq!(tree.contains_hash(&BlsScalar::from(17)), Some(17));
+    assert_eq!(tree.contains_hash(&BlsScalar::from(42)), None);
+
+    tree.push(MockLeaf::from(42));
+    assert_eq!(tree.contains_hash(&BlsScalar::from(42)), Some(42));
+}
+
+#[test]
+fn tree_checkpoint_rollback() {
+    let mut tree: Tree = (0..42).map(MockLeaf::from).collect();
+    let root = tree.root();
+
+    let checkpoint = tree.checkpoint();
+    assert_eq!(checkpoint.root(), root);
+
+    tree.extend((42..50).map(MockLeaf::from));
+    tree.update(3, MockLeaf::from(1000));
+    tree.remove(7);
+    assert_ne!(tree.root(), root);
+
+    tree.rollback(checkpoint);
+    assert_eq!(tree.root(), root);
+    assert_eq!(tree.leaves().count(), 42);
+
+    let branch = tree.branch(3).expect("The branch should exist");
+    assert!(branch.verify(&root, &BlsScalar::from(3)));
+}
+
+#[test]
+fn tree_consistency_proof() {
+    let tree: Tree = (0..1025).map(MockLeaf::from).collect();
+    let root =
+        |size: u64| (0..size).map(MockLeaf::from).collect::<Tree>().root();
+
+    for (old_size, new_size) in [
+        (0, 5),
+        (1, 1),
+        (3, 17),
+        (5, 5),
+        (16, 17),
+        (17, 1025),
+        (1024, 1025),
+    ] {
+        let proof = tree
+            .consistency_proof(old_size, new_size)
+            .expect("The proof should be created");
+        assert_eq!(proof.old_size(), old_size);
+        assert_eq!(proof.new_size(), new_size);
+
+        let old_root = root(old_size);
+        let new_root = root(new_size);
+        assert!(proof.verify(&old_root, &new_root));
+
+        if old_size != new_size {
+            assert!(!proof.verify(&new_root, &old_root));
+            assert!(!proof.verify(&old_root, &BlsScalar::from(42)));
+        }
+    }
+
+    assert!(tree.consistency_proof(17, 16).is_none());
+    assert!(tree.consistency_proof(17, 1026).is_none());
+}
+
+#[test]
+fn tree_export_import_nodes() {
+    assert!(Tree::new().export_nodes().is_empty());
+
+    for size in [1, 4, 5, 17, 1025] {
+        let tree: Tree = (0..size).map(MockLeaf::from).collect();
+
+        let mut nodes = tree.export_nodes();
+        assert_eq!(nodes.last(), Some(&tree.root()));
+
+        let imported =
+            Tree::import_nodes((0..size).map(MockLeaf::from), &nodes)
+                .expect("The nodes should match the leaves");
+        assert_eq!(imported.root(), tree.root());
+
+        nodes[0] += BlsScalar::one();
+        assert!(
+            Tree::import_nodes((0..size).map(MockLeaf::from), &nodes).is_none()
+        );
+    }
+}
+
+#[test]
+fn tree_defer_hashing() {
+    let mut tree = Tree::new();
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+    }
+    tree.update(17, MockLeaf::from(1000));
+
+    let mut deferred = Tree::new();
+    let len = deferred.defer_hashing(|t| {
+        for i in 0..42 {
+            assert_eq!(t.push(MockLeaf::from(i)), i);
+        }
+        t.update(17, MockLeaf::from(1000));
+        t.len()
+    });
+
+    assert_eq!(len, 42);
+    assert_eq!(deferred.root(), tree.root());
+}
+
+#[test]
+fn tree_frontier() {
+    let mut tree = Tree::default();
+    let mut frontier = FrontierTree::<DEPTH>::new();
+    assert_eq!(frontier.root(), &tree.root());
+
+    for i in 0..70 {
+        tree.push(MockLeaf::from(i));
+        let root = frontier.append(BlsScalar::from(i));
+
+        assert_eq!(root, tree.root());
+    }
+    assert_eq!(frontier.len(), 70);
+}
+
+#[test]
+#[should_panic]
+fn tree_frontier_full() {
+    let mut frontier = FrontierTree::<2>::new();
+
+    for i in 0..17 {
+        frontier.append(BlsScalar::from(i));
+    }
+}
+
+#[test]
+fn tree_compute_root() {
+    let mut tree = Tree::default();
+    let mut leaves = Vec::new();
+    assert_eq!(tree::compute_root::<DEPTH>(&leaves), Some(tree.root()));
+
+    for i in 0..70 {
+        tree.push(MockLeaf::from(i));
+        leaves.push(BlsScalar::from(i));
+
+        assert_eq!(tree::compute_root::<DEPTH>(&leaves), Some(tree.root()));
+    }
+
+    // Leaves that don't fit in the tree have no root
+    let leaves: Vec<BlsScalar> = (0..17).map(BlsScalar::from).collect();
+    assert!(tree::compute_root::<2>(&leaves[..16]).is_some());
+    assert_eq!(tree::compute_root::<2>(&leaves), None);
+}
+
+#[test]
+fn tree_builtin_leaves() {
+    let scalars: Vec<BlsScalar> = (0..20).map(BlsScalar::from).collect();
+
+    // A tree of scalar leaves has the same root as the bare scalars
+    let mut tree: PoseidonTree<ScalarLeaf, u64, DEPTH> = PoseidonTree::new();
+    tree.extend(scalars.iter().copied().map(ScalarLeaf::from));
+    assert_eq!(tree::compute_root::<DEPTH>(&scalars), Some(tree.root()));
+    assert_eq!(tree.get(7).map(|l| *l.scalar()), Some(scalars[7]));
+
+    let leaf = HashedLeaf::new([1u64, 2]);
+    let hash = sponge::hash(&[BlsScalar::from(1), BlsScalar::from(2)]);
+    assert_eq!(leaf.poseidon_hash(), hash);
+
+    let mut tree: PoseidonTree<HashedLeaf<[u64; 2]>, u64, DEPTH> =
+        PoseidonTree::new();
+    let pos = tree.push(leaf);
+    assert_eq!(tree.get(pos).map(|l| *l.payload()), Some([1, 2]));
+    let branch = tree.branch(pos).expect("the leaf was pushed");
+    assert!(branch.verify(&tree.root(), &hash));
+
+    // Keyed leaves are searched by their key, which isn't hashed
+    let mut tree: PoseidonTree<KeyedScalarLeaf<u64>, u64, DEPTH> =
+        PoseidonTree::new();
+    tree.extend(
+        scalars
+            .iter()
+            .enumerate()
+            .map(|(i, s)| KeyedScalarLeaf::new(*s, i as u64 / 4)),
+    );
+    assert_eq!(tree::compute_root::<DEPTH>(&scalars), Some(tree.root()));
+
+    let epoch: Vec<u64> =
+        tree.leaves_in_range(2..=2).map(|l| *l.pos()).collect();
+    assert_eq!(epoch, [8, 9, 10, 11]);
+}
+
+#[test]
+fn tree_builtin_leaves_remove() {
+    let scalars: Vec<BlsScalar> = (0..20).map(BlsScalar::from).collect();
+
+    // The removed leaf is hashed to the tombstone
+    let mut hashes = scalars.clone();
+    hashes[7] = tree::TOMBSTONE;
+    let root = tree::compute_root::<DEPTH>(&hashes);
+
+    let mut tree: PoseidonTree<ScalarLeaf, u64, DEPTH> =
+        scalars.iter().copied().map(ScalarLeaf::from).collect();
+    let removed = tree.remove(7).expect("The leaf should exist");
+    assert_eq!(*removed.scalar(), scalars[7]);
+    assert!(tree.get(7).map(|l| l.is_tombstone()).unwrap_or_default());
+    assert_eq!(Some(tree.root()), root);
+
+    let mut tree: PoseidonTree<KeyedScalarLeaf<u64>, u64, DEPTH> = scalars
+        .iter()
+        .enumerate()
+        .map(|(i, s)| KeyedScalarLeaf::new(*s, i as u64 / 4))
+        .collect();
+    let removed = tree.remove(7).expect("The leaf should exist");
+    assert_eq!(*removed.scalar(), scalars[7]);
+    assert!(tree.get(7).map(|l| l.is_tombstone()).unwrap_or_default());
+    assert_eq!(Some(tree.root()), root);
+
+    let mut tree: PoseidonTree<HashedLeaf<[u64; 2]>, u64, DEPTH> =
+        (0..20).map(|i| HashedLeaf::new([i, i * i])).collect();
+    let mut hashes: Vec<BlsScalar> =
+        tree.leaves().map(|l| l.poseidon_hash()).collect();
+    hashes[7] = tree::TOMBSTONE;
+
+    let removed = tree.remove(7).expect("The leaf should exist");
+    assert_eq!(*removed.payload(), [7, 49]);
+    assert!(tree.get(7).map(|l| l.is_tombstone()).unwrap_or_default());
+    assert_eq!(Some(tree.root()), tree::compute_root::<DEPTH>(&hashes));
+    assert!(tree.remove(20).is_none());
+}
+
+#[derive(Debug, Clone)]
+struct TaggedLeaf {
+    value: u64,
+    pos: u64,
+    inserted: Cell<Option<(u64, BlsScalar)>>,
+}
+
+impl From<u64> for TaggedLeaf {
+    fn from(value: u64) -> Self {
+        Self {
+            value,
+            pos: 0,
+            inserted: Cell::new(None),
+        }
+    }
+}
+
+impl Keyed<u64> for TaggedLeaf {
+    fn key(&self) -> &u64 {
+        &self.value
+    }
+}
+
+impl PoseidonLeaf for TaggedLeaf {
+    fn poseidon_hash(&self) -> BlsScalar {
+        self.try_poseidon_hash().unwrap_or_default()
+    }
+
+    fn pos(&self) -> &u64 {
+        &self.pos
+    }
+
+    fn set_pos(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+}
+
+impl PoseidonLeafExt for TaggedLeaf {
+    const DOMAIN: u64 = 7;
+
+    type Error = &'static str;
+
+    fn try_poseidon_encode(&self) -> Result<Vec<BlsScalar>, Self::Error> {
+        match self.value {
+            u64::MAX => Err("the value is reserved"),
+            value => Ok(vec![BlsScalar::from(value)]),
+        }
+    }
+
+    fn on_inserted(&self, pos: u64, root: &BlsScalar) {
+        self.inserted.set(Some((pos, *root)));
+    }
+}
+
+#[test]
+fn tree_leaf_lifecycle() {
+    let mut tree: PoseidonTree<TaggedLeaf, u64, DEPTH> = PoseidonTree::new();
+
+    // The domain tag separates the leaf from a payload of the same contents
+    let leaf = TaggedLeaf::from(5);
+    assert_ne!(leaf.poseidon_hash(), HashedLeaf::new(5u64).poseidon_hash());
+    assert_eq!(
+        leaf.poseidon_hash(),
+        sponge::hash(&[BlsScalar::from(7), BlsScalar::from(5)])
+    );
+
+    for i in 0..10 {
+        let pos = tree.try_push(TaggedLeaf::from(i)).expect("valid leaf");
+        let leaf = tree.get(pos).expect("the leaf was pushed");
+        assert_eq!(leaf.inserted.get(), Some((pos, tree.root())));
+    }
+
+    // Malformed leaves are rejected, leaving the tree untouched
+    let root = tree.root();
+    assert!(tree.try_push(TaggedLeaf::from(u64::MAX)).is_err());
+    assert!(tree.try_update(3, TaggedLeaf::from(u64::MAX)).is_err());
+    assert_eq!(tree.len(), 10);
+    assert_eq!(tree.root(), root);
+
+    let previous = tree.try_update(3, TaggedLeaf::from(42));
+    assert_eq!(previous.expect("valid leaf").map(|l| l.value), Some(3));
+    let leaf = tree.get(3).expect("the leaf was updated");
+    assert_eq!(leaf.inserted.get(), Some((3, tree.root())));
+
+    assert!(matches!(tree.try_update(10, TaggedLeaf::from(0)), Ok(None)));
+}
+
+#[test]
+fn tree_dyn() {
+    let mut tree = Tree::default();
+    let mut dyn_tree = DynPoseidonTree::<MockLeaf, u64>::new(DEPTH);
+    assert_eq!(dyn_tree.depth(), DEPTH);
+    assert_eq!(dyn_tree.root(), tree.root());
+
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+        dyn_tree.push(MockLeaf::from(i));
+    }
+    assert_eq!(dyn_tree.root(), tree.root());
+    assert_eq!(dyn_tree.leaves().count(), 42);
+
+    let branch = dyn_tree.branch(17).expect("the leaf should exist");
+    assert_eq!(branch.depth(), DEPTH);
+    assert!(branch.verify(&tree.root(), &BlsScalar::from(17)));
+
+    let other: DynPoseidonBranch =
+        tree.branch(17).expect("the leaf should exist").into();
+    assert_eq!(other.root(), branch.root());
+
+    dyn_tree.update(17, MockLeaf::from(1000));
+    assert_ne!(dyn_tree.root(), tree.root());
+}
+
+#[test]
+fn tree_dyn_growing() {
+    let mut tree = DynPoseidonTree::<MockLeaf, u64>::growing(1);
+
+    for i in 0..3 {
+        tree.push(MockLeaf::from(i));
+    }
+    let mut stale = tree.branch(1).expect("the leaf should exist");
+
+    tree.push(MockLeaf::from(3));
+    assert_eq!(tree.depth(), 1);
+
+    let mut branch = tree.branch(2).expect("the leaf should exist");
+    let root = tree.root();
+
+    // 42 leaves fit in a tree of depth 3
+    for i in 4..42 {
+        tree.push(MockLeaf::from(i));
+    }
+    assert_eq!(tree.depth(), 3);
+
+    let mut fixed = PoseidonTree::<MockLeaf, u64, 3>::new();
+    for i in 0..42 {
+        fixed.push(MockLeaf::from(i));
+    }
+    assert_eq!(tree.root(), fixed.root());
+
+    // An opening taken before deepening the tree opens its leaf in the
+    // grown tree, with the levels above its depth
+    let first = tree.branch(0).expect("the leaf should exist");
+    let levels = &first.as_ref()[branch.depth()..];
+
+    assert!(branch.upgrade(levels));
+    assert_eq!(branch.depth(), 3);
+    assert_eq!(branch.root(), &tree.root());
+    assert!(branch.verify(&tree.root(), &BlsScalar::from(2)));
+    assert!(!branch.verify(&root, &BlsScalar::from(2)));
+
+    // The opening of the same leaf in the grown tree matches
+    let current = tree.branch(2).expect("the leaf should exist");
+    for (level, current) in branch.as_ref().iter().zip(current.as_ref()) {
+        assert_eq!(level.to_bytes(), current.to_bytes());
+    }
+
+    // An opening taken before its tree was full holds stale siblings
+    assert!(!stale.upgrade(levels));
+    assert_eq!(stale.depth(), 1);
+
+    // The levels must hold the root of the opening
+    let mut other = DynPoseidonTree::<MockLeaf, u64>::growing(1);
+    for i in 100..104 {
+        other.push(MockLeaf::from(i));
+    }
+    let mut other = other.branch(2).expect("the leaf should exist");
+    assert!(!other.upgrade(levels));
+    assert_eq!(other.depth(), 1);
+}
+
+#[test]
+fn tree_dyn_merkle_opening() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let mut tree = DynPoseidonTree::<MockLeaf, u64>::new(12);
+    for i in 0..42 {
+        tree.push(MockLeaf::from(i));
+    }
+
+    let circuit = DynMerkleOpeningCircuit {
+        branch: tree.branch(21).expect("the leaf should exist"),
+    };
+
+    let (prover, verifier) =
+        Compiler::compile_with_circuit(&pp, label, &circuit)
+            .expect("Circuit should compile successfully");
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proof generation should succeed");
+
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+}
+
+#[test]
+fn tree_forest() {
+    let mut forest = Forest::<MockLeaf, u64, DEPTH, TREES>::new();
+    let empty = forest.root();
+
+    for i in 0..42 {
+        forest.push(i as usize % TREES, MockLeaf::from(i));
+    }
+    assert_eq!(forest.push(TREES, MockLeaf::from(42)), None);
+    assert_ne!(forest.root(), empty);
+
+    let mut tree = Tree::default();
+    for i in (1..42).step_by(TREES) {
+        tree.push(MockLeaf::from(i));
+    }
+    assert_eq!(forest.roots()[1], tree.root());
+
+    let opening = forest.opening(1, 5).expect("the leaf should exist");
+    assert_eq!(opening.index(), 1);
+    assert_eq!(opening.root(), forest.root());
+    assert!(opening.verify(&forest.root(), &BlsScalar::from(16)));
+    assert!(!opening.verify(&forest.root(), &BlsScalar::from(15)));
+    assert!(!opening.verify(&tree.root(), &BlsScalar::from(16)));
+
+    assert!(forest.opening(1, 14).is_none());
+    assert!(forest.opening(TREES, 0).is_none());
+}
+
+#[test]
+fn tree_forest_opening() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let (prover, verifier) =
+        Compiler::compile::<ForestOpeningCircuit>(&pp, label)
+            .expect("Circuit should compile successfully");
+
+    let mut forest = Forest::<MockLeaf, u64, DEPTH, TREES>::new();
+    for i in 0..42 {
+        forest.push(i as usize % TREES, MockLeaf::from(i));
+    }
+
+    // The same circuit opens the leaves of every tree
+    for (index, pos) in [(0, 3), (2, 13)] {
+        let opening =
+            forest.opening(index, pos).expect("the leaf should exist");
+        let leaf = BlsScalar::from(pos * TREES as u64 + index as u64);
+        let circuit = ForestOpeningCircuit { opening, leaf };
+
+        let (proof, public_inputs) = prover
+            .prove(&mut rng, &circuit)
+            .expect("Proof generation should succeed");
+
+        verifier
+            .verify(&proof, &public_inputs)
+            .expect("Proof verification should succeed");
+    }
+
+    // A leaf of another tree can't be opened
+    let circuit = ForestOpeningCircuit {
+        opening: forest.opening(0, 3).expect("the leaf should exist"),
+        leaf: BlsScalar::from(10),
+    };
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
+
+#[test]
+fn tree_multi_branch() {
+    let mut tree = Tree::default();
+    for i in 0..100 {
+        tree.push(MockLeaf::from(i));
+    }
+    let root = tree.root();
+
+    let proof = tree
+        .multi_branch(&[3, 1, 17, 64, 65, 99, 3])
+        .expect("the leaves should exist");
+    assert_eq!(proof.positions(), [1, 3, 17, 64, 65, 99]);
+    assert_eq!(proof.root(), &root);
+
+    // The paths of the leaves share their levels
+    assert_eq!(proof.levels().len(), DEPTH);
+    assert_eq!(proof.levels()[0].len(), 4);
+    assert_eq!(proof.levels()[1].len(), 4);
+    assert_eq!(proof.levels()[2].len(), 2);
+    assert_eq!(proof.levels()[3].len(), 1);
+
+    let mut leaves: Vec<BlsScalar> = proof
+        .positions()
+        .iter()
+        .map(|&p| BlsScalar::from(p))
+        .collect();
+    assert!(proof.verify(&root, &leaves));
+    assert!(!proof.verify(&root, &leaves[1..]));
+
+    leaves[2] = BlsScalar::from(18);
+    assert!(!proof.verify(&root, &leaves));
+
+    assert!(tree.multi_branch(&[3, 100]).is_none());
+    assert!(tree.multi_branch(&[]).is_none());
+}
+
+#[test]
+fn tree_merkle_multi_opening() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << (CAPACITY + 1), &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let mut tree = Tree::default();
+    for i in 0..100 {
+        tree.push(MockLeaf::from(i));
+    }
+
+    let proof = tree
+        .multi_branch(&[1, 3, 17, 64])
+        .expect("the leaves should exist");
+    let leaves = proof
+        .positions()
+        .iter()
+        .map(|&p| BlsScalar::from(p))
+        .collect();
+    let mut circuit = MultiOpeningCircuit { proof, leaves };
+
+    let (prover, verifier) =
+        Compiler::compile_with_circuit(&pp, label, &circuit)
+            .expect("Circuit should compile successfully");
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proof generation should succeed");
+
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+
+    // The leaves can't be opened on other positions
+    circuit.leaves.swap(0, 1);
+    prover
+        .prove(&mut rng, &circuit)
+        .expect_err("Proof generation should fail");
+}
+
+#[test]
+#[should_panic]
+fn tree_merkle_multi_opening_leaves_mismatch() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << 4, &mut OsRng).unwrap();
+
+    let mut tree = Tree::default();
+    for i in 0..100 {
+        tree.push(MockLeaf::from(i));
+    }
+
+    let proof = tree
+        .multi_branch(&[1, 3, 17, 64])
+        .expect("the leaves should exist");
+    let leaves = vec![BlsScalar::from(1), BlsScalar::from(3)];
+    let circuit = MultiOpeningCircuit { proof, leaves };
+
+    let _ = Compiler::compile_with_circuit(&pp, label, &circuit);
+}
+
+#[test]
+#[should_panic]
+fn tree_merkle_multi_opening_missing_levels() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << 4, &mut OsRng).unwrap();
+
+    // A proof with no levels doesn't reach a root
+    let circuit = MultiOpeningCircuit::default();
+
+    let _ = Compiler::compile_with_circuit(&pp, label, &circuit);
+}
+
+#[test]
+fn tree_subtree_branch() {
+    let label = b"dusk-network";
+    let pp = PublicParameters::setup(1 << CAPACITY, &mut OsRng).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let mut tree = Tree::default();
+    for i in 0..100 {
+        tree.push(MockLeaf::from(i));
+    }
+    let root = tree.root();
+
+    let leaf = tree.subtree_branch(37, 0).expect("the leaf should exist");
+    assert_eq!(leaf.depth(), DEPTH);
+    assert_eq!(*leaf, BlsScalar::from(37));
+
+    // Leaves of the same subtree open the same node
+    let subtree = tree.subtree_branch(37, 2).expect("the leaf should exist");
+    let other = tree.subtree_branch(46, 2).expect("the leaf should exist");
+    assert_eq!(subtree.depth(), DEPTH - 2);
+    assert_eq!(*subtree, *other);
+    assert!(subtree.verify(&root, &*subtree));
+    assert!(!subtree.verify(&root, &BlsScalar::from(37)));
+
+    let next = tree.subtree_branch(48, 2).expect("the leaf should exist");
+    assert_ne!(*subtree, *next);
+
+    assert!(tree.subtree_branch(100, 2).is_none());
+    assert!(tree.subtree_branch(37, DEPTH).is_none());
+
+    let circuit = DynMerkleOpeningCircuit { branch: subtree };
+
+    let (prover, verifier) =
+        Compiler::compile_with_circuit(&pp, label, &circuit)
+            .expect("Circuit should compile successfully");
+
+    let (proof, public_inputs) = prover
+        .prove(&mut rng, &circuit)
+        .expect("Proof generation should succeed");
+
+    verifier
+        .verify(&proof, &public_inputs)
+        .expect("Proof verification should succeed");
+}
+
+#[test]
+fn tree_maintained_opening() {
+    let mut tree = Tree::default();
+    for i in 0..10 {
+        tree.push(MockLeaf::from(i));
+    }
+
+    let mut opening =
+        tree.maintained_opening(6).expect("the leaf should exist");
+    let mut last = tree.maintained_opening(9).expect("the leaf should exist");
+    assert_eq!(opening.root(), &tree.root());
+    assert!(tree.maintained_opening(10).is_none());
+
+    // The openings follow the tree, across full groups of siblings
+    for i in 10..100 {
+        tree.push(MockLeaf::from(i));
+
+        let root = tree.root();
+        assert_eq!(opening.append(BlsScalar::from(i)), root);
+        assert_eq!(last.append(BlsScalar::from(i)), root);
+
+        assert_eq!(opening.len(), i + 1);
+        assert!(opening.branch().verify(&root, &BlsScalar::from(6)));
+        assert!(last.branch().verify(&root, &BlsScalar::from(9)));
+    }
+
+    // The root follows the tree up to its capacity
+    let mut tree = PoseidonTree::<MockLeaf, u64, 2>::new();
+    for i in 0..5 {
+        tree.push(MockLeaf::from(i));
+    }
+
+    let mut opening =
+        tree.maintained_opening(1).expect("the leaf should exist");
+    for i in 5..16 {
+        tree.push(MockLeaf::from(i));
+
+        let root = tree.root();
+        assert_eq!(opening.append(BlsScalar::from(i)), root);
+        assert!(opening.branch().verify(&root, &BlsScalar::from(1)));
+    }
+}
+
+#[test]
+fn tree_on_change() {
+    use std::sync::{Arc, Mutex};
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let mut tree = Tree::default();
+
+    let recorded = Arc::clone(&events);
+    tree.on_change(move |old_root, new_root, positions| {
+        recorded.lock().unwrap().push((
+            *old_root,
+            *new_root,
+            positions.to_vec(),
+        ));
+    });
+
+    let empty = tree.root();
+    tree.push(MockLeaf::from(0));
+    let first = tree.root();
+    tree.extend((1..5).map(MockLeaf::from));
+    let batch = tree.root();
+    tree.update(2, MockLeaf::from(42));
+    let updated = tree.root();
+    assert!(tree.update(10, MockLeaf::from(42)).is_none());
+    tree.pop();
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 4);
+    assert_eq!(events[0], (empty, first, vec![0]));
+    assert_eq!(events[1], (first, batch, vec![1, 2, 3, 4]));
+    assert_eq!(events[2], (batch, updated, vec![2]));
+    assert_eq!(events[3], (updated, tree.root(), vec![4]));
+}
+
+#[test]
+fn tree_root_history() {
+    let mut tree = Tree::default();
+    assert!(!tree.is_recent_root(&tree.root()));
+
+    tree.track_roots::<3>();
+    let empty = tree.root();
+    assert!(tree.is_recent_root(&empty));
+
+    tree.push(MockLeaf::from(0));
+    let first = tree.root();
+    tree.extend((1..5).map(MockLeaf::from));
+    let batch = tree.root();
+
+    // The batch is recorded as a single root
+    let history = tree.root_history::<3>().expect("the roots are tracked");
+    let roots = [batch, first, empty];
+    assert!(history.iter().eq(roots.iter()));
+    assert!(tree.root_history::<4>().is_none());
+
+    tree.update(2, MockLeaf::from(42));
+    let updated = tree.root();
+    assert!(tree.update(10, MockLeaf::from(42)).is_none());
+    tree.remove(3);
+    let removed = tree.root();
+
+    let history = tree.root_history::<3>().expect("the roots are tracked");
+    let roots = [removed, updated, batch];
+    assert!(history.iter().eq(roots.iter()));
+    assert!(tree.is_recent_root(&batch));
+    assert!(!tree.is_recent_root(&first));
+
+    // The history is cloned along with the tree
+    let mut clone = tree.clone();
+    clone.pop();
+    assert!(clone.is_recent_root(&clone.root()));
+    assert!(clone.is_recent_root(&removed));
+    assert!(!tree.is_recent_root(&clone.root()));
+}